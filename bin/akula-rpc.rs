@@ -1,18 +1,27 @@
 use akula::{
     akula_tracing::{self, Component},
     binutil::AkulaDataDir,
+    execution::analysis_cache::{self, AnalysisCache},
     kv::{mdbx::*, MdbxWithDirHandle},
     rpc::{
-        erigon::ErigonApiServerImpl, eth::EthApiServerImpl, net::NetApiServerImpl,
-        otterscan::OtterscanApiServerImpl, trace::TraceApiServerImpl, web3::Web3ApiServerImpl,
+        debug::{DebugApiServer, DebugApiServerImpl},
+        erigon::ErigonApiServerImpl,
+        eth::{EthApiServerImpl, DEFAULT_MAX_LOGS_BLOCK_RANGE},
+        net::NetApiServerImpl,
+        otterscan::OtterscanApiServerImpl,
+        pubsub::PubSubApiServerImpl,
+        trace::TraceApiServerImpl,
+        web3::Web3ApiServerImpl,
     },
 };
 use anyhow::format_err;
 use clap::Parser;
 use ethereum_jsonrpc::{
-    ErigonApiServer, EthApiServer, NetApiServer, OtterscanApiServer, TraceApiServer, Web3ApiServer,
+    ErigonApiServer, NetApiServer, OtterscanApiServer, TraceApiServer, Web3ApiServer,
+};
+use jsonrpsee::{
+    core::server::rpc_module::Methods, http_server::HttpServerBuilder, ws_server::WsServerBuilder,
 };
-use jsonrpsee::{core::server::rpc_module::Methods, http_server::HttpServerBuilder};
 use std::{future::pending, net::SocketAddr, sync::Arc};
 use tracing_subscriber::prelude::*;
 
@@ -27,6 +36,18 @@ pub struct Opt {
 
     #[clap(long)]
     pub grpc_listen_address: SocketAddr,
+
+    /// Most blocks a single `eth_getLogs` request may scan.
+    #[clap(long, default_value_t = DEFAULT_MAX_LOGS_BLOCK_RANGE)]
+    pub max_logs_block_range: u64,
+
+    /// Also serve JSONRPC over WebSocket at this IP address and port.
+    #[clap(long)]
+    pub ws_listen_address: Option<SocketAddr>,
+
+    /// Most memory analyses of executed bytecode may take (MiB).
+    #[clap(long, default_value_t = analysis_cache::DEFAULT_CAPACITY_MIB)]
+    pub analysis_cache_size: usize,
 }
 
 #[tokio::main]
@@ -35,6 +56,8 @@ async fn main() -> anyhow::Result<()> {
 
     akula_tracing::build_subscriber(Component::RPCDaemon).init();
 
+    AnalysisCache::init_global(opt.analysis_cache_size * 1024 * 1024);
+
     let db: Arc<MdbxWithDirHandle<NoWriteMap>> = Arc::new(
         MdbxEnvironment::<NoWriteMap>::open_ro(
             mdbx::Environment::new(),
@@ -58,15 +81,18 @@ async fn main() -> anyhow::Result<()> {
         EthApiServerImpl {
             db: db.clone(),
             call_gas_limit: 100_000_000,
+            max_logs_block_range: opt.max_logs_block_range,
+            txpool: None,
+            filters: Default::default(),
         }
-        .into_rpc(),
+        .into_methods(),
     )
     .unwrap();
     api.merge(NetApiServerImpl { network_id }.into_rpc())
         .unwrap();
     api.merge(ErigonApiServerImpl { db: db.clone() }.into_rpc())
         .unwrap();
-    api.merge(OtterscanApiServerImpl { db: db.clone() }.into_rpc())
+    api.merge(OtterscanApiServerImpl { db: db.clone() }.into_methods())
         .unwrap();
     api.merge(
         TraceApiServerImpl {
@@ -76,8 +102,37 @@ async fn main() -> anyhow::Result<()> {
         .into_rpc(),
     )
     .unwrap();
+    api.merge(
+        DebugApiServerImpl {
+            db: db.clone(),
+            call_gas_limit: 100_000_000,
+        }
+        .into_rpc(),
+    )
+    .unwrap();
     api.merge(Web3ApiServerImpl.into_rpc()).unwrap();
 
+    let _ws_server_handle = if let Some(ws_listen_address) = opt.ws_listen_address {
+        let mut ws_api = api.clone();
+        ws_api
+            .merge(
+                PubSubApiServerImpl {
+                    db: db.clone(),
+                    txpool: None,
+                }
+                .into_rpc(),
+            )
+            .unwrap();
+
+        Some(
+            WsServerBuilder::default()
+                .build(ws_listen_address)
+                .await?
+                .start(ws_api)?,
+        )
+    } else {
+        None
+    };
     let _server_handle = server.start(api)?;
 
     tokio::spawn({
@@ -134,6 +134,7 @@ async fn download_headers(
     let consensus: Arc<dyn Consensus> =
         engine_factory(Some(env.clone()), chain_config.chain_spec.clone())?.into();
     let txn = env.begin_mutable()?;
+    akula::kv::migrations::migrate(&txn)?;
     akula::genesis::initialize_genesis(
         &txn,
         &*Arc::new(tempfile::tempdir_in(etl_temp_path).context("failed to create ETL temp dir")?),
@@ -374,7 +375,12 @@ fn read_block(data_dir: AkulaDataDir, block_num: BlockNumber) -> anyhow::Result<
 
     let partial_header = PartialHeader::from(header.clone());
 
-    let block = Block::new(partial_header.clone(), body.transactions, body.ommers);
+    let block = Block::new(
+        partial_header.clone(),
+        body.transactions,
+        body.ommers,
+        body.withdrawals,
+    );
 
     ensure!(
         block.header.transactions_root == header.transactions_root,
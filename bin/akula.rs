@@ -1,29 +1,45 @@
 use akula::{
     akula_tracing::{self, Component},
     binutil::{AkulaDataDir, ExpandedPathBuf},
-    consensus::{engine_factory, Consensus, ForkChoiceMode},
+    consensus::{engine_factory, Consensus, EngineApiConfig, ForkChoiceMode, JwtSecret},
+    execution::analysis_cache::{self, AnalysisCache},
     kv::tables::CHAINDATA_TABLES,
     models::*,
     p2p::node::NodeBuilder,
     rpc::{
-        erigon::ErigonApiServerImpl, eth::EthApiServerImpl, net::NetApiServerImpl,
-        otterscan::OtterscanApiServerImpl, trace::TraceApiServerImpl, web3::Web3ApiServerImpl,
+        debug::{DebugApiServer, DebugApiServerImpl},
+        erigon::ErigonApiServerImpl,
+        eth::{EthApiServerImpl, DEFAULT_MAX_LOGS_BLOCK_RANGE},
+        net::NetApiServerImpl,
+        otterscan::OtterscanApiServerImpl,
+        pubsub::PubSubApiServerImpl,
+        trace::TraceApiServerImpl,
+        web3::Web3ApiServerImpl,
     },
     stagedsync::{
         self,
         stages::{BODIES, HEADERS},
     },
     stages::*,
+    txpool::{PoolConfig, TransactionPool},
     version_string,
 };
 use anyhow::Context;
 use clap::Parser;
 use ethereum_jsonrpc::{
-    ErigonApiServer, EthApiServer, NetApiServer, OtterscanApiServer, TraceApiServer, Web3ApiServer,
+    ErigonApiServer, NetApiServer, OtterscanApiServer, TraceApiServer, Web3ApiServer,
 };
 use http::Uri;
-use jsonrpsee::{core::server::rpc_module::Methods, http_server::HttpServerBuilder};
-use std::{future::pending, net::SocketAddr, panic, sync::Arc, time::Duration};
+use jsonrpsee::{
+    core::server::rpc_module::Methods, http_server::HttpServerBuilder, ws_server::WsServerBuilder,
+};
+use std::{
+    future::pending,
+    net::{IpAddr, SocketAddr},
+    panic,
+    sync::Arc,
+    time::Duration,
+};
 use tokio::time::sleep;
 use tracing::*;
 use tracing_subscriber::prelude::*;
@@ -82,6 +98,18 @@ pub struct Opt {
     #[clap(long)]
     pub execution_exit_after_batch: bool,
 
+    /// Persist transaction receipts during execution instead of recomputing them on demand.
+    #[clap(long)]
+    pub execution_write_receipts: bool,
+
+    /// Speculatively execute transactions of each block in parallel.
+    #[clap(long)]
+    pub execution_parallel: bool,
+
+    /// Most memory analyses of executed bytecode may take (MiB).
+    #[clap(long, default_value_t = analysis_cache::DEFAULT_CAPACITY_MIB)]
+    pub analysis_cache_size: usize,
+
     /// Skip commitment (state root) verification.
     #[clap(long)]
     pub skip_commitment: bool,
@@ -102,9 +130,30 @@ pub struct Opt {
     #[clap(long, default_value = "127.0.0.1:8545")]
     pub rpc_listen_address: SocketAddr,
 
+    /// Most blocks a single `eth_getLogs` request may scan.
+    #[clap(long, default_value_t = DEFAULT_MAX_LOGS_BLOCK_RANGE)]
+    pub rpc_max_logs_block_range: u64,
+
+    /// Enable JSONRPC over WebSocket at this IP address and port.
+    #[clap(long, default_value = "127.0.0.1:8546")]
+    pub ws_listen_address: SocketAddr,
+
     /// Enable gRPC at this IP address and port.
     #[clap(long, default_value = "127.0.0.1:7545")]
     pub grpc_listen_address: SocketAddr,
+
+    /// Engine API listening address.
+    #[clap(long = "authrpc.addr", default_value = "127.0.0.1")]
+    pub authrpc_addr: IpAddr,
+
+    /// Engine API listening port.
+    #[clap(long = "authrpc.port", default_value = "8551")]
+    pub authrpc_port: u16,
+
+    /// Path to hex-encoded JWT secret for Engine API authentication.
+    /// Generated in the data directory if not specified.
+    #[clap(long = "authrpc.jwtsecret")]
+    pub jwt_secret_path: Option<ExpandedPathBuf>,
 }
 
 #[allow(unreachable_code)]
@@ -114,6 +163,8 @@ fn main() -> anyhow::Result<()> {
 
     akula_tracing::build_subscriber(Component::Core).init();
 
+    AnalysisCache::init_global(opt.analysis_cache_size * 1024 * 1024);
+
     std::thread::Builder::new()
         .stack_size(128 * 1024 * 1024)
         .spawn(move || {
@@ -152,21 +203,38 @@ fn main() -> anyhow::Result<()> {
                     let span = span!(Level::INFO, "", " Genesis initialization ");
                     let _g = span.enter();
                     let txn = db.begin_mutable()?;
-                    let (chainspec, initialized) = akula::genesis::initialize_genesis(
+                    akula::kv::migrations::migrate(&txn)?;
+                    let (chainspec, _) = akula::genesis::initialize_genesis(
                         &txn,
                         &*etl_temp_dir,
                         bundled_chain_spec,
                         chain_config,
                     )?;
-                    if initialized {
-                        txn.commit()?;
-                    }
+                    txn.commit()?;
 
                     chainspec
                 };
 
-                let consensus: Arc<dyn Consensus> =
-                    engine_factory(Some(db.clone()), chainspec.clone())?.into();
+                let txpool = Arc::new(TransactionPool::new(
+                    db.clone(),
+                    chainspec.clone(),
+                    PoolConfig::default(),
+                )?);
+
+                let jwt_secret_path = opt
+                    .jwt_secret_path
+                    .map(|p| p.0)
+                    .unwrap_or_else(|| opt.data_dir.jwt_secret_path());
+                let consensus: Arc<dyn Consensus> = engine_factory(
+                    Some(EngineApiConfig {
+                        db: db.clone(),
+                        listen_address: SocketAddr::new(opt.authrpc_addr, opt.authrpc_port),
+                        jwt_secret: JwtSecret::load_or_generate(jwt_secret_path)?,
+                        transaction_source: txpool.clone(),
+                    }),
+                    chainspec.clone(),
+                )?
+                .into();
 
                 let network_id = chainspec.params.network_id;
 
@@ -175,7 +243,10 @@ fn main() -> anyhow::Result<()> {
                 if !opt.no_rpc {
                     tokio::spawn({
                         let db = db.clone();
+                        let txpool = txpool.clone();
                         let listen_address = opt.rpc_listen_address;
+                        let ws_listen_address = opt.ws_listen_address;
+                        let max_logs_block_range = opt.rpc_max_logs_block_range;
                         async move {
                             let server = HttpServerBuilder::default()
                                 .build(listen_address)
@@ -187,19 +258,30 @@ fn main() -> anyhow::Result<()> {
                                 EthApiServerImpl {
                                     db: db.clone(),
                                     call_gas_limit: 100_000_000,
+                                    max_logs_block_range,
+                                    txpool: Some(txpool.clone()),
+                                    filters: Default::default(),
                                 }
-                                .into_rpc(),
+                                .into_methods(),
                             )
                             .unwrap();
                             api.merge(NetApiServerImpl { network_id }.into_rpc())
                                 .unwrap();
                             api.merge(ErigonApiServerImpl { db: db.clone() }.into_rpc())
                                 .unwrap();
-                            api.merge(OtterscanApiServerImpl { db: db.clone() }.into_rpc())
+                            api.merge(OtterscanApiServerImpl { db: db.clone() }.into_methods())
                                 .unwrap();
                             api.merge(
                                 TraceApiServerImpl {
-                                    db,
+                                    db: db.clone(),
+                                    call_gas_limit: 100_000_000,
+                                }
+                                .into_rpc(),
+                            )
+                            .unwrap();
+                            api.merge(
+                                DebugApiServerImpl {
+                                    db: db.clone(),
                                     call_gas_limit: 100_000_000,
                                 }
                                 .into_rpc(),
@@ -207,7 +289,24 @@ fn main() -> anyhow::Result<()> {
                             .unwrap();
                             api.merge(Web3ApiServerImpl.into_rpc()).unwrap();
 
+                            let mut ws_api = api.clone();
+                            ws_api
+                                .merge(
+                                    PubSubApiServerImpl {
+                                        db,
+                                        txpool: Some(txpool),
+                                    }
+                                    .into_rpc(),
+                                )
+                                .unwrap();
+
                             let _server_handle = server.start(api).unwrap();
+                            let _ws_server_handle = WsServerBuilder::default()
+                                .build(ws_listen_address)
+                                .await
+                                .unwrap()
+                                .start(ws_api)
+                                .unwrap();
 
                             pending::<()>().await
                         }
@@ -313,6 +412,20 @@ fn main() -> anyhow::Result<()> {
                     }
                 });
 
+                tokio::spawn({
+                    let txpool = txpool.clone();
+                    let node = node.clone();
+                    async move {
+                        loop {
+                            match txpool.clone().run_gossip(node.clone()).await {
+                                Ok(()) => warn!("Transaction gossip stopped, restarting"),
+                                Err(e) => error!("Transaction gossip failed: {e}, restarting"),
+                            }
+                            sleep(Duration::from_secs(1)).await;
+                        }
+                    }
+                });
+
                 staged_sync.push(
                     HeaderDownload {
                         node: node.clone(),
@@ -346,6 +459,8 @@ fn main() -> anyhow::Result<()> {
                         exit_after_batch: opt.execution_exit_after_batch,
                         batch_until: None,
                         commit_every: None,
+                        write_receipts: opt.execution_write_receipts,
+                        parallel: opt.execution_parallel,
                     },
                     false,
                 );
@@ -384,6 +499,14 @@ fn main() -> anyhow::Result<()> {
                     },
                     !opt.prune,
                 );
+                staged_sync.push(
+                    LogIndex {
+                        temp_dir: etl_temp_dir.clone(),
+                        flush_interval: 50_000,
+                    },
+                    !opt.prune,
+                );
+                staged_sync.push(TxPool::new(txpool), true);
                 staged_sync.push(Finish, !opt.prune);
 
                 info!("Running staged sync");
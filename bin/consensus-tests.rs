@@ -7,6 +7,7 @@ use akula::{
         *,
     },
     crypto::keccak256,
+    execution::precompiled::PrecompiledContract,
     models::*,
     res::chainspec::*,
     *,
@@ -27,7 +28,10 @@ use std::{
     ops::AddAssign,
     path::{Path, PathBuf},
     str::FromStr,
-    sync::Arc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
     time::Instant,
 };
 use tokio::runtime::Builder;
@@ -130,6 +134,8 @@ enum Network {
     EIP2384,
     ArrowGlacier,
     Merge,
+    Shanghai,
+    Cancun,
 }
 
 impl FromStr for Network {
@@ -156,6 +162,8 @@ impl FromStr for Network {
             "EIP2384" => Self::EIP2384,
             "ArrowGlacier" => Self::ArrowGlacier,
             "Merge" => Self::Merge,
+            "Shanghai" => Self::Shanghai,
+            "Cancun" => Self::Cancun,
             _ => return Err(s.to_string()),
         })
     }
@@ -170,7 +178,17 @@ fn testconfig(
     let mut spec = MAINNET.clone();
     spec.name = format!("{:?}", name);
     spec.consensus.eip1559_block = upgrades.london;
-    let SealVerificationParams::Ethash { block_reward, difficulty_bomb, skip_pow_verification, homestead_formula, byzantium_formula,.. } = &mut spec.consensus.seal_verification else { unreachable!() };
+    let SealVerificationParams::Ethash {
+        block_reward,
+        difficulty_bomb,
+        skip_pow_verification,
+        homestead_formula,
+        byzantium_formula,
+        ..
+    } = &mut spec.consensus.seal_verification
+    else {
+        unreachable!()
+    };
     *difficulty_bomb = Some(DifficultyBomb {
         delays: btreemap! { BlockNumber(0) => bomb_delay },
     });
@@ -198,6 +216,8 @@ fn testconfig(
     spec
 }
 
+static PARALLEL_EXECUTION: AtomicBool = AtomicBool::new(false);
+
 static NETWORK_CONFIG: Lazy<HashMap<Network, ChainSpec>> = Lazy::new(|| {
     vec![
         (Network::Frontier, Upgrades::default(), None, 0),
@@ -434,6 +454,47 @@ static NETWORK_CONFIG: Lazy<HashMap<Network, ChainSpec>> = Lazy::new(|| {
                 berlin: Some(0.into()),
                 london: Some(0.into()),
                 paris: Some(0.into()),
+                ..Default::default()
+            },
+            None,
+            11_200_000,
+        ),
+        (
+            Network::Shanghai,
+            Upgrades {
+                homestead: Some(0.into()),
+                tangerine: Some(0.into()),
+                spurious: Some(0.into()),
+                byzantium: Some(0.into()),
+                constantinople: Some(0.into()),
+                petersburg: Some(0.into()),
+                istanbul: Some(0.into()),
+                berlin: Some(0.into()),
+                london: Some(0.into()),
+                paris: Some(0.into()),
+                shanghai_time: Some(0),
+                cancun_time: None,
+                eof_time: None,
+            },
+            None,
+            11_200_000,
+        ),
+        (
+            Network::Cancun,
+            Upgrades {
+                homestead: Some(0.into()),
+                tangerine: Some(0.into()),
+                spurious: Some(0.into()),
+                byzantium: Some(0.into()),
+                constantinople: Some(0.into()),
+                petersburg: Some(0.into()),
+                istanbul: Some(0.into()),
+                berlin: Some(0.into()),
+                london: Some(0.into()),
+                paris: Some(0.into()),
+                shanghai_time: Some(0),
+                cancun_time: Some(0),
+                eof_time: None,
             },
             None,
             11_200_000,
@@ -703,6 +764,7 @@ fn blockchain_test(testdata: BlockchainTest) -> anyhow::Result<()> {
     init_pre_state(&testdata.pre, &mut state);
 
     let mut blockchain = Blockchain::new(&mut state, config, genesis_block).unwrap();
+    blockchain.set_parallel_execution(PARALLEL_EXECUTION.load(Ordering::Relaxed));
 
     for block in &testdata.blocks {
         let block_common =
@@ -813,6 +875,99 @@ fn transaction_test(testdata: TransactionTest) -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Precompile test vector in the format of go-ethereum's `core/vm/testdata/precompiles`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct PrecompileTest {
+    name: String,
+    input: String,
+    #[serde(default)]
+    expected: Option<String>,
+    #[serde(default)]
+    gas: Option<u64>,
+    #[serde(default)]
+    expected_error: Option<String>,
+}
+
+/// Precompile exercised by a test vector file, failure vectors being prefixed with `fail-`.
+fn precompile_for_test_file(path: &Path) -> Option<Precompile> {
+    let stem = path.file_stem()?.to_str()?;
+    Some(match stem.strip_prefix("fail-").unwrap_or(stem) {
+        "blsG1Add" => Precompile::Bls12381G1Add,
+        "blsG1Mul" | "blsG1MultiExp" => Precompile::Bls12381G1Msm,
+        "blsG2Add" => Precompile::Bls12381G2Add,
+        "blsG2Mul" | "blsG2MultiExp" => Precompile::Bls12381G2Msm,
+        "blsPairing" => Precompile::Bls12381PairingCheck,
+        "blsMapG1" => Precompile::Bls12381MapFpToG1,
+        "blsMapG2" => Precompile::Bls12381MapFp2ToG2,
+        _ => return None,
+    })
+}
+
+#[instrument(skip(testdata))]
+fn precompile_test(precompile: &Precompile, testdata: PrecompileTest) -> anyhow::Result<()> {
+    let input = Bytes::from(hex::decode(&testdata.input)?);
+
+    match (&testdata.expected, precompile.run(input.clone())) {
+        (Some(expected), Some(output)) => {
+            ensure!(
+                output == hex::decode(expected)?,
+                "Output mismatch:\n{} != {}",
+                hex::encode(&output),
+                expected
+            );
+
+            if let Some(expected_gas) = testdata.gas {
+                let gas = precompile.gas(input, Revision::latest());
+                ensure!(
+                    gas == Some(expected_gas),
+                    "Gas mismatch: {:?} != {}",
+                    gas,
+                    expected_gas
+                );
+            }
+        }
+        (None, None) => {}
+        (_, output) => bail!(
+            "Unexpected result: {:?} != {:?}",
+            testdata.expected_error,
+            output.map(hex::encode)
+        ),
+    }
+
+    Ok(())
+}
+
+#[instrument]
+fn run_precompile_test_file(path: &Path, test_names: &HashSet<String>) -> RunResults {
+    let mut out = RunResults::default();
+
+    let Some(precompile) = precompile_for_test_file(path) else {
+        out.skipped += 1;
+        return out;
+    };
+
+    let tests: Vec<PrecompileTest> = serde_json::from_slice(&std::fs::read(path).unwrap()).unwrap();
+    for test in tests {
+        if !test_names.is_empty() && !test_names.contains(&test.name) {
+            continue;
+        }
+
+        let test_name = test.name.clone();
+        debug!("Running test {}", test_name);
+        out.push({
+            if let Err(e) = precompile_test(&precompile, test) {
+                error!("{}: {}: {}", path.to_string_lossy(), test_name, e);
+                Status::Failed
+            } else {
+                Status::Passed
+            }
+        });
+    }
+
+    out
+}
+
 type NetworkDifficultyTests = HashMap<String, DifficultyTest>;
 
 #[instrument(skip(testdata))]
@@ -836,7 +991,15 @@ fn difficulty_test(testdata: HashMap<String, Value>) -> anyhow::Result<()> {
             };
 
             let config = NETWORK_CONFIG[&network].clone();
-            let SealVerificationParams::Ethash { homestead_formula, byzantium_formula, difficulty_bomb, .. } = config.consensus.seal_verification else {unreachable!()};
+            let SealVerificationParams::Ethash {
+                homestead_formula,
+                byzantium_formula,
+                difficulty_bomb,
+                ..
+            } = config.consensus.seal_verification
+            else {
+                unreachable!()
+            };
 
             let calculated_difficulty = canonical_difficulty(
                 testdata.current_block_number,
@@ -903,6 +1066,12 @@ pub struct Opt {
     pub tests: ExpandedPathBuf,
     #[clap(long)]
     pub test_names: Vec<String>,
+    /// Execute blocks with speculative parallel execution of transactions
+    #[clap(long)]
+    pub parallel_execution: bool,
+    /// Path to precompile test vectors in go-ethereum format
+    #[clap(long)]
+    pub precompile_tests: Option<ExpandedPathBuf>,
 }
 
 #[derive(Debug, Default)]
@@ -961,7 +1130,10 @@ async fn run() {
         .with(env_filter)
         .init();
 
+    PARALLEL_EXECUTION.store(opt.parallel_execution, Ordering::Relaxed);
+
     let root_dir = opt.tests;
+    let precompile_dir = opt.precompile_tests;
     let test_names = Arc::new(opt.test_names.into_iter().collect());
 
     let mut tasks = Vec::new();
@@ -1034,6 +1206,20 @@ async fn run() {
         }
     }
 
+    if let Some(precompile_dir) = precompile_dir {
+        for entry in walkdir::WalkDir::new(&*precompile_dir) {
+            let e = entry.unwrap();
+
+            if e.file_type().is_file() {
+                let p = e.into_path();
+                let test_names = Arc::clone(&test_names);
+                tasks.push(tokio::spawn(async move {
+                    run_precompile_test_file(p.as_path(), &test_names)
+                }));
+            }
+        }
+    }
+
     for task in tasks {
         res += task.await.unwrap();
     }
@@ -214,6 +214,7 @@ pub mod block_body {
                 BlockBody {
                     transactions,
                     ommers: body.uncles,
+                    withdrawals: body.withdrawals,
                 },
                 body.base_tx_id,
             )));
@@ -250,6 +251,7 @@ pub mod block_body {
                     })
                     .collect(),
                 ommers: body.ommers,
+                withdrawals: body.withdrawals,
             }));
         }
 
@@ -348,6 +350,7 @@ mod tests {
             base_tx_id: 1.into(),
             tx_amount: 2,
             uncles: vec![],
+            withdrawals: None,
         };
 
         let db = new_mem_chaindata().unwrap();
@@ -38,6 +38,10 @@ impl AkulaDataDir {
     pub fn sentry_db(&self) -> PathBuf {
         self.0.join("sentrydb")
     }
+
+    pub fn jwt_secret_path(&self) -> PathBuf {
+        self.0.join("jwt.hex")
+    }
 }
 
 impl Default for AkulaDataDir {
@@ -1,11 +1,18 @@
 use super::protocol_param::fee;
 use crate::models::*;
 
-pub fn intrinsic_gas(txn: &Message, homestead: bool, istanbul: bool) -> u128 {
+pub fn intrinsic_gas(txn: &Message, homestead: bool, istanbul: bool, shanghai: bool) -> u128 {
     let mut gas = fee::G_TRANSACTION as u128;
 
-    if matches!(txn.action(), TransactionAction::Create) && homestead {
-        gas += u128::from(fee::G_TX_CREATE);
+    if matches!(txn.action(), TransactionAction::Create) {
+        if homestead {
+            gas += u128::from(fee::G_TX_CREATE);
+        }
+
+        // https://eips.ethereum.org/EIPS/eip-3860
+        if shanghai {
+            gas += ((txn.input().len() as u128 + 31) / 32) * u128::from(fee::G_INITCODE_WORD);
+        }
     }
 
     // https://eips.ethereum.org/EIPS/eip-2930
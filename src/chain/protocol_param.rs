@@ -23,12 +23,21 @@ pub mod fee {
     pub const G_TX_DATA_NON_ZERO_FRONTIER: u64 = 68;
     pub const G_TX_DATA_NON_ZERO_ISTANBUL: u64 = 16;
     pub const G_TRANSACTION: u64 = 21_000;
+
+    // https://eips.ethereum.org/EIPS/eip-3860
+    pub const G_INITCODE_WORD: u64 = 2;
 } // namespace fee
 
 pub mod param {
+    use ethereum_types::Address;
+    use hex_literal::hex;
+
     // https://eips.ethereum.org/EIPS/eip-170
     pub const MAX_CODE_SIZE: usize = 0x6000;
 
+    // https://eips.ethereum.org/EIPS/eip-3860
+    pub const MAX_INITCODE_SIZE: usize = 2 * MAX_CODE_SIZE;
+
     pub const G_QUAD_DIVISOR_BYZANTIUM: u64 = 20; // EIP-198
     pub const G_QUAD_DIVISOR_BERLIN: u64 = 3; // EIP-2565
 
@@ -40,4 +49,21 @@ pub mod param {
     pub const INITIAL_BASE_FEE: u64 = 1_000_000_000;
     pub const BASE_FEE_MAX_CHANGE_DENOMINATOR: u64 = 8;
     pub const ELASTICITY_MULTIPLIER: u64 = 2;
+
+    // https://eips.ethereum.org/EIPS/eip-4844
+    pub const GAS_PER_BLOB: u64 = 1 << 17;
+    pub const TARGET_BLOB_GAS_PER_BLOCK: u64 = 3 * GAS_PER_BLOB;
+    pub const MAX_BLOB_GAS_PER_BLOCK: u64 = 6 * GAS_PER_BLOB;
+    pub const MIN_BLOB_BASE_FEE: u64 = 1;
+    pub const BLOB_BASE_FEE_UPDATE_FRACTION: u64 = 3_338_477;
+    pub const VERSIONED_HASH_VERSION_KZG: u8 = 0x01;
+
+    // Caller and gas limit of calls made by the protocol itself into system contracts
+    // https://eips.ethereum.org/EIPS/eip-4788
+    pub const SYSTEM_ADDRESS: Address = Address(hex!("fffffffffffffffffffffffffffffffffffffffe"));
+    pub const SYSTEM_CALL_GAS_LIMIT: u64 = 30_000_000;
+
+    // https://eips.ethereum.org/EIPS/eip-4788
+    pub const BEACON_ROOTS_ADDRESS: Address =
+        Address(hex!("000f3df6d732807ef1319fb7b8bb8522d0beac02"));
 }
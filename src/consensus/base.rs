@@ -8,6 +8,8 @@ pub const MIN_GAS_LIMIT: u64 = 5000;
 pub struct ConsensusEngineBase {
     chain_id: ChainId,
     eip1559_block: Option<BlockNumber>,
+    shanghai_time: Option<u64>,
+    cancun_time: Option<u64>,
     max_extra_data_length: Option<usize>,
 }
 
@@ -15,11 +17,15 @@ impl ConsensusEngineBase {
     pub fn new(
         chain_id: ChainId,
         eip1559_block: Option<BlockNumber>,
+        shanghai_time: Option<u64>,
+        cancun_time: Option<u64>,
         max_extra_data_length: Option<usize>,
     ) -> Self {
         Self {
             chain_id,
             eip1559_block,
+            shanghai_time,
+            cancun_time,
             max_extra_data_length,
         }
     }
@@ -101,6 +107,44 @@ impl ConsensusEngineBase {
             .into());
         }
 
+        // https://eips.ethereum.org/EIPS/eip-4895
+        let shanghai = switch_is_active_at(self.shanghai_time, header.timestamp);
+        if header.withdrawals_root.is_some() != shanghai {
+            return Err(ValidationError::WrongWithdrawalsRoot {
+                expected: shanghai.then_some(EMPTY_ROOT),
+                got: header.withdrawals_root,
+            }
+            .into());
+        }
+
+        // https://eips.ethereum.org/EIPS/eip-4844
+        if let Some(blob_gas_used) = header.blob_gas_used {
+            if blob_gas_used > param::MAX_BLOB_GAS_PER_BLOCK {
+                return Err(ValidationError::BlobGasAboveLimit {
+                    used: blob_gas_used,
+                    limit: param::MAX_BLOB_GAS_PER_BLOCK,
+                }
+                .into());
+            }
+        }
+
+        let expected_excess_blob_gas = self.expected_excess_blob_gas(header, parent);
+        if header.excess_blob_gas != expected_excess_blob_gas {
+            return Err(ValidationError::WrongExcessBlobGas {
+                expected: expected_excess_blob_gas,
+                got: header.excess_blob_gas,
+            }
+            .into());
+        }
+
+        // https://eips.ethereum.org/EIPS/eip-4788
+        let cancun = switch_is_active_at(self.cancun_time, header.timestamp);
+        match (cancun, header.parent_beacon_block_root.is_some()) {
+            (true, false) => return Err(ValidationError::MissingParentBeaconBlockRoot.into()),
+            (false, true) => return Err(ValidationError::UnexpectedParentBeaconBlockRoot.into()),
+            _ => {}
+        }
+
         Ok(())
     }
 
@@ -148,47 +192,32 @@ impl ConsensusEngineBase {
     }
 
     // https://eips.ethereum.org/EIPS/eip-1559
-    fn expected_base_fee_per_gas(
+    pub fn expected_base_fee_per_gas(
         &self,
         header: &BlockHeader,
         parent: &BlockHeader,
     ) -> Option<U256> {
-        if let Some(fork_block) = self.eip1559_block {
-            if header.number >= fork_block {
-                if header.number == fork_block {
-                    return Some(param::INITIAL_BASE_FEE.into());
-                }
-
-                let parent_gas_target = parent.gas_limit / param::ELASTICITY_MULTIPLIER;
-
-                let parent_base_fee_per_gas = parent.base_fee_per_gas.unwrap();
-
-                if parent.gas_used == parent_gas_target {
-                    return Some(parent_base_fee_per_gas);
-                }
-
-                if parent.gas_used > parent_gas_target {
-                    let gas_used_delta = parent.gas_used - parent_gas_target;
-                    let base_fee_per_gas_delta = std::cmp::max(
-                        U256::ONE,
-                        parent_base_fee_per_gas * U256::from(gas_used_delta)
-                            / U256::from(parent_gas_target)
-                            / U256::from(param::BASE_FEE_MAX_CHANGE_DENOMINATOR),
-                    );
-                    return Some(parent_base_fee_per_gas + base_fee_per_gas_delta);
-                } else {
-                    let gas_used_delta = parent_gas_target - parent.gas_used;
-                    let base_fee_per_gas_delta = parent_base_fee_per_gas
-                        * U256::from(gas_used_delta)
-                        / U256::from(parent_gas_target)
-                        / U256::from(param::BASE_FEE_MAX_CHANGE_DENOMINATOR);
+        calc_base_fee_per_gas(self.eip1559_block, header.number, parent)
+    }
 
-                    return Some(parent_base_fee_per_gas.saturating_sub(base_fee_per_gas_delta));
-                }
-            }
+    // https://eips.ethereum.org/EIPS/eip-4844
+    pub fn expected_excess_blob_gas(
+        &self,
+        header: &BlockHeader,
+        parent: &BlockHeader,
+    ) -> Option<u64> {
+        if !switch_is_active_at(self.cancun_time, header.timestamp) {
+            return None;
         }
 
-        None
+        // Fork block's parent has no blob gas fields, both are treated as zero.
+        let parent_excess_blob_gas = parent.excess_blob_gas.unwrap_or(0);
+        let parent_blob_gas_used = parent.blob_gas_used.unwrap_or(0);
+
+        Some(
+            (parent_excess_blob_gas + parent_blob_gas_used)
+                .saturating_sub(param::TARGET_BLOB_GAS_PER_BLOCK),
+        )
     }
 
     pub fn pre_validate_block(&self, block: &Block) -> Result<(), DuoError> {
@@ -211,14 +240,117 @@ impl ConsensusEngineBase {
             .into());
         }
 
+        let expected_withdrawals_root = block.withdrawals.as_deref().map(root_hash);
+        if block.header.withdrawals_root != expected_withdrawals_root {
+            return Err(ValidationError::WrongWithdrawalsRoot {
+                expected: expected_withdrawals_root,
+                got: block.header.withdrawals_root,
+            }
+            .into());
+        }
+
+        let cancun = switch_is_active_at(self.cancun_time, block.header.timestamp);
+        let blob_base_fee = calc_blob_base_fee(block.header.excess_blob_gas.unwrap_or(0));
+        let mut block_blob_gas = 0;
         for txn in &block.transactions {
             pre_validate_transaction(txn, self.chain_id, block.header.base_fee_per_gas)?;
+
+            // https://eips.ethereum.org/EIPS/eip-4844
+            if txn.tx_type() == TxType::EIP4844 {
+                if !cancun {
+                    return Err(ValidationError::UnsupportedTransactionType.into());
+                }
+                if txn.max_fee_per_blob_gas() < blob_base_fee {
+                    return Err(ValidationError::MaxFeePerBlobGasLessThanBlobBase.into());
+                }
+                block_blob_gas += blob_gas(txn);
+            }
+        }
+
+        let expected_blob_gas_used = cancun.then_some(block_blob_gas);
+        if block.header.blob_gas_used != expected_blob_gas_used {
+            return Err(ValidationError::WrongBlobGasUsed {
+                expected: expected_blob_gas_used,
+                got: block.header.blob_gas_used,
+            }
+            .into());
         }
 
         Ok(())
     }
 }
 
+/// Blob gas consumed by the transaction, see [EIP-4844](https://eips.ethereum.org/EIPS/eip-4844).
+pub fn blob_gas(txn: &Message) -> u64 {
+    txn.blob_versioned_hashes().len() as u64 * param::GAS_PER_BLOB
+}
+
+/// Base fee of block `number` following `parent`, if EIP-1559 is active by then.
+pub fn calc_base_fee_per_gas(
+    eip1559_block: Option<BlockNumber>,
+    number: BlockNumber,
+    parent: &BlockHeader,
+) -> Option<U256> {
+    // https://eips.ethereum.org/EIPS/eip-1559
+    if let Some(fork_block) = eip1559_block {
+        if number >= fork_block {
+            if number == fork_block {
+                return Some(param::INITIAL_BASE_FEE.into());
+            }
+
+            let parent_gas_target = parent.gas_limit / param::ELASTICITY_MULTIPLIER;
+
+            let parent_base_fee_per_gas = parent.base_fee_per_gas.unwrap();
+
+            if parent.gas_used == parent_gas_target {
+                return Some(parent_base_fee_per_gas);
+            }
+
+            if parent.gas_used > parent_gas_target {
+                let gas_used_delta = parent.gas_used - parent_gas_target;
+                let base_fee_per_gas_delta = std::cmp::max(
+                    U256::ONE,
+                    parent_base_fee_per_gas * U256::from(gas_used_delta)
+                        / U256::from(parent_gas_target)
+                        / U256::from(param::BASE_FEE_MAX_CHANGE_DENOMINATOR),
+                );
+                return Some(parent_base_fee_per_gas + base_fee_per_gas_delta);
+            } else {
+                let gas_used_delta = parent_gas_target - parent.gas_used;
+                let base_fee_per_gas_delta = parent_base_fee_per_gas * U256::from(gas_used_delta)
+                    / U256::from(parent_gas_target)
+                    / U256::from(param::BASE_FEE_MAX_CHANGE_DENOMINATOR);
+
+                return Some(parent_base_fee_per_gas.saturating_sub(base_fee_per_gas_delta));
+            }
+        }
+    }
+
+    None
+}
+
+/// Price of a unit of blob gas given block's excess blob gas.
+pub fn calc_blob_base_fee(excess_blob_gas: u64) -> U256 {
+    fake_exponential(
+        param::MIN_BLOB_BASE_FEE.into(),
+        excess_blob_gas.into(),
+        param::BLOB_BASE_FEE_UPDATE_FRACTION.into(),
+    )
+}
+
+// Approximates factor * e ** (numerator / denominator) using Taylor expansion.
+fn fake_exponential(factor: U256, numerator: U256, denominator: U256) -> U256 {
+    let mut i = U256::ONE;
+    let mut output = U256::ZERO;
+    let mut numerator_accum = factor * denominator;
+    while numerator_accum > 0 {
+        output += numerator_accum;
+        numerator_accum = numerator_accum * numerator / (denominator * i);
+        i += U256::ONE;
+    }
+    output / denominator
+}
+
 #[derive(Debug)]
 pub struct BlockRewardSchedule(pub BTreeMap<BlockNumber, U256>);
 
@@ -297,6 +429,55 @@ mod tests {
         }
     }
 
+    #[test]
+    fn blob_base_fee() {
+        for (excess_blob_gas, expected) in [
+            (0, 1),
+            (2_314_057, 1),
+            (2_314_058, 2),
+            (10 * 1024 * 1024, 23),
+        ] {
+            assert_eq!(calc_blob_base_fee(excess_blob_gas), expected);
+        }
+    }
+
+    #[test]
+    fn excess_blob_gas() {
+        let base = ConsensusEngineBase::new(ChainId(1), None, Some(0), Some(0), None);
+
+        for (parent_excess, parent_used, expected) in [
+            (None, None, 0),
+            (Some(0), Some(0), 0),
+            (Some(0), Some(param::TARGET_BLOB_GAS_PER_BLOCK), 0),
+            (
+                Some(0),
+                Some(param::MAX_BLOB_GAS_PER_BLOCK),
+                param::GAS_PER_BLOB * 3,
+            ),
+            (
+                Some(param::GAS_PER_BLOB * 3),
+                Some(param::GAS_PER_BLOB),
+                param::GAS_PER_BLOB,
+            ),
+        ] {
+            let parent = BlockHeader {
+                excess_blob_gas: parent_excess,
+                blob_gas_used: parent_used,
+                ..BlockHeader::empty()
+            };
+            assert_eq!(
+                base.expected_excess_blob_gas(&BlockHeader::empty(), &parent),
+                Some(expected)
+            );
+        }
+
+        let pre_cancun = ConsensusEngineBase::new(ChainId(1), None, Some(0), None, None);
+        assert_eq!(
+            pre_cancun.expected_excess_blob_gas(&BlockHeader::empty(), &BlockHeader::empty()),
+            None
+        );
+    }
+
     #[test]
     fn block_reward() {
         let schedule = BlockRewardSchedule(
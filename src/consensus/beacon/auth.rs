@@ -0,0 +1,338 @@
+use anyhow::{bail, format_err, Context};
+use data_encoding::BASE64URL_NOPAD;
+use hmac::{Hmac, Mac};
+use hyper::{
+    header::{AUTHORIZATION, CONTENT_TYPE},
+    service::{make_service_fn, service_fn},
+    Body, HeaderMap, Method, Request, Response, Server, StatusCode,
+};
+use jsonrpsee::core::server::rpc_module::Methods;
+use serde::Deserialize;
+use serde_json::Value;
+use sha2::Sha256;
+use std::{
+    convert::Infallible,
+    fmt::Debug,
+    io::ErrorKind,
+    net::SocketAddr,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tracing::*;
+
+/// Maximum allowed difference between `iat` claim and local time, in seconds.
+const IAT_LEEWAY: u64 = 60;
+
+/// HS256 secret shared with the consensus client.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct JwtSecret([u8; 32]);
+
+impl Debug for JwtSecret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("JwtSecret(<redacted>)")
+    }
+}
+
+impl JwtSecret {
+    pub fn random() -> Self {
+        Self(rand::random())
+    }
+
+    pub fn from_hex(s: &str) -> anyhow::Result<Self> {
+        let s = s.trim();
+        let s = s.strip_prefix("0x").unwrap_or(s);
+
+        Ok(Self(hex::decode(s)?.try_into().map_err(|_| {
+            format_err!("JWT secret must be exactly 32 bytes long")
+        })?))
+    }
+
+    /// Reads hex-encoded secret from file, generating and persisting a new one if the file does not exist.
+    pub fn load_or_generate(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        match std::fs::read_to_string(path) {
+            Ok(s) => Self::from_hex(&s)
+                .with_context(|| format!("failed to parse JWT secret at {}", path.display())),
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                let secret = Self::random();
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                std::fs::write(path, hex::encode(secret.0))?;
+                info!("Generated new JWT secret at {}", path.display());
+
+                Ok(secret)
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn mac(&self) -> Hmac<Sha256> {
+        Hmac::new_from_slice(&self.0).unwrap()
+    }
+
+    /// Validates HS256 signature of the token and that its `iat` claim is close enough to `now`.
+    pub fn validate(&self, token: &str, now: u64) -> anyhow::Result<()> {
+        #[derive(Deserialize)]
+        struct Header {
+            alg: String,
+        }
+
+        #[derive(Deserialize)]
+        struct Claims {
+            iat: u64,
+        }
+
+        fn decode(part: &str) -> anyhow::Result<Vec<u8>> {
+            Ok(BASE64URL_NOPAD.decode(part.trim_end_matches('=').as_bytes())?)
+        }
+
+        let mut parts = token.split('.');
+        let (Some(header), Some(claims), Some(signature), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            bail!("malformed token");
+        };
+
+        let Header { alg } = serde_json::from_slice(&decode(header)?)?;
+        if alg != "HS256" {
+            bail!("unsupported algorithm {alg}");
+        }
+
+        let mut mac = self.mac();
+        mac.update(header.as_bytes());
+        mac.update(b".");
+        mac.update(claims.as_bytes());
+        mac.verify_slice(&decode(signature)?)
+            .map_err(|_| format_err!("invalid signature"))?;
+
+        let Claims { iat } = serde_json::from_slice(&decode(claims)?)?;
+        if now.abs_diff(iat) > IAT_LEEWAY {
+            bail!("stale token: issued at {iat}, now {now}");
+        }
+
+        Ok(())
+    }
+}
+
+fn authorize(secret: &JwtSecret, headers: &HeaderMap) -> anyhow::Result<()> {
+    let token = headers
+        .get(AUTHORIZATION)
+        .ok_or_else(|| format_err!("missing authorization header"))?
+        .to_str()?
+        .strip_prefix("Bearer ")
+        .ok_or_else(|| format_err!("expected bearer token"))?;
+
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+
+    secret.validate(token, now)
+}
+
+const PARSE_ERROR: &str =
+    r#"{"jsonrpc":"2.0","error":{"code":-32700,"message":"Parse error"},"id":null}"#;
+const INVALID_REQUEST: &str =
+    r#"{"jsonrpc":"2.0","error":{"code":-32600,"message":"Invalid request"},"id":null}"#;
+
+async fn call(methods: &Methods, request: &Value) -> String {
+    match methods.raw_json_request(&request.to_string()).await {
+        Ok((response, _)) => response,
+        Err(_) => INVALID_REQUEST.to_string(),
+    }
+}
+
+/// Executes a single call or a batch of them against `methods`.
+async fn dispatch(methods: &Methods, body: &[u8]) -> String {
+    match serde_json::from_slice::<Value>(body) {
+        Ok(Value::Array(requests)) if !requests.is_empty() => {
+            let mut responses = Vec::with_capacity(requests.len());
+            for request in &requests {
+                responses.push(call(methods, request).await);
+            }
+            format!("[{}]", responses.join(","))
+        }
+        Ok(Value::Array(_)) => INVALID_REQUEST.to_string(),
+        Ok(request) => call(methods, &request).await,
+        Err(_) => PARSE_ERROR.to_string(),
+    }
+}
+
+fn error_response(status: StatusCode, body: impl Into<Body>) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .body(body.into())
+        .unwrap()
+}
+
+async fn handle(
+    methods: Methods,
+    secret: JwtSecret,
+    req: Request<Body>,
+) -> Result<Response<Body>, Infallible> {
+    if let Err(e) = authorize(&secret, req.headers()) {
+        debug!("Rejected Engine API request: {e}");
+        return Ok(error_response(StatusCode::UNAUTHORIZED, e.to_string()));
+    }
+
+    if req.method() != Method::POST {
+        return Ok(error_response(
+            StatusCode::METHOD_NOT_ALLOWED,
+            "only POST requests are accepted",
+        ));
+    }
+
+    let body = match hyper::body::to_bytes(req.into_body()).await {
+        Ok(body) => body,
+        Err(e) => return Ok(error_response(StatusCode::BAD_REQUEST, e.to_string())),
+    };
+
+    Ok(Response::builder()
+        .header(CONTENT_TYPE, "application/json")
+        .body(Body::from(dispatch(&methods, &body).await))
+        .unwrap())
+}
+
+/// Serves JSON-RPC `methods` over HTTP on `listen_address`, only to requests carrying a valid JWT.
+pub async fn serve_authenticated(
+    listen_address: SocketAddr,
+    methods: Methods,
+    secret: JwtSecret,
+) -> anyhow::Result<()> {
+    let make_service = make_service_fn(move |_| {
+        let methods = methods.clone();
+        async move { Ok::<_, Infallible>(service_fn(move |req| handle(methods.clone(), secret, req))) }
+    });
+
+    Server::try_bind(&listen_address)?
+        .serve(make_service)
+        .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token(secret: &JwtSecret, header: &str, iat: u64) -> String {
+        let header = BASE64URL_NOPAD.encode(header.as_bytes());
+        let claims = BASE64URL_NOPAD.encode(format!(r#"{{"iat":{iat}}}"#).as_bytes());
+
+        let mut mac = secret.mac();
+        mac.update(format!("{header}.{claims}").as_bytes());
+        let signature = BASE64URL_NOPAD.encode(&mac.finalize().into_bytes());
+
+        format!("{header}.{claims}.{signature}")
+    }
+
+    #[test]
+    fn jwt() {
+        const HEADER: &str = r#"{"alg":"HS256","typ":"JWT"}"#;
+
+        let secret = JwtSecret::from_hex(
+            "0x7365637265747365637265747365637265747365637265747365637265747365\n",
+        )
+        .unwrap();
+        let now = 1_700_000_000;
+
+        secret.validate(&token(&secret, HEADER, now), now).unwrap();
+        secret
+            .validate(&token(&secret, HEADER, now - 60), now)
+            .unwrap();
+        secret
+            .validate(&token(&secret, HEADER, now + 60), now)
+            .unwrap();
+
+        assert!(secret
+            .validate(&token(&secret, HEADER, now - 61), now)
+            .is_err());
+        assert!(secret
+            .validate(&token(&secret, HEADER, now + 61), now)
+            .is_err());
+        assert!(secret
+            .validate(&token(&JwtSecret::random(), HEADER, now), now)
+            .is_err());
+        assert!(secret
+            .validate(&token(&secret, r#"{"alg":"none"}"#, now), now)
+            .is_err());
+        assert!(secret.validate("garbage", now).is_err());
+
+        assert!(JwtSecret::from_hex("0xdeadbeef").is_err());
+    }
+
+    #[tokio::test]
+    async fn authenticated_dispatch() {
+        let secret = JwtSecret::random();
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+
+        let mut module = jsonrpsee::RpcModule::new(());
+        module
+            .register_method("test_ping", |_, _| Ok("pong"))
+            .unwrap();
+        let methods = Methods::from(module);
+
+        let request = |token: Option<String>, body: &'static str| {
+            let mut builder = Request::builder().method(Method::POST);
+            if let Some(token) = token {
+                builder = builder.header(AUTHORIZATION, format!("Bearer {token}"));
+            }
+            builder.body(Body::from(body)).unwrap()
+        };
+        let body = |response: Response<Body>| async move {
+            String::from_utf8(
+                hyper::body::to_bytes(response.into_body())
+                    .await
+                    .unwrap()
+                    .to_vec(),
+            )
+            .unwrap()
+        };
+        let ping = r#"{"jsonrpc":"2.0","id":1,"method":"test_ping","params":[]}"#;
+
+        for token in [
+            None,
+            Some(token(&JwtSecret::random(), r#"{"alg":"HS256"}"#, now)),
+        ] {
+            let response = handle(methods.clone(), secret, request(token, ping))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        }
+
+        let valid_token = || Some(token(&secret, r#"{"alg":"HS256"}"#, now));
+
+        let response = handle(methods.clone(), secret, request(valid_token(), ping))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            body(response).await,
+            r#"{"jsonrpc":"2.0","result":"pong","id":1}"#
+        );
+
+        let batch = r#"[{"jsonrpc":"2.0","id":1,"method":"test_ping","params":[]},{"jsonrpc":"2.0","id":2,"method":"test_ping","params":[]}]"#;
+        let response = handle(methods.clone(), secret, request(valid_token(), batch))
+            .await
+            .unwrap();
+        assert_eq!(
+            body(response).await,
+            r#"[{"jsonrpc":"2.0","result":"pong","id":1},{"jsonrpc":"2.0","result":"pong","id":2}]"#
+        );
+
+        let response = handle(methods, secret, request(valid_token(), "{"))
+            .await
+            .unwrap();
+        assert_eq!(body(response).await, PARSE_ERROR);
+    }
+
+    #[test]
+    fn persist_secret() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("jwt.hex");
+
+        let secret = JwtSecret::load_or_generate(&path).unwrap();
+        assert_eq!(JwtSecret::load_or_generate(&path).unwrap(), secret);
+    }
+}
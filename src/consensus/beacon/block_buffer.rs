@@ -0,0 +1,144 @@
+use crate::models::{Block, BlockNumber, H256};
+use hashbrown::HashMap;
+use hashlink::LruCache;
+use std::collections::BTreeSet;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BufferedBlockStatus {
+    /// Block was executed on top of its parent state and resulted in its state root.
+    Valid,
+    /// Block is known, but its parent state was not available for execution.
+    Accepted,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BufferedBlock {
+    pub block: Block,
+    pub status: BufferedBlockStatus,
+}
+
+/// Blocks received from the consensus layer that are not yet part of the chain in the database.
+///
+/// Keeps side chains around so that a later fork choice update can reorg onto them,
+/// and remembers invalid blocks so that their descendants are rejected right away.
+#[derive(Debug)]
+pub struct BlockBuffer {
+    blocks: HashMap<H256, BufferedBlock>,
+    by_number: BTreeSet<(BlockNumber, H256)>,
+    invalid: LruCache<H256, H256>,
+}
+
+impl Default for BlockBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BlockBuffer {
+    const BLOCKS_CAP: usize = 1 << 10;
+    const INVALID_CAP: usize = 1 << 10;
+
+    pub fn new() -> Self {
+        Self {
+            blocks: Default::default(),
+            by_number: Default::default(),
+            invalid: LruCache::new(Self::INVALID_CAP),
+        }
+    }
+
+    #[inline]
+    pub fn get(&self, hash: H256) -> Option<&BufferedBlock> {
+        self.blocks.get(&hash)
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.blocks.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    /// Inserts block, evicting the lowest blocks if over capacity.
+    pub fn insert(&mut self, hash: H256, block: Block, status: BufferedBlockStatus) {
+        self.by_number.insert((block.header.number, hash));
+        self.blocks.insert(hash, BufferedBlock { block, status });
+
+        while self.blocks.len() > Self::BLOCKS_CAP {
+            let (_, hash) = self.by_number.pop_first().unwrap();
+            self.blocks.remove(&hash);
+        }
+    }
+
+    /// Forgets the block and remembers it as invalid, along with its latest valid ancestor.
+    pub fn mark_invalid(&mut self, hash: H256, latest_valid_hash: H256) {
+        if let Some(BufferedBlock { block, .. }) = self.blocks.remove(&hash) {
+            self.by_number.remove(&(block.header.number, hash));
+        }
+        self.invalid.insert(hash, latest_valid_hash);
+    }
+
+    /// Returns latest valid ancestor of the block if the block is known to be invalid.
+    #[inline]
+    pub fn invalid_ancestor(&self, hash: H256) -> Option<H256> {
+        self.invalid.peek(&hash).copied()
+    }
+
+    /// Drops all blocks at or below the given height, e. g. once it is finalized.
+    pub fn remove_up_to(&mut self, number: BlockNumber) {
+        while let Some(&(n, hash)) = self.by_number.first() {
+            if n > number {
+                break;
+            }
+
+            self.by_number.pop_first();
+            self.blocks.remove(&hash);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::BlockHeader;
+
+    fn block(number: u64) -> (H256, Block) {
+        let block = Block {
+            header: BlockHeader {
+                number: number.into(),
+                ..BlockHeader::empty()
+            },
+            transactions: vec![],
+            ommers: vec![],
+            withdrawals: None,
+        };
+
+        (block.header.hash(), block)
+    }
+
+    #[test]
+    fn buffer() {
+        let mut buffer = BlockBuffer::new();
+
+        for i in 0..=BlockBuffer::BLOCKS_CAP as u64 {
+            let (hash, block) = block(i);
+            buffer.insert(hash, block, BufferedBlockStatus::Valid);
+        }
+
+        assert_eq!(buffer.len(), BlockBuffer::BLOCKS_CAP);
+        assert!(buffer.get(block(0).0).is_none());
+        assert!(buffer.get(block(1).0).is_some());
+
+        let (hash, _) = block(5);
+        buffer.mark_invalid(hash, block(4).0);
+        assert!(buffer.get(hash).is_none());
+        assert_eq!(buffer.invalid_ancestor(hash), Some(block(4).0));
+
+        buffer.remove_up_to(BlockNumber(10));
+        assert_eq!(buffer.len(), BlockBuffer::BLOCKS_CAP - 10);
+        assert!(buffer.get(block(10).0).is_none());
+        assert!(buffer.get(block(11).0).is_some());
+    }
+}
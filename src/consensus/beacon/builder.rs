@@ -0,0 +1,342 @@
+use super::{execute_payload_block, BlockBuffer, MAX_SIDE_CHAIN_LENGTH};
+use crate::{
+    accessors::chain,
+    consensus::*,
+    crypto::keccak256,
+    execution::{
+        analysis_cache::AnalysisCache,
+        processor::{gas_after_intrinsic, ExecutionProcessor, TransactionValidationError},
+        tracer::NoopTracer,
+    },
+    kv::{mdbx::*, MdbxWithDirHandle},
+    models::{Block, BlockHeader, *},
+    stagedsync::stages::{EXECUTION, INTERMEDIATE_HASHES},
+    trie::{root_hash, state_root_with_overlay},
+    Buffer,
+};
+use anyhow::{bail, format_err};
+use bytes::Bytes;
+use ethereum_jsonrpc::ExecutionPayload;
+use parking_lot::Mutex;
+use std::fmt::Debug;
+
+/// Source of transactions for locally built blocks.
+pub trait TransactionSource: Debug + Send + Sync {
+    /// Pending transactions along with their senders, in the order they should be included.
+    fn best_transactions(&self) -> Vec<(MessageWithSignature, Address)>;
+}
+
+impl TransactionSource for () {
+    fn best_transactions(&self) -> Vec<(MessageWithSignature, Address)> {
+        vec![]
+    }
+}
+
+/// Attributes of the payload to build, as passed to any version of `engine_forkchoiceUpdated`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BuildAttributes {
+    pub timestamp: u64,
+    pub prev_randao: H256,
+    pub suggested_fee_recipient: Address,
+    /// Set since Shanghai.
+    pub withdrawals: Option<Vec<Withdrawal>>,
+    /// Set since Cancun.
+    pub parent_beacon_block_root: Option<H256>,
+}
+
+/// Built payload along with the parts `ExecutionPayloadV1` has no place for.
+#[derive(Clone, Debug)]
+pub struct BuiltPayload {
+    pub payload: ExecutionPayload,
+    pub withdrawals: Option<Vec<Withdrawal>>,
+    /// Set since Cancun, along with `excess_blob_gas`.
+    pub blob_gas_used: Option<u64>,
+    pub excess_blob_gas: Option<u64>,
+    /// Fees paid to fee recipient.
+    pub block_value: U256,
+}
+
+/// Derives payload id from the parent and attributes, so that repeated requests map to the same payload.
+pub fn payload_id(parent_hash: H256, attributes: &BuildAttributes) -> H64 {
+    let mut data = [
+        parent_hash.as_bytes(),
+        &attributes.timestamp.to_be_bytes(),
+        attributes.prev_randao.as_bytes(),
+        attributes.suggested_fee_recipient.as_bytes(),
+    ]
+    .concat();
+    if let Some(withdrawals) = &attributes.withdrawals {
+        data.extend_from_slice(&(withdrawals.len() as u64).to_be_bytes());
+        for withdrawal in withdrawals {
+            fastrlp::Encodable::encode(withdrawal, &mut data);
+        }
+    }
+    if let Some(parent_beacon_block_root) = attributes.parent_beacon_block_root {
+        data.extend_from_slice(parent_beacon_block_root.as_bytes());
+    }
+
+    H64::from_slice(&keccak256(data)[..8])
+}
+
+/// Builds a block on top of `parent_hash` out of transactions from `source`.
+///
+/// Parent must either be the head of executed chain in the database, or a buffered block
+/// descending from it, since state root is computed from hashed state and intermediate hashes.
+pub fn build_payload<E: EnvironmentKind>(
+    db: &MdbxWithDirHandle<E>,
+    block_buffer: &Mutex<BlockBuffer>,
+    source: &dyn TransactionSource,
+    parent_hash: H256,
+    attributes: &BuildAttributes,
+) -> anyhow::Result<BuiltPayload> {
+    let txn = db.begin()?;
+    let chain_spec = chain::chain_config::read(&txn)?
+        .ok_or_else(|| format_err!("chain specification not found"))?;
+
+    let executed_to = EXECUTION.get_progress(&txn)?.unwrap_or_default();
+    if INTERMEDIATE_HASHES.get_progress(&txn)? != Some(executed_to) {
+        bail!("intermediate hashes are not up to date with executed state");
+    }
+    let head_hash = chain::canonical_hash::read(&txn, executed_to)?
+        .ok_or_else(|| format_err!("no canonical hash for block #{executed_to}"))?;
+
+    // Collect buffered blocks between the database head and the parent.
+    let mut side_chain = vec![];
+    let mut hash = parent_hash;
+    while hash != head_hash {
+        if side_chain.len() >= MAX_SIDE_CHAIN_LENGTH {
+            bail!("parent {parent_hash} is too far from executed chain head");
+        }
+
+        let block = block_buffer
+            .lock()
+            .get(hash)
+            .map(|buffered| buffered.block.clone())
+            .ok_or_else(|| {
+                format_err!("parent {parent_hash} does not descend from executed chain head")
+            })?;
+        hash = block.header.parent_hash;
+        side_chain.push(block);
+    }
+    side_chain.reverse();
+
+    let mut parent = chain::header::read(&txn, head_hash, executed_to)?
+        .ok_or_else(|| format_err!("header not found for block #{executed_to}/{head_hash}"))?;
+
+    let mut engine = engine_factory(None, chain_spec.clone())?;
+    engine.set_state(ConsensusState::recover(&txn, &chain_spec, executed_to + 1)?);
+    let mut state = Buffer::new(&txn, None);
+    let mut analysis_cache = AnalysisCache::global();
+
+    for block in side_chain {
+        execute_payload_block(
+            &txn,
+            &mut *engine,
+            &mut state,
+            &mut analysis_cache,
+            &chain_spec,
+            &parent,
+            &block,
+        )?;
+        parent = block.header;
+    }
+
+    let timestamp = attributes.timestamp;
+    if timestamp <= parent.timestamp {
+        bail!(
+            "payload timestamp {timestamp} must be greater than parent's {}",
+            parent.timestamp
+        );
+    }
+
+    let mut partial_header = PartialHeader {
+        parent_hash,
+        beneficiary: attributes.suggested_fee_recipient,
+        state_root: H256::zero(),
+        receipts_root: EMPTY_ROOT,
+        logs_bloom: Bloom::zero(),
+        difficulty: U256::ZERO,
+        number: parent.number + 1,
+        gas_limit: parent.gas_limit,
+        gas_used: 0,
+        timestamp,
+        extra_data: Bytes::new(),
+        mix_hash: attributes.prev_randao,
+        nonce: H64::zero(),
+        base_fee_per_gas: None,
+    };
+    let base = ConsensusEngineBase::new(
+        chain_spec.params.chain_id,
+        chain_spec.consensus.eip1559_block,
+        chain_spec.upgrades.shanghai_time,
+        chain_spec.upgrades.cancun_time,
+        None,
+    );
+    partial_header.base_fee_per_gas = base.expected_base_fee_per_gas(
+        &BlockHeader::new(partial_header.clone(), EMPTY_LIST_HASH, EMPTY_ROOT),
+        &parent,
+    );
+    let excess_blob_gas = base.expected_excess_blob_gas(
+        &BlockHeader::new(partial_header.clone(), EMPTY_LIST_HASH, EMPTY_ROOT),
+        &parent,
+    );
+    // Blob transactions are never included into locally built blocks.
+    let blob_gas_used = excess_blob_gas.map(|_| 0);
+    let parent_beacon_block_root = match (
+        excess_blob_gas.is_some(),
+        attributes.parent_beacon_block_root,
+    ) {
+        (true, Some(root)) => Some(root),
+        (false, None) => None,
+        (true, None) => bail!("parent beacon block root is required since Cancun"),
+        (false, Some(_)) => bail!("parent beacon block root is not allowed before Cancun"),
+    };
+    let with_cancun_fields = |mut header: BlockHeader| {
+        header.blob_gas_used = blob_gas_used;
+        header.excess_blob_gas = excess_blob_gas;
+        header.parent_beacon_block_root = parent_beacon_block_root;
+        header
+    };
+
+    let block_spec = chain_spec.collect_block_spec(partial_header.number, partial_header.timestamp);
+
+    // Pick transactions that fit, without touching the buffered state.
+    let (transactions, receipts) = {
+        let header = with_cancun_fields(BlockHeader::new(
+            partial_header.clone(),
+            EMPTY_LIST_HASH,
+            EMPTY_ROOT,
+        ));
+        let body = BlockBodyWithSenders {
+            transactions: vec![],
+            ommers: vec![],
+            withdrawals: None,
+        };
+        let mut tracer = NoopTracer;
+        let mut processor = ExecutionProcessor::new(
+            &mut state,
+            &mut tracer,
+            &mut analysis_cache,
+            &mut *engine,
+            &header,
+            &body,
+            &block_spec,
+        );
+
+        processor.execute_pre_block_system_calls()?;
+
+        let mut transactions = vec![];
+        let mut receipts = vec![];
+        for (transaction, sender) in source.best_transactions() {
+            let message = &transaction.message;
+
+            if message.tx_type() == TxType::EIP4844
+                || pre_validate_transaction(
+                    message,
+                    chain_spec.params.chain_id,
+                    header.base_fee_per_gas,
+                )
+                .is_err()
+                || gas_after_intrinsic(message, block_spec.revision).is_err()
+            {
+                continue;
+            }
+
+            match processor.validate_transaction(message, sender) {
+                Ok(()) => {}
+                Err(TransactionValidationError::Validation(_)) => continue,
+                Err(TransactionValidationError::Internal(e)) => return Err(e),
+            }
+
+            receipts.push(processor.execute_transaction(message, sender)?);
+            transactions.push(transaction);
+        }
+
+        (transactions, receipts)
+    };
+
+    let withdrawals = match (
+        block_spec.revision >= Revision::Shanghai,
+        &attributes.withdrawals,
+    ) {
+        (true, Some(withdrawals)) => Some(withdrawals.clone()),
+        (false, None) => None,
+        (true, None) => bail!("withdrawals are required since Shanghai"),
+        (false, Some(_)) => bail!("withdrawals are not allowed before Shanghai"),
+    };
+
+    let base_fee_per_gas = partial_header.base_fee_per_gas.unwrap_or(U256::ZERO);
+    let block_value = transactions
+        .iter()
+        .zip(&receipts)
+        .scan(0, |cumulative_gas_used, (transaction, receipt)| {
+            let gas_used = receipt.cumulative_gas_used - *cumulative_gas_used;
+            *cumulative_gas_used = receipt.cumulative_gas_used;
+            Some(
+                U256::from(gas_used)
+                    * transaction
+                        .priority_fee_per_gas(base_fee_per_gas)
+                        .unwrap_or(U256::ZERO),
+            )
+        })
+        .fold(U256::ZERO, |total, fee| total + fee);
+
+    partial_header.gas_used = receipts.last().map(|r| r.cumulative_gas_used).unwrap_or(0);
+    partial_header.receipts_root = root_hash(&receipts);
+    partial_header.logs_bloom = receipts
+        .iter()
+        .fold(Bloom::zero(), |bloom, r| bloom | r.bloom);
+
+    // Execute the assembled block for real and compute resulting state root.
+    execute_payload_block(
+        &txn,
+        &mut *engine,
+        &mut state,
+        &mut analysis_cache,
+        &chain_spec,
+        &parent,
+        &{
+            let mut block = Block::new(
+                partial_header.clone(),
+                transactions.clone(),
+                vec![],
+                withdrawals.clone(),
+            );
+            block.header = with_cancun_fields(block.header);
+            block
+        },
+    )?;
+    partial_header.state_root = state_root_with_overlay(&txn, &state.hashed_state_overlay())?;
+
+    let mut block = Block::new(partial_header, transactions, vec![], withdrawals);
+    block.header = with_cancun_fields(block.header);
+
+    let payload = ExecutionPayload {
+        parent_hash: block.header.parent_hash,
+        fee_recipient: block.header.beneficiary,
+        state_root: block.header.state_root,
+        receipts_root: block.header.receipts_root,
+        logs_bloom: block.header.logs_bloom,
+        prev_randao: block.header.mix_hash,
+        block_number: block.header.number.0.into(),
+        gas_limit: block.header.gas_limit.into(),
+        gas_used: block.header.gas_used.into(),
+        timestamp: block.header.timestamp.into(),
+        extra_data: block.header.extra_data.clone(),
+        base_fee_per_gas: block.header.base_fee_per_gas.unwrap_or(U256::ZERO),
+        block_hash: block.header.hash(),
+        transactions: block
+            .transactions
+            .iter()
+            .map(|transaction| transaction.encode_envelope())
+            .collect(),
+    };
+
+    Ok(BuiltPayload {
+        payload,
+        withdrawals: block.withdrawals,
+        blob_gas_used: block.header.blob_gas_used,
+        excess_blob_gas: block.header.excess_blob_gas,
+        block_value,
+    })
+}
@@ -0,0 +1,1169 @@
+mod auth;
+mod block_buffer;
+mod builder;
+
+pub use self::{auth::*, block_buffer::*, builder::*};
+use super::*;
+use crate::{
+    accessors::chain,
+    execution::{analysis_cache::AnalysisCache, processor::ExecutionProcessor, tracer::NoopTracer},
+    kv::{mdbx::*, MdbxWithDirHandle},
+    models::{Block, BlockHeader, *},
+    rpc::{
+        eth::{EthApiServerImpl, DEFAULT_MAX_LOGS_BLOCK_RANGE},
+        helpers,
+        net::NetApiServerImpl,
+        web3::Web3ApiServerImpl,
+    },
+    stagedsync::stages::{EXECUTION, INTERMEDIATE_HASHES},
+    trie::{historical_overlay, state_root_with_overlay, HashedStateOverlay},
+    Buffer, TaskGuard,
+};
+use anyhow::{bail, format_err};
+use async_trait::async_trait;
+use ethereum_jsonrpc::*;
+use hashlink::LruCache;
+use jsonrpsee::{
+    core::{server::rpc_module::Methods, RpcResult},
+    types::{error::CallError, ErrorObject},
+    RpcModule,
+};
+use serde::{Deserialize, Serialize};
+use std::{future::pending, net::SocketAddr};
+use tracing::*;
+
+/// Maximum number of buffered ancestors re-executed to validate a side chain payload.
+const MAX_SIDE_CHAIN_LENGTH: usize = 128;
+
+/// Maximum number of built payloads kept for `engine_getPayload`.
+const MAX_PAYLOADS: usize = 16;
+
+const INVALID_PARAMS: i32 = -32602;
+const UNKNOWN_PAYLOAD: i32 = -38001;
+const UNSUPPORTED_FORK: i32 = -38005;
+
+fn engine_error(code: i32, message: impl Into<String>) -> jsonrpsee::core::Error {
+    CallError::Custom(ErrorObject::owned(
+        code,
+        message.into(),
+        Option::<String>::None,
+    ))
+    .into()
+}
+
+fn payload_status(status: PayloadStatusEnum, latest_valid_hash: Option<H256>) -> PayloadStatus {
+    PayloadStatus {
+        status,
+        latest_valid_hash,
+    }
+}
+
+/// Withdrawal as passed over Engine API.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct WithdrawalV1 {
+    index: ethereum_types::U64,
+    validator_index: ethereum_types::U64,
+    address: Address,
+    amount: ethereum_types::U64,
+}
+
+impl From<WithdrawalV1> for Withdrawal {
+    fn from(withdrawal: WithdrawalV1) -> Self {
+        Self {
+            index: withdrawal.index.as_u64(),
+            validator_index: withdrawal.validator_index.as_u64(),
+            address: withdrawal.address,
+            amount: withdrawal.amount.as_u64(),
+        }
+    }
+}
+
+impl From<Withdrawal> for WithdrawalV1 {
+    fn from(withdrawal: Withdrawal) -> Self {
+        Self {
+            index: withdrawal.index.into(),
+            validator_index: withdrawal.validator_index.into(),
+            address: withdrawal.address,
+            amount: withdrawal.amount.into(),
+        }
+    }
+}
+
+/// `ExecutionPayloadV1` or `ExecutionPayloadV2` of Engine API, the latter is not provided by
+/// `ethereum_jsonrpc` yet.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExecutionPayloadV2 {
+    #[serde(flatten)]
+    payload: ExecutionPayload,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    withdrawals: Option<Vec<WithdrawalV1>>,
+}
+
+/// `PayloadAttributesV1` or `PayloadAttributesV2` of Engine API.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PayloadAttributesV2 {
+    #[serde(flatten)]
+    attributes: PayloadAttributes,
+    #[serde(default)]
+    withdrawals: Option<Vec<WithdrawalV1>>,
+}
+
+impl From<PayloadAttributesV2> for BuildAttributes {
+    fn from(attributes: PayloadAttributesV2) -> Self {
+        Self {
+            timestamp: attributes.attributes.timestamp.as_u64(),
+            prev_randao: attributes.attributes.prev_randao,
+            suggested_fee_recipient: attributes.attributes.suggested_fee_recipient,
+            withdrawals: attributes
+                .withdrawals
+                .map(|withdrawals| withdrawals.into_iter().map(From::from).collect()),
+            parent_beacon_block_root: None,
+        }
+    }
+}
+
+/// `PayloadAttributesV3` of Engine API.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PayloadAttributesV3 {
+    #[serde(flatten)]
+    attributes: PayloadAttributes,
+    withdrawals: Vec<WithdrawalV1>,
+    parent_beacon_block_root: H256,
+}
+
+impl From<PayloadAttributesV3> for BuildAttributes {
+    fn from(attributes: PayloadAttributesV3) -> Self {
+        Self {
+            timestamp: attributes.attributes.timestamp.as_u64(),
+            prev_randao: attributes.attributes.prev_randao,
+            suggested_fee_recipient: attributes.attributes.suggested_fee_recipient,
+            withdrawals: Some(attributes.withdrawals.into_iter().map(From::from).collect()),
+            parent_beacon_block_root: Some(attributes.parent_beacon_block_root),
+        }
+    }
+}
+
+/// Response of `engine_getPayloadV2`.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GetPayloadV2Response {
+    execution_payload: ExecutionPayloadV2,
+    block_value: U256,
+}
+
+/// `ExecutionPayloadV3` of Engine API, not provided by `ethereum_jsonrpc` yet.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExecutionPayloadV3 {
+    #[serde(flatten)]
+    payload: ExecutionPayload,
+    withdrawals: Vec<WithdrawalV1>,
+    blob_gas_used: ethereum_types::U64,
+    excess_blob_gas: ethereum_types::U64,
+}
+
+/// `BlobsBundleV1` of Engine API.
+#[derive(Clone, Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BlobsBundleV1 {
+    commitments: Vec<types::Bytes>,
+    proofs: Vec<types::Bytes>,
+    blobs: Vec<types::Bytes>,
+}
+
+/// Response of `engine_getPayloadV3`.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GetPayloadV3Response {
+    execution_payload: ExecutionPayloadV3,
+    block_value: U256,
+    /// Always empty, since blob transactions are never included into locally built blocks.
+    blobs_bundle: BlobsBundleV1,
+    should_override_builder: bool,
+}
+
+fn payload_to_block(
+    payload: ExecutionPayload,
+    withdrawals: Option<Vec<Withdrawal>>,
+) -> anyhow::Result<Block> {
+    let transactions = payload
+        .transactions
+        .into_iter()
+        .map(|tx| MessageWithSignature::decode_envelope(&tx))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(Block::new(
+        PartialHeader {
+            parent_hash: payload.parent_hash,
+            beneficiary: payload.fee_recipient,
+            state_root: payload.state_root,
+            receipts_root: payload.receipts_root,
+            logs_bloom: payload.logs_bloom,
+            difficulty: U256::ZERO,
+            number: BlockNumber(payload.block_number.as_u64()),
+            gas_limit: payload.gas_limit.as_u64(),
+            gas_used: payload.gas_used.as_u64(),
+            timestamp: payload.timestamp.as_u64(),
+            extra_data: payload.extra_data,
+            mix_hash: payload.prev_randao,
+            nonce: H64::zero(),
+            base_fee_per_gas: Some(payload.base_fee_per_gas),
+        },
+        transactions,
+        vec![],
+        withdrawals,
+    ))
+}
+
+fn payload_v3_to_block(
+    payload: ExecutionPayloadV3,
+    expected_blob_versioned_hashes: &[H256],
+    parent_beacon_block_root: H256,
+) -> anyhow::Result<Block> {
+    let mut block = payload_to_block(
+        payload.payload,
+        Some(payload.withdrawals.into_iter().map(From::from).collect()),
+    )?;
+
+    let blob_versioned_hashes = block
+        .transactions
+        .iter()
+        .flat_map(|tx| tx.blob_versioned_hashes().iter().copied())
+        .collect::<Vec<_>>();
+    if blob_versioned_hashes != expected_blob_versioned_hashes {
+        bail!("blob versioned hashes do not match transactions");
+    }
+
+    block.header.blob_gas_used = Some(payload.blob_gas_used.as_u64());
+    block.header.excess_blob_gas = Some(payload.excess_blob_gas.as_u64());
+    block.header.parent_beacon_block_root = Some(parent_beacon_block_root);
+
+    Ok(block)
+}
+
+fn execute_payload_block<K, E>(
+    txn: &MdbxTransaction<'_, K, E>,
+    engine: &mut dyn Consensus,
+    state: &mut Buffer<'_, '_, K, E>,
+    analysis_cache: &mut AnalysisCache,
+    chain_spec: &ChainSpec,
+    parent: &BlockHeader,
+    block: &Block,
+) -> Result<(), DuoError>
+where
+    K: TransactionKind,
+    E: EnvironmentKind,
+{
+    engine.validate_block_header(&block.header, parent, false)?;
+    engine.pre_validate_block(block, txn)?;
+
+    let transactions = block
+        .transactions
+        .iter()
+        .map(|tx| {
+            Ok(MessageWithSender {
+                message: tx.message.clone(),
+                sender: tx
+                    .recover_sender()
+                    .map_err(|_| DuoError::Validation(ValidationError::MissingSender))?,
+            })
+        })
+        .collect::<Result<Vec<_>, DuoError>>()?;
+    let body = BlockBodyWithSenders {
+        transactions,
+        ommers: block.ommers.clone(),
+        withdrawals: block.withdrawals.clone(),
+    };
+
+    let block_spec = chain_spec.collect_block_spec(block.header.number, block.header.timestamp);
+    let mut tracer = NoopTracer;
+
+    ExecutionProcessor::new(
+        state,
+        &mut tracer,
+        analysis_cache,
+        engine,
+        &block.header,
+        &body,
+        &block_spec,
+    )
+    .execute_and_write_block()?;
+
+    Ok(())
+}
+
+/// Validates and executes the payload on top of its parent state, checking resulting state roots.
+///
+/// Parent state is either the executed canonical chain in the database, or a buffered side chain
+/// forking off it, which is re-executed in memory first. State roots are computed from
+/// intermediate hashes with executed changes laid over them, so while intermediate hashes lag
+/// behind executed state, payloads are only buffered and reported as syncing.
+fn process_payload<E: EnvironmentKind>(
+    db: &MdbxWithDirHandle<E>,
+    block_buffer: &Mutex<BlockBuffer>,
+    block_hash: H256,
+    block: anyhow::Result<Block>,
+) -> anyhow::Result<PayloadStatus> {
+    let block = match block {
+        Ok(block) => block,
+        Err(e) => {
+            return Ok(payload_status(
+                PayloadStatusEnum::Invalid {
+                    validation_error: format!("failed to decode payload: {e}"),
+                },
+                None,
+            ))
+        }
+    };
+
+    let computed_hash = block.header.hash();
+    if computed_hash != block_hash {
+        return Ok(payload_status(
+            PayloadStatusEnum::InvalidBlockHash {
+                validation_error: format!(
+                    "block hash mismatch: expected {block_hash}, computed {computed_hash}"
+                ),
+            },
+            None,
+        ));
+    }
+
+    {
+        let block_buffer = block_buffer.lock();
+        for hash in [block_hash, block.header.parent_hash] {
+            if let Some(latest_valid_hash) = block_buffer.invalid_ancestor(hash) {
+                return Ok(payload_status(
+                    PayloadStatusEnum::Invalid {
+                        validation_error: format!("links to previously rejected block {hash}"),
+                    },
+                    Some(latest_valid_hash),
+                ));
+            }
+        }
+
+        if let Some(BufferedBlock {
+            status: BufferedBlockStatus::Valid,
+            ..
+        }) = block_buffer.get(block_hash)
+        {
+            return Ok(payload_status(PayloadStatusEnum::Valid, Some(block_hash)));
+        }
+    }
+
+    let txn = db.begin()?;
+    let chain_spec = chain::chain_config::read(&txn)?
+        .ok_or_else(|| format_err!("chain specification not found"))?;
+    let executed_to = EXECUTION.get_progress(&txn)?.unwrap_or_default();
+    let hashed_to = INTERMEDIATE_HASHES.get_progress(&txn)?.unwrap_or_default();
+
+    let is_canonical = |hash| -> anyhow::Result<Option<BlockNumber>> {
+        if let Some(number) = chain::header_number::read(&txn, hash)? {
+            if chain::canonical_hash::read(&txn, number)? == Some(hash) {
+                return Ok(Some(number));
+            }
+        }
+
+        Ok(None)
+    };
+
+    if let Some(number) = is_canonical(block_hash)? {
+        if number <= executed_to {
+            return Ok(payload_status(PayloadStatusEnum::Valid, Some(block_hash)));
+        }
+    }
+
+    // Walk back through buffered blocks until we hit the canonical chain.
+    let mut chain = vec![block];
+    let (fork_point, fork_hash) = loop {
+        let parent_hash = chain.last().unwrap().header.parent_hash;
+
+        if let Some(number) = is_canonical(parent_hash)? {
+            if number > executed_to || hashed_to != executed_to {
+                // Parent is being synced, state or its root is not available yet.
+                let block = chain.swap_remove(0);
+                block_buffer
+                    .lock()
+                    .insert(block_hash, block, BufferedBlockStatus::Accepted);
+                return Ok(payload_status(PayloadStatusEnum::Syncing, None));
+            }
+
+            break (number, parent_hash);
+        }
+
+        let parent = block_buffer.lock().get(parent_hash).cloned();
+        match parent {
+            Some(BufferedBlock { block: parent, .. }) if chain.len() < MAX_SIDE_CHAIN_LENGTH => {
+                chain.push(parent);
+            }
+            Some(_) => {
+                let block = chain.swap_remove(0);
+                block_buffer
+                    .lock()
+                    .insert(block_hash, block, BufferedBlockStatus::Accepted);
+                return Ok(payload_status(PayloadStatusEnum::Accepted, None));
+            }
+            None => {
+                let block = chain.swap_remove(0);
+                block_buffer
+                    .lock()
+                    .insert(block_hash, block, BufferedBlockStatus::Accepted);
+                return Ok(payload_status(PayloadStatusEnum::Syncing, None));
+            }
+        }
+    };
+    chain.reverse();
+
+    block_buffer.lock().insert(
+        block_hash,
+        chain.last().unwrap().clone(),
+        BufferedBlockStatus::Accepted,
+    );
+
+    let mut parent = chain::header::read(&txn, fork_hash, fork_point)?
+        .ok_or_else(|| format_err!("header not found for block #{fork_point}/{fork_hash}"))?;
+
+    let mut engine = engine_factory(None, chain_spec.clone())?;
+    engine.set_state(ConsensusState::recover(&txn, &chain_spec, fork_point + 1)?);
+    let mut state = Buffer::new(
+        &txn,
+        if fork_point < executed_to {
+            Some(fork_point)
+        } else {
+            None
+        },
+    );
+    let mut analysis_cache = AnalysisCache::global();
+
+    // Reverts hashed state to the fork point, executed changes are laid on top of it.
+    let fork_overlay = if fork_point < executed_to {
+        historical_overlay(&txn, fork_point)?
+    } else {
+        HashedStateOverlay::default()
+    };
+
+    let mut latest_valid_hash = fork_hash;
+    for block in chain {
+        let hash = block.header.hash();
+        match execute_payload_block(
+            &txn,
+            &mut *engine,
+            &mut state,
+            &mut analysis_cache,
+            &chain_spec,
+            &parent,
+            &block,
+        ) {
+            Ok(()) => {}
+            Err(DuoError::Validation(error)) => {
+                warn!(
+                    "Rejected payload block #{}/{hash}: {error:?}",
+                    block.header.number
+                );
+
+                let mut block_buffer = block_buffer.lock();
+                block_buffer.mark_invalid(hash, latest_valid_hash);
+                block_buffer.mark_invalid(block_hash, latest_valid_hash);
+
+                return Ok(payload_status(
+                    PayloadStatusEnum::Invalid {
+                        validation_error: format!("{error:?}"),
+                    },
+                    Some(latest_valid_hash),
+                ));
+            }
+            Err(DuoError::Internal(e)) => {
+                warn!(
+                    "Failed to execute payload block #{}/{hash}: {e:?}",
+                    block.header.number
+                );
+
+                return Ok(payload_status(PayloadStatusEnum::Accepted, None));
+            }
+        }
+
+        let mut overlay = fork_overlay.clone();
+        overlay.apply(state.hashed_state_overlay());
+        let state_root = state_root_with_overlay(&txn, &overlay)?;
+        if state_root != block.header.state_root {
+            warn!(
+                "Rejected payload block #{}/{hash}: state root mismatch, expected {:?}, computed {state_root:?}",
+                block.header.number, block.header.state_root
+            );
+
+            let mut block_buffer = block_buffer.lock();
+            block_buffer.mark_invalid(hash, latest_valid_hash);
+            block_buffer.mark_invalid(block_hash, latest_valid_hash);
+
+            return Ok(payload_status(
+                PayloadStatusEnum::Invalid {
+                    validation_error: format!(
+                        "state root mismatch: expected {:?}, computed {state_root:?}",
+                        block.header.state_root
+                    ),
+                },
+                Some(latest_valid_hash),
+            ));
+        }
+
+        latest_valid_hash = hash;
+        parent = block.header.clone();
+        block_buffer
+            .lock()
+            .insert(hash, block, BufferedBlockStatus::Valid);
+    }
+
+    Ok(payload_status(PayloadStatusEnum::Valid, Some(block_hash)))
+}
+
+/// Reports whether fork choice head is a block we have validated.
+fn head_status<E: EnvironmentKind>(
+    db: &MdbxWithDirHandle<E>,
+    block_buffer: &Mutex<BlockBuffer>,
+    head: H256,
+    finalized: H256,
+) -> anyhow::Result<PayloadStatus> {
+    let mut block_buffer = block_buffer.lock();
+    if let Some(latest_valid_hash) = block_buffer.invalid_ancestor(head) {
+        return Ok(payload_status(
+            PayloadStatusEnum::Invalid {
+                validation_error: format!("block {head} was rejected"),
+            },
+            Some(latest_valid_hash),
+        ));
+    }
+
+    let txn = db.begin()?;
+
+    // Blocks below finalized one cannot become canonical anymore.
+    if !finalized.is_zero() {
+        if let Some(finalized) = chain::header_number::read(&txn, finalized)?
+            .or_else(|| block_buffer.get(finalized).map(|b| b.block.header.number))
+        {
+            block_buffer.remove_up_to(BlockNumber(finalized.0.saturating_sub(1)));
+        }
+    }
+
+    if let Some(BufferedBlock {
+        status: BufferedBlockStatus::Valid,
+        ..
+    }) = block_buffer.get(head)
+    {
+        return Ok(payload_status(PayloadStatusEnum::Valid, Some(head)));
+    }
+
+    if let Some(number) = chain::header_number::read(&txn, head)? {
+        if chain::canonical_hash::read(&txn, number)? == Some(head)
+            && number <= EXECUTION.get_progress(&txn)?.unwrap_or_default()
+        {
+            return Ok(payload_status(PayloadStatusEnum::Valid, Some(head)));
+        }
+    }
+
+    Ok(payload_status(PayloadStatusEnum::Syncing, None))
+}
+
+/// Payload being built in the background, set once building is over.
+type PayloadJob = watch::Receiver<Option<Result<BuiltPayload, String>>>;
+
+#[derive(Debug)]
+pub struct EngineApiServerImpl<E>
+where
+    E: EnvironmentKind,
+{
+    db: Arc<MdbxWithDirHandle<E>>,
+    block_buffer: Arc<Mutex<BlockBuffer>>,
+    transaction_source: Arc<dyn TransactionSource>,
+    payloads: Mutex<LruCache<H64, PayloadJob>>,
+    chain_tip_sender: watch::Sender<ExternalForkChoice>,
+    terminal_total_difficulty: Option<U256>,
+    terminal_block_hash: Option<H256>,
+    terminal_block_number: Option<BlockNumber>,
+    shanghai_time: Option<u64>,
+    cancun_time: Option<u64>,
+}
+
+impl<E> EngineApiServerImpl<E>
+where
+    E: EnvironmentKind,
+{
+    fn is_shanghai(&self, timestamp: u64) -> bool {
+        self.shanghai_time
+            .map(|shanghai_time| timestamp >= shanghai_time)
+            .unwrap_or(false)
+    }
+
+    fn is_cancun(&self, timestamp: u64) -> bool {
+        self.cancun_time
+            .map(|cancun_time| timestamp >= cancun_time)
+            .unwrap_or(false)
+    }
+
+    /// Methods before V3 are only served for pre-Cancun timestamps, and V3 ones only for Cancun ones.
+    fn check_cancun(&self, timestamp: u64, v3: bool, method: &str) -> RpcResult<()> {
+        match (self.is_cancun(timestamp), v3) {
+            (true, false) => Err(engine_error(
+                UNSUPPORTED_FORK,
+                format!("{method} is not supported since Cancun"),
+            )),
+            (false, true) => Err(engine_error(
+                UNSUPPORTED_FORK,
+                format!("{method} is only supported since Cancun"),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Withdrawals must be passed exactly since Shanghai.
+    fn check_withdrawals<T>(&self, timestamp: u64, withdrawals: &Option<T>) -> RpcResult<()> {
+        match (self.is_shanghai(timestamp), withdrawals.is_some()) {
+            (true, false) => Err(engine_error(
+                INVALID_PARAMS,
+                "withdrawals are required since Shanghai",
+            )),
+            (false, true) => Err(engine_error(
+                INVALID_PARAMS,
+                "withdrawals are not allowed before Shanghai",
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    async fn new_payload_with(
+        &self,
+        payload: ExecutionPayload,
+        withdrawals: Option<Vec<Withdrawal>>,
+    ) -> RpcResult<PayloadStatus> {
+        let db = self.db.clone();
+        let block_buffer = self.block_buffer.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let block_hash = payload.block_hash;
+            let block = payload_to_block(payload, withdrawals);
+            let status = process_payload(&db, &block_buffer, block_hash, block)?;
+            debug!("Payload status: {status:?}");
+            Ok(status)
+        })
+        .await
+        .unwrap_or_else(helpers::joinerror_to_result)
+    }
+
+    async fn new_payload_v2(&self, payload: ExecutionPayloadV2) -> RpcResult<PayloadStatus> {
+        let timestamp = payload.payload.timestamp.as_u64();
+        self.check_cancun(timestamp, false, "engine_newPayloadV2")?;
+        self.check_withdrawals(timestamp, &payload.withdrawals)?;
+
+        self.new_payload_with(
+            payload.payload,
+            payload
+                .withdrawals
+                .map(|withdrawals| withdrawals.into_iter().map(From::from).collect()),
+        )
+        .await
+    }
+
+    async fn fork_choice_updated_with(
+        &self,
+        fork_choice_state: ForkchoiceState,
+        payload_attributes: Option<BuildAttributes>,
+        method: &str,
+    ) -> RpcResult<ForkchoiceUpdatedResponse> {
+        debug!("Received fork choice information: {fork_choice_state:?}");
+
+        if let Some(attributes) = &payload_attributes {
+            self.check_cancun(
+                attributes.timestamp,
+                attributes.parent_beacon_block_root.is_some(),
+                method,
+            )?;
+            self.check_withdrawals(attributes.timestamp, &attributes.withdrawals)?;
+        }
+
+        let payload_status = tokio::task::spawn_blocking({
+            let db = self.db.clone();
+            let block_buffer = self.block_buffer.clone();
+            let head = fork_choice_state.head_block_hash;
+            let finalized = fork_choice_state.finalized_block_hash;
+            move || Ok(head_status(&db, &block_buffer, head, finalized)?)
+        })
+        .await
+        .unwrap_or_else(helpers::joinerror_to_result)?;
+
+        if !matches!(payload_status.status, PayloadStatusEnum::Invalid { .. }) {
+            let _ = self.chain_tip_sender.send(ExternalForkChoice {
+                head_block: fork_choice_state.head_block_hash,
+                finalized_block: fork_choice_state.finalized_block_hash,
+            });
+        }
+
+        let payload_id = match payload_attributes {
+            Some(attributes) if matches!(payload_status.status, PayloadStatusEnum::Valid) => {
+                let head = fork_choice_state.head_block_hash;
+                let payload_id = payload_id(head, &attributes);
+
+                // Payload id is returned right away, `engine_getPayload` waits for the build.
+                let mut payloads = self.payloads.lock();
+                if !payloads.contains_key(&payload_id) {
+                    let (sender, receiver) = watch::channel(None);
+                    payloads.insert(payload_id, receiver);
+
+                    tokio::task::spawn_blocking({
+                        let db = self.db.clone();
+                        let block_buffer = self.block_buffer.clone();
+                        let transaction_source = self.transaction_source.clone();
+                        move || {
+                            let built = build_payload(
+                                &db,
+                                &block_buffer,
+                                &*transaction_source,
+                                head,
+                                &attributes,
+                            );
+                            match &built {
+                                Ok(built) => debug!(
+                                    "Built payload {payload_id} with {} transactions on top of {head}",
+                                    built.payload.transactions.len()
+                                ),
+                                Err(e) => warn!("Failed to build payload on top of {head}: {e:?}"),
+                            }
+                            let _ = sender.send(Some(built.map_err(|e| format!("{e:?}"))));
+                        }
+                    });
+                }
+
+                Some(payload_id)
+            }
+            _ => None,
+        };
+
+        Ok(ForkchoiceUpdatedResponse {
+            payload_status,
+            payload_id,
+        })
+    }
+
+    /// Waits for the payload to be built.
+    async fn built_payload(&self, payload_id: H64) -> RpcResult<BuiltPayload> {
+        let mut job = self
+            .payloads
+            .lock()
+            .get(&payload_id)
+            .cloned()
+            .ok_or_else(|| engine_error(UNKNOWN_PAYLOAD, "Unknown payload"))?;
+
+        let built = loop {
+            let built = job.borrow().clone();
+            if let Some(built) = built {
+                break built;
+            }
+            if job.changed().await.is_err() {
+                return Err(format_err!("payload {payload_id} building was aborted").into());
+            }
+        };
+
+        Ok(built.map_err(|e| format_err!("failed to build payload {payload_id}: {e}"))?)
+    }
+
+    async fn get_payload_v2(&self, payload_id: H64) -> RpcResult<GetPayloadV2Response> {
+        let built = self.built_payload(payload_id).await?;
+        self.check_cancun(
+            built.payload.timestamp.as_u64(),
+            false,
+            "engine_getPayloadV2",
+        )?;
+
+        Ok(GetPayloadV2Response {
+            execution_payload: ExecutionPayloadV2 {
+                payload: built.payload,
+                withdrawals: built
+                    .withdrawals
+                    .map(|withdrawals| withdrawals.into_iter().map(From::from).collect()),
+            },
+            block_value: built.block_value,
+        })
+    }
+
+    async fn get_payload_v3(&self, payload_id: H64) -> RpcResult<GetPayloadV3Response> {
+        let built = self.built_payload(payload_id).await?;
+        self.check_cancun(
+            built.payload.timestamp.as_u64(),
+            true,
+            "engine_getPayloadV3",
+        )?;
+
+        let (withdrawals, blob_gas_used, excess_blob_gas) = match (
+            built.withdrawals,
+            built.blob_gas_used,
+            built.excess_blob_gas,
+        ) {
+            (Some(withdrawals), Some(blob_gas_used), Some(excess_blob_gas)) => {
+                (withdrawals, blob_gas_used, excess_blob_gas)
+            }
+            _ => {
+                return Err(
+                    format_err!("payload {payload_id} was built without Cancun fields").into(),
+                )
+            }
+        };
+
+        Ok(GetPayloadV3Response {
+            execution_payload: ExecutionPayloadV3 {
+                payload: built.payload,
+                withdrawals: withdrawals.into_iter().map(From::from).collect(),
+                blob_gas_used: blob_gas_used.into(),
+                excess_blob_gas: excess_blob_gas.into(),
+            },
+            block_value: built.block_value,
+            blobs_bundle: BlobsBundleV1::default(),
+            should_override_builder: false,
+        })
+    }
+
+    async fn new_payload_v3(
+        &self,
+        payload: ExecutionPayloadV3,
+        expected_blob_versioned_hashes: Vec<H256>,
+        parent_beacon_block_root: H256,
+    ) -> RpcResult<PayloadStatus> {
+        self.check_cancun(
+            payload.payload.timestamp.as_u64(),
+            true,
+            "engine_newPayloadV3",
+        )?;
+
+        let db = self.db.clone();
+        let block_buffer = self.block_buffer.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let block_hash = payload.payload.block_hash;
+            let block = payload_v3_to_block(
+                payload,
+                &expected_blob_versioned_hashes,
+                parent_beacon_block_root,
+            );
+            let status = process_payload(&db, &block_buffer, block_hash, block)?;
+            debug!("Payload status: {status:?}");
+            Ok(status)
+        })
+        .await
+        .unwrap_or_else(helpers::joinerror_to_result)
+    }
+
+    /// Engine API methods, including those `EngineApiServer` does not define yet.
+    fn into_rpc_module(self) -> RpcModule<Self> {
+        let mut module = self.into_rpc();
+        module
+            .register_async_method("engine_newPayloadV2", |params, this| async move {
+                this.new_payload_v2(params.one()?).await
+            })
+            .unwrap();
+        module
+            .register_async_method("engine_forkchoiceUpdatedV2", |params, this| async move {
+                let mut params = params.sequence();
+                let fork_choice_state = params.next::<ForkchoiceState>()?;
+                let payload_attributes = params.optional_next::<PayloadAttributesV2>()?;
+
+                this.fork_choice_updated_with(
+                    fork_choice_state,
+                    payload_attributes.map(From::from),
+                    "engine_forkchoiceUpdatedV2",
+                )
+                .await
+            })
+            .unwrap();
+        module
+            .register_async_method("engine_getPayloadV2", |params, this| async move {
+                this.get_payload_v2(params.one()?).await
+            })
+            .unwrap();
+        module
+            .register_async_method("engine_forkchoiceUpdatedV3", |params, this| async move {
+                let mut params = params.sequence();
+                let fork_choice_state = params.next::<ForkchoiceState>()?;
+                let payload_attributes = params.optional_next::<PayloadAttributesV3>()?;
+
+                this.fork_choice_updated_with(
+                    fork_choice_state,
+                    payload_attributes.map(From::from),
+                    "engine_forkchoiceUpdatedV3",
+                )
+                .await
+            })
+            .unwrap();
+        module
+            .register_async_method("engine_getPayloadV3", |params, this| async move {
+                this.get_payload_v3(params.one()?).await
+            })
+            .unwrap();
+        module
+            .register_async_method("engine_newPayloadV3", |params, this| async move {
+                let mut params = params.sequence();
+                let payload = params.next::<ExecutionPayloadV3>()?;
+                let expected_blob_versioned_hashes = params.next::<Vec<H256>>()?;
+                let parent_beacon_block_root = params.next::<H256>()?;
+
+                this.new_payload_v3(
+                    payload,
+                    expected_blob_versioned_hashes,
+                    parent_beacon_block_root,
+                )
+                .await
+            })
+            .unwrap();
+        module
+    }
+}
+
+#[async_trait]
+impl<E> EngineApiServer for EngineApiServerImpl<E>
+where
+    E: EnvironmentKind,
+{
+    async fn new_payload(&self, payload: ExecutionPayload) -> RpcResult<PayloadStatus> {
+        if self.is_shanghai(payload.timestamp.as_u64()) {
+            return Err(engine_error(
+                UNSUPPORTED_FORK,
+                "engine_newPayloadV1 is not supported since Shanghai",
+            ));
+        }
+
+        self.new_payload_with(payload, None).await
+    }
+    async fn fork_choice_updated(
+        &self,
+        fork_choice_state: ForkchoiceState,
+        payload_attributes: Option<PayloadAttributes>,
+    ) -> RpcResult<ForkchoiceUpdatedResponse> {
+        self.fork_choice_updated_with(
+            fork_choice_state,
+            payload_attributes.map(|attributes| {
+                PayloadAttributesV2 {
+                    attributes,
+                    withdrawals: None,
+                }
+                .into()
+            }),
+            "engine_forkchoiceUpdatedV1",
+        )
+        .await
+    }
+    async fn get_payload(&self, payload_id: H64) -> RpcResult<ExecutionPayload> {
+        let built = self.built_payload(payload_id).await?;
+        if built.withdrawals.is_some() {
+            return Err(engine_error(
+                UNSUPPORTED_FORK,
+                "engine_getPayloadV1 is not supported since Shanghai",
+            ));
+        }
+
+        Ok(built.payload)
+    }
+
+    async fn exchange_transition_configuration(
+        &self,
+        transition_configuration: TransitionConfiguration,
+    ) -> RpcResult<TransitionConfiguration> {
+        let our_transition_configuration = TransitionConfiguration {
+            terminal_total_difficulty: self.terminal_total_difficulty.unwrap_or_default(),
+            terminal_block_hash: self.terminal_block_hash.unwrap_or_default(),
+            terminal_block_number: self.terminal_block_number.unwrap_or_default().0.into(),
+        };
+
+        if transition_configuration != our_transition_configuration {
+            error!("Transition configuration mismatch! CL: {transition_configuration:?}, EL: {our_transition_configuration:?}");
+        }
+
+        Ok(our_transition_configuration)
+    }
+}
+
+#[derive(Debug)]
+pub struct BeaconConsensus {
+    base: ConsensusEngineBase,
+    block_reward: BlockRewardSchedule,
+    since: BlockNumber,
+    receiver: watch::Receiver<ExternalForkChoice>,
+    block_buffer: Arc<Mutex<BlockBuffer>>,
+    server_task: Option<TaskGuard<!>>,
+}
+
+/// Engine API server settings.
+#[derive(Debug)]
+pub struct EngineApiConfig {
+    pub db: Arc<MdbxWithDirHandle<WriteMap>>,
+    pub listen_address: SocketAddr,
+    pub jwt_secret: JwtSecret,
+    pub transaction_source: Arc<dyn TransactionSource>,
+}
+
+impl BeaconConsensus {
+    pub fn new(
+        engine_api: Option<EngineApiConfig>,
+        chain_id: ChainId,
+        network_id: NetworkId,
+        eip1559_block: Option<BlockNumber>,
+        shanghai_time: Option<u64>,
+        cancun_time: Option<u64>,
+        block_reward: BlockRewardSchedule,
+        terminal_total_difficulty: Option<U256>,
+        terminal_block_hash: Option<H256>,
+        terminal_block_number: Option<BlockNumber>,
+    ) -> Self {
+        let (chain_tip_sender, receiver) = tokio::sync::watch::channel(ExternalForkChoice {
+            head_block: H256::zero(),
+            finalized_block: H256::zero(),
+        });
+        let block_buffer = Arc::new(Mutex::new(BlockBuffer::new()));
+        Self {
+            base: ConsensusEngineBase::new(
+                chain_id,
+                eip1559_block,
+                shanghai_time,
+                cancun_time,
+                None,
+            ),
+            block_reward,
+            since: terminal_block_number.unwrap_or_default() + 1,
+            receiver,
+            block_buffer: block_buffer.clone(),
+            server_task: engine_api.map(move |engine_api| {
+                TaskGuard(tokio::spawn(async move {
+                    let EngineApiConfig {
+                        db,
+                        listen_address,
+                        jwt_secret,
+                        transaction_source,
+                    } = engine_api;
+
+                    let mut api = Methods::new();
+                    api.merge(
+                        EngineApiServerImpl {
+                            db: db.clone(),
+                            block_buffer,
+                            transaction_source,
+                            payloads: Mutex::new(LruCache::new(MAX_PAYLOADS)),
+                            chain_tip_sender,
+                            terminal_total_difficulty,
+                            terminal_block_hash,
+                            terminal_block_number,
+                            shanghai_time,
+                            cancun_time,
+                        }
+                        .into_rpc_module(),
+                    )
+                    .unwrap();
+                    api.merge(
+                        EthApiServerImpl {
+                            db,
+                            call_gas_limit: 0,
+                            max_logs_block_range: DEFAULT_MAX_LOGS_BLOCK_RANGE,
+                            txpool: None,
+                            filters: Default::default(),
+                        }
+                        .into_methods(),
+                    )
+                    .unwrap();
+                    api.merge(NetApiServerImpl { network_id }.into_rpc())
+                        .unwrap();
+                    api.merge(Web3ApiServerImpl.into_rpc()).unwrap();
+
+                    info!("Engine API listening on {listen_address}");
+                    if let Err(e) = serve_authenticated(listen_address, api, jwt_secret).await {
+                        error!("Engine API server failed: {e}");
+                    }
+
+                    pending().await
+                }))
+            }),
+        }
+    }
+}
+
+impl Consensus for BeaconConsensus {
+    fn fork_choice_mode(&self) -> ForkChoiceMode {
+        ForkChoiceMode::External(self.receiver.clone())
+    }
+
+    fn buffered_block(&self, hash: H256) -> Option<Block> {
+        self.block_buffer
+            .lock()
+            .get(hash)
+            .map(|buffered| buffered.block.clone())
+    }
+
+    fn pre_validate_block(
+        &self,
+        block: &crate::models::Block,
+        _: &dyn crate::BlockReader,
+    ) -> Result<(), super::DuoError> {
+        self.base.pre_validate_block(block)
+    }
+
+    fn validate_block_header(
+        &self,
+        header: &crate::models::BlockHeader,
+        parent: &crate::models::BlockHeader,
+        with_future_timestamp_check: bool,
+    ) -> Result<(), super::DuoError> {
+        self.base
+            .validate_block_header(header, parent, with_future_timestamp_check)?;
+
+        if header.number >= self.since {
+            if header.ommers_hash != EMPTY_LIST_HASH {
+                return Err(ValidationError::TooManyOmmers.into());
+            }
+
+            if header.difficulty != U256::ZERO {
+                return Err(ValidationError::WrongDifficulty.into());
+            }
+
+            if header.nonce != H64::zero() {
+                return Err(ValidationError::WrongHeaderNonce {
+                    expected: H64::zero(),
+                    got: header.nonce,
+                }
+                .into());
+            }
+        }
+
+        Ok(())
+    }
+
+    fn finalize(
+        &self,
+        header: &crate::models::BlockHeader,
+        ommers: &[crate::models::BlockHeader],
+    ) -> anyhow::Result<Vec<super::FinalizationChange>> {
+        let block_number = header.number;
+        let block_reward = self.block_reward.for_block(block_number);
+
+        Ok(if block_reward > 0 {
+            let mut changes = Vec::with_capacity(1 + ommers.len());
+
+            let mut miner_reward = block_reward;
+            for ommer in ommers {
+                let ommer_reward =
+                    (U256::from(8 + ommer.number.0 - block_number.0) * block_reward) >> 3;
+                changes.push(FinalizationChange::Reward {
+                    address: ommer.beneficiary,
+                    amount: ommer_reward,
+                    ommer: true,
+                });
+                miner_reward += block_reward / 32;
+            }
+
+            changes.push(FinalizationChange::Reward {
+                address: header.beneficiary,
+                amount: miner_reward,
+                ommer: false,
+            });
+
+            changes
+        } else {
+            vec![]
+        })
+    }
+}
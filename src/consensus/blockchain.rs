@@ -14,6 +14,7 @@ pub struct Blockchain<'state> {
     engine: Box<dyn Consensus>,
     bad_blocks: HashMap<H256, ValidationError>,
     receipts: Vec<Receipt>,
+    parallel_execution: bool,
 }
 
 impl<'state> Blockchain<'state> {
@@ -47,9 +48,14 @@ impl<'state> Blockchain<'state> {
             config,
             bad_blocks: Default::default(),
             receipts: Default::default(),
+            parallel_execution: false,
         })
     }
 
+    pub fn set_parallel_execution(&mut self, parallel_execution: bool) {
+        self.parallel_execution = parallel_execution;
+    }
+
     pub fn insert_block(&mut self, block: Block, check_state_root: bool) -> Result<(), DuoError> {
         let parent = self
             .state
@@ -140,13 +146,16 @@ impl<'state> Blockchain<'state> {
         let body = BlockBodyWithSenders {
             transactions: block.transactions.clone(),
             ommers: block.ommers.clone(),
+            withdrawals: block.withdrawals.clone(),
         };
 
-        let block_spec = self.config.collect_block_spec(block.header.number);
+        let block_spec = self
+            .config
+            .collect_block_spec(block.header.number, block.header.timestamp);
 
         let mut analysis_cache = AnalysisCache::default();
         let mut tracer = NoopTracer;
-        let processor = ExecutionProcessor::new(
+        let mut processor = ExecutionProcessor::new(
             self.state,
             &mut tracer,
             &mut analysis_cache,
@@ -155,6 +164,7 @@ impl<'state> Blockchain<'state> {
             &body,
             &block_spec,
         );
+        processor.set_parallel_execution(self.parallel_execution);
 
         let _ = processor.execute_and_write_block()?;
 
@@ -193,6 +203,7 @@ impl<'state> Blockchain<'state> {
                 header,
                 transactions: body.transactions,
                 ommers: body.ommers,
+                withdrawals: body.withdrawals,
             };
 
             self.execute_block(&block, false).unwrap();
@@ -238,6 +249,7 @@ impl<'state> Blockchain<'state> {
                     header,
                     transactions: body.transactions,
                     ommers: body.ommers,
+                    withdrawals: body.withdrawals,
                 },
                 hash,
             };
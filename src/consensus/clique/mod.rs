@@ -159,6 +159,8 @@ impl Clique {
     pub(crate) fn new(
         chain_id: ChainId,
         eip1559_block: Option<BlockNumber>,
+        shanghai_time: Option<u64>,
+        cancun_time: Option<u64>,
         period: Duration,
         epoch: u64,
         initial_signers: Vec<Address>,
@@ -166,7 +168,13 @@ impl Clique {
         let mut state = CliqueState::new(epoch);
         state.set_signers(initial_signers);
         Self {
-            base: ConsensusEngineBase::new(chain_id, eip1559_block, None),
+            base: ConsensusEngineBase::new(
+                chain_id,
+                eip1559_block,
+                shanghai_time,
+                cancun_time,
+                None,
+            ),
             state: Mutex::new(state),
             period: period.as_secs(),
             fork_choice_graph: Arc::new(Mutex::new(Default::default())),
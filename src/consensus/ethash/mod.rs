@@ -57,6 +57,8 @@ impl Ethash {
     pub fn new(
         chain_id: ChainId,
         eip1559_block: Option<BlockNumber>,
+        shanghai_time: Option<u64>,
+        cancun_time: Option<u64>,
         duration_limit: u64,
         block_reward: BlockRewardSchedule,
         homestead_formula: Option<BlockNumber>,
@@ -65,7 +67,13 @@ impl Ethash {
         skip_pow_verification: bool,
     ) -> Self {
         Self {
-            base: ConsensusEngineBase::new(chain_id, eip1559_block, Some(32)),
+            base: ConsensusEngineBase::new(
+                chain_id,
+                eip1559_block,
+                shanghai_time,
+                cancun_time,
+                Some(32),
+            ),
             dag_cache: DagCache::new(),
 
             duration_limit,
@@ -6,11 +6,7 @@ mod ethash;
 
 use self::fork_choice_graph::ForkChoiceGraph;
 pub use self::{base::*, beacon::*, blockchain::*, clique::*, ethash::*};
-use crate::{
-    kv::{mdbx::*, MdbxWithDirHandle},
-    models::*,
-    BlockReader,
-};
+use crate::{chain::protocol_param::param, kv::mdbx::*, models::*, BlockReader};
 use anyhow::bail;
 use derive_more::{Display, From};
 use mdbx::{EnvironmentKind, TransactionKind};
@@ -106,6 +102,14 @@ pub trait Consensus: Debug + Send + Sync + 'static {
     fn is_state_valid(&self, next_header: &BlockHeader) -> bool {
         true
     }
+
+    /// To be overridden for consensus engines that receive blocks out of band, e. g. via Engine API.
+    ///
+    /// Allows sync to pick up such blocks instead of downloading them from peers.
+    #[allow(unused_variables)]
+    fn buffered_block(&self, hash: H256) -> Option<Block> {
+        None
+    }
 }
 
 #[allow(clippy::large_enum_variant)]
@@ -128,6 +132,15 @@ pub enum BadTransactionError {
         available: u64,
         required: u64,
     }, // Tg > BHl - l(BR)u
+    InitCodeTooLarge {
+        size: usize,
+        limit: usize,
+    }, // EIP-3860: ‖Ti‖ > 2 * MAX_CODE_SIZE
+    BlobTransactionNotSupported, // EIP-4844: blob transaction before Cancun
+    MaxFeePerBlobGasTooLow {
+        max_fee_per_blob_gas: U256,
+        blob_base_fee: U256,
+    }, // EIP-4844: max_fee_per_blob_gas < blob_base_fee
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -215,6 +228,24 @@ pub enum ValidationError {
         expected: Option<U256>,
         got: Option<U256>,
     }, // see EIP-1559
+    WrongWithdrawalsRoot {
+        expected: Option<H256>,
+        got: Option<H256>,
+    }, // see EIP-4895
+    WrongBlobGasUsed {
+        expected: Option<u64>,
+        got: Option<u64>,
+    }, // see EIP-4844
+    WrongExcessBlobGas {
+        expected: Option<u64>,
+        got: Option<u64>,
+    }, // see EIP-4844
+    BlobGasAboveLimit {
+        used: u64,
+        limit: u64,
+    }, // see EIP-4844
+    MissingParentBeaconBlockRoot, // see EIP-4788
+    UnexpectedParentBeaconBlockRoot, // see EIP-4788
     InvalidSeal,     // Nonce or mix_hash
 
     // See [YP] Section 6.2 "Execution", Eq (58)
@@ -223,9 +254,10 @@ pub enum ValidationError {
         index: usize,
         error: BadTransactionError,
     },
-    IntrinsicGas,                 // g0 > Tg
-    MaxFeeLessThanBase,           // max_fee_per_gas < base_fee_per_gas (EIP-1559)
-    MaxPriorityFeeGreaterThanMax, // max_priority_fee_per_gas > max_fee_per_gas (EIP-1559)
+    IntrinsicGas,                     // g0 > Tg
+    MaxFeeLessThanBase,               // max_fee_per_gas < base_fee_per_gas (EIP-1559)
+    MaxPriorityFeeGreaterThanMax,     // max_priority_fee_per_gas > max_fee_per_gas (EIP-1559)
+    MaxFeePerBlobGasLessThanBlobBase, // max_fee_per_blob_gas < blob_base_fee (EIP-4844)
 
     // See [YP] Section 11.1 "Ommer Validation", Eq (157)
     OmmerUnknownParent {
@@ -252,6 +284,10 @@ pub enum ValidationError {
 
     UnsupportedTransactionType, // EIP-2718
 
+    NoBlobs,              // EIP-4844
+    TooManyBlobs,         // EIP-4844
+    WrongBlobHashVersion, // EIP-4844
+
     CliqueError(CliqueError),
 }
 
@@ -304,11 +340,28 @@ pub fn pre_validate_transaction(
         return Err(ValidationError::MaxPriorityFeeGreaterThanMax);
     }
 
+    // https://eips.ethereum.org/EIPS/eip-4844
+    if txn.tx_type() == TxType::EIP4844 {
+        let hashes = txn.blob_versioned_hashes();
+        if hashes.is_empty() {
+            return Err(ValidationError::NoBlobs);
+        }
+        if blob_gas(txn) > param::MAX_BLOB_GAS_PER_BLOCK {
+            return Err(ValidationError::TooManyBlobs);
+        }
+        if hashes
+            .iter()
+            .any(|hash| hash.0[0] != param::VERSIONED_HASH_VERSION_KZG)
+        {
+            return Err(ValidationError::WrongBlobHashVersion);
+        }
+    }
+
     Ok(())
 }
 
 pub fn engine_factory(
-    db: Option<Arc<MdbxWithDirHandle<WriteMap>>>,
+    engine_api: Option<EngineApiConfig>,
     chain_config: ChainSpec,
 ) -> anyhow::Result<Box<dyn Consensus>> {
     Ok(match chain_config.consensus.seal_verification {
@@ -322,6 +375,8 @@ pub fn engine_factory(
         } => Box::new(Ethash::new(
             chain_config.params.chain_id,
             chain_config.consensus.eip1559_block,
+            chain_config.upgrades.shanghai_time,
+            chain_config.upgrades.cancun_time,
             duration_limit,
             BlockRewardSchedule(block_reward),
             homestead_formula,
@@ -342,6 +397,8 @@ pub fn engine_factory(
             Box::new(Clique::new(
                 chain_config.params.chain_id,
                 chain_config.consensus.eip1559_block,
+                chain_config.upgrades.shanghai_time,
+                chain_config.upgrades.cancun_time,
                 period,
                 epoch,
                 initial_signers,
@@ -354,10 +411,12 @@ pub fn engine_factory(
             terminal_block_number,
             block_reward,
         } => Box::new(BeaconConsensus::new(
-            db,
+            engine_api,
             chain_config.params.chain_id,
             chain_config.params.network_id,
             chain_config.consensus.eip1559_block,
+            chain_config.upgrades.shanghai_time,
+            chain_config.upgrades.cancun_time,
             BlockRewardSchedule(block_reward),
             terminal_total_difficulty,
             terminal_block_hash,
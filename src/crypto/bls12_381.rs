@@ -0,0 +1,274 @@
+//! BLS12-381 curve operations over the encoding of [EIP-2537](https://eips.ethereum.org/EIPS/eip-2537).
+//!
+//! Field elements are 64 bytes big-endian with 16 bytes of zero padding, points are affine
+//! coordinates with the point at infinity encoded as all zeros. Any malformed input yields `None`.
+
+use blst::*;
+use hex_literal::hex;
+
+pub const FP_LENGTH: usize = 64;
+pub const FP2_LENGTH: usize = 2 * FP_LENGTH;
+pub const G1_LENGTH: usize = 2 * FP_LENGTH;
+pub const G2_LENGTH: usize = 2 * FP2_LENGTH;
+pub const SCALAR_LENGTH: usize = 32;
+
+const FP_PADDING: usize = 16;
+const SCALAR_BITS: usize = 256;
+
+const MODULUS: [u8; FP_LENGTH - FP_PADDING] = hex!(
+    "1a0111ea397fe69a4b1ba7b6434bacd764774b84f38512bf6730d2a0f6b0f6241eabfffeb153ffffb9feffffffffaaab"
+);
+
+fn decode_fp(input: &[u8]) -> Option<blst_fp> {
+    let (padding, bytes) = input.split_at(FP_PADDING);
+    if padding.iter().any(|&b| b != 0) || bytes >= &MODULUS[..] {
+        return None;
+    }
+
+    let mut out = blst_fp::default();
+    unsafe { blst_fp_from_bendian(&mut out, bytes.as_ptr()) };
+    Some(out)
+}
+
+fn encode_fp(out: &mut [u8], fp: &blst_fp) {
+    unsafe { blst_bendian_from_fp(out[FP_PADDING..].as_mut_ptr(), fp) };
+}
+
+fn decode_fp2(input: &[u8]) -> Option<blst_fp2> {
+    Some(blst_fp2 {
+        fp: [
+            decode_fp(&input[..FP_LENGTH])?,
+            decode_fp(&input[FP_LENGTH..])?,
+        ],
+    })
+}
+
+fn encode_fp2(out: &mut [u8], fp2: &blst_fp2) {
+    encode_fp(&mut out[..FP_LENGTH], &fp2.fp[0]);
+    encode_fp(&mut out[FP_LENGTH..], &fp2.fp[1]);
+}
+
+/// Decodes a point on the curve, additionally checking subgroup membership if asked to.
+fn decode_g1(input: &[u8], subgroup_check: bool) -> Option<blst_p1_affine> {
+    let point = blst_p1_affine {
+        x: decode_fp(&input[..FP_LENGTH])?,
+        y: decode_fp(&input[FP_LENGTH..])?,
+    };
+
+    // Infinity, encoded as all zeros, is considered on the curve and in the subgroup.
+    if !unsafe { blst_p1_affine_on_curve(&point) }
+        || (subgroup_check && !unsafe { blst_p1_affine_in_g1(&point) })
+    {
+        return None;
+    }
+
+    Some(point)
+}
+
+fn encode_g1(point: &blst_p1) -> Vec<u8> {
+    let mut affine = blst_p1_affine::default();
+    unsafe { blst_p1_to_affine(&mut affine, point) };
+
+    let mut out = vec![0; G1_LENGTH];
+    encode_fp(&mut out[..FP_LENGTH], &affine.x);
+    encode_fp(&mut out[FP_LENGTH..], &affine.y);
+    out
+}
+
+fn decode_g2(input: &[u8], subgroup_check: bool) -> Option<blst_p2_affine> {
+    let point = blst_p2_affine {
+        x: decode_fp2(&input[..FP2_LENGTH])?,
+        y: decode_fp2(&input[FP2_LENGTH..])?,
+    };
+
+    if !unsafe { blst_p2_affine_on_curve(&point) }
+        || (subgroup_check && !unsafe { blst_p2_affine_in_g2(&point) })
+    {
+        return None;
+    }
+
+    Some(point)
+}
+
+fn encode_g2(point: &blst_p2) -> Vec<u8> {
+    let mut affine = blst_p2_affine::default();
+    unsafe { blst_p2_to_affine(&mut affine, point) };
+
+    let mut out = vec![0; G2_LENGTH];
+    encode_fp2(&mut out[..FP2_LENGTH], &affine.x);
+    encode_fp2(&mut out[FP2_LENGTH..], &affine.y);
+    out
+}
+
+/// Big-endian scalar to the little-endian form expected by blst. Scalars are not reduced.
+fn decode_scalar(input: &[u8]) -> [u8; SCALAR_LENGTH] {
+    let mut out = [0; SCALAR_LENGTH];
+    out.copy_from_slice(input);
+    out.reverse();
+    out
+}
+
+/// Sum of two G1 points, not necessarily in the subgroup.
+pub fn g1_add(input: &[u8]) -> Option<Vec<u8>> {
+    if input.len() != 2 * G1_LENGTH {
+        return None;
+    }
+
+    let a = decode_g1(&input[..G1_LENGTH], false)?;
+    let b = decode_g1(&input[G1_LENGTH..], false)?;
+
+    let mut a_projective = blst_p1::default();
+    let mut sum = blst_p1::default();
+    unsafe {
+        blst_p1_from_affine(&mut a_projective, &a);
+        blst_p1_add_or_double_affine(&mut sum, &a_projective, &b);
+    }
+
+    Some(encode_g1(&sum))
+}
+
+/// Multi-scalar multiplication over (G1 point, scalar) pairs.
+pub fn g1_msm(input: &[u8]) -> Option<Vec<u8>> {
+    const PAIR_LENGTH: usize = G1_LENGTH + SCALAR_LENGTH;
+
+    if input.is_empty() || input.len() % PAIR_LENGTH != 0 {
+        return None;
+    }
+
+    // Zeroed projective point is infinity.
+    let mut acc = blst_p1::default();
+    for pair in input.chunks_exact(PAIR_LENGTH) {
+        let point = decode_g1(&pair[..G1_LENGTH], true)?;
+        let scalar = decode_scalar(&pair[G1_LENGTH..]);
+
+        let mut projective = blst_p1::default();
+        let mut product = blst_p1::default();
+        let mut sum = blst_p1::default();
+        unsafe {
+            blst_p1_from_affine(&mut projective, &point);
+            blst_p1_mult(&mut product, &projective, scalar.as_ptr(), SCALAR_BITS);
+            blst_p1_add_or_double(&mut sum, &acc, &product);
+        }
+        acc = sum;
+    }
+
+    Some(encode_g1(&acc))
+}
+
+/// Sum of two G2 points, not necessarily in the subgroup.
+pub fn g2_add(input: &[u8]) -> Option<Vec<u8>> {
+    if input.len() != 2 * G2_LENGTH {
+        return None;
+    }
+
+    let a = decode_g2(&input[..G2_LENGTH], false)?;
+    let b = decode_g2(&input[G2_LENGTH..], false)?;
+
+    let mut a_projective = blst_p2::default();
+    let mut sum = blst_p2::default();
+    unsafe {
+        blst_p2_from_affine(&mut a_projective, &a);
+        blst_p2_add_or_double_affine(&mut sum, &a_projective, &b);
+    }
+
+    Some(encode_g2(&sum))
+}
+
+/// Multi-scalar multiplication over (G2 point, scalar) pairs.
+pub fn g2_msm(input: &[u8]) -> Option<Vec<u8>> {
+    const PAIR_LENGTH: usize = G2_LENGTH + SCALAR_LENGTH;
+
+    if input.is_empty() || input.len() % PAIR_LENGTH != 0 {
+        return None;
+    }
+
+    let mut acc = blst_p2::default();
+    for pair in input.chunks_exact(PAIR_LENGTH) {
+        let point = decode_g2(&pair[..G2_LENGTH], true)?;
+        let scalar = decode_scalar(&pair[G2_LENGTH..]);
+
+        let mut projective = blst_p2::default();
+        let mut product = blst_p2::default();
+        let mut sum = blst_p2::default();
+        unsafe {
+            blst_p2_from_affine(&mut projective, &point);
+            blst_p2_mult(&mut product, &projective, scalar.as_ptr(), SCALAR_BITS);
+            blst_p2_add_or_double(&mut sum, &acc, &product);
+        }
+        acc = sum;
+    }
+
+    Some(encode_g2(&acc))
+}
+
+/// Whether the product of pairings of (G1 point, G2 point) pairs is one.
+pub fn pairing_check(input: &[u8]) -> Option<bool> {
+    const PAIR_LENGTH: usize = G1_LENGTH + G2_LENGTH;
+
+    if input.is_empty() || input.len() % PAIR_LENGTH != 0 {
+        return None;
+    }
+
+    let mut acc: Option<blst_fp12> = None;
+    for pair in input.chunks_exact(PAIR_LENGTH) {
+        let p = decode_g1(&pair[..G1_LENGTH], true)?;
+        let q = decode_g2(&pair[G1_LENGTH..], true)?;
+
+        // Pairing with infinity is one.
+        if unsafe { blst_p1_affine_is_inf(&p) || blst_p2_affine_is_inf(&q) } {
+            continue;
+        }
+
+        let mut miller_loop = blst_fp12::default();
+        unsafe { blst_miller_loop(&mut miller_loop, &q, &p) };
+
+        acc = Some(match acc {
+            Some(acc) => {
+                let mut product = blst_fp12::default();
+                unsafe { blst_fp12_mul(&mut product, &acc, &miller_loop) };
+                product
+            }
+            None => miller_loop,
+        });
+    }
+
+    Some(match acc {
+        Some(acc) => {
+            let mut result = blst_fp12::default();
+            unsafe {
+                blst_final_exp(&mut result, &acc);
+                blst_fp12_is_one(&result)
+            }
+        }
+        None => true,
+    })
+}
+
+/// Maps a field element to G1 with the simplified SWU map, clearing the cofactor.
+pub fn map_fp_to_g1(input: &[u8]) -> Option<Vec<u8>> {
+    if input.len() != FP_LENGTH {
+        return None;
+    }
+
+    let u = decode_fp(input)?;
+
+    let mut out = blst_p1::default();
+    unsafe { blst_map_to_g1(&mut out, &u, std::ptr::null()) };
+
+    Some(encode_g1(&out))
+}
+
+/// Maps an element of the quadratic extension field to G2 with the simplified SWU map,
+/// clearing the cofactor.
+pub fn map_fp2_to_g2(input: &[u8]) -> Option<Vec<u8>> {
+    if input.len() != FP2_LENGTH {
+        return None;
+    }
+
+    let u = decode_fp2(input)?;
+
+    let mut out = blst_p2::default();
+    unsafe { blst_map_to_g2(&mut out, &u, std::ptr::null()) };
+
+    Some(encode_g2(&out))
+}
@@ -5,6 +5,7 @@ use hex_literal::hex;
 use sha3::{Digest, Keccak256};
 
 pub mod blake2;
+pub mod bls12_381;
 
 /// Concrete `Hasher` impl for the Keccak-256 hash
 #[derive(Default, Debug, Clone, PartialEq, Eq)]
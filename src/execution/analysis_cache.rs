@@ -1,30 +1,179 @@
 use super::evm::AnalyzedCode;
 use ethereum_types::H256;
 use lru::LruCache;
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+use std::{fmt, sync::Arc};
 
-#[derive(Debug)]
+/// Default bound on the total size of cached analyses, in MiB.
+pub const DEFAULT_CAPACITY_MIB: usize = 512;
+
+/// Number of independently locked parts of the cache, so that concurrent lookups rarely contend.
+const SHARDS: usize = 16;
+
+static GLOBAL: OnceCell<AnalysisCache> = OnceCell::new();
+
+/// Code hash, and whether the code was analyzed as an EOF container.
+///
+/// Same code is analyzed differently before and after EOF activation, while its validation
+/// failure falls back to legacy analysis, which is cached as well.
+type Key = (H256, bool);
+
+struct Shard {
+    entries: LruCache<Key, AnalyzedCode>,
+    size: usize,
+    capacity: usize,
+}
+
+impl Shard {
+    fn put(&mut self, key: Key, code: AnalyzedCode) {
+        let size = code.size();
+        if size > self.capacity {
+            return;
+        }
+
+        if let Some(old) = self.entries.put(key, code) {
+            self.size -= old.size();
+        }
+        self.size += size;
+
+        while self.size > self.capacity {
+            if let Some((_, evicted)) = self.entries.pop_lru() {
+                self.size -= evicted.size();
+            } else {
+                break;
+            }
+        }
+    }
+}
+
+/// Thread-safe cache of analyzed bytecode keyed by code hash and analysis mode, bounded by total
+/// size of analyses.
+///
+/// Entries are spread over shards by code hash, each shard evicting its least recently used
+/// entries on its own. Clones share the same underlying cache.
+#[derive(Clone)]
 pub struct AnalysisCache {
-    inner: LruCache<H256, AnalyzedCode>,
+    shards: Arc<Vec<Mutex<Shard>>>,
+}
+
+impl fmt::Debug for AnalysisCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (entries, size, capacity) =
+            self.shards
+                .iter()
+                .fold((0, 0, 0), |(entries, size, capacity), shard| {
+                    let shard = shard.lock();
+                    (
+                        entries + shard.entries.len(),
+                        size + shard.size,
+                        capacity + shard.capacity,
+                    )
+                });
+        f.debug_struct("AnalysisCache")
+            .field("entries", &entries)
+            .field("size", &size)
+            .field("capacity", &capacity)
+            .finish()
+    }
 }
 
 impl Default for AnalysisCache {
     fn default() -> Self {
-        Self::new(5000)
+        Self::new(DEFAULT_CAPACITY_MIB * 1024 * 1024)
     }
 }
 
 impl AnalysisCache {
-    pub fn new(cap: usize) -> Self {
+    /// Creates a cache holding at most `capacity` bytes of analyses.
+    pub fn new(capacity: usize) -> Self {
         Self {
-            inner: LruCache::new(cap),
+            shards: Arc::new(
+                (0..SHARDS)
+                    .map(|_| {
+                        Mutex::new(Shard {
+                            entries: LruCache::unbounded(),
+                            size: 0,
+                            capacity: capacity / SHARDS,
+                        })
+                    })
+                    .collect(),
+            ),
         }
     }
 
-    pub fn get(&mut self, code_hash: &H256) -> Option<&AnalyzedCode> {
-        self.inner.get(code_hash)
+    /// Sets capacity of the [global](Self::global) cache, in bytes.
+    ///
+    /// Has no effect once the global cache is in use, returns whether it was set.
+    pub fn init_global(capacity: usize) -> bool {
+        GLOBAL.set(Self::new(capacity)).is_ok()
     }
 
-    pub fn put(&mut self, code_hash: H256, code: AnalyzedCode) {
-        self.inner.put(code_hash, code);
+    /// Process-wide cache, shared by block execution and RPC.
+    pub fn global() -> Self {
+        GLOBAL.get_or_init(Self::default).clone()
+    }
+
+    fn shard(&self, code_hash: &H256) -> &Mutex<Shard> {
+        &self.shards[code_hash.as_bytes()[0] as usize % SHARDS]
+    }
+
+    /// Analysis of code, as an EOF container if `eof` is set.
+    pub fn get(&self, code_hash: &H256, eof: bool) -> Option<AnalyzedCode> {
+        self.shard(code_hash)
+            .lock()
+            .entries
+            .get(&(*code_hash, eof))
+            .cloned()
+    }
+
+    pub fn put(&self, code_hash: H256, eof: bool, code: AnalyzedCode) {
+        self.shard(&code_hash).lock().put((code_hash, eof), code)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evicts_by_size() {
+        let code = AnalyzedCode::analyze(&[0x5b; 100]);
+        let size = code.size();
+
+        // Hashes differing in the last byte only land in the same shard.
+        let hash = H256::from_low_u64_be;
+
+        let cache = AnalysisCache::new(size * 2 * SHARDS);
+        cache.put(hash(1), false, code.clone());
+        cache.put(hash(2), false, code.clone());
+        assert!(cache.get(&hash(1), false).is_some());
+
+        // Evicts the least recently used entry.
+        cache.put(hash(3), false, code.clone());
+        assert!(cache.get(&hash(1), false).is_some());
+        assert!(cache.get(&hash(2), false).is_none());
+        assert!(cache.get(&hash(3), false).is_some());
+
+        // Other shards are not affected.
+        cache.put(H256::repeat_byte(1), false, code.clone());
+        assert!(cache.get(&hash(1), false).is_some());
+        assert!(cache.get(&hash(3), false).is_some());
+
+        // Shared between clones.
+        let clone = cache.clone();
+        clone.put(hash(4), false, code.clone());
+        assert!(cache.get(&hash(4), false).is_some());
+
+        // Analyses of the same code in different modes are kept apart.
+        assert!(cache.get(&hash(4), true).is_none());
+        cache.put(hash(4), true, code);
+        assert!(cache.get(&hash(4), true).is_some());
+        assert!(cache.get(&hash(4), false).is_some());
+
+        // Analyses larger than a shard are not kept.
+        let cache = AnalysisCache::new((size - 1) * SHARDS);
+        cache.put(hash(5), false, AnalyzedCode::analyze(&[0x5b; 100]));
+        assert!(cache.get(&hash(5), false).is_none());
     }
 }
@@ -0,0 +1,155 @@
+use akula::{
+    consensus::engine_factory,
+    crypto::keccak256,
+    execution::{analysis_cache::AnalysisCache, processor::ExecutionProcessor, tracer::NoopTracer},
+    models::*,
+    res::chainspec::MAINNET,
+    InMemoryState, StateWriter,
+};
+use bytes::Bytes;
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use hex_literal::hex;
+
+const HASHER: Address = H160(hex!("4a54e54a54e54a54e54a54e54a54e54a54e54a54"));
+
+// Hashes a word 1024 times and stores the result in the caller's slot.
+// 0      PUSH2  => 0400
+// 3      JUMPDEST
+// 4      PUSH1  => 20
+// 6      PUSH1  => 00
+// 8      SHA3
+// 9      PUSH1  => 00
+// 11     MSTORE
+// 12     PUSH1  => 01
+// 14     SWAP1
+// 15     SUB
+// 16     DUP1
+// 17     PUSH1  => 03
+// 19     JUMPI
+// 20     POP
+// 21     PUSH1  => 00
+// 23     MLOAD
+// 24     CALLER
+// 25     SSTORE
+// 26     STOP
+const HASHER_CODE: &[u8] = &hex!("6104005b6020600020600052600190038060035750600051335500");
+
+const TRANSACTIONS: u64 = 100;
+
+fn sender(index: u64) -> Address {
+    Address::from_low_u64_be(0x1000 + index)
+}
+
+fn header() -> BlockHeader {
+    BlockHeader::new(
+        PartialHeader {
+            number: 13_500_001.into(),
+            beneficiary: Address::from_low_u64_be(0xcb),
+            gas_limit: 30_000_000,
+            ..PartialHeader::empty()
+        },
+        EMPTY_LIST_HASH,
+        EMPTY_ROOT,
+    )
+}
+
+/// Block of independent calls, the best case for speculative execution.
+fn block() -> BlockBodyWithSenders {
+    BlockBodyWithSenders {
+        transactions: (0..TRANSACTIONS)
+            .map(|index| MessageWithSender {
+                message: Message::EIP1559 {
+                    chain_id: MAINNET.params.chain_id,
+                    nonce: 0,
+                    max_priority_fee_per_gas: U256::from(GIGA),
+                    max_fee_per_gas: U256::from(20 * GIGA),
+                    gas_limit: 200_000,
+                    action: TransactionAction::Call(HASHER),
+                    value: U256::ZERO,
+                    input: Bytes::new(),
+                    access_list: Default::default(),
+                },
+                sender: sender(index),
+            })
+            .collect(),
+        ommers: vec![],
+        withdrawals: None,
+    }
+}
+
+fn state() -> InMemoryState {
+    let mut state = InMemoryState::default();
+    for index in 0..TRANSACTIONS {
+        state.update_account(
+            sender(index),
+            None,
+            Some(Account {
+                balance: ETHER.into(),
+                ..Default::default()
+            }),
+        );
+    }
+    let code_hash = keccak256(HASHER_CODE);
+    state.update_account(
+        HASHER,
+        None,
+        Some(Account {
+            code_hash,
+            ..Default::default()
+        }),
+    );
+    state
+        .update_code(code_hash, Bytes::from_static(HASHER_CODE))
+        .unwrap();
+    state
+}
+
+/// Executes the block and returns gas used by it.
+fn execute(state: &mut InMemoryState, parallel_execution: bool) -> u64 {
+    let header = header();
+    let block = block();
+    let block_spec = MAINNET.collect_block_spec(header.number, header.timestamp);
+
+    let mut analysis_cache = AnalysisCache::default();
+    let mut engine = engine_factory(None, MAINNET.clone()).unwrap();
+    let mut tracer = NoopTracer;
+    let mut processor = ExecutionProcessor::new(
+        state,
+        &mut tracer,
+        &mut analysis_cache,
+        &mut *engine,
+        &header,
+        &block,
+        &block_spec,
+    );
+    processor.set_parallel_execution(parallel_execution);
+
+    processor
+        .execute_block_no_post_validation()
+        .unwrap()
+        .last()
+        .unwrap()
+        .cumulative_gas_used
+}
+
+fn parallel_execution(c: &mut Criterion) {
+    let gas_used = execute(&mut state(), false);
+    assert_eq!(execute(&mut state(), true), gas_used);
+
+    // Throughput is reported in gas per second.
+    let mut group = c.benchmark_group("parallel_execution");
+    group.throughput(Throughput::Elements(gas_used));
+    for (name, parallel_execution) in [("sequential", false), ("parallel", true)] {
+        group.bench_function(name, |b| {
+            b.iter_batched(
+                state,
+                |mut state| execute(&mut state, parallel_execution),
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, parallel_execution);
+criterion_main!(benches);
@@ -0,0 +1,53 @@
+use akula::{crypto::bls12_381, execution::precompiled::PrecompiledContract, models::Precompile};
+use bytes::Bytes;
+use criterion::{criterion_group, criterion_main, Criterion};
+
+fn bls12_381(c: &mut Criterion) {
+    let mut fp = [0; bls12_381::FP_LENGTH];
+    fp[63] = 1;
+    let mut fp2 = [0; bls12_381::FP2_LENGTH];
+    fp2[63] = 1;
+    fp2[127] = 2;
+
+    let g1 = bls12_381::map_fp_to_g1(&fp).unwrap();
+    let g2 = bls12_381::map_fp2_to_g2(&fp2).unwrap();
+
+    let scalar = [0xff; bls12_381::SCALAR_LENGTH];
+    let msm = |point: &[u8], k: usize| [point, &scalar].concat().repeat(k);
+
+    let mut group = c.benchmark_group("bls12_381");
+    for (name, precompile, input) in [
+        (
+            "g1_add",
+            Precompile::Bls12381G1Add,
+            [g1.clone(), g1.clone()].concat(),
+        ),
+        ("g1_msm_1", Precompile::Bls12381G1Msm, msm(&g1, 1)),
+        ("g1_msm_128", Precompile::Bls12381G1Msm, msm(&g1, 128)),
+        (
+            "g2_add",
+            Precompile::Bls12381G2Add,
+            [g2.clone(), g2.clone()].concat(),
+        ),
+        ("g2_msm_1", Precompile::Bls12381G2Msm, msm(&g2, 1)),
+        ("g2_msm_128", Precompile::Bls12381G2Msm, msm(&g2, 128)),
+        (
+            "pairing_check_2",
+            Precompile::Bls12381PairingCheck,
+            [g1.clone(), g2.clone(), g1.clone(), g2.clone()].concat(),
+        ),
+        ("map_fp_to_g1", Precompile::Bls12381MapFpToG1, fp.to_vec()),
+        (
+            "map_fp2_to_g2",
+            Precompile::Bls12381MapFp2ToG2,
+            fp2.to_vec(),
+        ),
+    ] {
+        let input = Bytes::from(input);
+        group.bench_function(name, |b| b.iter(|| precompile.run(input.clone()).unwrap()));
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bls12_381);
+criterion_main!(benches);
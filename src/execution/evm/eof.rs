@@ -0,0 +1,526 @@
+//! EVM Object Format v1 container parsing and validation.
+//!
+//! Covers the container layout of [EIP-3540](https://eips.ethereum.org/EIPS/eip-3540)
+//! with code sections of [EIP-4750](https://eips.ethereum.org/EIPS/eip-4750),
+//! code validation of [EIP-3670](https://eips.ethereum.org/EIPS/eip-3670),
+//! static relative jumps of [EIP-4200](https://eips.ethereum.org/EIPS/eip-4200)
+//! and stack validation of [EIP-5450](https://eips.ethereum.org/EIPS/eip-5450).
+use super::{
+    instructions::{instruction_table::get_instruction_table, PROPERTIES},
+    opcode::OpCode,
+    state::STACK_SIZE,
+};
+use crate::models::Revision;
+use std::ops::Range;
+use thiserror::Error;
+
+pub const MAGIC: [u8; 2] = [0xef, 0x00];
+pub const VERSION: u8 = 1;
+
+const KIND_TERMINATOR: u8 = 0x00;
+const KIND_TYPE: u8 = 0x01;
+const KIND_CODE: u8 = 0x02;
+const KIND_DATA: u8 = 0x03;
+
+const MAX_CODE_SECTIONS: usize = 1024;
+const MAX_INPUTS_OUTPUTS: u8 = 0x7f;
+const MAX_STACK_HEIGHT: u16 = 0x3ff;
+
+/// Maximum depth of nested CALLF invocations.
+pub const RETURN_STACK_LIMIT: usize = 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Error)]
+pub enum EofError {
+    #[error("invalid magic")]
+    InvalidMagic,
+    #[error("unsupported version")]
+    UnsupportedVersion,
+    #[error("incomplete section header")]
+    IncompleteHeader,
+    #[error("missing type section header")]
+    MissingTypeHeader,
+    #[error("missing code section header")]
+    MissingCodeHeader,
+    #[error("missing data section header")]
+    MissingDataHeader,
+    #[error("missing header terminator")]
+    MissingTerminator,
+    #[error("invalid number of code sections")]
+    InvalidCodeSectionCount,
+    #[error("type section size does not match number of code sections")]
+    InvalidTypeSectionSize,
+    #[error("empty code section")]
+    EmptyCodeSection,
+    #[error("container size does not match section sizes")]
+    InvalidContainerSize,
+    #[error("first code section must have zero inputs and outputs")]
+    InvalidFirstSectionType,
+    #[error("too many inputs or outputs")]
+    InputsOutputsLimitExceeded,
+    #[error("max stack height above limit")]
+    MaxStackHeightLimitExceeded,
+    #[error("undefined instruction")]
+    UndefinedInstruction,
+    #[error("truncated instruction")]
+    TruncatedInstruction,
+    #[error("invalid relative jump destination")]
+    InvalidJumpDestination,
+    #[error("invalid code section index")]
+    InvalidCodeSectionIndex,
+    #[error("stack underflow")]
+    StackUnderflow,
+    #[error("stack overflow")]
+    StackOverflow,
+    #[error("stack height mismatch")]
+    StackHeightMismatch,
+    #[error("stack height on RETF does not match section outputs")]
+    InvalidReturnHeight,
+    #[error("declared max stack height does not match code")]
+    InvalidMaxStackHeight,
+    #[error("unreachable instructions")]
+    UnreachableInstructions,
+    #[error("code section does not end with a terminating instruction")]
+    NoTerminatingInstruction,
+}
+
+/// Signature of a code section.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SectionType {
+    pub inputs: u8,
+    pub outputs: u8,
+    pub max_stack_height: u16,
+}
+
+/// Parsed EOF container header.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EofHeader {
+    pub types: Vec<SectionType>,
+    /// Positions of code sections within the container.
+    pub code_sections: Vec<Range<usize>>,
+    /// Position of the data section within the container.
+    pub data: Range<usize>,
+}
+
+/// Whether code is meant to be an EOF container, valid or not.
+pub fn is_eof(code: &[u8]) -> bool {
+    code.starts_with(&MAGIC)
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn u8(&mut self) -> Result<u8, EofError> {
+        let v = *self.data.get(self.pos).ok_or(EofError::IncompleteHeader)?;
+        self.pos += 1;
+        Ok(v)
+    }
+
+    fn u16(&mut self) -> Result<u16, EofError> {
+        Ok(u16::from_be_bytes([self.u8()?, self.u8()?]))
+    }
+
+    fn section_kind(&mut self, kind: u8, err: EofError) -> Result<(), EofError> {
+        if self.u8()? != kind {
+            return Err(err);
+        }
+        Ok(())
+    }
+}
+
+/// Parse container header and check that section sizes add up.
+pub fn parse(container: &[u8]) -> Result<EofHeader, EofError> {
+    if !is_eof(container) {
+        return Err(EofError::InvalidMagic);
+    }
+    if container.get(MAGIC.len()) != Some(&VERSION) {
+        return Err(EofError::UnsupportedVersion);
+    }
+
+    let mut r = Reader {
+        data: container,
+        pos: MAGIC.len() + 1,
+    };
+
+    r.section_kind(KIND_TYPE, EofError::MissingTypeHeader)?;
+    let types_size = r.u16()? as usize;
+
+    r.section_kind(KIND_CODE, EofError::MissingCodeHeader)?;
+    let num_code_sections = r.u16()? as usize;
+    if num_code_sections == 0 || num_code_sections > MAX_CODE_SECTIONS {
+        return Err(EofError::InvalidCodeSectionCount);
+    }
+    if types_size != num_code_sections * 4 {
+        return Err(EofError::InvalidTypeSectionSize);
+    }
+    let mut code_sizes = Vec::with_capacity(num_code_sections);
+    for _ in 0..num_code_sections {
+        let size = r.u16()? as usize;
+        if size == 0 {
+            return Err(EofError::EmptyCodeSection);
+        }
+        code_sizes.push(size);
+    }
+
+    r.section_kind(KIND_DATA, EofError::MissingDataHeader)?;
+    let data_size = r.u16()? as usize;
+
+    r.section_kind(KIND_TERMINATOR, EofError::MissingTerminator)?;
+
+    let types_offset = r.pos;
+    let code_offset = types_offset + types_size;
+    let data_offset = code_offset + code_sizes.iter().sum::<usize>();
+    if container.len() != data_offset + data_size {
+        return Err(EofError::InvalidContainerSize);
+    }
+
+    let mut types = Vec::with_capacity(num_code_sections);
+    for entry in container[types_offset..code_offset].chunks_exact(4) {
+        let ty = SectionType {
+            inputs: entry[0],
+            outputs: entry[1],
+            max_stack_height: u16::from_be_bytes([entry[2], entry[3]]),
+        };
+        if ty.inputs > MAX_INPUTS_OUTPUTS || ty.outputs > MAX_INPUTS_OUTPUTS {
+            return Err(EofError::InputsOutputsLimitExceeded);
+        }
+        if ty.max_stack_height > MAX_STACK_HEIGHT {
+            return Err(EofError::MaxStackHeightLimitExceeded);
+        }
+        types.push(ty);
+    }
+    if types[0].inputs != 0 || types[0].outputs != 0 {
+        return Err(EofError::InvalidFirstSectionType);
+    }
+
+    let mut code_sections = Vec::with_capacity(num_code_sections);
+    let mut offset = code_offset;
+    for size in code_sizes {
+        code_sections.push(offset..offset + size);
+        offset += size;
+    }
+
+    Ok(EofHeader {
+        types,
+        code_sections,
+        data: data_offset..container.len(),
+    })
+}
+
+/// Parse the container and validate all of its code sections against the given revision.
+pub fn validate(container: &[u8], revision: Revision) -> Result<EofHeader, EofError> {
+    let header = parse(container)?;
+
+    for (section, range) in header.code_sections.iter().enumerate() {
+        let code = &container[range.clone()];
+        let instruction_starts = validate_instructions(code, header.code_sections.len(), revision)?;
+        validate_stack(code, &instruction_starts, section, &header.types)?;
+    }
+
+    Ok(header)
+}
+
+#[inline]
+pub(crate) fn read_i16(code: &[u8], pos: usize) -> i16 {
+    i16::from_be_bytes([code[pos], code[pos + 1]])
+}
+
+#[inline]
+pub(crate) fn read_u16(code: &[u8], pos: usize) -> u16 {
+    u16::from_be_bytes([code[pos], code[pos + 1]])
+}
+
+fn immediate_size(code: &[u8], pos: usize) -> Result<usize, EofError> {
+    let op = OpCode(code[pos]);
+    Ok(
+        if op.to_u8() >= OpCode::PUSH1.to_u8() && op.to_u8() <= OpCode::PUSH32.to_u8() {
+            (op.to_u8() - OpCode::PUSH1.to_u8() + 1) as usize
+        } else {
+            match op {
+                OpCode::RJUMP | OpCode::RJUMPI | OpCode::CALLF => 2,
+                OpCode::RJUMPV => {
+                    let max_index = *code.get(pos + 1).ok_or(EofError::TruncatedInstruction)?;
+                    1 + (max_index as usize + 1) * 2
+                }
+                _ => 0,
+            }
+        },
+    )
+}
+
+fn relative_target(next: usize, offset: i16) -> Result<usize, EofError> {
+    usize::try_from(next as isize + offset as isize).map_err(|_| EofError::InvalidJumpDestination)
+}
+
+/// Check opcodes, immediates and jump destinations of a code section.
+///
+/// Returns a map of positions at which instructions start.
+fn validate_instructions(
+    code: &[u8],
+    num_code_sections: usize,
+    revision: Revision,
+) -> Result<Vec<bool>, EofError> {
+    let instruction_table = get_instruction_table(revision);
+
+    let mut instruction_starts = vec![false; code.len()];
+    let mut jump_targets = Vec::new();
+
+    let mut pos = 0;
+    while pos < code.len() {
+        instruction_starts[pos] = true;
+
+        let op = OpCode(code[pos]);
+        if instruction_table[op.to_usize()].gas_cost < 0
+            || matches!(op, OpCode::JUMP | OpCode::JUMPI | OpCode::PC)
+        {
+            return Err(EofError::UndefinedInstruction);
+        }
+
+        let next = pos + 1 + immediate_size(code, pos)?;
+        if next > code.len() {
+            return Err(EofError::TruncatedInstruction);
+        }
+
+        match op {
+            OpCode::RJUMP | OpCode::RJUMPI => {
+                jump_targets.push(relative_target(next, read_i16(code, pos + 1))?);
+            }
+            OpCode::RJUMPV => {
+                for i in 0..=code[pos + 1] as usize {
+                    jump_targets.push(relative_target(next, read_i16(code, pos + 2 + i * 2))?);
+                }
+            }
+            OpCode::CALLF => {
+                if read_u16(code, pos + 1) as usize >= num_code_sections {
+                    return Err(EofError::InvalidCodeSectionIndex);
+                }
+            }
+            _ => {}
+        }
+
+        pos = next;
+    }
+
+    for target in jump_targets {
+        if !instruction_starts.get(target).copied().unwrap_or(false) {
+            return Err(EofError::InvalidJumpDestination);
+        }
+    }
+
+    Ok(instruction_starts)
+}
+
+/// Verify that every instruction of a code section is reachable with a single known stack height
+/// and that the section's declared signature matches its code.
+fn validate_stack(
+    code: &[u8],
+    instruction_starts: &[bool],
+    section: usize,
+    types: &[SectionType],
+) -> Result<(), EofError> {
+    let ty = types[section];
+
+    let mut heights = vec![None::<usize>; code.len()];
+    heights[0] = Some(ty.inputs as usize);
+    let mut max_height = ty.inputs as usize;
+
+    let mut worklist = vec![0];
+    while let Some(pos) = worklist.pop() {
+        let height = heights[pos].unwrap();
+        let op = OpCode(code[pos]);
+
+        let (required, next_height) = match op {
+            OpCode::CALLF => {
+                let callee = types[read_u16(code, pos + 1) as usize];
+                let base = height
+                    .checked_sub(callee.inputs as usize)
+                    .ok_or(EofError::StackUnderflow)?;
+                if base + callee.max_stack_height as usize > STACK_SIZE {
+                    return Err(EofError::StackOverflow);
+                }
+                (callee.inputs as usize, Some(base + callee.outputs as usize))
+            }
+            OpCode::RETF => {
+                if height != ty.outputs as usize {
+                    return Err(EofError::InvalidReturnHeight);
+                }
+                (ty.outputs as usize, Some(height))
+            }
+            _ => {
+                let properties = PROPERTIES[op.to_usize()].unwrap();
+                (
+                    properties.stack_height_required as usize,
+                    usize::try_from(height as isize + properties.stack_height_change as isize).ok(),
+                )
+            }
+        };
+        let next_height = match next_height {
+            Some(next_height) if height >= required => next_height,
+            _ => return Err(EofError::StackUnderflow),
+        };
+        if next_height > STACK_SIZE {
+            return Err(EofError::StackOverflow);
+        }
+        max_height = max_height.max(next_height);
+
+        let next = pos + 1 + immediate_size(code, pos)?;
+        let successors = match op {
+            OpCode::STOP
+            | OpCode::RETURN
+            | OpCode::REVERT
+            | OpCode::INVALID
+            | OpCode::SELFDESTRUCT
+            | OpCode::RETF => vec![],
+            OpCode::RJUMP => vec![relative_target(next, read_i16(code, pos + 1))?],
+            OpCode::RJUMPI => vec![next, relative_target(next, read_i16(code, pos + 1))?],
+            OpCode::RJUMPV => {
+                let mut successors = vec![next];
+                for i in 0..=code[pos + 1] as usize {
+                    successors.push(relative_target(next, read_i16(code, pos + 2 + i * 2))?);
+                }
+                successors
+            }
+            _ => vec![next],
+        };
+
+        for successor in successors {
+            if successor >= code.len() {
+                return Err(EofError::NoTerminatingInstruction);
+            }
+            match heights[successor] {
+                None => {
+                    heights[successor] = Some(next_height);
+                    worklist.push(successor);
+                }
+                Some(h) if h != next_height => return Err(EofError::StackHeightMismatch),
+                Some(_) => {}
+            }
+        }
+    }
+
+    if instruction_starts
+        .iter()
+        .zip(&heights)
+        .any(|(&start, height)| start && height.is_none())
+    {
+        return Err(EofError::UnreachableInstructions);
+    }
+
+    if max_height != ty.max_stack_height as usize {
+        return Err(EofError::InvalidMaxStackHeight);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex_literal::hex;
+
+    #[test]
+    fn parse_minimal() {
+        let container = hex!("ef0001 010004 0200010001 030000 00 00000000 00");
+        let header = parse(&container).unwrap();
+        assert_eq!(
+            header.types,
+            vec![SectionType {
+                inputs: 0,
+                outputs: 0,
+                max_stack_height: 0
+            }]
+        );
+        assert_eq!(header.code_sections, vec![19..20]);
+        assert_eq!(header.data, 20..20);
+        assert_eq!(validate(&container, Revision::Eof), Ok(header));
+    }
+
+    #[test]
+    fn parse_errors() {
+        for (container, err) in [
+            (&hex!("ef")[..], EofError::InvalidMagic),
+            (&hex!("ef0002")[..], EofError::UnsupportedVersion),
+            (&hex!("ef0001 010004")[..], EofError::IncompleteHeader),
+            (
+                &hex!("ef0001 020001 0001 030000 00 00")[..],
+                EofError::MissingTypeHeader,
+            ),
+            (
+                &hex!("ef0001 010008 0200010001 030000 00 00000000 00")[..],
+                EofError::InvalidTypeSectionSize,
+            ),
+            (
+                &hex!("ef0001 010004 0200010000 030000 00 00000000")[..],
+                EofError::EmptyCodeSection,
+            ),
+            (
+                &hex!("ef0001 010004 0200010001 030000 00 00000000 00 aa")[..],
+                EofError::InvalidContainerSize,
+            ),
+            (
+                &hex!("ef0001 010004 0200010001 030000 00 01000001 00")[..],
+                EofError::InvalidFirstSectionType,
+            ),
+        ] {
+            assert_eq!(parse(container), Err(err));
+        }
+    }
+
+    #[test]
+    fn code_validation() {
+        for (code, max_stack_height, res) in [
+            // PUSH1 0, POP, STOP
+            (&hex!("600050 00")[..], 1, Ok(())),
+            // Truncated PUSH2
+            (&hex!("6100")[..], 1, Err(EofError::TruncatedInstruction)),
+            // JUMP is not allowed
+            (&hex!("600056")[..], 1, Err(EofError::UndefinedInstruction)),
+            // Falls off the end
+            (
+                &hex!("6000 50")[..],
+                1,
+                Err(EofError::NoTerminatingInstruction),
+            ),
+            // RJUMP into own immediate
+            (
+                &hex!("e0ffff")[..],
+                0,
+                Err(EofError::InvalidJumpDestination),
+            ),
+            // RJUMP over unreachable STOP
+            (
+                &hex!("e00001 00 00")[..],
+                0,
+                Err(EofError::UnreachableInstructions),
+            ),
+            // POP on empty stack
+            (&hex!("50 00")[..], 0, Err(EofError::StackUnderflow)),
+            // Wrong declared max stack height
+            (
+                &hex!("600050 00")[..],
+                2,
+                Err(EofError::InvalidMaxStackHeight),
+            ),
+            // Loop growing the stack
+            (
+                &hex!("6000 e0fffb")[..],
+                1,
+                Err(EofError::StackHeightMismatch),
+            ),
+        ] {
+            let mut container = hex!("ef0001 010004 020001").to_vec();
+            container.extend_from_slice(&(code.len() as u16).to_be_bytes());
+            container.extend_from_slice(&hex!("030000 00 0000"));
+            container.extend_from_slice(&(max_stack_height as u16).to_be_bytes());
+            container.extend_from_slice(code);
+            assert_eq!(
+                validate(&container, Revision::Eof).map(|_| ()),
+                res,
+                "{}",
+                hex::encode(code)
+            );
+        }
+    }
+}
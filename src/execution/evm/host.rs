@@ -56,6 +56,10 @@ pub struct TxContext {
     pub chain_id: U256,
     /// The block base fee per gas (EIP-1559, EIP-3198).
     pub block_base_fee: U256,
+    /// Versioned hashes of the transaction's blobs (EIP-4844).
+    pub blob_hashes: Vec<U256>,
+    /// The block blob base fee per gas (EIP-7516).
+    pub blob_base_fee: U256,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -80,6 +84,12 @@ pub trait Host {
     fn get_storage(&mut self, address: Address, key: U256) -> U256;
     /// Set value of a storage key.
     fn set_storage(&mut self, address: Address, key: U256, value: U256) -> StorageStatus;
+    /// Get value of a transient storage key (EIP-1153).
+    ///
+    /// Returns `U256::ZERO` if it has not been set in this transaction.
+    fn get_transient_storage(&mut self, address: Address, key: U256) -> U256;
+    /// Set value of a transient storage key (EIP-1153).
+    fn set_transient_storage(&mut self, address: Address, key: U256, value: U256);
     /// Get balance of an account.
     ///
     /// Returns `Ok(0)` if account does not exist.
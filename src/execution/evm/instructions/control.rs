@@ -1,4 +1,9 @@
-use crate::execution::evm::{interpreter::JumpdestMap, state::ExecutionState, StatusCode};
+use crate::execution::evm::{
+    eof::{self, EofHeader},
+    interpreter::JumpdestMap,
+    state::{ExecutionState, STACK_SIZE},
+    StatusCode,
+};
 use ethnum::U256;
 
 #[inline]
@@ -30,6 +35,47 @@ pub(crate) fn op_jump(
     Ok(dst.as_usize())
 }
 
+/// Destination of a relative jump with offset immediate at `offset_pos`,
+/// counted from the instruction at `next`.
+#[inline]
+pub(crate) fn rjump_target(code: &[u8], offset_pos: usize, next: usize) -> usize {
+    (next as isize + eof::read_i16(code, offset_pos) as isize) as usize
+}
+
+#[inline]
+pub(crate) fn rjumpv(state: &mut ExecutionState, code: &[u8], pc: usize) -> usize {
+    let case = state.stack.pop();
+    let max_index = code[pc + 1] as usize;
+    let next = pc + 2 + (max_index + 1) * 2;
+
+    if case <= max_index as u128 {
+        rjump_target(code, pc + 2 + case.as_usize() * 2, next)
+    } else {
+        next
+    }
+}
+
+#[inline]
+pub(crate) fn callf(
+    state: &mut ExecutionState,
+    header: &EofHeader,
+    code: &[u8],
+    pc: usize,
+) -> Result<usize, StatusCode> {
+    let index = eof::read_u16(code, pc + 1) as usize;
+    let ty = &header.types[index];
+
+    if state.stack.len() + ty.max_stack_height as usize - ty.inputs as usize > STACK_SIZE
+        || state.return_stack.len() == eof::RETURN_STACK_LIMIT
+    {
+        return Err(StatusCode::StackOverflow);
+    }
+
+    state.return_stack.push(pc + 3);
+
+    Ok(header.code_sections[index].start)
+}
+
 #[inline]
 pub(crate) fn calldataload(state: &mut ExecutionState) {
     let index = state.stack.pop();
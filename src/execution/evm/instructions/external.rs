@@ -113,6 +113,24 @@ pub(crate) fn chainid_accessor(tx_context: TxContext) -> U256 {
     tx_context.chain_id
 }
 
+#[inline]
+pub(crate) fn blobhash<H: Host>(
+    state: &mut ExecutionState,
+    host: &mut H,
+) -> Result<(), StatusCode> {
+    let index = state.stack.pop();
+
+    let tx_context = host.get_tx_context()?;
+    let hash = if index < u128::try_from(tx_context.blob_hashes.len()).unwrap() {
+        tx_context.blob_hashes[index.as_usize()]
+    } else {
+        U256::ZERO
+    };
+    state.stack.push(hash);
+
+    Ok(())
+}
+
 #[inline]
 pub(crate) fn basefee_accessor(tx_context: TxContext) -> U256 {
     tx_context.block_base_fee
@@ -279,6 +297,29 @@ pub(crate) fn sstore<H: Host, const REVISION: Revision>(
     ok_or_out_of_gas(state.gas_left)
 }
 
+#[inline]
+pub(crate) fn tload<H: Host>(state: &mut ExecutionState, host: &mut H) {
+    let location = state.stack.pop();
+
+    state
+        .stack
+        .push(host.get_transient_storage(state.message.recipient, location));
+}
+
+#[inline]
+pub(crate) fn tstore<H: Host>(state: &mut ExecutionState, host: &mut H) -> Result<(), StatusCode> {
+    if state.message.is_static {
+        return Err(StatusCode::StaticModeViolation);
+    }
+
+    let location = state.stack.pop();
+    let value = state.stack.pop();
+
+    host.set_transient_storage(state.message.recipient, location, value);
+
+    Ok(())
+}
+
 #[inline]
 #[allow(clippy::collapsible_if)]
 pub(crate) fn selfdestruct<H: Host, const REVISION: Revision>(
@@ -166,6 +166,31 @@ pub(crate) fn calldatacopy(state: &mut ExecutionState) -> Result<(), StatusCode>
     Ok(())
 }
 
+#[inline]
+pub(crate) fn mcopy(state: &mut ExecutionState) -> Result<(), StatusCode> {
+    let dst_index = state.stack.pop();
+    let src_index = state.stack.pop();
+    let size = state.stack.pop();
+
+    let region = get_memory_region(state, core::cmp::max(dst_index, src_index), size)
+        .map_err(|_| StatusCode::OutOfGas)?;
+
+    if let Some(region) = region {
+        let copy_cost = num_words(region.size.get()) * 3;
+        state.gas_left -= copy_cost;
+        if state.gas_left < 0 {
+            return Err(StatusCode::OutOfGas);
+        }
+
+        // Both offsets fit into memory once the region for the larger one has been expanded.
+        let dst = dst_index.as_usize();
+        let src = src_index.as_usize();
+        state.memory.copy_within(src..src + region.size.get(), dst);
+    }
+
+    Ok(())
+}
+
 pub(crate) fn keccak256(state: &mut ExecutionState) -> Result<(), StatusCode> {
     let index = state.stack.pop();
     let size = state.stack.pop();
@@ -87,6 +87,8 @@ pub const fn properties<const OPCODE: OpCode>() -> Properties {
         OpCode::CHAINID => Properties::new(0, 1),
         OpCode::SELFBALANCE => Properties::new(0, 1),
         OpCode::BASEFEE => Properties::new(0, 1),
+        OpCode::BLOBHASH => Properties::new(1, 0),
+        OpCode::BLOBBASEFEE => Properties::new(0, 1),
 
         OpCode::POP => Properties::new(1, -1),
         OpCode::MLOAD => Properties::new(1, 0),
@@ -100,6 +102,10 @@ pub const fn properties<const OPCODE: OpCode>() -> Properties {
         OpCode::MSIZE => Properties::new(0, 1),
         OpCode::GAS => Properties::new(0, 1),
         OpCode::JUMPDEST => Properties::new(0, 0),
+        OpCode::TLOAD => Properties::new(1, 0),
+        OpCode::TSTORE => Properties::new(2, -2),
+        OpCode::MCOPY => Properties::new(3, -3),
+        OpCode::PUSH0 => Properties::new(0, 1),
 
         OpCode::PUSH1 => Properties::new(0, 1),
         OpCode::PUSH2 => Properties::new(0, 1),
@@ -174,6 +180,12 @@ pub const fn properties<const OPCODE: OpCode>() -> Properties {
         OpCode::LOG3 => Properties::new(5, -5),
         OpCode::LOG4 => Properties::new(6, -6),
 
+        OpCode::RJUMP => Properties::new(0, 0),
+        OpCode::RJUMPI => Properties::new(1, -1),
+        OpCode::RJUMPV => Properties::new(1, -1),
+        OpCode::CALLF => Properties::new(0, 0),
+        OpCode::RETF => Properties::new(0, 0),
+
         OpCode::CREATE => Properties::new(3, -2),
         OpCode::CALL => Properties::new(7, -6),
         OpCode::CALLCODE => Properties::new(7, -6),
@@ -386,6 +398,23 @@ const fn gas_costs() -> GasCostTable {
 
     table[Revision::Paris as usize] = table[Revision::London as usize];
 
+    table[Revision::Shanghai as usize] = table[Revision::Paris as usize];
+    table[Revision::Shanghai as usize][OpCode::PUSH0.to_usize()] = 2;
+
+    table[Revision::Cancun as usize] = table[Revision::Shanghai as usize];
+    table[Revision::Cancun as usize][OpCode::BLOBHASH.to_usize()] = 3;
+    table[Revision::Cancun as usize][OpCode::BLOBBASEFEE.to_usize()] = 2;
+    table[Revision::Cancun as usize][OpCode::TLOAD.to_usize()] = WARM_STORAGE_READ_COST as i16;
+    table[Revision::Cancun as usize][OpCode::TSTORE.to_usize()] = WARM_STORAGE_READ_COST as i16;
+    table[Revision::Cancun as usize][OpCode::MCOPY.to_usize()] = 3;
+
+    table[Revision::Eof as usize] = table[Revision::Cancun as usize];
+    table[Revision::Eof as usize][OpCode::RJUMP.to_usize()] = 2;
+    table[Revision::Eof as usize][OpCode::RJUMPI.to_usize()] = 4;
+    table[Revision::Eof as usize][OpCode::RJUMPV.to_usize()] = 4;
+    table[Revision::Eof as usize][OpCode::CALLF.to_usize()] = 5;
+    table[Revision::Eof as usize][OpCode::RETF.to_usize()] = 3;
+
     table
 }
 
@@ -476,6 +505,8 @@ const fn property_table() -> [Option<Properties>; 256] {
     table[OpCode::CHAINID.to_usize()] = Some(Properties::new(0, 1));
     table[OpCode::SELFBALANCE.to_usize()] = Some(Properties::new(0, 1));
     table[OpCode::BASEFEE.to_usize()] = Some(Properties::new(0, 1));
+    table[OpCode::BLOBHASH.to_usize()] = Some(Properties::new(1, 0));
+    table[OpCode::BLOBBASEFEE.to_usize()] = Some(Properties::new(0, 1));
 
     table[OpCode::POP.to_usize()] = Some(Properties::new(1, -1));
     table[OpCode::MLOAD.to_usize()] = Some(Properties::new(1, 0));
@@ -489,6 +520,10 @@ const fn property_table() -> [Option<Properties>; 256] {
     table[OpCode::MSIZE.to_usize()] = Some(Properties::new(0, 1));
     table[OpCode::GAS.to_usize()] = Some(Properties::new(0, 1));
     table[OpCode::JUMPDEST.to_usize()] = Some(Properties::new(0, 0));
+    table[OpCode::TLOAD.to_usize()] = Some(Properties::new(1, 0));
+    table[OpCode::TSTORE.to_usize()] = Some(Properties::new(2, -2));
+    table[OpCode::MCOPY.to_usize()] = Some(Properties::new(3, -3));
+    table[OpCode::PUSH0.to_usize()] = Some(Properties::new(0, 1));
 
     table[OpCode::PUSH1.to_usize()] = Some(Properties::new(0, 1));
     table[OpCode::PUSH2.to_usize()] = Some(Properties::new(0, 1));
@@ -563,6 +598,12 @@ const fn property_table() -> [Option<Properties>; 256] {
     table[OpCode::LOG3.to_usize()] = Some(Properties::new(5, -5));
     table[OpCode::LOG4.to_usize()] = Some(Properties::new(6, -6));
 
+    table[OpCode::RJUMP.to_usize()] = Some(Properties::new(0, 0));
+    table[OpCode::RJUMPI.to_usize()] = Some(Properties::new(1, -1));
+    table[OpCode::RJUMPV.to_usize()] = Some(Properties::new(1, -1));
+    table[OpCode::CALLF.to_usize()] = Some(Properties::new(0, 0));
+    table[OpCode::RETF.to_usize()] = Some(Properties::new(0, 0));
+
     table[OpCode::CREATE.to_usize()] = Some(Properties::new(3, -2));
     table[OpCode::CALL.to_usize()] = Some(Properties::new(7, -6));
     table[OpCode::CALLCODE.to_usize()] = Some(Properties::new(7, -6));
@@ -1,13 +1,14 @@
 use self::instruction_table::*;
 use super::{
     common::{InterpreterMessage, *},
+    eof::{self, EofError, EofHeader, SectionType},
     instructions::{control::*, stack_manip::*, *},
     state::*,
     *,
 };
 use crate::models::*;
 use ethnum::U256;
-use std::sync::Arc;
+use std::{mem::size_of, ops::Range, sync::Arc};
 
 #[inline]
 fn check_requirements(
@@ -47,6 +48,7 @@ pub struct AnalyzedCode {
     jumpdest_map: JumpdestMap,
     code: Bytes,
     padded_code: Bytes,
+    eof: Option<Arc<EofHeader>>,
 }
 
 impl AnalyzedCode {
@@ -112,9 +114,70 @@ impl AnalyzedCode {
             jumpdest_map,
             code,
             padded_code,
+            eof: None,
         }
     }
 
+    /// Validate EOF container and prepare it for execution.
+    pub fn analyze_eof(code: &[u8], revision: Revision) -> Result<Self, EofError> {
+        let header = eof::validate(code, revision)?;
+
+        let mut padded_code = code.to_vec();
+        padded_code.push(OpCode::STOP.to_u8());
+        let padded_code = Bytes::from(padded_code);
+        let mut code = padded_code.clone();
+        code.truncate(code.len() - 1);
+
+        Ok(Self {
+            jumpdest_map: JumpdestMap(Vec::<bool>::new().into()),
+            code,
+            padded_code,
+            eof: Some(Arc::new(header)),
+        })
+    }
+
+    /// Analyze code as it would be executed in the given revision.
+    ///
+    /// Invalid EOF containers are analyzed as legacy code, which then fails on the leading 0xEF.
+    pub fn analyze_with_revision(code: &[u8], revision: Revision) -> Self {
+        if revision >= Revision::Eof && eof::is_eof(code) {
+            if let Ok(analysis) = Self::analyze_eof(code, revision) {
+                return analysis;
+            }
+        }
+
+        Self::analyze(code)
+    }
+
+    /// Whether this is a validated EOF container.
+    pub fn is_eof(&self) -> bool {
+        self.eof.is_some()
+    }
+
+    /// Approximate memory footprint of the analysis, in bytes.
+    pub fn size(&self) -> usize {
+        // Analyses made here keep `code` as a prefix sharing the buffer of `padded_code`.
+        let code = if self.code.as_ptr() == self.padded_code.as_ptr() {
+            0
+        } else {
+            self.code.len()
+        };
+
+        size_of::<Self>()
+            + self.jumpdest_map.0.len() * size_of::<bool>()
+            + code
+            + self.padded_code.len()
+            + self
+                .eof
+                .as_deref()
+                .map(|header| {
+                    size_of::<EofHeader>()
+                        + header.types.len() * size_of::<SectionType>()
+                        + header.code_sections.len() * size_of::<Range<usize>>()
+                })
+                .unwrap_or(0)
+    }
+
     /// Execute analyzed EVM bytecode using provided `Host` context.
     pub fn execute<H>(
         &self,
@@ -160,6 +223,15 @@ impl AnalyzedCode {
             (true, Revision::Paris) => {
                 execute_message::<H, true, { Revision::Paris }>(self, &mut state, host)
             }
+            (true, Revision::Shanghai) => {
+                execute_message::<H, true, { Revision::Shanghai }>(self, &mut state, host)
+            }
+            (true, Revision::Cancun) => {
+                execute_message::<H, true, { Revision::Cancun }>(self, &mut state, host)
+            }
+            (true, Revision::Eof) => {
+                execute_message::<H, true, { Revision::Eof }>(self, &mut state, host)
+            }
             (false, Revision::Frontier) => {
                 execute_message::<H, false, { Revision::Frontier }>(self, &mut state, host)
             }
@@ -193,6 +265,15 @@ impl AnalyzedCode {
             (false, Revision::Paris) => {
                 execute_message::<H, false, { Revision::Paris }>(self, &mut state, host)
             }
+            (false, Revision::Shanghai) => {
+                execute_message::<H, false, { Revision::Shanghai }>(self, &mut state, host)
+            }
+            (false, Revision::Cancun) => {
+                execute_message::<H, false, { Revision::Cancun }>(self, &mut state, host)
+            }
+            (false, Revision::Eof) => {
+                execute_message::<H, false, { Revision::Eof }>(self, &mut state, host)
+            }
         };
 
         match res {
@@ -216,11 +297,19 @@ fn execute_message<H, const TRACE: bool, const REVISION: Revision>(
 where
     H: Host,
 {
-    let instruction_table = get_instruction_table(REVISION);
+    // Legacy code keeps the pre-EOF instruction set.
+    let instruction_table = if REVISION >= Revision::Eof && s.eof.is_none() {
+        get_instruction_table(Revision::Cancun)
+    } else {
+        get_instruction_table(REVISION)
+    };
 
     let mut reverted = false;
 
-    let mut pc = 0;
+    let mut pc = s
+        .eof
+        .as_ref()
+        .map_or(0, |header| header.code_sections[0].start);
 
     loop {
         let op = OpCode(s.padded_code[pc]);
@@ -393,6 +482,10 @@ where
                 .push(host.get_tx_context()?.block_gas_limit.into()),
             OpCode::CHAINID => state.stack.push(host.get_tx_context()?.chain_id),
             OpCode::BASEFEE => state.stack.push(host.get_tx_context()?.block_base_fee),
+            OpCode::BLOBHASH => {
+                external::blobhash(state, host)?;
+            }
+            OpCode::BLOBBASEFEE => state.stack.push(host.get_tx_context()?.blob_base_fee),
             OpCode::SELFBALANCE => {
                 external::selfbalance(state, host);
             }
@@ -428,6 +521,14 @@ where
                 .stack
                 .push(u128::try_from(state.gas_left).unwrap().into()),
             OpCode::JUMPDEST => {}
+            OpCode::TLOAD => {
+                external::tload(state, host);
+            }
+            OpCode::TSTORE => {
+                external::tstore(state, host)?;
+            }
+            OpCode::MCOPY => memory::mcopy(state)?,
+            OpCode::PUSH0 => state.stack.push(U256::ZERO),
             OpCode::PUSH1 => {
                 push1(&mut state.stack, s.padded_code[pc + 1]);
                 pc += 1;
@@ -518,6 +619,40 @@ where
             OpCode::STATICCALL => {
                 call::do_call::<_, REVISION, { CallKind::Call }, true>(state, host)?
             }
+            OpCode::RJUMP => {
+                pc = rjump_target(&s.padded_code, pc + 1, pc + 3);
+
+                continue;
+            }
+            OpCode::RJUMPI => {
+                if state.stack.pop() != 0 {
+                    pc = rjump_target(&s.padded_code, pc + 1, pc + 3);
+
+                    continue;
+                } else {
+                    pc += 2;
+                }
+            }
+            OpCode::RJUMPV => {
+                pc = rjumpv(state, &s.padded_code, pc);
+
+                continue;
+            }
+            OpCode::CALLF => {
+                pc = callf(state, s.eof.as_ref().unwrap(), &s.padded_code, pc)?;
+
+                continue;
+            }
+            OpCode::RETF => {
+                if let Some(return_pc) = state.return_stack.pop() {
+                    pc = return_pc;
+
+                    continue;
+                }
+
+                // RETF from the first code section ends execution.
+                break;
+            }
             OpCode::RETURN | OpCode::REVERT => {
                 ret(state)?;
                 reverted = op == OpCode::REVERT;
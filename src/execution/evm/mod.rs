@@ -10,7 +10,11 @@ pub use state::{ExecutionState, Stack};
 /// Maximum allowed EVM bytecode size.
 pub const MAX_CODE_SIZE: usize = 0x6000;
 
+/// Maximum allowed size of contract creation code since Shanghai.
+pub const MAX_INITCODE_SIZE: usize = 2 * MAX_CODE_SIZE;
+
 mod common;
+pub mod eof;
 pub mod host;
 #[macro_use]
 pub mod instructions;
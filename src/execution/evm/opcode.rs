@@ -73,6 +73,8 @@ impl OpCode {
     pub const CHAINID: OpCode = OpCode(0x46);
     pub const SELFBALANCE: OpCode = OpCode(0x47);
     pub const BASEFEE: OpCode = OpCode(0x48);
+    pub const BLOBHASH: OpCode = OpCode(0x49);
+    pub const BLOBBASEFEE: OpCode = OpCode(0x4a);
 
     pub const POP: OpCode = OpCode(0x50);
     pub const MLOAD: OpCode = OpCode(0x51);
@@ -86,6 +88,10 @@ impl OpCode {
     pub const MSIZE: OpCode = OpCode(0x59);
     pub const GAS: OpCode = OpCode(0x5a);
     pub const JUMPDEST: OpCode = OpCode(0x5b);
+    pub const TLOAD: OpCode = OpCode(0x5c);
+    pub const TSTORE: OpCode = OpCode(0x5d);
+    pub const MCOPY: OpCode = OpCode(0x5e);
+    pub const PUSH0: OpCode = OpCode(0x5f);
 
     pub const PUSH1: OpCode = OpCode(0x60);
     pub const PUSH2: OpCode = OpCode(0x61);
@@ -157,6 +163,12 @@ impl OpCode {
     pub const LOG3: OpCode = OpCode(0xa3);
     pub const LOG4: OpCode = OpCode(0xa4);
 
+    pub const RJUMP: OpCode = OpCode(0xe0);
+    pub const RJUMPI: OpCode = OpCode(0xe1);
+    pub const RJUMPV: OpCode = OpCode(0xe2);
+    pub const CALLF: OpCode = OpCode(0xe3);
+    pub const RETF: OpCode = OpCode(0xe4);
+
     pub const CREATE: OpCode = OpCode(0xf0);
     pub const CALL: OpCode = OpCode(0xf1);
     pub const CALLCODE: OpCode = OpCode(0xf2);
@@ -226,6 +238,8 @@ impl OpCode {
             OpCode::CHAINID => "CHAINID",
             OpCode::SELFBALANCE => "SELFBALANCE",
             OpCode::BASEFEE => "BASEFEE",
+            OpCode::BLOBHASH => "BLOBHASH",
+            OpCode::BLOBBASEFEE => "BLOBBASEFEE",
             OpCode::POP => "POP",
             OpCode::MLOAD => "MLOAD",
             OpCode::MSTORE => "MSTORE",
@@ -238,6 +252,10 @@ impl OpCode {
             OpCode::MSIZE => "MSIZE",
             OpCode::GAS => "GAS",
             OpCode::JUMPDEST => "JUMPDEST",
+            OpCode::TLOAD => "TLOAD",
+            OpCode::TSTORE => "TSTORE",
+            OpCode::MCOPY => "MCOPY",
+            OpCode::PUSH0 => "PUSH0",
             OpCode::PUSH1 => "PUSH1",
             OpCode::PUSH2 => "PUSH2",
             OpCode::PUSH3 => "PUSH3",
@@ -307,6 +325,11 @@ impl OpCode {
             OpCode::LOG2 => "LOG2",
             OpCode::LOG3 => "LOG3",
             OpCode::LOG4 => "LOG4",
+            OpCode::RJUMP => "RJUMP",
+            OpCode::RJUMPI => "RJUMPI",
+            OpCode::RJUMPV => "RJUMPV",
+            OpCode::CALLF => "CALLF",
+            OpCode::RETF => "RETF",
             OpCode::CREATE => "CREATE",
             OpCode::CALL => "CALL",
             OpCode::CALLCODE => "CALLCODE",
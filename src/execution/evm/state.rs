@@ -106,6 +106,8 @@ pub struct ExecutionState<'m> {
     #[getset(get = "pub", get_mut = "pub")]
     pub(crate) return_data: Bytes,
     pub(crate) output_data: Bytes,
+    /// Return addresses of active CALLF invocations in EOF code.
+    pub(crate) return_stack: Vec<usize>,
 }
 
 impl<'m> ExecutionState<'m> {
@@ -117,6 +119,7 @@ impl<'m> ExecutionState<'m> {
             message,
             return_data: Default::default(),
             output_data: Bytes::new(),
+            return_stack: Vec::new(),
         }
     }
 }
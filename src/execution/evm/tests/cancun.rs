@@ -0,0 +1,200 @@
+use crate::{
+    execution::evm::{opcode::*, util::*, *},
+    models::*,
+};
+use ethnum::U256;
+use hex_literal::hex;
+
+#[test]
+fn cancun_opcodes_pre_cancun() {
+    for op in [
+        OpCode::TLOAD,
+        OpCode::TSTORE,
+        OpCode::MCOPY,
+        OpCode::BLOBHASH,
+        OpCode::BLOBBASEFEE,
+    ] {
+        EvmTester::new()
+            .revision(Revision::Shanghai)
+            .code(Bytecode::new().opcode(op))
+            .status(StatusCode::UndefinedInstruction)
+            .check()
+    }
+}
+
+#[test]
+fn transient_storage() {
+    // https://eips.ethereum.org/EIPS/eip-1153
+    let t = EvmTester::new().revision(Revision::Cancun);
+    t.clone()
+        .code(
+            Bytecode::new()
+                .pushv(42)
+                .pushv(1)
+                .opcode(OpCode::TSTORE)
+                .opcode(OpCode::STOP),
+        )
+        .status(StatusCode::Success)
+        .gas_used(106)
+        .inspect_host(|host, msg| {
+            let account = &host.accounts[&msg.recipient];
+            assert_eq!(account.transient_storage[&U256::from(1_u128)], 42);
+            assert!(account.storage.is_empty());
+        })
+        .check();
+
+    t.code(
+        Bytecode::new()
+            .pushv(42)
+            .pushv(1)
+            .opcode(OpCode::TSTORE)
+            .pushv(1)
+            .opcode(OpCode::TLOAD)
+            .ret_top(),
+    )
+    .status(StatusCode::Success)
+    .output_value(42_u128)
+    .check()
+}
+
+#[test]
+fn transient_storage_unset() {
+    EvmTester::new()
+        .revision(Revision::Cancun)
+        .code(Bytecode::new().pushv(1).opcode(OpCode::TLOAD).ret_top())
+        .status(StatusCode::Success)
+        .output_value(0_u128)
+        .check()
+}
+
+#[test]
+fn tstore_static() {
+    EvmTester::new()
+        .revision(Revision::Cancun)
+        .set_static(true)
+        .code(Bytecode::new().pushv(42).pushv(1).opcode(OpCode::TSTORE))
+        .status(StatusCode::StaticModeViolation)
+        .check()
+}
+
+#[test]
+fn mcopy() {
+    // https://eips.ethereum.org/EIPS/eip-5656
+    let t = EvmTester::new().revision(Revision::Cancun);
+    t.clone()
+        .code(
+            Bytecode::new()
+                .pushv(32)
+                .pushv(0)
+                .pushv(0)
+                .opcode(OpCode::MCOPY)
+                .opcode(OpCode::STOP),
+        )
+        .status(StatusCode::Success)
+        .gas_used(18)
+        .check();
+
+    t.clone()
+        .code(
+            Bytecode::new()
+                .mstore_value(32, 0xff)
+                .pushv(32)
+                .pushv(32)
+                .pushv(0)
+                .opcode(OpCode::MCOPY)
+                .ret(0, 32),
+        )
+        .status(StatusCode::Success)
+        .output_value(0xff_u128)
+        .check();
+
+    t.code(
+        Bytecode::new()
+            .pushv(0)
+            .pushv(0x1000000)
+            .pushv(0x1000000)
+            .opcode(OpCode::MCOPY)
+            .opcode(OpCode::STOP),
+    )
+    .status(StatusCode::Success)
+    .gas_used(12)
+    .check()
+}
+
+#[test]
+fn mcopy_overlapping() {
+    let data = hex!("000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f");
+    let mut expected = vec![0];
+    expected.extend_from_slice(&data);
+
+    EvmTester::new()
+        .revision(Revision::Cancun)
+        .code(
+            Bytecode::new()
+                .pushb(data)
+                .mstore(0)
+                .pushv(32)
+                .pushv(0)
+                .pushv(1)
+                .opcode(OpCode::MCOPY)
+                .ret(0, 33),
+        )
+        .status(StatusCode::Success)
+        .output_data(expected)
+        .check()
+}
+
+#[test]
+fn blobhash() {
+    // https://eips.ethereum.org/EIPS/eip-4844#opcode-to-get-versioned-hashes
+    let t = EvmTester::new()
+        .revision(Revision::Cancun)
+        .apply_host_fn(|host, _| {
+            host.tx_context.blob_hashes = vec![0x11_u128.into(), 0x22_u128.into()];
+        });
+    t.clone()
+        .code(
+            Bytecode::new()
+                .pushv(0)
+                .opcode(OpCode::BLOBHASH)
+                .opcode(OpCode::STOP),
+        )
+        .status(StatusCode::Success)
+        .gas_used(6)
+        .check();
+
+    t.clone()
+        .code(Bytecode::new().pushv(1).opcode(OpCode::BLOBHASH).ret_top())
+        .status(StatusCode::Success)
+        .output_value(0x22_u128)
+        .check();
+
+    t.code(Bytecode::new().pushv(2).opcode(OpCode::BLOBHASH).ret_top())
+        .status(StatusCode::Success)
+        .output_value(0_u128)
+        .check()
+}
+
+#[test]
+fn blobbasefee() {
+    // https://eips.ethereum.org/EIPS/eip-7516
+    let t = EvmTester::new()
+        .revision(Revision::Cancun)
+        .apply_host_fn(|host, _| {
+            host.tx_context.blob_base_fee = 9_u128.into();
+        });
+    t.clone()
+        .code(
+            Bytecode::new()
+                .opcode(OpCode::BLOBBASEFEE)
+                .opcode(OpCode::STOP),
+        )
+        .status(StatusCode::Success)
+        .gas_used(2)
+        .check();
+
+    t.code(Bytecode::new().opcode(OpCode::BLOBBASEFEE).ret_top())
+        .status(StatusCode::Success)
+        .output_value(9_u128)
+        .check()
+}
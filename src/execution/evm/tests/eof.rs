@@ -0,0 +1,291 @@
+use crate::{
+    execution::evm::{opcode::*, util::*, *},
+    models::*,
+};
+use hex_literal::hex;
+
+/// Builds an EOF container out of (inputs, outputs, max stack height, code) sections.
+fn container(sections: Vec<(u8, u8, u16, Bytecode)>, data: &[u8]) -> Vec<u8> {
+    let mut out = hex!("ef0001 010000").to_vec();
+    out[4..6].copy_from_slice(&(sections.len() as u16 * 4).to_be_bytes());
+    out.push(0x02);
+    out.extend_from_slice(&(sections.len() as u16).to_be_bytes());
+    for (_, _, _, code) in &sections {
+        out.extend_from_slice(&(code.len() as u16).to_be_bytes());
+    }
+    out.push(0x03);
+    out.extend_from_slice(&(data.len() as u16).to_be_bytes());
+    out.push(0x00);
+    for (inputs, outputs, max_stack_height, _) in &sections {
+        out.extend_from_slice(&[*inputs, *outputs]);
+        out.extend_from_slice(&max_stack_height.to_be_bytes());
+    }
+    for (_, _, _, code) in &sections {
+        out.extend_from_slice(code.as_ref());
+    }
+    out.extend_from_slice(data);
+    out
+}
+
+fn main_section(code: Bytecode, max_stack_height: u16) -> Vec<u8> {
+    container(vec![(0, 0, max_stack_height, code)], &[])
+}
+
+#[test]
+fn eof_opcodes_pre_eof() {
+    for op in [
+        OpCode::RJUMP,
+        OpCode::RJUMPI,
+        OpCode::RJUMPV,
+        OpCode::CALLF,
+        OpCode::RETF,
+    ] {
+        EvmTester::new()
+            .revision(Revision::Cancun)
+            .code(Bytecode::new().opcode(op).append(hex!("0000")))
+            .status(StatusCode::UndefinedInstruction)
+            .check()
+    }
+}
+
+#[test]
+fn eof_opcodes_in_legacy_code() {
+    for op in [
+        OpCode::RJUMP,
+        OpCode::RJUMPI,
+        OpCode::RJUMPV,
+        OpCode::CALLF,
+        OpCode::RETF,
+    ] {
+        EvmTester::new()
+            .revision(Revision::Eof)
+            .code(Bytecode::new().opcode(op).append(hex!("0000")))
+            .status(StatusCode::UndefinedInstruction)
+            .check()
+    }
+}
+
+#[test]
+fn container_pre_eof() {
+    EvmTester::new()
+        .revision(Revision::Cancun)
+        .code(main_section(Bytecode::new().opcode(OpCode::STOP), 0))
+        .status(StatusCode::UndefinedInstruction)
+        .check()
+}
+
+#[test]
+fn invalid_container() {
+    // POP on empty stack.
+    EvmTester::new()
+        .revision(Revision::Eof)
+        .code(main_section(
+            Bytecode::new().opcode(OpCode::POP).opcode(OpCode::STOP),
+            0,
+        ))
+        .status(StatusCode::UndefinedInstruction)
+        .check()
+}
+
+#[test]
+fn rjump() {
+    // https://eips.ethereum.org/EIPS/eip-4200
+    let t = EvmTester::new().revision(Revision::Eof);
+    t.clone()
+        .code(main_section(
+            Bytecode::new()
+                .opcode(OpCode::RJUMP)
+                .append(hex!("0000"))
+                .opcode(OpCode::STOP),
+            0,
+        ))
+        .status(StatusCode::Success)
+        .gas_used(2)
+        .check();
+
+    // Jump forward to push the value, then back to return it.
+    t.code(main_section(
+        Bytecode::new()
+            .opcode(OpCode::RJUMP)
+            .append(hex!("0009"))
+            .ret_top()
+            .pushv(0x2a)
+            .opcode(OpCode::RJUMP)
+            .append(hex!("fff2")),
+        2,
+    ))
+    .status(StatusCode::Success)
+    .output_value(0x2a_u128)
+    .check()
+}
+
+#[test]
+fn rjumpi() {
+    let code = |condition: u128| {
+        main_section(
+            Bytecode::new()
+                .pushv(condition)
+                .opcode(OpCode::RJUMPI)
+                .append(hex!("000b"))
+                .pushv(0x0b)
+                .ret_top()
+                .pushv(0x2a)
+                .ret_top(),
+            2,
+        )
+    };
+
+    let t = EvmTester::new().revision(Revision::Eof);
+    t.clone()
+        .code(main_section(
+            Bytecode::new()
+                .pushv(0)
+                .opcode(OpCode::RJUMPI)
+                .append(hex!("0000"))
+                .opcode(OpCode::STOP),
+            1,
+        ))
+        .status(StatusCode::Success)
+        .gas_used(7)
+        .check();
+    t.clone()
+        .code(code(1))
+        .status(StatusCode::Success)
+        .output_value(0x2a_u128)
+        .check();
+    t.code(code(0))
+        .status(StatusCode::Success)
+        .output_value(0x0b_u128)
+        .check()
+}
+
+#[test]
+fn rjumpv() {
+    let code = |case: u128| {
+        main_section(
+            Bytecode::new()
+                .pushv(case)
+                .opcode(OpCode::RJUMPV)
+                .append(hex!("01 000b 0016"))
+                .pushv(0x0c)
+                .ret_top()
+                .pushv(0x0a)
+                .ret_top()
+                .pushv(0x0b)
+                .ret_top(),
+            2,
+        )
+    };
+
+    let t = EvmTester::new().revision(Revision::Eof);
+    t.clone()
+        .code(main_section(
+            Bytecode::new()
+                .pushv(0)
+                .opcode(OpCode::RJUMPV)
+                .append(hex!("00 0000"))
+                .opcode(OpCode::STOP),
+            1,
+        ))
+        .status(StatusCode::Success)
+        .gas_used(7)
+        .check();
+    for (case, expected) in [(0_u128, 0x0a_u128), (1, 0x0b), (2, 0x0c), (u128::MAX, 0x0c)] {
+        t.clone()
+            .code(code(case))
+            .status(StatusCode::Success)
+            .output_value(expected)
+            .check();
+    }
+}
+
+#[test]
+fn callf_retf() {
+    // https://eips.ethereum.org/EIPS/eip-4750
+    EvmTester::new()
+        .revision(Revision::Eof)
+        .code(container(
+            vec![
+                (
+                    0,
+                    0,
+                    2,
+                    Bytecode::new()
+                        .pushv(2)
+                        .pushv(3)
+                        .opcode(OpCode::CALLF)
+                        .append(hex!("0001"))
+                        .ret_top(),
+                ),
+                (
+                    2,
+                    1,
+                    2,
+                    Bytecode::new().opcode(OpCode::ADD).opcode(OpCode::RETF),
+                ),
+            ],
+            &[],
+        ))
+        .status(StatusCode::Success)
+        .output_value(5_u128)
+        .check()
+}
+
+#[test]
+fn callf_return_stack_overflow() {
+    EvmTester::new()
+        .revision(Revision::Eof)
+        .code(container(
+            vec![
+                (
+                    0,
+                    0,
+                    0,
+                    Bytecode::new()
+                        .opcode(OpCode::CALLF)
+                        .append(hex!("0001"))
+                        .opcode(OpCode::STOP),
+                ),
+                (
+                    0,
+                    0,
+                    0,
+                    Bytecode::new()
+                        .opcode(OpCode::CALLF)
+                        .append(hex!("0001"))
+                        .opcode(OpCode::RETF),
+                ),
+            ],
+            &[],
+        ))
+        .status(StatusCode::StackOverflow)
+        .check()
+}
+
+#[test]
+fn codecopy_sees_whole_container() {
+    let data = hex!("deadbeef");
+    let code = container(
+        vec![(
+            0,
+            0,
+            3,
+            Bytecode::new()
+                .opcode(OpCode::CODESIZE)
+                .pushv(0)
+                .pushv(0)
+                .opcode(OpCode::CODECOPY)
+                .opcode(OpCode::CODESIZE)
+                .pushv(0)
+                .opcode(OpCode::RETURN),
+        )],
+        &data,
+    );
+
+    EvmTester::new()
+        .revision(Revision::Eof)
+        .code(code.clone())
+        .status(StatusCode::Success)
+        .output_data(code)
+        .check()
+}
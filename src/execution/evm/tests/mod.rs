@@ -1,6 +1,9 @@
 mod basefee;
 mod call;
+mod cancun;
 mod eip2929;
+mod eof;
 mod execute;
 mod other;
+mod shanghai;
 mod state;
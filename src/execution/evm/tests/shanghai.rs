@@ -0,0 +1,52 @@
+use crate::{
+    execution::evm::{opcode::*, util::*, *},
+    models::*,
+};
+
+#[test]
+fn push0_pre_shanghai() {
+    EvmTester::new()
+        .revision(Revision::Paris)
+        .code(Bytecode::new().opcode(OpCode::PUSH0))
+        .status(StatusCode::UndefinedInstruction)
+        .check()
+}
+
+#[test]
+fn push0() {
+    // https://eips.ethereum.org/EIPS/eip-3855
+    let t = EvmTester::new().revision(Revision::Shanghai);
+    t.clone()
+        .code(Bytecode::new().opcode(OpCode::PUSH0).opcode(OpCode::STOP))
+        .status(StatusCode::Success)
+        .gas_used(2)
+        .check();
+
+    t.code(
+        Bytecode::new()
+            .pushv(1)
+            .opcode(OpCode::PUSH0)
+            .opcode(OpCode::ADD)
+            .ret_top(),
+    )
+    .status(StatusCode::Success)
+    .output_value(1_u128)
+    .check()
+}
+
+#[test]
+fn create_initcode_size_limit() {
+    // https://eips.ethereum.org/EIPS/eip-3860
+    EvmTester::new()
+        .revision(Revision::Shanghai)
+        .code(
+            Bytecode::new()
+                .pushv(MAX_INITCODE_SIZE as u128 + 1)
+                .pushv(0)
+                .pushv(0)
+                .opcode(OpCode::CREATE),
+        )
+        .gas(10_000_000)
+        .status(StatusCode::OutOfGas)
+        .check()
+}
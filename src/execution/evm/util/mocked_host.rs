@@ -46,6 +46,8 @@ pub struct Account {
     pub balance: U256,
     /// The account storage map.
     pub storage: HashMap<U256, StorageValue>,
+    /// The account transient storage map.
+    pub transient_storage: HashMap<U256, U256>,
 }
 
 const MAX_RECORDED_ACCOUNT_ACCESSES: usize = 200;
@@ -86,6 +88,8 @@ impl Default for MockedHost {
                 block_difficulty: U256::ZERO,
                 chain_id: U256::ZERO,
                 block_base_fee: U256::ZERO,
+                blob_hashes: Vec::new(),
+                blob_base_fee: U256::ZERO,
             },
             block_hash: U256::ZERO,
             call_result: Output {
@@ -167,6 +171,21 @@ impl Host for MockedHost {
         status
     }
 
+    fn get_transient_storage(&mut self, address: ethereum_types::Address, key: U256) -> U256 {
+        self.accounts
+            .get(&address)
+            .and_then(|account| account.transient_storage.get(&key).copied())
+            .unwrap_or(U256::ZERO)
+    }
+
+    fn set_transient_storage(&mut self, address: ethereum_types::Address, key: U256, value: U256) {
+        self.accounts
+            .entry(address)
+            .or_default()
+            .transient_storage
+            .insert(key, value);
+    }
+
     fn get_balance(&mut self, address: ethereum_types::Address) -> ethnum::U256 {
         self.recorded.record_account_access(address);
 
@@ -22,7 +22,7 @@ fn exec(
         host.access_account(message.sender);
         host.access_account(message.recipient);
     }
-    let code = AnalyzedCode::analyze(&code);
+    let code = AnalyzedCode::analyze_with_revision(&code, revision);
 
     code.execute(host, &message, revision)
 }
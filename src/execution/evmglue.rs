@@ -6,9 +6,10 @@ use super::{
 };
 use crate::{
     chain::protocol_param::{fee, param},
+    consensus::calc_blob_base_fee,
     crypto::keccak256,
     execution::evm::{
-        host::*, AnalyzedCode, CallKind, CreateMessage, InterpreterMessage, Output, StatusCode,
+        eof, host::*, AnalyzedCode, CallKind, CreateMessage, InterpreterMessage, Output, StatusCode,
     },
     h256_to_u256,
     models::*,
@@ -39,6 +40,7 @@ where
     message: &'t Message,
     sender: Address,
     beneficiary: Address,
+    blob_base_fee: U256,
 }
 
 pub fn execute<'db, 'tracer, 'analysis, B: HeaderReader + StateReader>(
@@ -52,6 +54,39 @@ pub fn execute<'db, 'tracer, 'analysis, B: HeaderReader + StateReader>(
     beneficiary: Address,
     gas: u64,
 ) -> anyhow::Result<CallResult> {
+    execute_with_blob_base_fee(
+        state,
+        tracer,
+        analysis_cache,
+        header,
+        block_spec,
+        message,
+        sender,
+        beneficiary,
+        gas,
+        None,
+    )
+}
+
+/// Same as [`execute`], but with blob base fee replaced by `blob_base_fee` if given, rather than
+/// derived from excess blob gas of the header.
+pub fn execute_with_blob_base_fee<'db, 'tracer, 'analysis, B: HeaderReader + StateReader>(
+    state: &mut IntraBlockState<'db, B>,
+    tracer: &'tracer mut dyn Tracer,
+    analysis_cache: &'analysis mut AnalysisCache,
+    header: &BlockHeader,
+    block_spec: &BlockExecutionSpec,
+    message: &Message,
+    sender: Address,
+    beneficiary: Address,
+    gas: u64,
+    blob_base_fee: Option<U256>,
+) -> anyhow::Result<CallResult> {
+    // https://eips.ethereum.org/EIPS/eip-3651
+    if block_spec.revision >= Revision::Shanghai {
+        state.access_account(beneficiary);
+    }
+
     let mut evm = Evm {
         header,
         tracer,
@@ -61,6 +96,12 @@ pub fn execute<'db, 'tracer, 'analysis, B: HeaderReader + StateReader>(
         message,
         sender,
         beneficiary,
+        blob_base_fee: blob_base_fee.unwrap_or_else(|| {
+            header
+                .excess_blob_gas
+                .map(calc_blob_base_fee)
+                .unwrap_or(U256::ZERO)
+        }),
     };
 
     let res = if let TransactionAction::Call(to) = message.action() {
@@ -142,6 +183,29 @@ where
             message.endowment,
         );
 
+        let res = self.create_contract(message, contract_addr)?;
+
+        self.tracer.capture_end(
+            message.depth.try_into().unwrap(),
+            message.gas.try_into().unwrap(),
+            &res,
+        );
+
+        Ok(res)
+    }
+
+    fn create_contract(
+        &mut self,
+        message: &CreateMessage,
+        contract_addr: Address,
+    ) -> anyhow::Result<Output> {
+        let mut res = Output {
+            status_code: StatusCode::Success,
+            gas_left: message.gas,
+            output_data: Bytes::new(),
+            create_address: None,
+        };
+
         if self.state.get_nonce(contract_addr)? != 0
             || self.state.get_code_hash(contract_addr)? != EMPTY_HASH
         {
@@ -151,6 +215,22 @@ where
             return Ok(res);
         }
 
+        let revision = self.block_spec.revision;
+        let eof_initcode = revision >= Revision::Eof && eof::is_eof(&message.initcode);
+        let initcode = if eof_initcode {
+            // https://eips.ethereum.org/EIPS/eip-3540
+            match AnalyzedCode::analyze_eof(&message.initcode, revision) {
+                Ok(analysis) => analysis,
+                Err(_) => {
+                    res.status_code = StatusCode::ContractValidationFailure;
+                    res.gas_left = 0;
+                    return Ok(res);
+                }
+            }
+        } else {
+            AnalyzedCode::analyze(&message.initcode)
+        };
+
         let snapshot = self.state.take_snapshot();
 
         self.state.create_contract(contract_addr)?;
@@ -159,8 +239,10 @@ where
             self.state.set_nonce(contract_addr, 1)?;
         }
 
-        self.state.subtract_from_balance(message.sender, value)?;
-        self.state.add_to_balance(contract_addr, value)?;
+        self.state
+            .subtract_from_balance(message.sender, message.endowment)?;
+        self.state
+            .add_to_balance(contract_addr, message.endowment)?;
 
         let deploy_message = InterpreterMessage {
             kind: CallKind::Call,
@@ -174,17 +256,21 @@ where
             value: message.endowment,
         };
 
-        res = self.execute(&deploy_message, &message.initcode, None)?;
+        res = self.execute_analyzed(&deploy_message, &initcode);
 
         if res.status_code == StatusCode::Success {
             let code_len = res.output_data.len();
             let code_deploy_gas = code_len as u64 * fee::G_CODE_DEPOSIT;
 
-            if self.block_spec.revision >= Revision::London
-                && code_len > 0
-                && res.output_data[0] == 0xEF
-            {
+            let invalid_code = if eof_initcode {
+                // https://eips.ethereum.org/EIPS/eip-3540
+                eof::validate(&res.output_data, revision).is_err()
+            } else {
                 // https://eips.ethereum.org/EIPS/eip-3541
+                revision >= Revision::London && code_len > 0 && res.output_data[0] == 0xEF
+            };
+
+            if invalid_code {
                 res.status_code = StatusCode::ContractValidationFailure;
             } else if self.block_spec.revision >= Revision::Spurious
                 && code_len > param::MAX_CODE_SIZE
@@ -249,6 +335,19 @@ where
             message.value,
         );
 
+        let res = self.call_code(message, code_kind)?;
+
+        self.tracer
+            .capture_end(message.depth as usize, message.gas as u64, &res);
+
+        Ok(res)
+    }
+
+    fn call_code(
+        &mut self,
+        message: &InterpreterMessage,
+        code_kind: CodeKind,
+    ) -> anyhow::Result<Output> {
         // https://eips.ethereum.org/EIPS/eip-161
         if message.value == 0
             && self.block_spec.revision >= Revision::Spurious
@@ -295,8 +394,7 @@ where
                 self.execute(message, code, Some(&code_hash))?
             }
             CodeKind::Precompile => {
-                let num = message.code_address.0[ADDRESS_LENGTH - 1] as usize;
-                let contract = &precompiled::CONTRACTS[num - 1];
+                let contract = precompiled::get(self.block_spec, message.code_address).unwrap();
                 let input = message.input_data.clone();
                 let mut res = Output {
                     status_code: StatusCode::Success,
@@ -304,12 +402,13 @@ where
                     output_data: Bytes::new(),
                     create_address: None,
                 };
-                if let Some(gas) = (contract.gas)(input.clone(), self.block_spec.revision)
+                if let Some(gas) = contract
+                    .gas(input.clone(), self.block_spec.revision)
                     .and_then(|g| i64::try_from(g).ok())
                 {
                     if gas > message.gas {
                         res.status_code = StatusCode::OutOfGas;
-                    } else if let Some(output) = (contract.run)(input) {
+                    } else if let Some(output) = contract.run(input) {
                         res.status_code = StatusCode::Success;
                         res.gas_left = message.gas - gas;
                         res.output_data = output;
@@ -339,55 +438,37 @@ where
         code: &[u8],
         code_hash: Option<&H256>,
     ) -> anyhow::Result<Output> {
+        let revision = self.block_spec.revision;
+        let eof_code = revision >= Revision::Eof && eof::is_eof(code);
+
         let analysis = if let Some(code_hash) = code_hash {
-            if let Some(cache) = self.analysis_cache.get(code_hash).cloned() {
+            // Same code may have been analyzed as legacy before EOF activation. Containers failing
+            // validation are cached in their legacy analysis, so they are not validated again.
+            if let Some(cache) = self.analysis_cache.get(code_hash, eof_code) {
                 cache
             } else {
-                let analysis = AnalyzedCode::analyze(code);
-                self.analysis_cache.put(*code_hash, analysis.clone());
+                let analysis = AnalyzedCode::analyze_with_revision(code, revision);
+                self.analysis_cache
+                    .put(*code_hash, eof_code, analysis.clone());
                 analysis
             }
         } else {
-            AnalyzedCode::analyze(code)
+            AnalyzedCode::analyze_with_revision(code, revision)
         };
 
+        Ok(self.execute_analyzed(msg, &analysis))
+    }
+
+    fn execute_analyzed(&mut self, msg: &InterpreterMessage, analysis: &AnalyzedCode) -> Output {
         let revision = self.block_spec.revision;
 
         let mut host = EvmHost { inner: self };
 
-        let output = analysis.execute(&mut host, msg, revision);
-
-        self.tracer.capture_end(
-            msg.depth.try_into().unwrap(),
-            msg.gas.try_into().unwrap(),
-            &output,
-        );
-
-        Ok(output)
-    }
-
-    fn number_of_precompiles(&self) -> u8 {
-        match self.block_spec.revision {
-            Revision::Frontier | Revision::Homestead | Revision::Tangerine | Revision::Spurious => {
-                precompiled::NUM_OF_FRONTIER_CONTRACTS as u8
-            }
-            Revision::Byzantium | Revision::Constantinople | Revision::Petersburg => {
-                precompiled::NUM_OF_BYZANTIUM_CONTRACTS as u8
-            }
-            Revision::Istanbul | Revision::Berlin | Revision::London | Revision::Paris => {
-                precompiled::NUM_OF_ISTANBUL_CONTRACTS as u8
-            }
-        }
+        analysis.execute(&mut host, msg, revision)
     }
 
     fn is_precompiled(&self, contract: Address) -> bool {
-        if contract.is_zero() {
-            false
-        } else {
-            let mut max_precompiled = Address::zero();
-            max_precompiled.0[ADDRESS_LENGTH - 1] = self.number_of_precompiles() as u8;
-            contract <= max_precompiled
-        }
+        precompiled::get(self.block_spec, contract).is_some()
     }
 }
 
@@ -515,6 +596,16 @@ impl<'r, 'state, 'tracer, 'analysis, 'h, 'c, 't, 'a, B: HeaderReader + StateRead
         }
     }
 
+    fn get_transient_storage(&mut self, address: Address, location: U256) -> U256 {
+        self.inner.state.get_transient_storage(address, location)
+    }
+
+    fn set_transient_storage(&mut self, address: Address, location: U256, value: U256) {
+        self.inner
+            .state
+            .set_transient_storage(address, location, value)
+    }
+
     fn get_balance(&mut self, address: Address) -> U256 {
         self.inner.state.get_balance(address).unwrap()
     }
@@ -560,13 +651,27 @@ impl<'r, 'state, 'tracer, 'analysis, 'h, 'c, 't, 'a, B: HeaderReader + StateRead
     }
 
     fn selfdestruct(&mut self, address: Address, beneficiary: Address) {
-        self.inner.state.record_selfdestruct(address);
         let balance = self.inner.state.get_balance(address).unwrap();
-        self.inner
-            .state
-            .add_to_balance(beneficiary, balance)
-            .unwrap();
-        self.inner.state.set_balance(address, 0).unwrap();
+
+        // https://eips.ethereum.org/EIPS/eip-6780
+        if self.inner.block_spec.revision >= Revision::Cancun
+            && !self.inner.state.created_in_transaction(address)
+        {
+            if beneficiary != address {
+                self.inner
+                    .state
+                    .add_to_balance(beneficiary, balance)
+                    .unwrap();
+                self.inner.state.set_balance(address, 0).unwrap();
+            }
+        } else {
+            self.inner.state.record_selfdestruct(address);
+            self.inner
+                .state
+                .add_to_balance(beneficiary, balance)
+                .unwrap();
+            self.inner.state.set_balance(address, 0).unwrap();
+        }
 
         self.tracer(|t| t.capture_self_destruct(address, beneficiary, balance));
     }
@@ -607,6 +712,15 @@ impl<'r, 'state, 'tracer, 'analysis, 'h, 'c, 't, 'a, B: HeaderReader + StateRead
         };
         let chain_id = self.inner.block_spec.params.chain_id.0.into();
         let block_base_fee = base_fee_per_gas;
+        let blob_hashes = self
+            .inner
+            .message
+            .blob_versioned_hashes()
+            .iter()
+            .copied()
+            .map(h256_to_u256)
+            .collect();
+        let blob_base_fee = self.inner.blob_base_fee;
 
         Ok(TxContext {
             tx_gas_price,
@@ -618,6 +732,8 @@ impl<'r, 'state, 'tracer, 'analysis, 'h, 'c, 't, 'a, B: HeaderReader + StateRead
             block_difficulty,
             chain_id,
             block_base_fee,
+            blob_hashes,
+            blob_base_fee,
         })
     }
 
@@ -652,6 +768,8 @@ impl<'r, 'state, 'tracer, 'analysis, 'h, 'c, 't, 'a, B: HeaderReader + StateRead
     }
 
     fn access_account(&mut self, address: Address) -> AccessStatus {
+        self.inner.tracer.capture_account_access(address);
+
         if self.inner.is_precompiled(address) {
             AccessStatus::Warm
         } else {
@@ -660,6 +778,8 @@ impl<'r, 'state, 'tracer, 'analysis, 'h, 'c, 't, 'a, B: HeaderReader + StateRead
     }
 
     fn access_storage(&mut self, address: Address, location: U256) -> AccessStatus {
+        self.inner.tracer.capture_storage_access(address, location);
+
         self.inner.state.access_storage(address, location)
     }
 }
@@ -667,7 +787,11 @@ impl<'r, 'state, 'tracer, 'analysis, 'h, 'c, 't, 'a, B: HeaderReader + StateRead
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{execution::tracer::NoopTracer, res::chainspec::MAINNET, InMemoryState};
+    use crate::{
+        execution::tracer::{AccessListTracer, NoopTracer},
+        res::chainspec::MAINNET,
+        InMemoryState,
+    };
     use bytes_literal::bytes;
     use hex_literal::hex;
 
@@ -686,7 +810,7 @@ mod tests {
             &mut tracer,
             &mut AnalysisCache::default(),
             &header,
-            &MAINNET.collect_block_spec(header.number),
+            &MAINNET.collect_block_spec(header.number, header.timestamp),
             message,
             sender,
             beneficiary,
@@ -1143,4 +1267,159 @@ mod tests {
             StatusCode::Success
         );
     }
+
+    #[test]
+    fn eof_deployment() {
+        let mut spec = MAINNET.clone();
+        spec.upgrades.eof_time = Some(0);
+
+        let header = BlockHeader::new(
+            PartialHeader {
+                number: 20_000_000.into(),
+                timestamp: 1_720_000_000,
+                ..PartialHeader::empty()
+            },
+            EMPTY_LIST_HASH,
+            EMPTY_ROOT,
+        );
+        let block_spec = spec.collect_block_spec(header.number, header.timestamp);
+        assert_eq!(block_spec.revision, Revision::Eof);
+
+        let sender = hex!("1000000000000000000000000000000000000000").into();
+
+        let mut db = InMemoryState::default();
+        let mut state = IntraBlockState::new(&mut db);
+
+        let mut create = |initcode: &[u8]| {
+            let message = Message::Legacy {
+                action: TransactionAction::Create,
+                input: initcode.to_vec().into(),
+
+                chain_id: Default::default(),
+                nonce: Default::default(),
+                gas_price: Default::default(),
+                gas_limit: Default::default(),
+                value: Default::default(),
+            };
+            let address = create_address(sender, state.get_nonce(sender).unwrap());
+            let res = super::execute(
+                &mut state,
+                &mut NoopTracer,
+                &mut AnalysisCache::default(),
+                &header,
+                &block_spec,
+                &message,
+                sender,
+                header.beneficiary,
+                100_000,
+            )
+            .unwrap();
+            (res.status_code, state.get_code(address).unwrap())
+        };
+
+        let runtime = hex!("ef0001 010004 0200010001 030000 00 00000000 00");
+
+        // Initcode copies its data section to memory and returns it.
+        let initcode = [
+            &hex!("ef0001 010004 020001000c 030014 00 00000003")[..],
+            &hex!("6014 601f 6000 39 6014 6000 f3"),
+            &runtime,
+        ]
+        .concat();
+        assert_eq!(
+            create(&initcode),
+            (StatusCode::Success, Some(runtime.to_vec().into()))
+        );
+
+        // EOF initcode may only deploy valid EOF code.
+        let initcode = [
+            &hex!("ef0001 010004 020001000c 030001 00 00000003")[..],
+            &hex!("6001 601f 6000 39 6001 6000 f3"),
+            &hex!("fe"),
+        ]
+        .concat();
+        assert_eq!(
+            create(&initcode),
+            (StatusCode::ContractValidationFailure, None)
+        );
+
+        // Initcode itself fails validation.
+        assert_eq!(
+            create(&hex!("ef0001 010004 0200010002 030000 00 00000000 5000")),
+            (StatusCode::ContractValidationFailure, None)
+        );
+    }
+
+    #[test]
+    fn access_list_tracer() {
+        let header = BlockHeader::new(
+            PartialHeader {
+                number: 15_000_000.into(),
+                ..PartialHeader::empty()
+            },
+            EMPTY_LIST_HASH,
+            EMPTY_ROOT,
+        );
+        let block_spec = MAINNET.collect_block_spec(header.number, header.timestamp);
+
+        let sender = hex!("1000000000000000000000000000000000000000").into();
+        let caller_address = hex!("2000000000000000000000000000000000000000").into();
+        let callee_address: Address = hex!("3000000000000000000000000000000000000000").into();
+
+        // Calls the callee, then the identity precompile.
+        let caller_code = [
+            &hex!("6000 6000 6000 6000 6000 73")[..],
+            &callee_address.0,
+            &hex!("5a f1"),
+            &hex!("6000 6000 6000 6000 6000 6004 5a f1 00"),
+        ]
+        .concat();
+        // Reads storage slot 1.
+        let callee_code = hex!("600154 00");
+
+        let mut db = InMemoryState::default();
+        let mut state = IntraBlockState::new(&mut db);
+        state.set_code(caller_address, caller_code.into()).unwrap();
+        state
+            .set_code(callee_address, callee_code.to_vec().into())
+            .unwrap();
+
+        let message = Message::Legacy {
+            action: TransactionAction::Call(caller_address),
+
+            chain_id: Default::default(),
+            nonce: Default::default(),
+            gas_price: Default::default(),
+            gas_limit: Default::default(),
+            value: Default::default(),
+            input: Default::default(),
+        };
+
+        let mut tracer = AccessListTracer::new(
+            &block_spec,
+            [sender, caller_address].into_iter().collect(),
+            &[],
+        );
+        let res = super::execute(
+            &mut state,
+            &mut tracer,
+            &mut AnalysisCache::default(),
+            &header,
+            &block_spec,
+            &message,
+            sender,
+            header.beneficiary,
+            100_000,
+        )
+        .unwrap();
+        assert_eq!(res.status_code, StatusCode::Success);
+
+        assert_eq!(
+            tracer.into_access_list(),
+            vec![AccessListItem {
+                address: callee_address,
+                slots: vec![H256::from_low_u64_be(1)],
+            }]
+        );
+    }
 }
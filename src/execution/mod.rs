@@ -9,6 +9,7 @@ pub mod address;
 pub mod analysis_cache;
 pub mod evm;
 pub mod evmglue;
+pub mod parallel;
 pub mod precompiled;
 pub mod processor;
 pub mod tracer;
@@ -23,7 +24,7 @@ pub fn execute_block<S: State>(
     let mut engine = consensus::engine_factory(None, config.clone())?;
     let mut tracer = NoopTracer;
 
-    let config = config.collect_block_spec(header.number);
+    let config = config.collect_block_spec(header.number, header.timestamp);
     ExecutionProcessor::new(
         state,
         &mut tracer,
@@ -150,6 +151,7 @@ mod tests {
             &BlockBodyWithSenders {
                 transactions: vec![tx],
                 ommers: vec![],
+                withdrawals: None,
             },
         )
         .unwrap();
@@ -201,6 +203,7 @@ mod tests {
             &BlockBodyWithSenders {
                 transactions: vec![tx],
                 ommers: vec![],
+                withdrawals: None,
             },
         )
         .unwrap();
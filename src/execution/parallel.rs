@@ -0,0 +1,664 @@
+//! Optimistic parallel execution of block transactions, in the spirit of Block-STM.
+//!
+//! All transactions of a block are first executed speculatively on a rayon pool, each against
+//! the state at the beginning of the block, recording every account and storage value they read.
+//! Results are then committed in block order: a transaction whose reads still match the
+//! committed state is applied as is, otherwise it is re-executed sequentially. This makes the
+//! outcome identical to sequential execution.
+//!
+//! Workers never touch the executed state itself, but read it through
+//! [`StateReader::concurrent_reader`], e.g. a read-only database transaction per worker.
+
+use super::{
+    analysis_cache::AnalysisCache,
+    evm::Output,
+    processor::{execute_transaction_with_deferred_reward, validate_transaction},
+    tracer::{MessageKind, Tracer},
+};
+use crate::{
+    consensus::DuoError,
+    models::*,
+    state::{IntraBlockState, WriteSet},
+    ConcurrentReader, HeaderReader, StateReader,
+};
+use anyhow::format_err;
+use bytes::Bytes;
+use parking_lot::Mutex;
+use rayon::prelude::*;
+use std::collections::HashMap;
+use tracing::*;
+
+/// Values observed by a speculatively executed transaction.
+#[derive(Debug, Default)]
+pub struct ReadSet {
+    pub accounts: HashMap<Address, Option<Account>>,
+    pub storage: HashMap<(Address, U256), U256>,
+}
+
+/// State at the beginning of the block as seen by speculatively executed transactions.
+///
+/// Changes made within the block so far are taken from `base`, the rest is read via `reader`.
+#[derive(Debug)]
+struct SpeculativeState<'a, 'db, S>
+where
+    S: StateReader,
+{
+    base: &'a IntraBlockState<'db, S>,
+    reader: &'a dyn ConcurrentReader,
+    reads: Mutex<ReadSet>,
+    error: Mutex<Option<anyhow::Error>>,
+}
+
+impl<'a, 'db, S> SpeculativeState<'a, 'db, S>
+where
+    S: StateReader,
+{
+    fn new(base: &'a IntraBlockState<'db, S>, reader: &'a dyn ConcurrentReader) -> Self {
+        Self {
+            base,
+            reader,
+            reads: Default::default(),
+            error: Default::default(),
+        }
+    }
+
+    /// EVM cannot be interrupted by a failed read, so execution goes on with a placeholder value
+    /// and its result is discarded afterwards.
+    fn fail<T>(&self, error: anyhow::Error, placeholder: T) -> T {
+        self.error.lock().get_or_insert(error);
+        placeholder
+    }
+}
+
+impl<'a, 'db, S> HeaderReader for SpeculativeState<'a, 'db, S>
+where
+    S: StateReader,
+{
+    fn read_header(
+        &self,
+        block_number: BlockNumber,
+        block_hash: H256,
+    ) -> anyhow::Result<Option<BlockHeader>> {
+        // Reader may lag behind the executed state, so a missing header is not final.
+        Ok(Some(
+            match self.reader.read_header(block_number, block_hash) {
+                Ok(Some(header)) => header,
+                Ok(None) => self.fail(
+                    format_err!("header #{block_number}/{block_hash} not found"),
+                    BlockHeader::empty(),
+                ),
+                Err(e) => self.fail(e, BlockHeader::empty()),
+            },
+        ))
+    }
+}
+
+impl<'a, 'db, S> StateReader for SpeculativeState<'a, 'db, S>
+where
+    S: StateReader,
+{
+    fn read_account(&self, address: Address) -> anyhow::Result<Option<Account>> {
+        let account = if let Some(object) = self.base.objects.get(&address) {
+            object.current
+        } else {
+            match self.reader.read_account(address) {
+                Ok(account) => account,
+                Err(e) => return Ok(self.fail(e, None)),
+            }
+        };
+
+        self.reads.lock().accounts.insert(address, account);
+
+        Ok(account)
+    }
+
+    fn read_code(&self, code_hash: H256) -> anyhow::Result<Bytes> {
+        if let Some(code) = self.base.new_code.get(&code_hash) {
+            return Ok(code.clone());
+        }
+
+        // Code is not validated when committing, so it must not be missing.
+        Ok(match self.reader.read_code(code_hash) {
+            Ok(code) if code.is_empty() && code_hash != EMPTY_HASH => {
+                self.fail(format_err!("code {code_hash} not found"), Bytes::new())
+            }
+            Ok(code) => code,
+            Err(e) => self.fail(e, Bytes::new()),
+        })
+    }
+
+    fn read_storage(&self, address: Address, location: U256) -> anyhow::Result<U256> {
+        let storage = self.base.storage.get(&address);
+        let value = if let Some(&value) = storage.and_then(|storage| storage.current.get(&location))
+        {
+            value
+        } else if let Some(value) = storage.and_then(|storage| storage.committed.get(&location)) {
+            value.original
+        } else if self.base.incarnations.contains_key(&address)
+            || matches!(self.base.objects.get(&address), Some(object) if object.initial.is_none())
+        {
+            U256::ZERO
+        } else {
+            match self.reader.read_storage(address, location) {
+                Ok(value) => value,
+                Err(e) => return Ok(self.fail(e, U256::ZERO)),
+            }
+        };
+
+        self.reads.lock().storage.insert((address, location), value);
+
+        Ok(value)
+    }
+}
+
+#[derive(Debug)]
+enum TracerEvent {
+    Start {
+        depth: u16,
+        from: Address,
+        to: Address,
+        call_type: MessageKind,
+        input: Bytes,
+        gas: u64,
+        value: U256,
+    },
+    End {
+        depth: usize,
+        start_gas: u64,
+        output: Output,
+    },
+    SelfDestruct {
+        caller: Address,
+        beneficiary: Address,
+        balance: U256,
+    },
+    AccountRead(Address),
+    AccountWrite(Address),
+    AccountAccess(Address),
+    StorageAccess {
+        address: Address,
+        location: U256,
+    },
+}
+
+/// Buffers tracer calls of a speculatively executed transaction until it is committed.
+#[derive(Debug, Default)]
+pub struct RecordingTracer {
+    events: Vec<TracerEvent>,
+}
+
+impl Tracer for RecordingTracer {
+    fn capture_start(
+        &mut self,
+        depth: u16,
+        from: Address,
+        to: Address,
+        call_type: MessageKind,
+        input: Bytes,
+        gas: u64,
+        value: U256,
+    ) {
+        self.events.push(TracerEvent::Start {
+            depth,
+            from,
+            to,
+            call_type,
+            input,
+            gas,
+            value,
+        });
+    }
+
+    fn capture_end(&mut self, depth: usize, start_gas: u64, output: &Output) {
+        self.events.push(TracerEvent::End {
+            depth,
+            start_gas,
+            output: output.clone(),
+        });
+    }
+
+    fn capture_self_destruct(&mut self, caller: Address, beneficiary: Address, balance: U256) {
+        self.events.push(TracerEvent::SelfDestruct {
+            caller,
+            beneficiary,
+            balance,
+        });
+    }
+
+    fn capture_account_read(&mut self, account: Address) {
+        self.events.push(TracerEvent::AccountRead(account));
+    }
+
+    fn capture_account_write(&mut self, account: Address) {
+        self.events.push(TracerEvent::AccountWrite(account));
+    }
+
+    fn capture_account_access(&mut self, address: Address) {
+        self.events.push(TracerEvent::AccountAccess(address));
+    }
+
+    fn capture_storage_access(&mut self, address: Address, location: U256) {
+        self.events
+            .push(TracerEvent::StorageAccess { address, location });
+    }
+}
+
+impl RecordingTracer {
+    pub fn replay(self, tracer: &mut dyn Tracer) {
+        for event in self.events {
+            match event {
+                TracerEvent::Start {
+                    depth,
+                    from,
+                    to,
+                    call_type,
+                    input,
+                    gas,
+                    value,
+                } => tracer.capture_start(depth, from, to, call_type, input, gas, value),
+                TracerEvent::End {
+                    depth,
+                    start_gas,
+                    output,
+                } => tracer.capture_end(depth, start_gas, &output),
+                TracerEvent::SelfDestruct {
+                    caller,
+                    beneficiary,
+                    balance,
+                } => tracer.capture_self_destruct(caller, beneficiary, balance),
+                TracerEvent::AccountRead(account) => tracer.capture_account_read(account),
+                TracerEvent::AccountWrite(account) => tracer.capture_account_write(account),
+                TracerEvent::AccountAccess(address) => tracer.capture_account_access(address),
+                TracerEvent::StorageAccess { address, location } => {
+                    tracer.capture_storage_access(address, location)
+                }
+            }
+        }
+    }
+}
+
+/// Outcome of a speculatively executed transaction.
+#[derive(Debug)]
+pub struct SpeculativeResult {
+    pub reads: ReadSet,
+    pub writes: WriteSet,
+    /// Receipt with `cumulative_gas_used` set to gas used by this transaction alone.
+    pub receipt: Receipt,
+    /// Amount yet to be credited to the block beneficiary.
+    pub reward: U256,
+    pub tracer: RecordingTracer,
+}
+
+#[allow(clippy::too_many_arguments)]
+fn speculate_transaction<S>(
+    base: &IntraBlockState<'_, S>,
+    reader: &dyn ConcurrentReader,
+    block_spec: &BlockExecutionSpec,
+    header: &BlockHeader,
+    analysis_cache: &mut AnalysisCache,
+    beneficiary: Address,
+    txn: &MessageWithSender,
+) -> Result<Option<SpeculativeResult>, DuoError>
+where
+    S: HeaderReader + StateReader,
+{
+    let mut view = SpeculativeState::new(base, reader);
+    let mut state = IntraBlockState::new(&mut view);
+
+    if validate_transaction(&mut state, block_spec, header, &txn.message, txn.sender).is_err() {
+        return Ok(None);
+    }
+
+    let mut tracer = RecordingTracer::default();
+    let (_, receipt, reward) = execute_transaction_with_deferred_reward(
+        &mut state,
+        block_spec,
+        header,
+        &mut tracer,
+        analysis_cache,
+        &txn.message,
+        txn.sender,
+        beneficiary,
+    )?;
+
+    let writes = state.into_write_set();
+    if let Some(e) = view.error.into_inner() {
+        return Err(DuoError::Internal(e));
+    }
+    let reads = view.reads.into_inner();
+
+    // Beneficiary is credited by every transaction, so committing its value as observed
+    // before the block would be wrong almost always.
+    if reads.accounts.contains_key(&beneficiary) {
+        return Ok(None);
+    }
+
+    Ok(Some(SpeculativeResult {
+        reads,
+        writes,
+        receipt,
+        reward,
+        tracer,
+    }))
+}
+
+/// Executes all transactions against `base` in parallel.
+///
+/// Transactions that could not be executed speculatively, e.g. because they were invalid at the
+/// beginning of the block, are reported as `None` and should be executed sequentially.
+/// Nothing is executed if the state cannot be read concurrently, see
+/// [`StateReader::concurrent_reader`].
+pub fn speculate<S>(
+    base: &IntraBlockState<'_, S>,
+    analysis_cache: &AnalysisCache,
+    block_spec: &BlockExecutionSpec,
+    header: &BlockHeader,
+    beneficiary: Address,
+    transactions: &[MessageWithSender],
+) -> Vec<Option<SpeculativeResult>>
+where
+    S: HeaderReader + StateReader,
+{
+    // Readers are opened on worker threads, since database transactions are bound to the thread
+    // they were started on. Failures are not fatal: such transactions are executed sequentially,
+    // which surfaces any persistent error.
+    transactions
+        .par_iter()
+        .enumerate()
+        .map_init(
+            || base.state.concurrent_reader(),
+            |reader, (index, txn)| {
+                let reader = match reader {
+                    Ok(Some(reader)) => reader,
+                    Ok(None) => return None,
+                    Err(e) => {
+                        debug!("Failed to open state for speculative execution: {e}");
+                        return None;
+                    }
+                };
+
+                match speculate_transaction(
+                    base,
+                    &**reader,
+                    block_spec,
+                    header,
+                    &mut analysis_cache.clone(),
+                    beneficiary,
+                    txn,
+                ) {
+                    Ok(result) => result,
+                    Err(e) => {
+                        debug!("Speculative execution of transaction #{index} failed: {e}");
+                        None
+                    }
+                }
+            },
+        )
+        .collect()
+}
+
+/// Checks that values observed by a speculatively executed transaction are still current.
+pub fn validate_reads<S>(
+    state: &mut IntraBlockState<'_, S>,
+    reads: &ReadSet,
+) -> anyhow::Result<bool>
+where
+    S: StateReader,
+{
+    for (&address, &account) in &reads.accounts {
+        if state.get_account(address)? != account {
+            return Ok(false);
+        }
+    }
+
+    for (&(address, location), &value) in &reads.storage {
+        if state.get_current_storage(address, location)? != value {
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        consensus::engine_factory,
+        crypto::keccak256,
+        execution::{processor::ExecutionProcessor, tracer::NoopTracer},
+        kv::{mdbx::*, new_mem_chaindata},
+        res::chainspec::MAINNET,
+        stagedsync::stages::EXECUTION,
+        Buffer, InMemoryState, StateWriter,
+    };
+    use hex_literal::hex;
+
+    const MINER: Address = H160(hex!("5a0b54d5dc17e0aadc383d2db43b0a0d3e029c4c"));
+    const COUNTER: Address = H160(hex!("c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0"));
+    const RECIPIENT: Address = H160(hex!("6d20c1c07e56b7098eb8c50ee03ba0f6f498a91d"));
+    const SENDERS: [Address; 4] = [
+        H160(hex!("b685342b8c54347aad148e1f22eff3eb3eb29391")),
+        H160(hex!("4bf2054ffae7a454a35fd8cf4be21b23b1f25a6f")),
+        H160(hex!("834e9b529ac9fa63b39a06f8d8c9b0d6791fa5df")),
+        H160(hex!("004512399a230565b99be5c3b0030a56f3ace68c")),
+    ];
+
+    // Increments its 0th storage on every call.
+    // 0      PUSH1  => 00
+    // 2      SLOAD
+    // 3      PUSH1  => 01
+    // 5      ADD
+    // 6      PUSH1  => 00
+    // 8      SSTORE
+    const COUNTER_CODE: &[u8] = &hex!("600054600101600055");
+
+    fn header() -> BlockHeader {
+        BlockHeader::new(
+            PartialHeader {
+                number: 13_500_001.into(),
+                beneficiary: MINER,
+                gas_limit: 1_000_000,
+                ..PartialHeader::empty()
+            },
+            EMPTY_LIST_HASH,
+            EMPTY_ROOT,
+        )
+    }
+
+    fn block() -> BlockBodyWithSenders {
+        let t = |sender, nonce, to, value: u128| MessageWithSender {
+            message: Message::EIP1559 {
+                chain_id: MAINNET.params.chain_id,
+                nonce,
+                max_priority_fee_per_gas: U256::from(GIGA),
+                max_fee_per_gas: U256::from(20 * GIGA),
+                gas_limit: 100_000,
+                action: TransactionAction::Call(to),
+                value: value.as_u256(),
+                input: Bytes::new(),
+                access_list: Default::default(),
+            },
+            sender,
+        };
+
+        BlockBodyWithSenders {
+            transactions: vec![
+                // Both increment the counter, the latter has to be re-executed.
+                (t)(SENDERS[0], 0, COUNTER, 0),
+                (t)(SENDERS[1], 0, COUNTER, 0),
+                // Independent of everything else.
+                (t)(SENDERS[2], 0, RECIPIENT, 1_000),
+                // Depends on the nonce bumped by the previous transaction.
+                (t)(SENDERS[2], 1, RECIPIENT, 1_000),
+                // Observes the beneficiary.
+                (t)(SENDERS[3], 0, MINER, 1),
+            ],
+            ommers: vec![],
+            withdrawals: None,
+        }
+    }
+
+    fn prepare_state<S: StateWriter>(state: &mut S) {
+        for sender in SENDERS {
+            state.update_account(
+                sender,
+                None,
+                Some(Account {
+                    balance: ETHER.into(),
+                    ..Default::default()
+                }),
+            );
+        }
+        let code_hash = keccak256(COUNTER_CODE);
+        state.update_account(
+            COUNTER,
+            None,
+            Some(Account {
+                code_hash,
+                ..Default::default()
+            }),
+        );
+        state
+            .update_code(code_hash, Bytes::from_static(COUNTER_CODE))
+            .unwrap();
+    }
+
+    /// Executes the block and returns receipts with resulting state of touched accounts.
+    fn execute<S>(state: &mut S, parallel_execution: bool) -> (Vec<Receipt>, Vec<Option<Account>>)
+    where
+        S: HeaderReader + StateReader + StateWriter,
+    {
+        let header = header();
+        let block = block();
+        let block_spec = MAINNET.collect_block_spec(header.number, header.timestamp);
+
+        let mut analysis_cache = AnalysisCache::default();
+        let mut engine = engine_factory(None, MAINNET.clone()).unwrap();
+        let mut tracer = NoopTracer;
+        let mut processor = ExecutionProcessor::new(
+            state,
+            &mut tracer,
+            &mut analysis_cache,
+            &mut *engine,
+            &header,
+            &block,
+            &block_spec,
+        );
+        processor.set_parallel_execution(parallel_execution);
+
+        let receipts = processor.execute_block_no_post_validation().unwrap();
+        processor
+            .into_state()
+            .write_to_state(header.number)
+            .unwrap();
+
+        assert_eq!(state.read_storage(COUNTER, U256::ZERO).unwrap(), 2);
+
+        let accounts = SENDERS
+            .iter()
+            .chain(&[COUNTER, RECIPIENT, MINER])
+            .map(|&address| state.read_account(address).unwrap())
+            .collect();
+
+        (receipts, accounts)
+    }
+
+    #[test]
+    fn parallel_execution_matches_sequential() {
+        let run = |parallel_execution| {
+            let mut state = InMemoryState::default();
+            prepare_state(&mut state);
+
+            let result = execute(&mut state, parallel_execution);
+
+            (result, state.state_root_hash())
+        };
+
+        assert_eq!(run(true), run(false));
+    }
+
+    #[test]
+    fn parallel_execution_on_database() {
+        let db = new_mem_chaindata().unwrap();
+
+        // Workers read through read-only transactions, which only see committed state.
+        let txn = db.begin_mutable().unwrap();
+        let mut buffer = Buffer::new(&txn, None);
+        prepare_state(&mut buffer);
+        buffer.write_to_db().unwrap();
+        txn.commit().unwrap();
+
+        let txn = db.begin_mutable().unwrap();
+        let mut buffer = Buffer::new(&txn, None);
+        let header = header();
+        let block = block();
+        let results = speculate(
+            &IntraBlockState::new(&mut buffer),
+            &AnalysisCache::default(),
+            &MAINNET.collect_block_spec(header.number, header.timestamp),
+            &header,
+            MINER,
+            &block.transactions,
+        );
+        assert!(results[0].is_some());
+        assert!(results[2].is_some());
+        // Observes the beneficiary.
+        assert!(results[4].is_none());
+        drop(txn);
+
+        let run = |parallel_execution| {
+            let txn = db.begin_mutable().unwrap();
+            let mut buffer = Buffer::new(&txn, None);
+            execute(&mut buffer, parallel_execution)
+        };
+
+        assert_eq!(run(true), run(false));
+    }
+
+    #[test]
+    fn no_speculation_on_uncommitted_execution() {
+        let db = new_mem_chaindata().unwrap();
+
+        let txn = db.begin_mutable().unwrap();
+        let mut buffer = Buffer::new(&txn, None);
+        prepare_state(&mut buffer);
+        buffer.write_to_db().unwrap();
+        txn.commit().unwrap();
+
+        let header = header();
+        let block = block();
+        let block_spec = MAINNET.collect_block_spec(header.number, header.timestamp);
+        let speculate_on = |txn: &MdbxTransaction<'_, RW, WriteMap>| {
+            speculate(
+                &IntraBlockState::new(&mut Buffer::new(txn, None)),
+                &AnalysisCache::default(),
+                &block_spec,
+                &header,
+                MINER,
+                &block.transactions,
+            )
+        };
+
+        // Previous batch of the execution stage bumped the counter, but is not committed yet,
+        // so read-only transactions still see the counter at zero.
+        let txn = db.begin_mutable().unwrap();
+        let mut buffer = Buffer::new(&txn, None);
+        buffer
+            .update_storage(COUNTER, U256::ZERO, U256::ZERO, U256::from(5_u8))
+            .unwrap();
+        buffer.write_to_db().unwrap();
+        EXECUTION
+            .save_progress(&txn, BlockNumber(header.number.0 - 1))
+            .unwrap();
+        assert!(speculate_on(&txn).iter().all(Option::is_none));
+
+        txn.commit().unwrap();
+        let txn = db.begin_mutable().unwrap();
+        let results = speculate_on(&txn);
+        assert_eq!(
+            results[0].as_ref().unwrap().reads.storage[&(COUNTER, U256::ZERO)],
+            5
+        );
+    }
+}
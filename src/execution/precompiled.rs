@@ -1,6 +1,7 @@
 use crate::{chain::protocol_param::param, crypto::*, models::*, util::*};
 use arrayref::array_ref;
 use bytes::{Buf, Bytes};
+use hex_literal::hex;
 use num_bigint::BigUint;
 use num_traits::Zero;
 use ripemd::*;
@@ -20,12 +21,30 @@ use substrate_bn::*;
 pub type GasFunction = fn(Bytes, Revision) -> Option<u64>;
 pub type RunFunction = fn(Bytes) -> Option<Bytes>;
 
+/// Precompiled contract, priced and run by the host instead of the interpreter.
+pub trait PrecompiledContract: Send + Sync {
+    /// Gas cost of a call with given input, `None` if it does not fit into `u64`.
+    fn gas(&self, input: Bytes, revision: Revision) -> Option<u64>;
+    /// Output of a call, `None` if the call fails.
+    fn run(&self, input: Bytes) -> Option<Bytes>;
+}
+
 pub struct Contract {
     pub gas: GasFunction,
     pub run: RunFunction,
 }
 
-pub const CONTRACTS: [Contract; NUM_OF_ISTANBUL_CONTRACTS] = [
+impl PrecompiledContract for Contract {
+    fn gas(&self, input: Bytes, revision: Revision) -> Option<u64> {
+        (self.gas)(input, revision)
+    }
+
+    fn run(&self, input: Bytes) -> Option<Bytes> {
+        (self.run)(input)
+    }
+}
+
+pub const CONTRACTS: [Contract; NUM_OF_CANCUN_CONTRACTS] = [
     Contract {
         gas: ecrecover_gas,
         run: ecrecover_run,
@@ -62,11 +81,114 @@ pub const CONTRACTS: [Contract; NUM_OF_ISTANBUL_CONTRACTS] = [
         gas: blake2_f_gas,
         run: blake2_f_run,
     },
+    Contract {
+        gas: point_evaluation_gas,
+        run: point_evaluation_run,
+    },
 ];
 
 pub const NUM_OF_FRONTIER_CONTRACTS: usize = 4;
 pub const NUM_OF_BYZANTIUM_CONTRACTS: usize = 8;
 pub const NUM_OF_ISTANBUL_CONTRACTS: usize = 9;
+pub const NUM_OF_CANCUN_CONTRACTS: usize = 10;
+
+fn num_of_contracts(revision: Revision) -> usize {
+    match revision {
+        Revision::Frontier | Revision::Homestead | Revision::Tangerine | Revision::Spurious => {
+            NUM_OF_FRONTIER_CONTRACTS
+        }
+        Revision::Byzantium | Revision::Constantinople | Revision::Petersburg => {
+            NUM_OF_BYZANTIUM_CONTRACTS
+        }
+        Revision::Istanbul
+        | Revision::Berlin
+        | Revision::London
+        | Revision::Paris
+        | Revision::Shanghai => NUM_OF_ISTANBUL_CONTRACTS,
+        Revision::Cancun | Revision::Eof => NUM_OF_CANCUN_CONTRACTS,
+    }
+}
+
+/// Precompiled contract at given address in a block.
+///
+/// Precompiles activated in the chain spec take precedence over the standard set of the revision.
+pub fn get(block_spec: &BlockExecutionSpec, address: Address) -> Option<&dyn PrecompiledContract> {
+    if let Some(crate::models::Contract::Precompile(precompile)) =
+        block_spec.system_contract_changes.get(&address)
+    {
+        return Some(precompile);
+    }
+
+    if address.0[..ADDRESS_LENGTH - 1].iter().any(|&b| b != 0) {
+        return None;
+    }
+
+    match address.0[ADDRESS_LENGTH - 1] as usize {
+        0 => None,
+        num if num <= num_of_contracts(block_spec.revision) => Some(&CONTRACTS[num - 1]),
+        _ => None,
+    }
+}
+
+fn linear_gas(input: &Bytes, base: u64, word: u64) -> Option<u64> {
+    base.checked_add(word.checked_mul((input.len() as u64 + 31) / 32)?)
+}
+
+impl PrecompiledContract for Precompile {
+    fn gas(&self, input: Bytes, revision: Revision) -> Option<u64> {
+        match *self {
+            Precompile::EcRecover { base, word }
+            | Precompile::Sha256 { base, word }
+            | Precompile::Ripemd160 { base, word }
+            | Precompile::Identity { base, word } => linear_gas(&input, base, word),
+            Precompile::ModExp { ref version } => expmod_gas(
+                input,
+                match version {
+                    ModExpVersion::ModExp198 => Revision::Byzantium,
+                    ModExpVersion::ModExp2565 => Revision::Berlin,
+                },
+            ),
+            Precompile::AltBn128Add { price }
+            | Precompile::AltBn128Mul { price }
+            | Precompile::P256Verify { price } => Some(price),
+            Precompile::Bls12381G1Add => Some(BLS12_G1_ADD_GAS),
+            Precompile::Bls12381G1Msm => bls12_g1_msm_gas(input, revision),
+            Precompile::Bls12381G2Add => Some(BLS12_G2_ADD_GAS),
+            Precompile::Bls12381G2Msm => bls12_g2_msm_gas(input, revision),
+            Precompile::Bls12381PairingCheck => bls12_pairing_gas(input, revision),
+            Precompile::Bls12381MapFpToG1 => Some(BLS12_MAP_FP_TO_G1_GAS),
+            Precompile::Bls12381MapFp2ToG2 => Some(BLS12_MAP_FP2_TO_G2_GAS),
+            Precompile::AltBn128Pairing { base, pair } => {
+                base.checked_add(pair.checked_mul(input.len() as u64 / SNARKV_STRIDE as u64)?)
+            }
+            Precompile::Blake2F { gas_per_round } => {
+                blake2_f_gas(input, Revision::Istanbul)?.checked_mul(gas_per_round)
+            }
+        }
+    }
+
+    fn run(&self, input: Bytes) -> Option<Bytes> {
+        match self {
+            Precompile::EcRecover { .. } => ecrecover_run(input),
+            Precompile::Sha256 { .. } => sha256_run(input),
+            Precompile::Ripemd160 { .. } => ripemd160_run(input),
+            Precompile::Identity { .. } => id_run(input),
+            Precompile::ModExp { .. } => expmod_run(input),
+            Precompile::AltBn128Add { .. } => bn_add_run(input),
+            Precompile::AltBn128Mul { .. } => bn_mul_run(input),
+            Precompile::AltBn128Pairing { .. } => snarkv_run(input),
+            Precompile::Blake2F { .. } => blake2_f_run(input),
+            Precompile::P256Verify { .. } => p256_verify_run(input),
+            Precompile::Bls12381G1Add => bls12_381::g1_add(&input).map(Bytes::from),
+            Precompile::Bls12381G1Msm => bls12_381::g1_msm(&input).map(Bytes::from),
+            Precompile::Bls12381G2Add => bls12_381::g2_add(&input).map(Bytes::from),
+            Precompile::Bls12381G2Msm => bls12_381::g2_msm(&input).map(Bytes::from),
+            Precompile::Bls12381PairingCheck => bls12_pairing_run(input),
+            Precompile::Bls12381MapFpToG1 => bls12_381::map_fp_to_g1(&input).map(Bytes::from),
+            Precompile::Bls12381MapFp2ToG2 => bls12_381::map_fp2_to_g2(&input).map(Bytes::from),
+        }
+    }
+}
 
 fn ecrecover_gas(_: Bytes, _: Revision) -> Option<u64> {
     Some(3_000)
@@ -447,11 +569,155 @@ fn blake2_f_run(input: Bytes) -> Option<Bytes> {
     Some(output_buf.to_vec().into())
 }
 
+// https://eips.ethereum.org/EIPS/eip-4844#point-evaluation-precompile
+const FIELD_ELEMENTS_PER_BLOB: u64 = 4096;
+const BLS_MODULUS: [u8; 32] =
+    hex!("73eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000001");
+
+fn point_evaluation_gas(_: Bytes, _: Revision) -> Option<u64> {
+    Some(50_000)
+}
+
+fn point_evaluation_run(input: Bytes) -> Option<Bytes> {
+    use c_kzg::{Bytes32, Bytes48, KzgProof};
+
+    if input.len() != 192 {
+        return None;
+    }
+
+    let versioned_hash = &input[..32];
+    let z = Bytes32::from_bytes(&input[32..64]).ok()?;
+    let y = Bytes32::from_bytes(&input[64..96]).ok()?;
+    let commitment = &input[96..144];
+    let proof = Bytes48::from_bytes(&input[144..192]).ok()?;
+
+    let mut commitment_hash = Sha256::digest(commitment);
+    commitment_hash[0] = param::VERSIONED_HASH_VERSION_KZG;
+    if commitment_hash[..] != *versioned_hash {
+        return None;
+    }
+
+    let commitment = Bytes48::from_bytes(commitment).ok()?;
+    if !KzgProof::verify_kzg_proof(&commitment, &z, &y, &proof, c_kzg::ethereum_kzg_settings())
+        .ok()?
+    {
+        return None;
+    }
+
+    let mut out = vec![0; 64];
+    out[24..32].copy_from_slice(&FIELD_ELEMENTS_PER_BLOB.to_be_bytes());
+    out[32..].copy_from_slice(&BLS_MODULUS);
+
+    Some(out.into())
+}
+
+// https://eips.ethereum.org/EIPS/eip-2537#gas-schedule
+const BLS12_G1_ADD_GAS: u64 = 375;
+const BLS12_G2_ADD_GAS: u64 = 600;
+const BLS12_G1_MUL_GAS: u64 = 12_000;
+const BLS12_G2_MUL_GAS: u64 = 22_500;
+const BLS12_PAIRING_BASE_GAS: u64 = 37_700;
+const BLS12_PAIRING_PER_PAIR_GAS: u64 = 32_600;
+const BLS12_MAP_FP_TO_G1_GAS: u64 = 5_500;
+const BLS12_MAP_FP2_TO_G2_GAS: u64 = 23_800;
+
+const BLS12_MSM_DISCOUNT_MULTIPLIER: u64 = 1_000;
+const BLS12_G1_MSM_DISCOUNTS: [u64; 128] = [
+    1000, 949, 848, 797, 764, 750, 738, 728, 719, 712, 705, 698, 692, 687, 682, 677, 673, 669, 665,
+    661, 658, 654, 651, 648, 645, 642, 640, 637, 635, 632, 630, 627, 625, 623, 621, 619, 617, 615,
+    613, 611, 609, 608, 606, 604, 603, 601, 599, 598, 596, 595, 593, 592, 591, 589, 588, 586, 585,
+    584, 582, 581, 580, 579, 577, 576, 575, 574, 573, 572, 570, 569, 568, 567, 566, 565, 564, 563,
+    562, 561, 560, 559, 558, 557, 556, 555, 554, 553, 552, 551, 550, 549, 548, 547, 547, 546, 545,
+    544, 543, 542, 541, 540, 540, 539, 538, 537, 536, 536, 535, 534, 533, 532, 532, 531, 530, 529,
+    528, 528, 527, 526, 525, 525, 524, 523, 522, 522, 521, 520, 520, 519,
+];
+const BLS12_G2_MSM_DISCOUNTS: [u64; 128] = [
+    1000, 1000, 923, 884, 855, 832, 812, 796, 782, 770, 759, 749, 740, 732, 724, 717, 711, 704,
+    699, 693, 688, 683, 679, 674, 670, 666, 663, 659, 655, 652, 649, 646, 643, 640, 637, 634, 632,
+    629, 627, 624, 622, 620, 618, 615, 613, 611, 609, 607, 606, 604, 602, 600, 598, 597, 595, 593,
+    592, 590, 589, 587, 586, 584, 583, 582, 580, 579, 578, 576, 575, 574, 573, 571, 570, 569, 568,
+    567, 566, 565, 563, 562, 561, 560, 559, 558, 557, 556, 555, 554, 553, 552, 552, 551, 550, 549,
+    548, 547, 546, 545, 545, 544, 543, 542, 541, 541, 540, 539, 538, 537, 537, 536, 535, 535, 534,
+    533, 532, 532, 531, 530, 530, 529, 528, 528, 527, 526, 526, 525, 524, 524,
+];
+
+fn bls12_msm_gas(input: &Bytes, pair_length: usize, mul_gas: u64, discounts: &[u64]) -> u64 {
+    let k = input.len() / pair_length;
+    if k == 0 {
+        // bls12_381::g1_msm and g2_msm will fail anyway
+        return 0;
+    }
+    let discount = discounts[min(k, discounts.len()) - 1];
+    k as u64 * mul_gas * discount / BLS12_MSM_DISCOUNT_MULTIPLIER
+}
+
+fn bls12_g1_msm_gas(input: Bytes, _: Revision) -> Option<u64> {
+    Some(bls12_msm_gas(
+        &input,
+        bls12_381::G1_LENGTH + bls12_381::SCALAR_LENGTH,
+        BLS12_G1_MUL_GAS,
+        &BLS12_G1_MSM_DISCOUNTS,
+    ))
+}
+
+fn bls12_g2_msm_gas(input: Bytes, _: Revision) -> Option<u64> {
+    Some(bls12_msm_gas(
+        &input,
+        bls12_381::G2_LENGTH + bls12_381::SCALAR_LENGTH,
+        BLS12_G2_MUL_GAS,
+        &BLS12_G2_MSM_DISCOUNTS,
+    ))
+}
+
+fn bls12_pairing_gas(input: Bytes, _: Revision) -> Option<u64> {
+    let k = (input.len() / (bls12_381::G1_LENGTH + bls12_381::G2_LENGTH)) as u64;
+    Some(BLS12_PAIRING_PER_PAIR_GAS * k + BLS12_PAIRING_BASE_GAS)
+}
+
+fn bls12_pairing_run(input: Bytes) -> Option<Bytes> {
+    let mut out = [0; 32];
+    out[31] = bls12_381::pairing_check(&input)?.into();
+    Some(out.to_vec().into())
+}
+
+fn p256_verify_inner(input: &[u8]) -> Option<()> {
+    use p256::{
+        ecdsa::{signature::hazmat::PrehashVerifier, Signature, VerifyingKey},
+        EncodedPoint, FieldBytes,
+    };
+
+    if input.len() != 160 {
+        return None;
+    }
+
+    let signature = Signature::from_scalars(
+        FieldBytes::clone_from_slice(&input[32..64]),
+        FieldBytes::clone_from_slice(&input[64..96]),
+    )
+    .ok()?;
+    let key = VerifyingKey::from_encoded_point(&EncodedPoint::from_affine_coordinates(
+        FieldBytes::from_slice(&input[96..128]),
+        FieldBytes::from_slice(&input[128..160]),
+        false,
+    ))
+    .ok()?;
+
+    key.verify_prehash(&input[..32], &signature).ok()
+}
+
+fn p256_verify_run(input: Bytes) -> Option<Bytes> {
+    // Invalid input or signature is not a failure, just empty output.
+    Some(if p256_verify_inner(&input).is_some() {
+        U256::ONE.to_be_bytes().to_vec().into()
+    } else {
+        Bytes::new()
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use bytes_literal::bytes;
-    use hex_literal::hex;
 
     #[test]
     fn ecrecover() {
@@ -713,4 +979,196 @@ mod tests {
             )
         );
     }
+
+    #[test]
+    fn point_evaluation() {
+        // Commitment and proof of the zero polynomial, both points at infinity.
+        let input = hex!(
+            "010657f37554c781402a22917dee2f75def7ab966d7b770905398eba3c444014"
+            "0000000000000000000000000000000000000000000000000000000000000000"
+            "0000000000000000000000000000000000000000000000000000000000000000"
+            "c00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000"
+            "c00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000"
+        );
+        assert_eq!(
+            point_evaluation_run(input.to_vec().into()).unwrap(),
+            bytes!("000000000000000000000000000000000000000000000000000000000000100073eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000001")
+        );
+
+        let mut wrong_hash = input;
+        wrong_hash[31] ^= 1;
+        assert_eq!(point_evaluation_run(wrong_hash.to_vec().into()), None);
+
+        assert_eq!(point_evaluation_run(input[..191].to_vec().into()), None);
+    }
+
+    #[test]
+    fn p256_verify() {
+        use p256::ecdsa::{signature::hazmat::PrehashSigner, Signature, SigningKey};
+
+        let key = SigningKey::from_bytes(&[0x11; 32]).unwrap();
+        let hash = [0x22; 32];
+        let signature: Signature = key.sign_prehash(&hash).unwrap();
+        let point = key.verifying_key().to_encoded_point(false);
+
+        let mut input = hash.to_vec();
+        input.extend_from_slice(signature.as_ref());
+        input.extend_from_slice(point.x().unwrap());
+        input.extend_from_slice(point.y().unwrap());
+
+        let precompile = Precompile::P256Verify { price: 3_450 };
+        assert_eq!(
+            precompile.gas(input.clone().into(), Revision::Cancun),
+            Some(3_450)
+        );
+        assert_eq!(
+            precompile.run(input.clone().into()).unwrap(),
+            bytes!("0000000000000000000000000000000000000000000000000000000000000001")
+        );
+
+        let mut wrong_hash = input.clone();
+        wrong_hash[0] ^= 1;
+        assert_eq!(precompile.run(wrong_hash.into()), Some(Bytes::new()));
+
+        assert_eq!(
+            precompile.run(input[..159].to_vec().into()),
+            Some(Bytes::new())
+        );
+    }
+
+    #[test]
+    fn chain_spec_precompiles() {
+        use crate::res::chainspec::MAINNET;
+
+        let p256_verify = Address::from_low_u64_be(0x100);
+        let mut spec = MAINNET.clone();
+        spec.timestamp_contracts.insert(
+            1_800_000_000,
+            [(
+                p256_verify,
+                crate::models::Contract::Precompile(Precompile::P256Verify { price: 3_450 }),
+            )]
+            .into_iter()
+            .collect(),
+        );
+
+        let before = spec.collect_block_spec(BlockNumber(20_000_000), 1_799_999_999);
+        let after = spec.collect_block_spec(BlockNumber(20_000_001), 1_800_000_000);
+        assert!(get(&before, p256_verify).is_none());
+        assert!(get(&after, p256_verify).is_some());
+
+        // Standard precompiles by revision.
+        let sha256 = Address::from_low_u64_be(2);
+        let point_evaluation = Address::from_low_u64_be(0x0a);
+        assert!(get(&after, Address::zero()).is_none());
+        assert!(get(&after, sha256).is_some());
+        assert!(get(&after, point_evaluation).is_some());
+        let shanghai = spec.collect_block_spec(BlockNumber(17_034_870), 1_681_338_455);
+        assert!(get(&shanghai, point_evaluation).is_none());
+    }
+
+    #[test]
+    fn bls12_381() {
+        // https://eips.ethereum.org/EIPS/eip-2537#curve-parameters
+        let g1 = hex!(
+            "0000000000000000000000000000000017f1d3a73197d7942695638c4fa9ac0fc3688c4f9774b905a14e3a3f171bac586c55e83ff97a1aeffb3af00adb22c6bb"
+            "0000000000000000000000000000000008b3f481e3aaa0f1a09e30ed741d8ae4fcf5e095d5d00af600db18cb2c04b3edd03cc744a2888ae40caa232946c5e7e1"
+        );
+        let infinity = [0; 128];
+        let input = |parts: &[&[u8]]| -> Bytes { parts.concat().into() };
+        let two = 2.as_u256().to_be_bytes();
+        let mut order_minus_one = BLS_MODULUS;
+        order_minus_one[31] -= 1;
+
+        let g1_double = Precompile::Bls12381G1Add.run(input(&[&g1, &g1])).unwrap();
+        assert_eq!(
+            Precompile::Bls12381G1Msm.run(input(&[&g1, &two])).unwrap(),
+            g1_double
+        );
+        assert_eq!(
+            Precompile::Bls12381G1Add
+                .run(input(&[&g1, &infinity]))
+                .unwrap(),
+            g1.to_vec()
+        );
+        assert_eq!(
+            Precompile::Bls12381G1Msm
+                .run(input(&[&g1, &BLS_MODULUS]))
+                .unwrap(),
+            infinity.to_vec()
+        );
+
+        let mut u = [0; 128];
+        u[63] = 1;
+        u[127] = 2;
+        let g2 = Precompile::Bls12381MapFp2ToG2.run(input(&[&u])).unwrap();
+        assert_eq!(
+            Precompile::Bls12381G2Msm.run(input(&[&g2, &two])).unwrap(),
+            Precompile::Bls12381G2Add.run(input(&[&g2, &g2])).unwrap()
+        );
+
+        let g1_neg = Precompile::Bls12381G1Msm
+            .run(input(&[&g1, &order_minus_one]))
+            .unwrap();
+        let pairing = Precompile::Bls12381PairingCheck;
+        assert_eq!(
+            pairing.run(input(&[&g1, &g2, &g1_neg, &g2])).unwrap(),
+            bytes!("0000000000000000000000000000000000000000000000000000000000000001")
+        );
+        assert_eq!(
+            pairing.run(input(&[&g1, &g2])).unwrap(),
+            bytes!("0000000000000000000000000000000000000000000000000000000000000000")
+        );
+        assert_eq!(
+            pairing.run(input(&[&g1, &[0; 256]])).unwrap(),
+            bytes!("0000000000000000000000000000000000000000000000000000000000000001")
+        );
+
+        // Malformed inputs
+        let mut padded = g1;
+        padded[0] = 1;
+        assert_eq!(Precompile::Bls12381G1Add.run(input(&[&padded, &g1])), None);
+        let mut off_curve = g1;
+        off_curve[127] ^= 1;
+        assert_eq!(
+            Precompile::Bls12381G1Add.run(input(&[&off_curve, &g1])),
+            None
+        );
+        assert_eq!(Precompile::Bls12381G1Add.run(input(&[&g1])), None);
+        assert_eq!(Precompile::Bls12381G1Msm.run(Bytes::new()), None);
+        let mut unreduced = [0; 64];
+        unreduced[16..].copy_from_slice(&hex!(
+            "1a0111ea397fe69a4b1ba7b6434bacd764774b84f38512bf6730d2a0f6b0f6241eabfffeb153ffffb9feffffffffaaab"
+        ));
+        assert_eq!(
+            Precompile::Bls12381MapFpToG1.run(input(&[&unreduced])),
+            None
+        );
+
+        // https://eips.ethereum.org/EIPS/eip-2537#gas-schedule
+        let gas = |precompile: Precompile, input: Bytes| precompile.gas(input, Revision::Cancun);
+        assert_eq!(
+            gas(Precompile::Bls12381G1Add, input(&[&g1, &g1])),
+            Some(375)
+        );
+        assert_eq!(
+            gas(Precompile::Bls12381G1Msm, input(&[&g1, &two])),
+            Some(12_000)
+        );
+        assert_eq!(
+            gas(Precompile::Bls12381G1Msm, input(&[&g1, &two, &g1, &two])),
+            Some(22_776)
+        );
+        assert_eq!(
+            gas(Precompile::Bls12381G2Msm, input(&[&g2, &two, &g2, &two])),
+            Some(45_000)
+        );
+        assert_eq!(
+            gas(
+                Precompile::Bls12381PairingCheck,
+                input(&[&g1, &g2, &g1_neg, &g2])
+            ),
+            Some(102_900)
+        );
+    }
 }
@@ -1,4 +1,8 @@
-use super::{analysis_cache::AnalysisCache, tracer::Tracer};
+use super::{
+    analysis_cache::AnalysisCache,
+    parallel::{self, SpeculativeResult},
+    tracer::{NoopTracer, Tracer},
+};
 use crate::{
     chain::{
         intrinsic_gas::*,
@@ -28,6 +32,7 @@ where
     block: &'b BlockBodyWithSenders,
     block_spec: &'c BlockExecutionSpec,
     cumulative_gas_used: u64,
+    parallel_execution: bool,
 }
 
 fn refund_gas<'r, S>(
@@ -74,6 +79,66 @@ pub fn execute_transaction<'r, S>(
     sender: Address,
     beneficiary: Address,
 ) -> Result<(Bytes, Receipt), DuoError>
+where
+    S: HeaderReader + StateReader,
+{
+    execute_transaction_inner(
+        state,
+        block_spec,
+        header,
+        tracer,
+        analysis_cache,
+        cumulative_gas_used,
+        message,
+        sender,
+        beneficiary,
+        false,
+    )
+    .map(|(output, receipt, _)| (output, receipt))
+}
+
+/// Same as [`execute_transaction`], but leaves crediting the beneficiary to the caller.
+///
+/// Returns the amount due to the beneficiary. Receipt only accounts for gas used by this transaction.
+pub fn execute_transaction_with_deferred_reward<'r, S>(
+    state: &mut IntraBlockState<'r, S>,
+    block_spec: &BlockExecutionSpec,
+    header: &BlockHeader,
+    tracer: &mut dyn Tracer,
+    analysis_cache: &mut AnalysisCache,
+    message: &Message,
+    sender: Address,
+    beneficiary: Address,
+) -> Result<(Bytes, Receipt, U256), DuoError>
+where
+    S: HeaderReader + StateReader,
+{
+    execute_transaction_inner(
+        state,
+        block_spec,
+        header,
+        tracer,
+        analysis_cache,
+        &mut 0,
+        message,
+        sender,
+        beneficiary,
+        true,
+    )
+}
+
+fn execute_transaction_inner<'r, S>(
+    state: &mut IntraBlockState<'r, S>,
+    block_spec: &BlockExecutionSpec,
+    header: &BlockHeader,
+    tracer: &mut dyn Tracer,
+    analysis_cache: &mut AnalysisCache,
+    cumulative_gas_used: &mut u64,
+    message: &Message,
+    sender: Address,
+    beneficiary: Address,
+    defer_reward: bool,
+) -> Result<(Bytes, Receipt, U256), DuoError>
 where
     S: HeaderReader + StateReader,
 {
@@ -92,6 +157,15 @@ where
         U256::from(message.gas_limit()) * effective_gas_price,
     )?;
 
+    // https://eips.ethereum.org/EIPS/eip-4844
+    // Blob gas is charged at the blob base fee and burned, it is never refunded.
+    if let Some(excess_blob_gas) = header.excess_blob_gas {
+        state.subtract_from_balance(
+            sender,
+            U256::from(blob_gas(message)) * calc_blob_base_fee(excess_blob_gas),
+        )?;
+    }
+
     if let TransactionAction::Call(to) = message.action() {
         state.access_account(to);
         // EVM itself increments the nonce for contract creation
@@ -105,16 +179,7 @@ where
         }
     }
 
-    let g0 = intrinsic_gas(
-        message,
-        rev >= Revision::Homestead,
-        rev >= Revision::Istanbul,
-    );
-    let gas = u128::from(message.gas_limit())
-        .checked_sub(g0)
-        .ok_or(ValidationError::IntrinsicGas)?
-        .try_into()
-        .unwrap();
+    let gas = gas_after_intrinsic(message, rev)?;
 
     let vm_res = evmglue::execute(
         state,
@@ -144,7 +209,10 @@ where
     let priority_fee_per_gas = message
         .priority_fee_per_gas(base_fee_per_gas)
         .ok_or(ValidationError::MaxFeeLessThanBase)?;
-    state.add_to_balance(beneficiary, U256::from(gas_used) * priority_fee_per_gas)?;
+    let reward = U256::from(gas_used) * priority_fee_per_gas;
+    if !defer_reward {
+        state.add_to_balance(beneficiary, reward)?;
+    }
 
     state.destruct_selfdestructs()?;
     if rev >= Revision::Spurious {
@@ -164,9 +232,89 @@ where
             bloom: logs_bloom(state.logs()),
             logs: state.logs().to_vec(),
         },
+        if defer_reward { reward } else { U256::ZERO },
     ))
 }
 
+/// Calls system contract at `address` on behalf of [`param::SYSTEM_ADDRESS`], outside of any transaction.
+///
+/// The call is neither paid for nor counted towards block gas, and is skipped if there is no code at `address`.
+pub fn execute_system_call<'r, S>(
+    state: &mut IntraBlockState<'r, S>,
+    block_spec: &BlockExecutionSpec,
+    header: &BlockHeader,
+    analysis_cache: &mut AnalysisCache,
+    address: Address,
+    input: Bytes,
+) -> Result<(), DuoError>
+where
+    S: HeaderReader + StateReader,
+{
+    if state.get_code_hash(address)? == EMPTY_HASH {
+        return Ok(());
+    }
+
+    state.clear_journal_and_substate();
+
+    let message = Message::Legacy {
+        chain_id: None,
+        nonce: 0,
+        // Never charged, only keeps effective gas price valid for the transaction context.
+        gas_price: header.base_fee_per_gas.unwrap_or(U256::ZERO),
+        gas_limit: param::SYSTEM_CALL_GAS_LIMIT,
+        action: TransactionAction::Call(address),
+        value: U256::ZERO,
+        input,
+    };
+
+    evmglue::execute(
+        state,
+        &mut NoopTracer,
+        analysis_cache,
+        header,
+        block_spec,
+        &message,
+        param::SYSTEM_ADDRESS,
+        header.beneficiary,
+        param::SYSTEM_CALL_GAS_LIMIT,
+    )?;
+
+    if block_spec.revision >= Revision::Spurious {
+        state.destruct_touched_dead()?;
+    }
+
+    state.finalize_transaction();
+
+    Ok(())
+}
+
+/// Runs system calls that precede the first transaction of the block.
+pub fn execute_pre_block_system_calls<'r, S>(
+    state: &mut IntraBlockState<'r, S>,
+    block_spec: &BlockExecutionSpec,
+    header: &BlockHeader,
+    analysis_cache: &mut AnalysisCache,
+) -> Result<(), DuoError>
+where
+    S: HeaderReader + StateReader,
+{
+    // https://eips.ethereum.org/EIPS/eip-4788
+    if block_spec.revision >= Revision::Cancun {
+        if let Some(parent_beacon_block_root) = header.parent_beacon_block_root {
+            execute_system_call(
+                state,
+                block_spec,
+                header,
+                analysis_cache,
+                param::BEACON_ROOTS_ADDRESS,
+                Bytes::copy_from_slice(parent_beacon_block_root.as_bytes()),
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
 #[derive(Debug)]
 pub enum TransactionValidationError {
     Validation(BadTransactionError),
@@ -179,6 +327,119 @@ impl From<anyhow::Error> for TransactionValidationError {
     }
 }
 
+/// Gas left for execution once the intrinsic cost of the transaction is paid.
+pub fn gas_after_intrinsic(message: &Message, revision: Revision) -> Result<u64, ValidationError> {
+    let g0 = intrinsic_gas(
+        message,
+        revision >= Revision::Homestead,
+        revision >= Revision::Istanbul,
+        revision >= Revision::Shanghai,
+    );
+    Ok(u128::from(message.gas_limit())
+        .checked_sub(g0)
+        .ok_or(ValidationError::IntrinsicGas)?
+        .try_into()
+        .unwrap())
+}
+
+/// Checks transaction against sender's account, except for the nonce.
+pub fn validate_sender_account(
+    block_spec: &BlockExecutionSpec,
+    header: &BlockHeader,
+    message: &Message,
+    sender: Address,
+    code_hash: H256,
+    balance: U256,
+) -> Result<(), BadTransactionError> {
+    // https://eips.ethereum.org/EIPS/eip-3607
+    if code_hash != EMPTY_HASH {
+        return Err(BadTransactionError::SenderNoEOA { sender });
+    }
+
+    // https://eips.ethereum.org/EIPS/eip-4844
+    if message.tx_type() == TxType::EIP4844 {
+        let Some(excess_blob_gas) = header.excess_blob_gas else {
+            return Err(BadTransactionError::BlobTransactionNotSupported);
+        };
+
+        let blob_base_fee = calc_blob_base_fee(excess_blob_gas);
+        if message.max_fee_per_blob_gas() < blob_base_fee {
+            return Err(BadTransactionError::MaxFeePerBlobGasTooLow {
+                max_fee_per_blob_gas: message.max_fee_per_blob_gas(),
+                blob_base_fee,
+            });
+        }
+    }
+
+    // https://github.com/ethereum/EIPs/pull/3594
+    let max_gas_cost = U512::from(message.gas_limit())
+        * U512::from(ethereum_types::U256::from(
+            message.max_fee_per_gas().to_be_bytes(),
+        ))
+        + U512::from(blob_gas(message))
+            * U512::from(ethereum_types::U256::from(
+                message.max_fee_per_blob_gas().to_be_bytes(),
+            ));
+    // See YP, Eq (57) in Section 6.2 "Execution"
+    let v0 = max_gas_cost + U512::from(ethereum_types::U256::from(message.value().to_be_bytes()));
+    let available_balance = ethereum_types::U256::from(balance.to_be_bytes()).into();
+    if available_balance < v0 {
+        return Err(BadTransactionError::InsufficientFunds {
+            account: sender,
+            available: available_balance,
+            required: v0,
+        });
+    }
+
+    // https://eips.ethereum.org/EIPS/eip-3860
+    if block_spec.revision >= Revision::Shanghai
+        && matches!(message.action(), TransactionAction::Create)
+        && message.input().len() > param::MAX_INITCODE_SIZE
+    {
+        return Err(BadTransactionError::InitCodeTooLarge {
+            size: message.input().len(),
+            limit: param::MAX_INITCODE_SIZE,
+        });
+    }
+
+    Ok(())
+}
+
+pub fn validate_transaction<'r, S>(
+    state: &mut IntraBlockState<'r, S>,
+    block_spec: &BlockExecutionSpec,
+    header: &BlockHeader,
+    message: &Message,
+    sender: Address,
+) -> Result<(), TransactionValidationError>
+where
+    S: StateReader,
+{
+    pre_validate_transaction(message, block_spec.params.chain_id, header.base_fee_per_gas)
+        .expect("Tx must have been prevalidated");
+
+    let expected_nonce = state.get_nonce(sender)?;
+    if expected_nonce != message.nonce() {
+        return Err(TransactionValidationError::Validation(
+            BadTransactionError::WrongNonce {
+                account: sender,
+                expected: expected_nonce,
+                got: message.nonce(),
+            },
+        ));
+    }
+
+    validate_sender_account(
+        block_spec,
+        header,
+        message,
+        sender,
+        state.get_code_hash(sender)?,
+        state.get_balance(sender)?,
+    )
+    .map_err(TransactionValidationError::Validation)
+}
+
 impl<'r, 'tracer, 'analysis, 'e, 'h, 'b, 'c, S>
     ExecutionProcessor<'r, 'tracer, 'analysis, 'e, 'h, 'b, 'c, S>
 where
@@ -202,6 +463,7 @@ where
             block,
             block_spec,
             cumulative_gas_used: 0,
+            parallel_execution: false,
         }
     }
 
@@ -221,53 +483,26 @@ where
         self.tracer = tracer
     }
 
+    /// Speculatively execute transactions of the block in parallel, see [`parallel`].
+    ///
+    /// Has no effect with tracers that trace instructions, or if the state cannot be read
+    /// concurrently.
+    pub fn set_parallel_execution(&mut self, parallel_execution: bool) {
+        self.parallel_execution = parallel_execution
+    }
+
     pub fn validate_transaction(
         &mut self,
         message: &Message,
         sender: Address,
     ) -> Result<(), TransactionValidationError> {
-        pre_validate_transaction(
+        validate_transaction(
+            &mut self.state,
+            self.block_spec,
+            self.header,
             message,
-            self.block_spec.params.chain_id,
-            self.header.base_fee_per_gas,
-        )
-        .expect("Tx must have been prevalidated");
-        if self.state.get_code_hash(sender)? != EMPTY_HASH {
-            return Err(TransactionValidationError::Validation(
-                BadTransactionError::SenderNoEOA { sender },
-            ));
-        }
-
-        let expected_nonce = self.state.get_nonce(sender)?;
-        if expected_nonce != message.nonce() {
-            return Err(TransactionValidationError::Validation(
-                BadTransactionError::WrongNonce {
-                    account: sender,
-                    expected: expected_nonce,
-                    got: message.nonce(),
-                },
-            ));
-        }
-
-        // https://github.com/ethereum/EIPs/pull/3594
-        let max_gas_cost = U512::from(message.gas_limit())
-            * U512::from(ethereum_types::U256::from(
-                message.max_fee_per_gas().to_be_bytes(),
-            ));
-        // See YP, Eq (57) in Section 6.2 "Execution"
-        let v0 =
-            max_gas_cost + U512::from(ethereum_types::U256::from(message.value().to_be_bytes()));
-        let available_balance =
-            ethereum_types::U256::from(self.state.get_balance(sender)?.to_be_bytes()).into();
-        if available_balance < v0 {
-            return Err(TransactionValidationError::Validation(
-                BadTransactionError::InsufficientFunds {
-                    account: sender,
-                    available: available_balance,
-                    required: v0,
-                },
-            ));
-        }
+            sender,
+        )?;
 
         let available_gas = self.available_gas();
         if available_gas < message.gas_limit() {
@@ -306,6 +541,57 @@ where
         .map(|(_, receipt)| receipt)
     }
 
+    pub fn execute_system_call(&mut self, address: Address, input: Bytes) -> Result<(), DuoError> {
+        execute_system_call(
+            &mut self.state,
+            self.block_spec,
+            self.header,
+            self.analysis_cache,
+            address,
+            input,
+        )
+    }
+
+    pub fn execute_pre_block_system_calls(&mut self) -> Result<(), DuoError> {
+        execute_pre_block_system_calls(
+            &mut self.state,
+            self.block_spec,
+            self.header,
+            self.analysis_cache,
+        )
+    }
+
+    /// Applies a speculatively executed transaction, unless it observed state that has changed since.
+    fn commit_speculative_result(
+        &mut self,
+        txn: &MessageWithSender,
+        result: SpeculativeResult,
+    ) -> Result<Option<Receipt>, DuoError> {
+        if self.available_gas() < txn.message.gas_limit()
+            || !parallel::validate_reads(&mut self.state, &result.reads)?
+        {
+            return Ok(None);
+        }
+
+        let beneficiary = self.engine.get_beneficiary(self.header);
+
+        self.state.clear_journal_and_substate();
+        self.state.apply_write_set(result.writes)?;
+        self.state.add_to_balance(beneficiary, result.reward)?;
+        if self.block_spec.revision >= Revision::Spurious {
+            self.state.destruct_touched_dead()?;
+        }
+        self.state.finalize_transaction();
+
+        result.tracer.replay(self.tracer);
+
+        let mut receipt = result.receipt;
+        self.cumulative_gas_used += receipt.cumulative_gas_used;
+        receipt.cumulative_gas_used = self.cumulative_gas_used;
+
+        Ok(Some(receipt))
+    }
+
     pub fn execute_block_no_post_validation_while(
         &mut self,
         mut pred: impl FnMut(usize, &MessageWithSender) -> bool,
@@ -316,11 +602,36 @@ where
             self.state.set_balance(address, balance)?;
         }
 
+        self.execute_pre_block_system_calls()?;
+
+        let mut speculative_results = if self.parallel_execution
+            && self.block.transactions.len() > 1
+            && !self.tracer.trace_instructions()
+        {
+            parallel::speculate(
+                &self.state,
+                self.analysis_cache,
+                self.block_spec,
+                self.header,
+                self.engine.get_beneficiary(self.header),
+                &self.block.transactions,
+            )
+        } else {
+            Vec::new()
+        };
+
         for (i, txn) in self.block.transactions.iter().enumerate() {
             if !(pred)(i, txn) {
                 return Ok(receipts);
             }
 
+            if let Some(result) = speculative_results.get_mut(i).and_then(Option::take) {
+                if let Some(receipt) = self.commit_speculative_result(txn, result)? {
+                    receipts.push(receipt);
+                    continue;
+                }
+            }
+
             self.validate_transaction(&txn.message, txn.sender)
                 .map_err(|e| match e {
                     TransactionValidationError::Validation(error) => {
@@ -331,6 +642,12 @@ where
             receipts.push(self.execute_transaction(&txn.message, txn.sender)?);
         }
 
+        // https://eips.ethereum.org/EIPS/eip-4895
+        for withdrawal in self.block.withdrawals.iter().flatten() {
+            self.state
+                .add_to_balance(withdrawal.address, withdrawal.amount_wei())?;
+        }
+
         for change in self.engine.finalize(self.header, &self.block.ommers)? {
             match change {
                 FinalizationChange::Reward {
@@ -457,7 +774,7 @@ mod tests {
         let mut state = InMemoryState::default();
         let mut analysis_cache = AnalysisCache::default();
         let mut engine = engine_factory(None, MAINNET.clone()).unwrap();
-        let block_spec = MAINNET.collect_block_spec(header.number);
+        let block_spec = MAINNET.collect_block_spec(header.number, header.timestamp);
         let mut tracer = NoopTracer;
         let mut processor = ExecutionProcessor::new(
             &mut state,
@@ -500,7 +817,7 @@ mod tests {
         let mut state = InMemoryState::default();
         let mut analysis_cache = AnalysisCache::default();
         let mut engine = engine_factory(None, MAINNET.clone()).unwrap();
-        let block_spec = MAINNET.collect_block_spec(header.number);
+        let block_spec = MAINNET.collect_block_spec(header.number, header.timestamp);
         let mut tracer = NoopTracer;
         let mut processor = ExecutionProcessor::new(
             &mut state,
@@ -565,7 +882,7 @@ mod tests {
         let mut state = InMemoryState::default();
         let mut analysis_cache = AnalysisCache::default();
         let mut engine = engine_factory(None, MAINNET.clone()).unwrap();
-        let block_spec = MAINNET.collect_block_spec(header.number);
+        let block_spec = MAINNET.collect_block_spec(header.number, header.timestamp);
         let mut tracer = NoopTracer;
         let mut processor = ExecutionProcessor::new(
             &mut state,
@@ -683,7 +1000,7 @@ mod tests {
         let mut state = InMemoryState::default();
         let mut analysis_cache = AnalysisCache::default();
         let mut engine = engine_factory(None, MAINNET.clone()).unwrap();
-        let block_spec = MAINNET.collect_block_spec(header.number);
+        let block_spec = MAINNET.collect_block_spec(header.number, header.timestamp);
         let mut tracer = NoopTracer;
         let mut processor = ExecutionProcessor::new(
             &mut state,
@@ -790,7 +1107,7 @@ mod tests {
 
         let mut analysis_cache = AnalysisCache::default();
         let mut engine = engine_factory(None, MAINNET.clone()).unwrap();
-        let block_spec = MAINNET.collect_block_spec(header.number);
+        let block_spec = MAINNET.collect_block_spec(header.number, header.timestamp);
         let mut tracer = NoopTracer;
         let mut processor = ExecutionProcessor::new(
             &mut state,
@@ -846,7 +1163,7 @@ mod tests {
         let mut state = InMemoryState::default();
         let mut analysis_cache = AnalysisCache::default();
         let mut engine = engine_factory(None, MAINNET.clone()).unwrap();
-        let block_spec = MAINNET.collect_block_spec(header.number);
+        let block_spec = MAINNET.collect_block_spec(header.number, header.timestamp);
         let mut tracer = NoopTracer;
         let mut processor = ExecutionProcessor::new(
             &mut state,
@@ -868,4 +1185,179 @@ mod tests {
         // suicide_beneficiary should've been touched and deleted
         assert_eq!(state.read_account(suicide_beneficiary).unwrap(), None);
     }
+
+    #[test]
+    fn eip3860_reject_oversized_initcode() {
+        let mut chain_spec = MAINNET.clone();
+        chain_spec.upgrades.shanghai_time = Some(0);
+
+        let partial_header = PartialHeader {
+            number: 1.into(),
+            gas_limit: 30_000_000,
+            ..PartialHeader::empty()
+        };
+        let header = BlockHeader::new(partial_header, EMPTY_LIST_HASH, EMPTY_ROOT);
+
+        let message = Message::Legacy {
+            chain_id: None,
+            nonce: 0,
+            gas_price: U256::ZERO,
+            gas_limit: 10_000_000,
+            action: TransactionAction::Create,
+            value: U256::ZERO,
+            input: vec![0; param::MAX_INITCODE_SIZE + 1].into(),
+        };
+        let sender = hex!("71562b71999873DB5b286dF957af199Ec94617F7").into();
+
+        let block = Default::default();
+
+        let mut state = InMemoryState::default();
+        let mut analysis_cache = AnalysisCache::default();
+        let mut engine = engine_factory(None, chain_spec.clone()).unwrap();
+        let block_spec = chain_spec.collect_block_spec(header.number, header.timestamp);
+        let mut tracer = NoopTracer;
+        let mut processor = ExecutionProcessor::new(
+            &mut state,
+            &mut tracer,
+            &mut analysis_cache,
+            &mut *engine,
+            &header,
+            &block,
+            &block_spec,
+        );
+
+        assert!(matches!(
+            processor.validate_transaction(&message, sender),
+            Err(TransactionValidationError::Validation(
+                BadTransactionError::InitCodeTooLarge { .. }
+            ))
+        ));
+    }
+
+    #[test]
+    fn withdrawals() {
+        let mut chain_spec = MAINNET.clone();
+        chain_spec.upgrades.shanghai_time = Some(0);
+
+        let block_number = 1.into();
+        let partial_header = PartialHeader {
+            number: block_number,
+            gas_limit: 30_000_000,
+            ..PartialHeader::empty()
+        };
+        let header = BlockHeader::new(partial_header, EMPTY_LIST_HASH, EMPTY_ROOT);
+
+        let address = hex!("00000000000000000000000000000000000000ff").into();
+        let block = BlockBodyWithSenders {
+            transactions: vec![],
+            ommers: vec![],
+            withdrawals: Some(vec![
+                Withdrawal {
+                    index: 0,
+                    validator_index: 0,
+                    address,
+                    amount: 32 * GIGA,
+                },
+                Withdrawal {
+                    index: 1,
+                    validator_index: 1,
+                    address,
+                    amount: 1,
+                },
+            ]),
+        };
+
+        let mut state = InMemoryState::default();
+        let mut analysis_cache = AnalysisCache::default();
+        let mut engine = engine_factory(None, chain_spec.clone()).unwrap();
+        let block_spec = chain_spec.collect_block_spec(header.number, header.timestamp);
+        let mut tracer = NoopTracer;
+        let mut processor = ExecutionProcessor::new(
+            &mut state,
+            &mut tracer,
+            &mut analysis_cache,
+            &mut *engine,
+            &header,
+            &block,
+            &block_spec,
+        );
+
+        processor.execute_block_no_post_validation().unwrap();
+        processor.into_state().write_to_state(block_number).unwrap();
+
+        assert_eq!(
+            state.read_account(address).unwrap().unwrap().balance,
+            U256::from(32 * GIGA + 1) * U256::from(GIGA)
+        );
+    }
+
+    #[test]
+    fn eip4788_beacon_root_system_call() {
+        let partial_header = PartialHeader {
+            number: 19_426_587.into(),
+            timestamp: 1_710_338_135,
+            gas_limit: 30_000_000,
+            base_fee_per_gas: Some(GIGA.as_u256()),
+            ..PartialHeader::empty()
+        };
+        let mut header = BlockHeader::new(partial_header, EMPTY_LIST_HASH, EMPTY_ROOT);
+        let parent_beacon_block_root = H256::repeat_byte(0xbe);
+        header.parent_beacon_block_root = Some(parent_beacon_block_root);
+
+        let block = Default::default();
+
+        // Stores caller at 0th slot and calldata at the slot keyed by block timestamp.
+        // 0  CALLER
+        // 1  PUSH1 => 00
+        // 3  SSTORE
+        // 4  PUSH1 => 00
+        // 6  CALLDATALOAD
+        // 7  TIMESTAMP
+        // 8  SSTORE
+        // 9  STOP
+        let code = hex!("33600055600035425500");
+
+        let mut state = InMemoryState::default();
+        let mut analysis_cache = AnalysisCache::default();
+        let mut engine = engine_factory(None, MAINNET.clone()).unwrap();
+        let block_spec = MAINNET.collect_block_spec(header.number, header.timestamp);
+        let mut tracer = NoopTracer;
+        let mut processor = ExecutionProcessor::new(
+            &mut state,
+            &mut tracer,
+            &mut analysis_cache,
+            &mut *engine,
+            &header,
+            &block,
+            &block_spec,
+        );
+
+        // Nothing happens until the contract is deployed.
+        processor.execute_pre_block_system_calls().unwrap();
+        assert!(!processor
+            .state()
+            .exists(param::BEACON_ROOTS_ADDRESS)
+            .unwrap());
+
+        processor
+            .state()
+            .set_code(param::BEACON_ROOTS_ADDRESS, code.to_vec().into())
+            .unwrap();
+        processor.execute_pre_block_system_calls().unwrap();
+
+        let state = processor.state();
+        assert_eq!(
+            state
+                .get_current_storage(param::BEACON_ROOTS_ADDRESS, U256::ZERO)
+                .unwrap(),
+            h256_to_u256(H256::from(param::SYSTEM_ADDRESS))
+        );
+        assert_eq!(
+            state
+                .get_current_storage(param::BEACON_ROOTS_ADDRESS, header.timestamp.as_u256())
+                .unwrap(),
+            h256_to_u256(parent_beacon_block_root)
+        );
+        assert!(!state.exists(param::SYSTEM_ADDRESS).unwrap());
+    }
 }
@@ -0,0 +1,88 @@
+use super::*;
+use crate::{
+    execution::{evm::StatusCode, precompiled},
+    u256_to_h256,
+};
+use std::collections::{BTreeSet, HashSet};
+
+/// Collects accounts and storage slots accessed during execution into an
+/// [EIP-2930](https://eips.ethereum.org/EIPS/eip-2930) access list.
+///
+/// Excluded addresses (normally sender and recipient) and precompiles are never recorded,
+/// as they are warm regardless of the access list.
+#[derive(Debug)]
+pub struct AccessListTracer<'a> {
+    block_spec: &'a BlockExecutionSpec,
+    excluded: HashSet<Address>,
+    access_list: BTreeMap<Address, BTreeSet<H256>>,
+    status_code: Option<StatusCode>,
+}
+
+impl<'a> AccessListTracer<'a> {
+    /// Creates a tracer starting off with entries of `access_list`.
+    pub fn new(
+        block_spec: &'a BlockExecutionSpec,
+        excluded: HashSet<Address>,
+        access_list: &[AccessListItem],
+    ) -> Self {
+        let mut this = Self {
+            block_spec,
+            excluded,
+            access_list: Default::default(),
+            status_code: None,
+        };
+
+        for item in access_list {
+            if this.is_recorded(item.address) {
+                this.access_list
+                    .entry(item.address)
+                    .or_default()
+                    .extend(item.slots.iter().copied());
+            }
+        }
+
+        this
+    }
+
+    fn is_recorded(&self, address: Address) -> bool {
+        !self.excluded.contains(&address) && precompiled::get(self.block_spec, address).is_none()
+    }
+
+    /// Status the outermost call or creation ended with, unless it failed before being entered.
+    pub fn status_code(&self) -> Option<StatusCode> {
+        self.status_code
+    }
+
+    pub fn into_access_list(self) -> AccessList {
+        self.access_list
+            .into_iter()
+            .map(|(address, slots)| AccessListItem {
+                address,
+                slots: slots.into_iter().collect(),
+            })
+            .collect()
+    }
+}
+
+impl Tracer for AccessListTracer<'_> {
+    fn capture_end(&mut self, depth: usize, _: u64, output: &Output) {
+        if depth == 0 {
+            self.status_code = Some(output.status_code);
+        }
+    }
+
+    fn capture_account_access(&mut self, address: Address) {
+        if self.is_recorded(address) {
+            self.access_list.entry(address).or_default();
+        }
+    }
+
+    fn capture_storage_access(&mut self, address: Address, location: U256) {
+        if self.is_recorded(address) {
+            self.access_list
+                .entry(address)
+                .or_default()
+                .insert(u256_to_h256(location));
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use super::*;
+use crate::{
+    execution::evm::{Output, StatusCode},
+    models::*,
+};
+use bytes::Bytes;
+use ethereum_jsonrpc::types;
+use serde::Serialize;
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallFrame {
+    #[serde(rename = "type")]
+    pub call_type: &'static str,
+    pub from: Address,
+    pub to: Address,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    pub gas: U64,
+    pub gas_used: U64,
+    pub input: types::Bytes,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<types::Bytes>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub calls: Vec<CallFrame>,
+}
+
+/// Geth's `callTracer`, building a tree of message calls.
+#[derive(Debug, Default)]
+pub struct CallFrameTracer {
+    only_top_call: bool,
+    stack: Vec<CallFrame>,
+    root: Option<CallFrame>,
+}
+
+impl CallFrameTracer {
+    pub fn new(only_top_call: bool) -> Self {
+        Self {
+            only_top_call,
+            ..Default::default()
+        }
+    }
+
+    pub fn into_frame(self) -> Option<CallFrame> {
+        self.root
+    }
+}
+
+impl Tracer for CallFrameTracer {
+    fn capture_start(
+        &mut self,
+        _: u16,
+        from: Address,
+        to: Address,
+        call_type: MessageKind,
+        input: Bytes,
+        gas: u64,
+        value: U256,
+    ) {
+        let call_type = match call_type {
+            MessageKind::Create { salt: None } => "CREATE",
+            MessageKind::Create { salt: Some(_) } => "CREATE2",
+            MessageKind::Call { call_kind, .. } => match call_kind {
+                CallKind::Call => "CALL",
+                CallKind::CallCode => "CALLCODE",
+                CallKind::DelegateCall => "DELEGATECALL",
+                CallKind::StaticCall => "STATICCALL",
+            },
+        };
+
+        self.stack.push(CallFrame {
+            call_type,
+            from,
+            to,
+            value: (call_type != "STATICCALL").then(|| format!("0x{value:x}")),
+            gas: gas.into(),
+            gas_used: U64::zero(),
+            input: input.into(),
+            output: None,
+            error: None,
+            calls: vec![],
+        });
+    }
+
+    fn capture_end(&mut self, _: usize, start_gas: u64, output: &Output) {
+        let Some(mut frame) = self.stack.pop() else {
+            return;
+        };
+
+        frame.gas_used = start_gas
+            .saturating_sub(std::cmp::max(output.gas_left, 0) as u64)
+            .into();
+        if !output.output_data.is_empty() {
+            frame.output = Some(output.output_data.clone().into());
+        }
+        frame.error = match output.status_code {
+            StatusCode::Success => None,
+            StatusCode::Revert => Some("execution reverted".to_string()),
+            ref other => Some(other.to_string()),
+        };
+
+        match self.stack.last_mut() {
+            Some(parent) => {
+                if !self.only_top_call {
+                    parent.calls.push(frame);
+                }
+            }
+            None => self.root = Some(frame),
+        }
+    }
+
+    fn capture_self_destruct(&mut self, caller: Address, beneficiary: Address, balance: U256) {
+        if self.only_top_call {
+            return;
+        }
+
+        if let Some(parent) = self.stack.last_mut() {
+            parent.calls.push(CallFrame {
+                call_type: "SELFDESTRUCT",
+                from: caller,
+                to: beneficiary,
+                value: Some(format!("0x{balance:x}")),
+                gas: U64::zero(),
+                gas_used: U64::zero(),
+                input: Bytes::new().into(),
+                output: None,
+                error: None,
+                calls: vec![],
+            });
+        }
+    }
+}
@@ -1,8 +1,16 @@
+pub mod access_list_tracer;
 pub mod adhoc;
+pub mod call_frame_tracer;
 pub mod eip3155_tracer;
+pub mod prestate_tracer;
+pub mod struct_logger;
 
+pub use access_list_tracer::AccessListTracer;
 use auto_impl::auto_impl;
+pub use call_frame_tracer::{CallFrame, CallFrameTracer};
 pub use eip3155_tracer::StdoutTracer;
+pub use prestate_tracer::{PrestateAccount, PrestateDiff, PrestateTracer};
+pub use struct_logger::{StructLogResult, StructLogger, StructLoggerConfig};
 
 use crate::{
     execution::evm::{ExecutionState, OpCode},
@@ -71,6 +79,8 @@ pub trait Tracer: Debug + Send {
     fn capture_self_destruct(&mut self, caller: Address, beneficiary: Address, balance: U256) {}
     fn capture_account_read(&mut self, account: Address) {}
     fn capture_account_write(&mut self, account: Address) {}
+    fn capture_account_access(&mut self, address: Address) {}
+    fn capture_storage_access(&mut self, address: Address, location: U256) {}
 }
 
 /// Tracer which does nothing.
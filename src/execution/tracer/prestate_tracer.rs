@@ -0,0 +1,187 @@
+use super::*;
+use crate::{
+    execution::evm::{ExecutionState, OpCode, Output},
+    models::*,
+    u256_to_h256, StateReader,
+};
+use bytes::Bytes;
+use ethereum_jsonrpc::types;
+use serde::Serialize;
+use std::collections::BTreeSet;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct PrestateAccount {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub balance: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<types::Bytes>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub storage: BTreeMap<H256, H256>,
+}
+
+/// Output of `prestateTracer` in diff mode, with accounts modified by a transaction only.
+///
+/// `post` holds just the fields that changed, `pre` the whole accounts without intact slots.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct PrestateDiff {
+    pub pre: BTreeMap<Address, PrestateAccount>,
+    pub post: BTreeMap<Address, PrestateAccount>,
+}
+
+/// Geth's `prestateTracer`, collecting accounts and storage slots touched by a transaction.
+///
+/// Values themselves are read afterwards with [`PrestateTracer::read_prestate`],
+/// from the state transaction was executed on.
+#[derive(Debug, Default)]
+pub struct PrestateTracer {
+    frames: Vec<Address>,
+    touched: BTreeMap<Address, BTreeSet<U256>>,
+}
+
+impl PrestateTracer {
+    pub fn touch(&mut self, address: Address) {
+        self.touched.entry(address).or_default();
+    }
+
+    fn read_account<S: StateReader>(
+        state: &S,
+        address: Address,
+        locations: &BTreeSet<U256>,
+    ) -> anyhow::Result<PrestateAccount> {
+        let account = state.read_account(address)?.unwrap_or_default();
+
+        let mut storage = BTreeMap::new();
+        for &location in locations {
+            storage.insert(
+                u256_to_h256(location),
+                u256_to_h256(state.read_storage(address, location)?),
+            );
+        }
+
+        Ok(PrestateAccount {
+            balance: Some(format!("0x{:x}", account.balance)),
+            nonce: (account.nonce > 0).then_some(account.nonce),
+            code: if account.code_hash != EMPTY_HASH {
+                Some(state.read_code(account.code_hash)?.into())
+            } else {
+                None
+            },
+            storage,
+        })
+    }
+
+    pub fn read_prestate<S: StateReader>(
+        &self,
+        state: &S,
+    ) -> anyhow::Result<BTreeMap<Address, PrestateAccount>> {
+        self.touched
+            .iter()
+            .map(|(&address, locations)| {
+                Ok((address, Self::read_account(state, address, locations)?))
+            })
+            .collect()
+    }
+
+    /// Compares prestate read before the transaction with `state` after it, as Geth's diff mode does.
+    pub fn read_diff<S: StateReader>(
+        &self,
+        mut prestate: BTreeMap<Address, PrestateAccount>,
+        state: &S,
+    ) -> anyhow::Result<PrestateDiff> {
+        let mut out = PrestateDiff::default();
+        for (&address, locations) in &self.touched {
+            let Some(mut pre) = prestate.remove(&address) else {
+                continue;
+            };
+
+            if state.read_account(address)?.is_none() {
+                // Self-destructed accounts only appear in `pre`, unless there was nothing to destroy.
+                pre.storage.retain(|_, value| !value.is_zero());
+                if pre != Self::read_account(state, address, &BTreeSet::new())? {
+                    out.pre.insert(address, pre);
+                }
+                continue;
+            }
+
+            let current = Self::read_account(state, address, locations)?;
+            let mut post = PrestateAccount {
+                balance: current
+                    .balance
+                    .filter(|balance| Some(balance) != pre.balance.as_ref()),
+                nonce: current.nonce.filter(|&nonce| Some(nonce) != pre.nonce),
+                code: current.code.filter(|code| Some(code) != pre.code.as_ref()),
+                storage: BTreeMap::new(),
+            };
+            let mut modified =
+                post.balance.is_some() || post.nonce.is_some() || post.code.is_some();
+            for (location, value) in current.storage {
+                if pre.storage.get(&location) == Some(&value) {
+                    pre.storage.remove(&location);
+                } else {
+                    modified = true;
+                    if !value.is_zero() {
+                        post.storage.insert(location, value);
+                    }
+                }
+            }
+            pre.storage.retain(|_, value| !value.is_zero());
+
+            if modified {
+                out.pre.insert(address, pre);
+                out.post.insert(address, post);
+            }
+        }
+
+        Ok(out)
+    }
+}
+
+impl Tracer for PrestateTracer {
+    fn trace_instructions(&self) -> bool {
+        true
+    }
+
+    fn capture_start(
+        &mut self,
+        _: u16,
+        from: Address,
+        to: Address,
+        _: MessageKind,
+        _: Bytes,
+        _: u64,
+        _: U256,
+    ) {
+        self.touch(from);
+        self.touch(to);
+        self.frames.push(to);
+    }
+
+    fn capture_state(&mut self, env: &ExecutionState, _: usize, op: OpCode, _: u64, _: u16) {
+        if env.stack.is_empty() {
+            return;
+        }
+
+        let top = *env.stack.get(0);
+        match op {
+            OpCode::SLOAD | OpCode::SSTORE => {
+                if let Some(&address) = self.frames.last() {
+                    self.touched.entry(address).or_default().insert(top);
+                }
+            }
+            OpCode::BALANCE | OpCode::EXTCODESIZE | OpCode::EXTCODECOPY | OpCode::EXTCODEHASH => {
+                self.touch(Address::from(u256_to_h256(top)));
+            }
+            _ => {}
+        }
+    }
+
+    fn capture_end(&mut self, _: usize, _: u64, _: &Output) {
+        self.frames.pop();
+    }
+
+    fn capture_self_destruct(&mut self, _: Address, beneficiary: Address, _: U256) {
+        self.touch(beneficiary);
+    }
+}
@@ -0,0 +1,191 @@
+use super::*;
+use crate::{
+    execution::evm::{ExecutionState, OpCode, Output, StatusCode},
+    models::*,
+    u256_to_h256,
+};
+use bytes::Bytes;
+use serde::Serialize;
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct StructLoggerConfig {
+    pub disable_stack: bool,
+    pub disable_memory: bool,
+    pub disable_storage: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StructLog {
+    pub pc: usize,
+    pub op: &'static str,
+    pub gas: u64,
+    pub gas_cost: u64,
+    pub depth: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stack: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memory: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub storage: Option<BTreeMap<String, String>>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StructLogResult {
+    pub gas: u64,
+    pub failed: bool,
+    pub return_value: String,
+    pub struct_logs: Vec<StructLog>,
+}
+
+#[derive(Debug)]
+struct Frame {
+    address: Address,
+    /// Index of the latest log of this frame, whose cost is known only once the next one starts.
+    last_log: Option<usize>,
+    /// `SLOAD` whose result appears on the stack at the next step.
+    pending_load: Option<(usize, U256)>,
+}
+
+/// Geth's default tracer, logging every executed instruction.
+#[derive(Debug, Default)]
+pub struct StructLogger {
+    config: StructLoggerConfig,
+    frames: Vec<Frame>,
+    storage: HashMap<Address, BTreeMap<U256, U256>>,
+    logs: Vec<StructLog>,
+    failed: bool,
+    return_value: Bytes,
+}
+
+impl StructLogger {
+    pub fn new(config: StructLoggerConfig) -> Self {
+        Self {
+            config,
+            ..Default::default()
+        }
+    }
+
+    fn storage_snapshot(&self, address: Address) -> Option<BTreeMap<String, String>> {
+        if self.config.disable_storage {
+            return None;
+        }
+
+        Some(
+            self.storage
+                .get(&address)
+                .into_iter()
+                .flatten()
+                .map(|(&location, &value)| {
+                    (
+                        hex::encode(u256_to_h256(location)),
+                        hex::encode(u256_to_h256(value)),
+                    )
+                })
+                .collect(),
+        )
+    }
+
+    fn store(&mut self, log: usize, address: Address, location: U256, value: U256) {
+        self.storage
+            .entry(address)
+            .or_default()
+            .insert(location, value);
+        self.logs[log].storage = self.storage_snapshot(address);
+    }
+
+    pub fn into_result(self, gas_used: u64) -> StructLogResult {
+        StructLogResult {
+            gas: gas_used,
+            failed: self.failed,
+            return_value: hex::encode(&self.return_value),
+            struct_logs: self.logs,
+        }
+    }
+}
+
+impl Tracer for StructLogger {
+    fn trace_instructions(&self) -> bool {
+        true
+    }
+
+    fn capture_start(
+        &mut self,
+        _: u16,
+        _: Address,
+        to: Address,
+        _: MessageKind,
+        _: Bytes,
+        _: u64,
+        _: U256,
+    ) {
+        self.frames.push(Frame {
+            address: to,
+            last_log: None,
+            pending_load: None,
+        });
+    }
+
+    fn capture_state(
+        &mut self,
+        env: &ExecutionState,
+        pc: usize,
+        op: OpCode,
+        cost: u64,
+        depth: u16,
+    ) {
+        let gas = env.gas_left as u64;
+        let Some(frame) = self.frames.last_mut() else {
+            return;
+        };
+        let address = frame.address;
+        let log = self.logs.len();
+        let last_log = frame.last_log.replace(log);
+        let pending_load = std::mem::replace(
+            &mut frame.pending_load,
+            (op == OpCode::SLOAD && !env.stack.is_empty()).then(|| (log, *env.stack.get(0))),
+        );
+
+        if let Some(last_log) = last_log {
+            self.logs[last_log].gas_cost = self.logs[last_log].gas.saturating_sub(gas);
+        }
+        if let Some((load_log, location)) = pending_load {
+            self.store(load_log, address, location, *env.stack.get(0));
+        }
+
+        self.logs.push(StructLog {
+            pc,
+            op: op.name(),
+            gas,
+            gas_cost: cost,
+            depth: depth + 1,
+            stack: (!self.config.disable_stack)
+                .then(|| env.stack.0.iter().map(|v| format!("0x{v:x}")).collect()),
+            memory: (!self.config.disable_memory)
+                .then(|| env.memory.chunks(32).map(hex::encode).collect()),
+            storage: None,
+        });
+
+        if op == OpCode::SSTORE && env.stack.len() >= 2 {
+            self.store(log, address, *env.stack.get(0), *env.stack.get(1));
+        }
+    }
+
+    fn capture_end(&mut self, depth: usize, _: u64, output: &Output) {
+        if let Some(frame) = self.frames.pop() {
+            if let Some(last_log) = frame.last_log {
+                if matches!(output.status_code, StatusCode::Success | StatusCode::Revert) {
+                    self.logs[last_log].gas_cost = self.logs[last_log]
+                        .gas
+                        .saturating_sub(output.gas_left as u64);
+                }
+            }
+        }
+
+        if depth == 0 {
+            self.failed = output.status_code != StatusCode::Success;
+            self.return_value = output.output_data.clone();
+        }
+    }
+}
@@ -169,6 +169,15 @@ where
         self.inner.id()
     }
 
+    /// Begins another read-only transaction in the same environment.
+    ///
+    /// It only sees committed data, not changes made by this transaction.
+    pub fn begin_ro(&self) -> anyhow::Result<MdbxTransaction<'_, RO, E>> {
+        Ok(MdbxTransaction {
+            inner: self.inner.env().begin_ro_txn()?,
+        })
+    }
+
     pub fn cursor<'tx, T>(&'tx self, table: T) -> anyhow::Result<MdbxCursor<'tx, K, T>>
     where
         'env: 'tx,
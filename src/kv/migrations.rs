@@ -0,0 +1,228 @@
+//! Upgrades of data written by older versions to the current on-disk layout.
+
+use super::{mdbx::*, tables, CustomTable};
+use crate::models::*;
+use anyhow::{bail, format_err};
+use bytes::Bytes;
+use parity_scale_codec::{Decode, Encode};
+use tracing::*;
+
+/// Layout version written by this build.
+///
+/// * `0` - headers and bodies before Shanghai: no withdrawals, blob gas or beacon root fields.
+/// * `1` - current layout.
+pub const SCHEMA_VERSION: u64 = 1;
+
+/// Block header as it was stored in schema version 0.
+#[derive(Encode, Decode)]
+struct HeaderV0 {
+    parent_hash: H256,
+    ommers_hash: H256,
+    beneficiary: H160,
+    state_root: H256,
+    transactions_root: H256,
+    receipts_root: H256,
+    logs_bloom: Bloom,
+    difficulty: U256,
+    number: BlockNumber,
+    gas_limit: u64,
+    gas_used: u64,
+    timestamp: u64,
+    extra_data: Bytes,
+    mix_hash: H256,
+    nonce: H64,
+    base_fee_per_gas: Option<U256>,
+}
+
+impl From<HeaderV0> for BlockHeader {
+    fn from(h: HeaderV0) -> Self {
+        Self {
+            parent_hash: h.parent_hash,
+            ommers_hash: h.ommers_hash,
+            beneficiary: h.beneficiary,
+            state_root: h.state_root,
+            transactions_root: h.transactions_root,
+            receipts_root: h.receipts_root,
+            logs_bloom: h.logs_bloom,
+            difficulty: h.difficulty,
+            number: h.number,
+            gas_limit: h.gas_limit,
+            gas_used: h.gas_used,
+            timestamp: h.timestamp,
+            extra_data: h.extra_data,
+            mix_hash: h.mix_hash,
+            nonce: h.nonce,
+            base_fee_per_gas: h.base_fee_per_gas,
+            withdrawals_root: None,
+            blob_gas_used: None,
+            excess_blob_gas: None,
+            parent_beacon_block_root: None,
+        }
+    }
+}
+
+/// Block body as it was stored in schema version 0.
+#[derive(Encode, Decode)]
+struct BodyForStorageV0 {
+    base_tx_id: TxIndex,
+    tx_amount: u64,
+    uncles: Vec<HeaderV0>,
+}
+
+impl From<BodyForStorageV0> for BodyForStorage {
+    fn from(b: BodyForStorageV0) -> Self {
+        Self {
+            base_tx_id: b.base_tx_id,
+            tx_amount: b.tx_amount,
+            uncles: b.uncles.into_iter().map(From::from).collect(),
+            withdrawals: None,
+        }
+    }
+}
+
+fn decode_exact<T: Decode>(mut b: &[u8]) -> anyhow::Result<T> {
+    let v = T::decode(&mut b)?;
+    if !b.is_empty() {
+        bail!("{} trailing bytes", b.len());
+    }
+    Ok(v)
+}
+
+/// Re-encodes every value of the table from the old layout into the current one.
+fn reencode<E, Old, New>(txn: &MdbxTransaction<'_, RW, E>, table: &str) -> anyhow::Result<u64>
+where
+    E: EnvironmentKind,
+    Old: Decode,
+    New: From<Old> + Encode,
+{
+    let mut cursor = txn.cursor(CustomTable::from(table.to_string()))?;
+    let mut migrated = 0;
+    let mut entry = cursor.first()?;
+    while let Some((key, value)) = entry {
+        let v = decode_exact::<Old>(&value)
+            .map_err(|e| format_err!("failed to decode {table} entry {key:?}: {e}"))?;
+        cursor.upsert(key, New::from(v).encode())?;
+        migrated += 1;
+        entry = cursor.next()?;
+    }
+
+    Ok(migrated)
+}
+
+/// Brings the database to [`SCHEMA_VERSION`].
+///
+/// Must be called on a writable transaction before anything else reads the database.
+/// Empty database is simply marked as having the current version.
+pub fn migrate<E: EnvironmentKind>(txn: &MdbxTransaction<'_, RW, E>) -> anyhow::Result<()> {
+    let version = match txn.get(tables::SchemaVersion, ())? {
+        Some(version) if version > SCHEMA_VERSION => bail!(
+            "database schema version {version} is newer than supported version {SCHEMA_VERSION}"
+        ),
+        Some(version) => version,
+        None => 0,
+    };
+
+    if version < 1 {
+        let headers = reencode::<_, HeaderV0, BlockHeader>(txn, tables::Header::const_db_name())?;
+        let bodies = reencode::<_, BodyForStorageV0, BodyForStorage>(
+            txn,
+            tables::BlockBody::const_db_name(),
+        )?;
+        if headers > 0 || bodies > 0 {
+            info!("Migrated {headers} headers and {bodies} bodies to schema version 1");
+        }
+    }
+
+    txn.set(tables::SchemaVersion, (), SCHEMA_VERSION)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        accessors::chain,
+        kv::{new_mem_chaindata, traits::TableEncode},
+    };
+    use hex_literal::hex;
+
+    fn header_v0(number: u64) -> HeaderV0 {
+        HeaderV0 {
+            parent_hash: H256::repeat_byte(1),
+            ommers_hash: H256::repeat_byte(2),
+            beneficiary: H160(hex!("00000000000000000000000000000000000a11ce")),
+            state_root: H256::repeat_byte(3),
+            transactions_root: H256::repeat_byte(4),
+            receipts_root: H256::repeat_byte(5),
+            logs_bloom: Bloom::repeat_byte(6),
+            difficulty: 131_072_u64.into(),
+            number: number.into(),
+            gas_limit: 30_000_000,
+            gas_used: 21_000,
+            timestamp: 1_600_000_000 + number,
+            extra_data: Bytes::from_static(b"extra"),
+            mix_hash: H256::repeat_byte(7),
+            nonce: H64::repeat_byte(8),
+            base_fee_per_gas: Some(7_u64.into()),
+        }
+    }
+
+    #[test]
+    fn migrate_v0() {
+        let db = new_mem_chaindata().unwrap();
+        let txn = db.begin_mutable().unwrap();
+
+        let hash = H256::repeat_byte(0xaa);
+        let key = TableEncode::encode((BlockNumber(5), hash))
+            .as_ref()
+            .to_vec();
+        let header = BlockHeader::from(header_v0(5));
+        let uncle = BlockHeader::from(header_v0(4));
+
+        txn.set(
+            CustomTable::from(tables::Header::const_db_name().to_string()),
+            key.clone(),
+            header_v0(5).encode(),
+        )
+        .unwrap();
+        txn.set(
+            CustomTable::from(tables::BlockBody::const_db_name().to_string()),
+            key,
+            BodyForStorageV0 {
+                base_tx_id: 10.into(),
+                tx_amount: 3,
+                uncles: vec![header_v0(4)],
+            }
+            .encode(),
+        )
+        .unwrap();
+
+        // Old layout does not decode as the current one.
+        assert!(chain::header::read(&txn, hash, 5).is_err());
+
+        migrate(&txn).unwrap();
+        assert_eq!(
+            txn.get(tables::SchemaVersion, ()).unwrap(),
+            Some(SCHEMA_VERSION)
+        );
+
+        assert_eq!(
+            chain::header::read(&txn, hash, 5).unwrap(),
+            Some(header.clone())
+        );
+        assert_eq!(
+            chain::storage_body::read(&txn, hash, 5).unwrap(),
+            Some(BodyForStorage {
+                base_tx_id: 10.into(),
+                tx_amount: 3,
+                uncles: vec![uncle],
+                withdrawals: None,
+            })
+        );
+
+        // Already migrated database is left as is.
+        migrate(&txn).unwrap();
+        assert_eq!(chain::header::read(&txn, hash, 5).unwrap(), Some(header));
+    }
+}
@@ -1,4 +1,5 @@
 pub mod mdbx;
+pub mod migrations;
 pub mod tables;
 pub mod traits;
 
@@ -422,6 +422,22 @@ impl TableDecode for Vec<H256> {
     }
 }
 
+impl TableEncode for Vec<crate::models::Receipt> {
+    type Encoded = Vec<u8>;
+
+    fn encode(self) -> Self::Encoded {
+        let mut v = Vec::new();
+        fastrlp::encode_list::<crate::models::Receipt, _>(&self, &mut v);
+        v
+    }
+}
+
+impl TableDecode for Vec<crate::models::Receipt> {
+    fn decode(mut b: &[u8]) -> anyhow::Result<Self> {
+        Ok(<Self as fastrlp::Decodable>::decode(&mut b)?)
+    }
+}
+
 impl TableEncode for H256 {
     type Encoded = [u8; KECCAK_LENGTH];
 
@@ -538,6 +554,32 @@ impl TableDecode for BitmapKey<Address> {
     }
 }
 
+impl TableEncode for BitmapKey<H256> {
+    type Encoded = [u8; KECCAK_LENGTH + BLOCK_NUMBER_LENGTH];
+
+    fn encode(self) -> Self::Encoded {
+        let mut out = [0; KECCAK_LENGTH + BLOCK_NUMBER_LENGTH];
+        out[..KECCAK_LENGTH].copy_from_slice(&self.inner.encode());
+        out[KECCAK_LENGTH..].copy_from_slice(&self.block_number.encode());
+        out
+    }
+}
+
+impl TableDecode for BitmapKey<H256> {
+    fn decode(b: &[u8]) -> anyhow::Result<Self> {
+        if b.len() != KECCAK_LENGTH + BLOCK_NUMBER_LENGTH {
+            return Err(
+                InvalidLength::<{ KECCAK_LENGTH + BLOCK_NUMBER_LENGTH }> { got: b.len() }.into(),
+            );
+        }
+
+        Ok(Self {
+            inner: H256::decode(&b[..KECCAK_LENGTH])?,
+            block_number: BlockNumber::decode(&b[KECCAK_LENGTH..])?,
+        })
+    }
+}
+
 impl TableEncode for BitmapKey<(Address, H256)> {
     type Encoded = [u8; ADDRESS_LENGTH + KECCAK_LENGTH + BLOCK_NUMBER_LENGTH];
 
@@ -816,9 +858,10 @@ decl_table!(BlockBody => HeaderKey => BodyForStorage => BlockNumber);
 decl_table!(BlockTransaction => TxIndex => MessageWithSignature);
 decl_table!(TotalGas => BlockNumber => u64);
 decl_table!(TotalTx => BlockNumber => u64);
-decl_table!(LogAddressIndex => Address => RoaringTreemap);
+decl_table!(Receipt => BlockNumber => Vec<crate::models::Receipt>);
+decl_table!(LogAddressIndex => BitmapKey<Address> => RoaringTreemap);
 decl_table!(LogAddressesByBlock => BlockNumber => Address);
-decl_table!(LogTopicIndex => H256 => RoaringTreemap);
+decl_table!(LogTopicIndex => BitmapKey<H256> => RoaringTreemap);
 decl_table!(LogTopicsByBlock => BlockNumber => H256);
 decl_table!(CallTraceSet => BlockNumber => CallTraceSetEntry);
 decl_table!(CallFromIndex => BitmapKey<Address> => RoaringTreemap);
@@ -830,6 +873,7 @@ decl_table!(PruneProgress => StageId => BlockNumber);
 decl_table!(TxSender => HeaderKey => Vec<Address>);
 decl_table!(LastHeader => () => HeaderKey);
 decl_table!(Issuance => Vec<u8> => Vec<u8>);
+decl_table!(SchemaVersion => () => u64);
 
 pub type DatabaseChart = BTreeMap<&'static str, TableInfo>;
 
@@ -866,6 +910,7 @@ pub static CHAINDATA_TABLES: Lazy<Arc<DatabaseChart>> = Lazy::new(|| {
             table_entry!(BlockTransaction),
             table_entry!(TotalGas),
             table_entry!(TotalTx),
+            table_entry!(Receipt),
             table_entry!(LogAddressIndex),
             table_entry!(LogAddressesByBlock),
             table_entry!(LogTopicIndex),
@@ -880,6 +925,7 @@ pub static CHAINDATA_TABLES: Lazy<Arc<DatabaseChart>> = Lazy::new(|| {
             table_entry!(TxSender),
             table_entry!(LastHeader),
             table_entry!(Issuance),
+            table_entry!(SchemaVersion),
         ]
         .into_iter()
         .collect(),
@@ -925,6 +971,30 @@ mod tests {
         }
     }
 
+    #[test]
+    fn receipts() {
+        let receipts = vec![
+            crate::models::Receipt::new(
+                TxType::Legacy,
+                true,
+                21_000,
+                vec![Log {
+                    address: Address::repeat_byte(0xaa),
+                    topics: vec![H256::repeat_byte(0xbb)],
+                    data: hex!("deadbeef").to_vec().into(),
+                }],
+            ),
+            crate::models::Receipt::new(TxType::EIP2930, false, 42_000, vec![]),
+            crate::models::Receipt::new(TxType::EIP1559, true, 63_000, vec![]),
+        ];
+
+        let encoded = receipts.clone().encode();
+        assert_eq!(
+            <Vec<crate::models::Receipt> as TableDecode>::decode(&encoded).unwrap(),
+            receipts
+        );
+    }
+
     #[test]
     fn table_meta() {
         assert!(!CHAINDATA_TABLES[tables::Account::const_db_name()].dup_sort);
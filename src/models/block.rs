@@ -5,11 +5,101 @@ use derive_more::Deref;
 use fastrlp::*;
 use parity_scale_codec::*;
 
-#[derive(Clone, Debug, PartialEq, Eq, RlpEncodable, RlpDecodable)]
+/// RLP header of a block or block body, withdrawals list is present only since Shanghai.
+fn rlp_body_header(
+    header: Option<&BlockHeader>,
+    transactions: &[MessageWithSignature],
+    ommers: &[BlockHeader],
+    withdrawals: Option<&[Withdrawal]>,
+) -> Header {
+    let mut rlp_head = Header {
+        list: true,
+        payload_length: 0,
+    };
+
+    if let Some(header) = header {
+        rlp_head.payload_length += header.length();
+    }
+    rlp_head.payload_length += list_length(transactions);
+    rlp_head.payload_length += list_length(ommers);
+    if let Some(withdrawals) = withdrawals {
+        rlp_head.payload_length += list_length(withdrawals);
+    }
+
+    rlp_head
+}
+
+fn decode_withdrawals(
+    buf: &mut &[u8],
+    leftover: usize,
+) -> Result<Option<Vec<Withdrawal>>, DecodeError> {
+    let withdrawals = if buf.len() > leftover {
+        Some(Decodable::decode(buf)?)
+    } else {
+        None
+    };
+
+    if buf.len() != leftover {
+        return Err(DecodeError::UnexpectedLength);
+    }
+
+    Ok(withdrawals)
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Block {
     pub header: BlockHeader,
     pub transactions: Vec<MessageWithSignature>,
     pub ommers: Vec<BlockHeader>,
+    pub withdrawals: Option<Vec<Withdrawal>>,
+}
+
+impl Encodable for Block {
+    fn encode(&self, out: &mut dyn BufMut) {
+        rlp_body_header(
+            Some(&self.header),
+            &self.transactions,
+            &self.ommers,
+            self.withdrawals.as_deref(),
+        )
+        .encode(out);
+        Encodable::encode(&self.header, out);
+        encode_list(&self.transactions, out);
+        encode_list(&self.ommers, out);
+        if let Some(withdrawals) = &self.withdrawals {
+            encode_list(withdrawals, out);
+        }
+    }
+    fn length(&self) -> usize {
+        let rlp_head = rlp_body_header(
+            Some(&self.header),
+            &self.transactions,
+            &self.ommers,
+            self.withdrawals.as_deref(),
+        );
+        length_of_length(rlp_head.payload_length) + rlp_head.payload_length
+    }
+}
+
+impl Decodable for Block {
+    fn decode(buf: &mut &[u8]) -> Result<Self, DecodeError> {
+        let rlp_head = Header::decode(buf)?;
+        if !rlp_head.list {
+            return Err(DecodeError::UnexpectedString);
+        }
+        let leftover = buf.len() - rlp_head.payload_length;
+        let header = Decodable::decode(buf)?;
+        let transactions = Decodable::decode(buf)?;
+        let ommers = Decodable::decode(buf)?;
+        let withdrawals = decode_withdrawals(buf, leftover)?;
+
+        Ok(Self {
+            header,
+            transactions,
+            ommers,
+            withdrawals,
+        })
+    }
 }
 
 impl Block {
@@ -18,14 +108,19 @@ impl Block {
         partial_header: PartialHeader,
         transactions: Vec<MessageWithSignature>,
         ommers: Vec<BlockHeader>,
+        withdrawals: Option<Vec<Withdrawal>>,
     ) -> Self {
         let ommers_hash = Self::ommers_hash(&ommers);
         let transactions_root = root_hash(&transactions);
 
+        let mut header = BlockHeader::new(partial_header, ommers_hash, transactions_root);
+        header.withdrawals_root = withdrawals.as_deref().map(root_hash);
+
         Self {
-            header: BlockHeader::new(partial_header, ommers_hash, transactions_root),
+            header,
             transactions,
             ommers,
+            withdrawals,
         }
     }
 
@@ -41,6 +136,7 @@ pub struct BlockWithSenders {
     pub header: BlockHeader,
     pub transactions: Vec<MessageWithSender>,
     pub ommers: Vec<BlockHeader>,
+    pub withdrawals: Option<Vec<Withdrawal>>,
 }
 
 impl From<Block> for BlockWithSenders {
@@ -62,14 +158,61 @@ impl From<Block> for BlockWithSenders {
             header: block.header,
             transactions,
             ommers: block.ommers,
+            withdrawals: block.withdrawals,
         }
     }
 }
 
-#[derive(Clone, Debug, Default, PartialEq, Eq, RlpEncodable, RlpDecodable)]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
 pub struct BlockBody {
     pub transactions: Vec<MessageWithSignature>,
     pub ommers: Vec<BlockHeader>,
+    pub withdrawals: Option<Vec<Withdrawal>>,
+}
+
+impl Encodable for BlockBody {
+    fn encode(&self, out: &mut dyn BufMut) {
+        rlp_body_header(
+            None,
+            &self.transactions,
+            &self.ommers,
+            self.withdrawals.as_deref(),
+        )
+        .encode(out);
+        encode_list(&self.transactions, out);
+        encode_list(&self.ommers, out);
+        if let Some(withdrawals) = &self.withdrawals {
+            encode_list(withdrawals, out);
+        }
+    }
+    fn length(&self) -> usize {
+        let rlp_head = rlp_body_header(
+            None,
+            &self.transactions,
+            &self.ommers,
+            self.withdrawals.as_deref(),
+        );
+        length_of_length(rlp_head.payload_length) + rlp_head.payload_length
+    }
+}
+
+impl Decodable for BlockBody {
+    fn decode(buf: &mut &[u8]) -> Result<Self, DecodeError> {
+        let rlp_head = Header::decode(buf)?;
+        if !rlp_head.list {
+            return Err(DecodeError::UnexpectedString);
+        }
+        let leftover = buf.len() - rlp_head.payload_length;
+        let transactions = Decodable::decode(buf)?;
+        let ommers = Decodable::decode(buf)?;
+        let withdrawals = decode_withdrawals(buf, leftover)?;
+
+        Ok(Self {
+            transactions,
+            ommers,
+            withdrawals,
+        })
+    }
 }
 
 impl BlockBody {
@@ -80,6 +223,10 @@ impl BlockBody {
     pub fn ommers_hash(&self) -> H256 {
         Block::ommers_hash(&self.ommers)
     }
+
+    pub fn withdrawals_root(&self) -> Option<H256> {
+        self.withdrawals.as_deref().map(root_hash)
+    }
 }
 
 impl From<Block> for BlockBody {
@@ -87,6 +234,7 @@ impl From<Block> for BlockBody {
         Self {
             transactions: block.transactions,
             ommers: block.ommers,
+            withdrawals: block.withdrawals,
         }
     }
 }
@@ -95,13 +243,15 @@ impl From<Block> for BlockBody {
 pub struct BlockBodyWithSenders {
     pub transactions: Vec<MessageWithSender>,
     pub ommers: Vec<BlockHeader>,
+    pub withdrawals: Option<Vec<Withdrawal>>,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Encode, Decode, RlpDecodable)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Encode, Decode)]
 pub struct BodyForStorage {
     pub base_tx_id: TxIndex,
     pub tx_amount: u64,
     pub uncles: Vec<BlockHeader>,
+    pub withdrawals: Option<Vec<Withdrawal>>,
 }
 
 #[derive(Clone, Debug, Deref, Default)]
@@ -271,7 +421,7 @@ mod tests {
             ]
         );
 
-        let block = Block::new(partial_header, transactions, ommers);
+        let block = Block::new(partial_header, transactions, ommers, None);
 
         assert_eq!(
             block.header.transactions_root,
@@ -347,8 +497,13 @@ mod tests {
                     .into(),
                 nonce: hex!("68b769c5451a7aea").into(),
                 base_fee_per_gas: None,
+                withdrawals_root: None,
+                blob_gas_used: None,
+                excess_blob_gas: None,
+                parent_beacon_block_root: None,
             }]
         );
+        assert_eq!(bb.withdrawals, None);
 
         let mut out = BytesMut::new();
         bb.encode(&mut out);
@@ -424,11 +579,48 @@ mod tests {
                     .into(),
                 nonce: hex!("0000000000000023").into(),
                 base_fee_per_gas: None,
+                withdrawals_root: None,
+                blob_gas_used: None,
+                excess_blob_gas: None,
+                parent_beacon_block_root: None,
             }],
+            withdrawals: None,
+        };
+
+        let mut out = BytesMut::new();
+        body.encode(&mut out);
+
+        let out = &mut &*out;
+        let decoded = <BlockBody as Decodable>::decode(out).unwrap();
+        assert!(out.is_empty());
+
+        assert_eq!(decoded, body);
+    }
+
+    #[test]
+    fn block_body_with_withdrawals_rlp() {
+        let body = BlockBody {
+            transactions: vec![],
+            ommers: vec![],
+            withdrawals: Some(vec![
+                Withdrawal {
+                    index: 0,
+                    validator_index: 65_535,
+                    address: hex!("00000000000000000000000000000000000000ff").into(),
+                    amount: 32 * GIGA,
+                },
+                Withdrawal {
+                    index: 1,
+                    validator_index: 65_536,
+                    address: hex!("0000000000000000000000000000000000001000").into(),
+                    amount: 1,
+                },
+            ]),
         };
 
         let mut out = BytesMut::new();
         body.encode(&mut out);
+        assert_eq!(body.length(), out.len());
 
         let out = &mut &*out;
         let decoded = <BlockBody as Decodable>::decode(out).unwrap();
@@ -507,4 +699,47 @@ mod tests {
 
         assert_eq!(decoded, v);
     }
+
+    #[test]
+    fn shanghai_header_rlp() {
+        let v = BlockHeader {
+            number: 17_034_870.into(),
+            base_fee_per_gas: Some(2_700_000_000_u64.into()),
+            withdrawals_root: Some(EMPTY_ROOT),
+            ..BlockHeader::empty()
+        };
+
+        let mut out = BytesMut::new();
+        Encodable::encode(&v, &mut out);
+        assert_eq!(v.length(), out.len());
+
+        let mut out = &*out;
+        let decoded = <BlockHeader as Decodable>::decode(&mut out).unwrap();
+        assert!(out.is_empty());
+
+        assert_eq!(decoded, v);
+    }
+
+    #[test]
+    fn cancun_header_rlp() {
+        let v = BlockHeader {
+            number: 19_426_587.into(),
+            base_fee_per_gas: Some(2_700_000_000_u64.into()),
+            withdrawals_root: Some(EMPTY_ROOT),
+            blob_gas_used: Some(131_072),
+            excess_blob_gas: Some(0),
+            parent_beacon_block_root: Some(H256::repeat_byte(0xbe)),
+            ..BlockHeader::empty()
+        };
+
+        let mut out = BytesMut::new();
+        Encodable::encode(&v, &mut out);
+        assert_eq!(v.length(), out.len());
+
+        let mut out = &*out;
+        let decoded = <BlockHeader as Decodable>::decode(&mut out).unwrap();
+        assert!(out.is_empty());
+
+        assert_eq!(decoded, v);
+    }
 }
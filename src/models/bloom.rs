@@ -27,6 +27,13 @@ where
     bloom
 }
 
+/// Checks whether the bloom filter may contain the given address or topic.
+pub fn bloom_contains(bloom: &Bloom, x: &[u8]) -> bool {
+    let mut needle = Bloom::zero();
+    m3_2048(&mut needle, x);
+    bloom.contains_bloom(&needle)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
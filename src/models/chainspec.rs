@@ -28,16 +28,37 @@ pub struct ChainSpec {
     pub genesis: Genesis,
     #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
     pub contracts: BTreeMap<BlockNumber, HashMap<Address, Contract>>,
+    /// Contract changes activated by block timestamp, applied over block-activated ones.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub timestamp_contracts: BTreeMap<u64, HashMap<Address, Contract>>,
     #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
     pub balances: BTreeMap<BlockNumber, HashMap<Address, U256>>,
     pub p2p: P2PParams,
 }
 
 impl ChainSpec {
-    pub fn collect_block_spec(&self, block_number: impl Into<BlockNumber>) -> BlockExecutionSpec {
+    /// Execution spec of a block with given number and timestamp.
+    ///
+    /// Timestamp-activated upgrades take precedence over block-activated ones.
+    /// Transitions into them are not reported in `active_transitions`,
+    /// since that would require the parent's timestamp.
+    pub fn collect_block_spec(
+        &self,
+        block_number: impl Into<BlockNumber>,
+        timestamp: u64,
+    ) -> BlockExecutionSpec {
         let block_number = block_number.into();
         let mut revision = Revision::Frontier;
         let mut active_transitions = HashSet::new();
+        for (fork_time, r) in [
+            (self.upgrades.eof_time, Revision::Eof),
+            (self.upgrades.cancun_time, Revision::Cancun),
+            (self.upgrades.shanghai_time, Revision::Shanghai),
+        ] {
+            if revision == Revision::Frontier && switch_is_active_at(fork_time, timestamp) {
+                revision = r;
+            }
+        }
         for (fork, r) in [
             (self.upgrades.paris, Revision::Paris),
             (self.upgrades.london, Revision::London),
@@ -68,18 +89,24 @@ impl ChainSpec {
             revision,
             active_transitions,
             params: self.params.clone(),
-            system_contract_changes: self.contracts.iter().fold(
-                HashMap::new(),
-                |mut acc, (bn, contracts)| {
-                    if block_number >= *bn {
-                        for (addr, contract) in contracts {
-                            acc.insert(*addr, contract.clone());
-                        }
+            system_contract_changes: self
+                .contracts
+                .iter()
+                .filter(|(bn, _)| block_number >= **bn)
+                .map(|(_, contracts)| contracts)
+                .chain(
+                    self.timestamp_contracts
+                        .iter()
+                        .filter(|(fork_time, _)| timestamp >= **fork_time)
+                        .map(|(_, contracts)| contracts),
+                )
+                .fold(HashMap::new(), |mut acc, contracts| {
+                    for (addr, contract) in contracts {
+                        acc.insert(*addr, contract.clone());
                     }
 
                     acc
-                },
-            ),
+                }),
             balance_changes: self
                 .balances
                 .get(&block_number)
@@ -115,6 +142,22 @@ impl ChainSpec {
 
         forks
     }
+
+    /// Timestamps of upgrades activated by time, excluding those active since genesis.
+    ///
+    /// Hashed into fork id after block forks, see [EIP-6122](https://eips.ethereum.org/EIPS/eip-6122).
+    pub fn gather_timestamp_forks(&self) -> BTreeSet<u64> {
+        [
+            self.upgrades.shanghai_time,
+            self.upgrades.cancun_time,
+            self.upgrades.eof_time,
+        ]
+        .into_iter()
+        .flatten()
+        .chain(self.timestamp_contracts.keys().copied())
+        .filter(|&fork_time| fork_time > self.genesis.timestamp)
+        .collect()
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -153,6 +196,10 @@ pub fn switch_is_active(switch: Option<BlockNumber>, block_number: BlockNumber)
     block_number >= switch.unwrap_or(BlockNumber(u64::MAX))
 }
 
+pub fn switch_is_active_at(switch: Option<u64>, timestamp: u64) -> bool {
+    timestamp >= switch.unwrap_or(u64::MAX)
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum SealVerificationParams {
     Clique {
@@ -298,6 +345,28 @@ pub struct Upgrades {
         with = "::serde_with::rust::unwrap_or_skip"
     )]
     pub paris: Option<BlockNumber>,
+
+    // Upgrades below are activated by block timestamp rather than number.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "::serde_with::rust::unwrap_or_skip"
+    )]
+    pub shanghai_time: Option<u64>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "::serde_with::rust::unwrap_or_skip"
+    )]
+    pub cancun_time: Option<u64>,
+    /// Experimental EVM Object Format revision, meant for private networks only.
+    /// Must not precede `cancun_time`.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "::serde_with::rust::unwrap_or_skip"
+    )]
+    pub eof_time: Option<u64>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -411,6 +480,14 @@ pub enum Precompile {
     AltBn128Mul { price: u64 },
     AltBn128Pairing { base: u64, pair: u64 },
     Blake2F { gas_per_round: u64 },
+    P256Verify { price: u64 },
+    Bls12381G1Add,
+    Bls12381G1Msm,
+    Bls12381G2Add,
+    Bls12381G2Msm,
+    Bls12381PairingCheck,
+    Bls12381MapFpToG1,
+    Bls12381MapFp2ToG2,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -462,6 +539,9 @@ mod tests {
                     berlin: Some(8290928.into()),
                     london: Some(8897988.into()),
                     paris: None,
+                    shanghai_time: None,
+                    cancun_time: None,
+                    eof_time: None,
                 },
                 params: Params {
                     chain_id: ChainId(4),
@@ -488,6 +568,7 @@ mod tests {
                     },
                 },
                 contracts: Default::default(),
+                timestamp_contracts: Default::default(),
                 balances: btreemap! {
                     0.into() => (0x00..=0xff)
                     .map(|address| (Address::from_low_u64_be(address), 1u64.as_u256()))
@@ -524,4 +605,35 @@ mod tests {
             .collect()
         );
     }
+
+    #[test]
+    fn timestamp_forks() {
+        assert_eq!(
+            MAINNET.gather_timestamp_forks(),
+            [1_681_338_455, 1_710_338_135].into_iter().collect()
+        );
+        assert_eq!(
+            MAINNET
+                .collect_block_spec(BlockNumber(17_034_869), 1_681_338_443)
+                .revision,
+            Revision::London
+        );
+        assert_eq!(
+            MAINNET
+                .collect_block_spec(BlockNumber(17_034_870), 1_681_338_455)
+                .revision,
+            Revision::Shanghai
+        );
+        assert_eq!(
+            MAINNET
+                .collect_block_spec(BlockNumber(19_426_587), 1_710_338_135)
+                .revision,
+            Revision::Cancun
+        );
+
+        let mut spec = SEPOLIA.clone();
+        spec.upgrades.shanghai_time = Some(spec.genesis.timestamp);
+        spec.upgrades.cancun_time = Some(spec.genesis.timestamp);
+        assert!(spec.gather_timestamp_forks().is_empty());
+    }
 }
@@ -1,6 +1,6 @@
 use crate::{
     genesis::GenesisState,
-    models::{BlockNumber, ChainSpec, NetworkId, H256},
+    models::{ChainSpec, NetworkId, H256},
 };
 
 const REPOSITORY_URL: &str = "https://github.com/akula-bft/akula";
@@ -34,11 +34,21 @@ impl ChainConfig {
         &self.chain_spec.name
     }
 
-    pub fn forks(&self) -> Vec<BlockNumber> {
+    /// Fork block numbers, hashed into fork id first.
+    pub fn block_forks(&self) -> Vec<u64> {
         self.chain_spec
             .gather_forks()
             .into_iter()
-            .collect::<Vec<_>>()
+            .map(|fork| *fork)
+            .collect()
+    }
+
+    /// Fork timestamps, hashed into fork id after block forks.
+    pub fn time_forks(&self) -> Vec<u64> {
+        self.chain_spec
+            .gather_timestamp_forks()
+            .into_iter()
+            .collect()
     }
 
     pub fn bootnodes(&self) -> Vec<String> {
@@ -24,6 +24,10 @@ pub struct BlockHeader {
     pub mix_hash: H256,
     pub nonce: H64,
     pub base_fee_per_gas: Option<U256>,
+    pub withdrawals_root: Option<H256>,
+    pub blob_gas_used: Option<u64>,
+    pub excess_blob_gas: Option<u64>,
+    pub parent_beacon_block_root: Option<H256>,
 }
 
 impl BlockHeader {
@@ -54,6 +58,22 @@ impl BlockHeader {
             rlp_head.payload_length += base_fee_per_gas.length();
         }
 
+        if self.withdrawals_root.is_some() {
+            rlp_head.payload_length += KECCAK_LENGTH + 1;
+        }
+
+        if let Some(blob_gas_used) = self.blob_gas_used {
+            rlp_head.payload_length += blob_gas_used.length();
+        }
+
+        if let Some(excess_blob_gas) = self.excess_blob_gas {
+            rlp_head.payload_length += excess_blob_gas.length();
+        }
+
+        if self.parent_beacon_block_root.is_some() {
+            rlp_head.payload_length += KECCAK_LENGTH + 1;
+        }
+
         rlp_head
     }
 }
@@ -79,6 +99,18 @@ impl Encodable for BlockHeader {
         if let Some(base_fee_per_gas) = self.base_fee_per_gas {
             Encodable::encode(&base_fee_per_gas, out);
         }
+        if let Some(withdrawals_root) = self.withdrawals_root {
+            Encodable::encode(&withdrawals_root, out);
+        }
+        if let Some(blob_gas_used) = self.blob_gas_used {
+            Encodable::encode(&blob_gas_used, out);
+        }
+        if let Some(excess_blob_gas) = self.excess_blob_gas {
+            Encodable::encode(&excess_blob_gas, out);
+        }
+        if let Some(parent_beacon_block_root) = self.parent_beacon_block_root {
+            Encodable::encode(&parent_beacon_block_root, out);
+        }
     }
     fn length(&self) -> usize {
         let rlp_head = self.rlp_header();
@@ -113,6 +145,26 @@ impl Decodable for BlockHeader {
         } else {
             None
         };
+        let withdrawals_root = if buf.len() > leftover {
+            Some(Decodable::decode(buf)?)
+        } else {
+            None
+        };
+        let blob_gas_used = if buf.len() > leftover {
+            Some(Decodable::decode(buf)?)
+        } else {
+            None
+        };
+        let excess_blob_gas = if buf.len() > leftover {
+            Some(Decodable::decode(buf)?)
+        } else {
+            None
+        };
+        let parent_beacon_block_root = if buf.len() > leftover {
+            Some(Decodable::decode(buf)?)
+        } else {
+            None
+        };
 
         Ok(Self {
             parent_hash,
@@ -131,6 +183,10 @@ impl Decodable for BlockHeader {
             mix_hash,
             nonce,
             base_fee_per_gas,
+            withdrawals_root,
+            blob_gas_used,
+            excess_blob_gas,
+            parent_beacon_block_root,
         })
     }
 }
@@ -155,10 +211,13 @@ impl BlockHeader {
             mix_hash: partial_header.mix_hash,
             nonce: partial_header.nonce,
             base_fee_per_gas: partial_header.base_fee_per_gas,
+            withdrawals_root: None,
+            blob_gas_used: None,
+            excess_blob_gas: None,
+            parent_beacon_block_root: None,
         }
     }
 
-    #[cfg(test)]
     pub(crate) const fn empty() -> Self {
         Self {
             parent_hash: H256::zero(),
@@ -177,6 +236,10 @@ impl BlockHeader {
             mix_hash: H256::zero(),
             nonce: H64::zero(),
             base_fee_per_gas: None,
+            withdrawals_root: None,
+            blob_gas_used: None,
+            excess_blob_gas: None,
+            parent_beacon_block_root: None,
         }
     }
 
@@ -204,6 +267,10 @@ impl BlockHeader {
             timestamp: u64,
             extra_data: Bytes,
             base_fee_per_gas: Option<U256>,
+            withdrawals_root: Option<H256>,
+            blob_gas_used: Option<u64>,
+            excess_blob_gas: Option<u64>,
+            parent_beacon_block_root: Option<H256>,
         }
 
         impl TruncatedHeader {
@@ -231,6 +298,22 @@ impl BlockHeader {
                     rlp_head.payload_length += base_fee_per_gas.length();
                 }
 
+                if self.withdrawals_root.is_some() {
+                    rlp_head.payload_length += KECCAK_LENGTH + 1;
+                }
+
+                if let Some(blob_gas_used) = self.blob_gas_used {
+                    rlp_head.payload_length += blob_gas_used.length();
+                }
+
+                if let Some(excess_blob_gas) = self.excess_blob_gas {
+                    rlp_head.payload_length += excess_blob_gas.length();
+                }
+
+                if self.parent_beacon_block_root.is_some() {
+                    rlp_head.payload_length += KECCAK_LENGTH + 1;
+                }
+
                 rlp_head
             }
         }
@@ -254,6 +337,18 @@ impl BlockHeader {
                 if let Some(base_fee_per_gas) = self.base_fee_per_gas {
                     Encodable::encode(&base_fee_per_gas, out);
                 }
+                if let Some(withdrawals_root) = self.withdrawals_root {
+                    Encodable::encode(&withdrawals_root, out);
+                }
+                if let Some(blob_gas_used) = self.blob_gas_used {
+                    Encodable::encode(&blob_gas_used, out);
+                }
+                if let Some(excess_blob_gas) = self.excess_blob_gas {
+                    Encodable::encode(&excess_blob_gas, out);
+                }
+                if let Some(parent_beacon_block_root) = self.parent_beacon_block_root {
+                    Encodable::encode(&parent_beacon_block_root, out);
+                }
             }
             fn length(&self) -> usize {
                 let rlp_head = self.rlp_header();
@@ -278,6 +373,10 @@ impl BlockHeader {
             timestamp: self.timestamp,
             extra_data: self.extra_data.clone(),
             base_fee_per_gas: self.base_fee_per_gas,
+            withdrawals_root: self.withdrawals_root,
+            blob_gas_used: self.blob_gas_used,
+            excess_blob_gas: self.excess_blob_gas,
+            parent_beacon_block_root: self.parent_beacon_block_root,
         }
         .encode(&mut buffer);
 
@@ -8,10 +8,11 @@ mod log;
 mod receipt;
 mod revision;
 mod transaction;
+mod withdrawal;
 
 pub use self::{
     account::*, block::*, bloom::*, chainspec::*, config::*, header::*, log::*, receipt::*,
-    revision::*, transaction::*,
+    revision::*, transaction::*, withdrawal::*,
 };
 
 use derive_more::*;
@@ -130,22 +130,25 @@ impl Decodable for Receipt {
         } else if rlp_head.payload_length == 0 {
             return Err(DecodeError::InputTooShort);
         } else {
-            if buf.is_empty() {
+            if buf.len() < rlp_head.payload_length {
                 return Err(DecodeError::InputTooShort);
             }
 
-            let tx_type = TxType::try_from(buf.get_u8())?;
+            let mut payload = &buf[..rlp_head.payload_length];
+            buf.advance(rlp_head.payload_length);
 
-            if tx_type != TxType::EIP2930 && tx_type != TxType::EIP1559 {
+            let tx_type = TxType::try_from(payload.get_u8())?;
+
+            if tx_type == TxType::Legacy {
                 return Err(DecodeError::Custom("Unsupported transaction type"));
             }
 
-            let this = eip2718_decode(buf, tx_type)?;
+            let this = eip2718_decode(&mut payload, tx_type)?;
 
-            if !buf.is_empty() {
+            if !payload.is_empty() {
                 return Err(DecodeError::ListLengthMismatch {
                     expected: 0,
-                    got: buf.len(),
+                    got: payload.len(),
                 });
             }
 
@@ -37,6 +37,21 @@ pub enum Revision {
 
     /// [The Paris revision.](https://github.com/ethereum/eth1.0-specs/blob/master/network-upgrades/mainnet-upgrades/paris.md)
     Paris = 10,
+
+    /// [The Shanghai revision.](https://github.com/ethereum/execution-specs/blob/master/network-upgrades/mainnet-upgrades/shanghai.md)
+    Shanghai = 11,
+
+    /// [The Cancun revision.](https://github.com/ethereum/execution-specs/blob/master/network-upgrades/mainnet-upgrades/cancun.md)
+    Cancun = 12,
+
+    /// Experimental EVM Object Format v1 revision, not scheduled on any public network.
+    ///
+    /// Enables [EIP-3540](https://eips.ethereum.org/EIPS/eip-3540),
+    /// [EIP-3670](https://eips.ethereum.org/EIPS/eip-3670),
+    /// [EIP-4200](https://eips.ethereum.org/EIPS/eip-4200),
+    /// [EIP-4750](https://eips.ethereum.org/EIPS/eip-4750) and
+    /// [EIP-5450](https://eips.ethereum.org/EIPS/eip-5450) on top of Cancun.
+    Eof = 13,
 }
 
 impl Revision {
@@ -53,11 +68,14 @@ impl Revision {
             Self::Berlin,
             Self::London,
             Self::Paris,
+            Self::Shanghai,
+            Self::Cancun,
+            Self::Eof,
         ]
     }
 
     pub const fn latest() -> Self {
-        Self::Paris
+        Self::Eof
     }
 
     pub const fn len() -> usize {
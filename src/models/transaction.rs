@@ -23,6 +23,7 @@ pub enum TxType {
     Legacy = 0,
     EIP2930 = 1,
     EIP1559 = 2,
+    EIP4844 = 3,
 }
 
 impl TryFrom<u8> for TxType {
@@ -32,6 +33,7 @@ impl TryFrom<u8> for TxType {
             0 => Ok(TxType::Legacy),
             1 => Ok(TxType::EIP2930),
             2 => Ok(TxType::EIP1559),
+            3 => Ok(TxType::EIP4844),
             _ => Err(DecodeError::Custom("Invalid tx type")),
         }
     }
@@ -296,6 +298,30 @@ pub enum Message {
         input: Bytes,
         access_list: Vec<AccessListItem>,
     },
+    /// Blob transaction, see [EIP-4844](https://eips.ethereum.org/EIPS/eip-4844).
+    ///
+    /// Cannot create contracts, hence plain `to` instead of `action`.
+    EIP4844 {
+        #[codec(compact)]
+        chain_id: ChainId,
+        #[codec(compact)]
+        nonce: u64,
+        #[codec(compact)]
+        max_priority_fee_per_gas: U256,
+        #[codec(compact)]
+        max_fee_per_gas: U256,
+        #[codec(compact)]
+        gas_limit: u64,
+        to: Address,
+        #[codec(compact)]
+        value: U256,
+        #[educe(Debug(method = "write_hex_string"))]
+        input: Bytes,
+        access_list: Vec<AccessListItem>,
+        #[codec(compact)]
+        max_fee_per_blob_gas: U256,
+        blob_versioned_hashes: Vec<H256>,
+    },
 }
 
 impl Message {
@@ -434,6 +460,51 @@ impl Message {
                 }
                 .encode(&mut buf);
             }
+            Message::EIP4844 {
+                chain_id,
+                nonce,
+                max_priority_fee_per_gas,
+                max_fee_per_gas,
+                gas_limit,
+                to,
+                value,
+                input,
+                access_list,
+                max_fee_per_blob_gas,
+                blob_versioned_hashes,
+            } => {
+                buf.put_u8(3);
+
+                #[derive(RlpEncodable)]
+                struct S<'a> {
+                    chain_id: ChainId,
+                    nonce: u64,
+                    max_priority_fee_per_gas: &'a U256,
+                    max_fee_per_gas: &'a U256,
+                    gas_limit: u64,
+                    to: &'a Address,
+                    value: &'a U256,
+                    input: &'a Bytes,
+                    access_list: &'a Vec<AccessListItem>,
+                    max_fee_per_blob_gas: &'a U256,
+                    blob_versioned_hashes: &'a Vec<H256>,
+                }
+
+                S {
+                    chain_id: *chain_id,
+                    nonce: *nonce,
+                    max_priority_fee_per_gas,
+                    max_fee_per_gas,
+                    gas_limit: *gas_limit,
+                    to,
+                    value,
+                    input,
+                    access_list,
+                    max_fee_per_blob_gas,
+                    blob_versioned_hashes,
+                }
+                .encode(&mut buf);
+            }
         };
 
         keccak256(&buf)
@@ -596,6 +667,65 @@ impl MessageWithSignature {
                     tmp.put_u8(2);
                     s.encode(&mut tmp);
 
+                    Encodable::encode(&(&*tmp as &[u8]), out);
+                }
+            }
+            Message::EIP4844 {
+                chain_id,
+                nonce,
+                max_priority_fee_per_gas,
+                max_fee_per_gas,
+                gas_limit,
+                to,
+                value,
+                input,
+                access_list,
+                max_fee_per_blob_gas,
+                blob_versioned_hashes,
+            } => {
+                #[derive(RlpEncodable)]
+                struct S<'a> {
+                    chain_id: &'a ChainId,
+                    nonce: &'a u64,
+                    max_priority_fee_per_gas: &'a U256,
+                    max_fee_per_gas: &'a U256,
+                    gas_limit: &'a u64,
+                    to: &'a Address,
+                    value: &'a U256,
+                    input: &'a Bytes,
+                    access_list: &'a Vec<AccessListItem>,
+                    max_fee_per_blob_gas: &'a U256,
+                    blob_versioned_hashes: &'a Vec<H256>,
+                    odd_y_parity: bool,
+                    r: U256,
+                    s: U256,
+                }
+
+                let s = S {
+                    chain_id,
+                    nonce,
+                    max_priority_fee_per_gas,
+                    max_fee_per_gas,
+                    gas_limit,
+                    to,
+                    value,
+                    input,
+                    access_list,
+                    max_fee_per_blob_gas,
+                    blob_versioned_hashes,
+                    odd_y_parity: self.signature.odd_y_parity,
+                    r: U256::from_be_bytes(self.signature.r.0),
+                    s: U256::from_be_bytes(self.signature.s.0),
+                };
+
+                if standalone {
+                    out.put_u8(3);
+                    s.encode(out);
+                } else {
+                    let mut tmp = BytesMut::new();
+                    tmp.put_u8(3);
+                    s.encode(&mut tmp);
+
                     Encodable::encode(&(&*tmp as &[u8]), out);
                 }
             }
@@ -784,6 +914,61 @@ impl MessageWithSignature {
             });
         }
 
+        if first == 0x03 {
+            #[derive(RlpDecodable)]
+            struct S {
+                chain_id: ChainId,
+                nonce: u64,
+                max_priority_fee_per_gas: U256,
+                max_fee_per_gas: U256,
+                gas_limit: u64,
+                to: Address,
+                value: U256,
+                input: Bytes,
+                access_list: Vec<AccessListItem>,
+                max_fee_per_blob_gas: U256,
+                blob_versioned_hashes: Vec<H256>,
+                odd_y_parity: bool,
+                r: U256,
+                s: U256,
+            }
+
+            let S {
+                chain_id,
+                nonce,
+                max_priority_fee_per_gas,
+                max_fee_per_gas,
+                gas_limit,
+                to,
+                value,
+                input,
+                access_list,
+                max_fee_per_blob_gas,
+                blob_versioned_hashes,
+                odd_y_parity,
+                r,
+                s,
+            } = S::decode(buf)?;
+
+            return Ok(Self {
+                message: Message::EIP4844 {
+                    chain_id,
+                    nonce,
+                    max_priority_fee_per_gas,
+                    max_fee_per_gas,
+                    gas_limit,
+                    to,
+                    value,
+                    input,
+                    access_list,
+                    max_fee_per_blob_gas,
+                    blob_versioned_hashes,
+                },
+                signature: MessageSignature::new(odd_y_parity, u256_to_h256(r), u256_to_h256(s))
+                    .ok_or(DecodeError::Custom("Invalid transaction signature format"))?,
+            });
+        }
+
         Err(DecodeError::Custom("invalid tx type"))
     }
 }
@@ -794,6 +979,7 @@ impl Message {
             Self::Legacy { .. } => TxType::Legacy,
             Self::EIP2930 { .. } => TxType::EIP2930,
             Self::EIP1559 { .. } => TxType::EIP1559,
+            Self::EIP4844 { .. } => TxType::EIP4844,
         }
     }
 
@@ -801,7 +987,7 @@ impl Message {
         match *self {
             Self::Legacy { chain_id, .. } => chain_id,
             Self::EIP2930 { chain_id, .. } => Some(chain_id),
-            Self::EIP1559 { chain_id, .. } => Some(chain_id),
+            Self::EIP1559 { chain_id, .. } | Self::EIP4844 { chain_id, .. } => Some(chain_id),
         }
     }
 
@@ -809,7 +995,8 @@ impl Message {
         match *self {
             Self::Legacy { nonce, .. }
             | Self::EIP2930 { nonce, .. }
-            | Self::EIP1559 { nonce, .. } => nonce,
+            | Self::EIP1559 { nonce, .. }
+            | Self::EIP4844 { nonce, .. } => nonce,
         }
     }
 
@@ -819,6 +1006,10 @@ impl Message {
             Self::EIP1559 {
                 max_priority_fee_per_gas,
                 ..
+            }
+            | Self::EIP4844 {
+                max_priority_fee_per_gas,
+                ..
             } => max_priority_fee_per_gas,
         }
     }
@@ -828,6 +1019,9 @@ impl Message {
             Self::Legacy { gas_price, .. } | Self::EIP2930 { gas_price, .. } => gas_price,
             Self::EIP1559 {
                 max_fee_per_gas, ..
+            }
+            | Self::EIP4844 {
+                max_fee_per_gas, ..
             } => max_fee_per_gas,
         }
     }
@@ -836,7 +1030,8 @@ impl Message {
         match *self {
             Self::Legacy { gas_limit, .. }
             | Self::EIP2930 { gas_limit, .. }
-            | Self::EIP1559 { gas_limit, .. } => gas_limit,
+            | Self::EIP1559 { gas_limit, .. }
+            | Self::EIP4844 { gas_limit, .. } => gas_limit,
         }
     }
 
@@ -845,6 +1040,7 @@ impl Message {
             Self::Legacy { action, .. }
             | Self::EIP2930 { action, .. }
             | Self::EIP1559 { action, .. } => action,
+            Self::EIP4844 { to, .. } => TransactionAction::Call(to),
         }
     }
 
@@ -852,7 +1048,8 @@ impl Message {
         match *self {
             Self::Legacy { value, .. }
             | Self::EIP2930 { value, .. }
-            | Self::EIP1559 { value, .. } => value,
+            | Self::EIP1559 { value, .. }
+            | Self::EIP4844 { value, .. } => value,
         }
     }
 
@@ -860,16 +1057,37 @@ impl Message {
         match self {
             Self::Legacy { input, .. }
             | Self::EIP2930 { input, .. }
-            | Self::EIP1559 { input, .. } => input,
+            | Self::EIP1559 { input, .. }
+            | Self::EIP4844 { input, .. } => input,
         }
     }
 
     pub const fn access_list(&self) -> Cow<'_, AccessList> {
         match self {
             Self::Legacy { .. } => Cow::Owned(AccessList::new()),
-            Self::EIP2930 { access_list, .. } | Self::EIP1559 { access_list, .. } => {
-                Cow::Borrowed(access_list)
-            }
+            Self::EIP2930 { access_list, .. }
+            | Self::EIP1559 { access_list, .. }
+            | Self::EIP4844 { access_list, .. } => Cow::Borrowed(access_list),
+        }
+    }
+
+    pub const fn max_fee_per_blob_gas(&self) -> U256 {
+        match *self {
+            Self::Legacy { .. } | Self::EIP2930 { .. } | Self::EIP1559 { .. } => U256::ZERO,
+            Self::EIP4844 {
+                max_fee_per_blob_gas,
+                ..
+            } => max_fee_per_blob_gas,
+        }
+    }
+
+    pub fn blob_versioned_hashes(&self) -> &[H256] {
+        match self {
+            Self::Legacy { .. } | Self::EIP2930 { .. } | Self::EIP1559 { .. } => &[],
+            Self::EIP4844 {
+                blob_versioned_hashes,
+                ..
+            } => blob_versioned_hashes,
         }
     }
 
@@ -886,6 +1104,43 @@ impl Message {
 }
 
 impl MessageWithSignature {
+    /// Decodes transaction from its EIP-2718 envelope, i.e. the form used in transaction trie,
+    /// where typed transactions are not wrapped into RLP string.
+    pub fn decode_envelope(mut buf: &[u8]) -> Result<Self, DecodeError> {
+        let s = match buf.first() {
+            None => return Err(DecodeError::InputTooShort),
+            Some(&b) if b >= EMPTY_LIST_CODE => <Self as Decodable>::decode(&mut buf)?,
+            Some(_) => {
+                let mut wrapped = BytesMut::with_capacity(buf.len() + 9);
+                Header {
+                    list: false,
+                    payload_length: buf.len(),
+                }
+                .encode(&mut wrapped);
+                wrapped.extend_from_slice(buf);
+                buf = &[];
+
+                <Self as Decodable>::decode(&mut &*wrapped)?
+            }
+        };
+
+        if !buf.is_empty() {
+            return Err(DecodeError::ListLengthMismatch {
+                expected: 0,
+                got: buf.len(),
+            });
+        }
+
+        Ok(s)
+    }
+
+    /// Encodes transaction into its EIP-2718 envelope.
+    pub fn encode_envelope(&self) -> Bytes {
+        let mut buf = BytesMut::new();
+        self.trie_encode(&mut buf);
+        buf.freeze()
+    }
+
     pub fn hash(&self) -> H256 {
         let mut buf = BytesMut::new();
         self.trie_encode(&mut buf);
@@ -926,6 +1181,123 @@ impl MessageWithSignature {
     }
 }
 
+/// Blobs of a blob transaction with their KZG commitments and proofs, see [EIP-4844](https://eips.ethereum.org/EIPS/eip-4844#networking).
+///
+/// Only gossiped between peers, blocks carry versioned hashes alone.
+#[derive(Clone, Debug, PartialEq, Eq, RlpEncodable, RlpDecodable)]
+pub struct BlobTransactionSidecar {
+    pub blobs: Vec<Bytes>,
+    pub commitments: Vec<Bytes>,
+    pub proofs: Vec<Bytes>,
+}
+
+/// Transaction in the form exchanged in `PooledTransactions`,
+/// where blob transactions are followed by their sidecar.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NetworkTransaction {
+    pub transaction: MessageWithSignature,
+    pub sidecar: Option<BlobTransactionSidecar>,
+}
+
+impl From<MessageWithSignature> for NetworkTransaction {
+    fn from(transaction: MessageWithSignature) -> Self {
+        Self {
+            transaction,
+            sidecar: None,
+        }
+    }
+}
+
+impl Encodable for NetworkTransaction {
+    fn encode(&self, out: &mut dyn BufMut) {
+        let Some(sidecar) = &self.sidecar else {
+            return Encodable::encode(&self.transaction, out);
+        };
+
+        // type || rlp([tx_payload_body, blobs, commitments, proofs])
+        let envelope = self.transaction.encode_envelope();
+        let (tx_type, body) = envelope.split_first().unwrap();
+
+        let mut tmp = BytesMut::new();
+        tmp.put_u8(*tx_type);
+        Header {
+            list: true,
+            payload_length: body.len()
+                + Encodable::length(&sidecar.blobs)
+                + Encodable::length(&sidecar.commitments)
+                + Encodable::length(&sidecar.proofs),
+        }
+        .encode(&mut tmp);
+        tmp.extend_from_slice(body);
+        Encodable::encode(&sidecar.blobs, &mut tmp);
+        Encodable::encode(&sidecar.commitments, &mut tmp);
+        Encodable::encode(&sidecar.proofs, &mut tmp);
+
+        Header {
+            list: false,
+            payload_length: tmp.len(),
+        }
+        .encode(out);
+        out.put_slice(&tmp);
+    }
+}
+
+impl Decodable for NetworkTransaction {
+    fn decode(buf: &mut &[u8]) -> Result<Self, DecodeError> {
+        let mut payload = &**buf;
+        let h = Header::decode(&mut payload)?;
+        if h.list || h.payload_length == 0 || payload.first() != Some(&(TxType::EIP4844 as u8)) {
+            return <MessageWithSignature as Decodable>::decode(buf).map(Self::from);
+        }
+        if payload.len() < h.payload_length {
+            return Err(DecodeError::InputTooShort);
+        }
+        let total_length = buf.len() - payload.len() + h.payload_length;
+        payload = &payload[1..h.payload_length];
+
+        // Without sidecar the outer list holds transaction fields, with it - the fields' list.
+        let mut fields = payload;
+        let outer = Header::decode(&mut fields)?;
+        if !outer.list {
+            return Err(DecodeError::UnexpectedString);
+        }
+        if !matches!(fields.first(), Some(&b) if b >= EMPTY_LIST_CODE) {
+            return <MessageWithSignature as Decodable>::decode(buf).map(Self::from);
+        }
+
+        let mut body = fields;
+        let body_header = Header::decode(&mut body)?;
+        let body_length = fields.len() - body.len() + body_header.payload_length;
+        if fields.len() < body_length {
+            return Err(DecodeError::InputTooShort);
+        }
+        let mut envelope = Vec::with_capacity(1 + body_length);
+        envelope.push(TxType::EIP4844 as u8);
+        envelope.extend_from_slice(&fields[..body_length]);
+        let transaction = MessageWithSignature::decode_envelope(&envelope)?;
+
+        let mut rest = &fields[body_length..];
+        let sidecar = BlobTransactionSidecar {
+            blobs: Decodable::decode(&mut rest)?,
+            commitments: Decodable::decode(&mut rest)?,
+            proofs: Decodable::decode(&mut rest)?,
+        };
+        if !rest.is_empty() {
+            return Err(DecodeError::ListLengthMismatch {
+                expected: 0,
+                got: rest.len(),
+            });
+        }
+
+        buf.advance(total_length);
+
+        Ok(Self {
+            transaction,
+            sidecar: Some(sidecar),
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -952,6 +1324,11 @@ mod tests {
         let mut consensus_encoded = BytesMut::new();
         v.trie_encode(&mut consensus_encoded);
         assert_eq!(encoded[standalone_idx..], consensus_encoded);
+        assert_eq!(v.encode_envelope(), consensus_encoded);
+        assert_eq!(
+            MessageWithSignature::decode_envelope(&consensus_encoded).unwrap(),
+            *v
+        );
     }
 
     #[test]
@@ -1061,6 +1438,73 @@ mod tests {
         check_transaction(&v, 2);
     }
 
+    fn blob_transaction() -> MessageWithSignature {
+        MessageWithSignature {
+            message: Message::EIP4844 {
+                chain_id: ChainId(1),
+                nonce: 3,
+                max_priority_fee_per_gas: 1_000_000_000_u64.into(),
+                max_fee_per_gas: 30_000_000_000_u64.into(),
+                gas_limit: 21_000,
+                to: hex!("811a752c8cd697e3cb27279c330ed1ada745a8d7").into(),
+                value: 0.as_u256(),
+                input: Bytes::new(),
+                access_list: vec![],
+                max_fee_per_blob_gas: 10_000_000_u64.into(),
+                blob_versioned_hashes: vec![hex!(
+                    "010657f37554c781402a22917dee2f75def7ab966d7b770905398eba3c444014"
+                )
+                .into()],
+            },
+            signature: MessageSignature::new(
+                true,
+                hex!("36b241b061a36a32ab7fe86c7aa9eb592dd59018cd0443adc0903590c16b02b0"),
+                hex!("5edcc541b4741c5cc6dd347c5ed9577ef293a62787b4510465fadbfe39ee4094"),
+            )
+            .unwrap(),
+        }
+    }
+
+    #[test]
+    fn transaction_eip4844() {
+        let v = blob_transaction();
+        assert_eq!(v.tx_type(), TxType::EIP4844);
+        assert_eq!(v.max_fee_per_blob_gas(), 10_000_000_u64.as_u256());
+        assert_eq!(v.blob_versioned_hashes().len(), 1);
+
+        check_transaction(&v, 2);
+    }
+
+    #[test]
+    fn network_transaction() {
+        for v in [
+            NetworkTransaction::from(blob_transaction()),
+            NetworkTransaction {
+                transaction: blob_transaction(),
+                sidecar: Some(BlobTransactionSidecar {
+                    blobs: vec![vec![0; 131_072].into()],
+                    commitments: vec![vec![0xc0; 48].into()],
+                    proofs: vec![vec![0xc0; 48].into()],
+                }),
+            },
+        ] {
+            let mut encoded = BytesMut::new();
+            Encodable::encode(&v, &mut encoded);
+
+            let encoded_view = &mut &*encoded;
+            let decoded = <NetworkTransaction as Decodable>::decode(encoded_view).unwrap();
+            assert!(encoded_view.is_empty());
+            assert_eq!(decoded, v);
+        }
+
+        // Transactions without sidecar are encoded as is.
+        let mut plain = BytesMut::new();
+        Encodable::encode(&blob_transaction(), &mut plain);
+        let mut network = BytesMut::new();
+        Encodable::encode(&NetworkTransaction::from(blob_transaction()), &mut network);
+        assert_eq!(plain, network);
+    }
+
     #[test]
     fn y_parity_and_chain_id() {
         for range in [0..27, 29..35] {
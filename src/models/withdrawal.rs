@@ -0,0 +1,41 @@
+use super::*;
+use crate::trie::*;
+use bytes::BufMut;
+use fastrlp::*;
+use parity_scale_codec::*;
+use serde::*;
+
+/// Validator withdrawal pushed from the beacon chain, see [EIP-4895](https://eips.ethereum.org/EIPS/eip-4895).
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    Encode,
+    Decode,
+    RlpEncodable,
+    RlpDecodable,
+)]
+pub struct Withdrawal {
+    pub index: u64,
+    pub validator_index: u64,
+    pub address: Address,
+    /// Amount in Gwei.
+    pub amount: u64,
+}
+
+impl Withdrawal {
+    /// Amount in wei.
+    pub fn amount_wei(&self) -> U256 {
+        U256::from(self.amount) * U256::from(GIGA)
+    }
+}
+
+impl TrieEncode for Withdrawal {
+    fn trie_encode(&self, buf: &mut dyn BufMut) {
+        Encodable::encode(self, buf)
+    }
+}
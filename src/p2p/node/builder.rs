@@ -36,13 +36,14 @@ impl NodeBuilder {
         self
     }
 
-    pub fn set_chain_head(mut self, height: BlockNumber, hash: H256, td: U256) -> Self {
-        let status = Status {
-            height,
-            hash,
-            total_difficulty: H256::from(td.to_be_bytes()),
-        };
-        self.status = Some(status);
+    pub fn set_chain_head(
+        mut self,
+        height: BlockNumber,
+        hash: H256,
+        td: U256,
+        timestamp: u64,
+    ) -> Self {
+        self.status = Some(Status::new(height, hash, td, timestamp));
         self
     }
 
@@ -60,7 +61,8 @@ impl NodeBuilder {
 
         let config = self.config;
         let status = RwLock::new(self.status.unwrap_or_else(|| Status::from(&config)));
-        let forks = config.forks().into_iter().map(|f| *f).collect::<Vec<_>>();
+        let block_forks = config.block_forks();
+        let time_forks = config.time_forks();
 
         let (chain_tip_sender, chain_tip) = watch::channel(Default::default());
 
@@ -74,7 +76,8 @@ impl NodeBuilder {
             bad_blocks: Default::default(),
             block_cache: Mutex::new(LruCache::new(64)),
             block_cache_notify: Notify::new(),
-            forks,
+            block_forks,
+            time_forks,
         })
     }
 }
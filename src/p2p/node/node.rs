@@ -4,6 +4,7 @@ use super::{stash::Stash, stream::*};
 use crate::{
     models::{BlockNumber, ChainConfig, MessageWithSignature, H256},
     p2p::types::*,
+    sentry::eth::fork_head,
 };
 use bytes::{BufMut, BytesMut};
 use dashmap::DashSet;
@@ -53,8 +54,10 @@ pub struct Node {
     pub block_cache_notify: Notify,
     /// Table of block hashes of the blocks known to not belong to the canonical chain.
     pub bad_blocks: DashSet<H256>,
-    /// Chain forks.
-    pub forks: Vec<u64>,
+    /// Fork block numbers.
+    pub block_forks: Vec<u64>,
+    /// Fork timestamps.
+    pub time_forks: Vec<u64>,
 }
 
 impl Node {
@@ -224,6 +227,7 @@ impl Node {
                 .stream_by_predicate([
                     ethereum_interfaces::sentry::MessageId::GetBlockBodies66 as i32,
                     ethereum_interfaces::sentry::MessageId::GetBlockHeaders66 as i32,
+                    ethereum_interfaces::sentry::MessageId::GetReceipts66 as i32,
                 ])
                 .await;
 
@@ -256,6 +260,21 @@ impl Node {
                                 .send_message(msg, PeerFilter::Peer(peer_id, sentry_id))
                                 .await;
                         }
+                        Message::GetReceipts(inner) => {
+                            let msg = Message::Receipts(Receipts {
+                                request_id: inner.request_id,
+                                receipts: handler.stash.get_receipts(inner.hashes).unwrap_or_else(
+                                    |e| {
+                                        warn!("Failed to read receipts for peer: {e}");
+                                        vec![]
+                                    },
+                                ),
+                            });
+
+                            handler
+                                .send_message(msg, PeerFilter::Peer(peer_id, sentry_id))
+                                .await;
+                        }
                         _ => unreachable!(),
                     }
                 }
@@ -284,6 +303,7 @@ impl Node {
             height,
             hash,
             total_difficulty,
+            timestamp,
         } = *self.status.read();
         let config = &self.config;
         let status_data = grpc_sentry::StatusData {
@@ -292,9 +312,14 @@ impl Node {
             best_hash: Some(hash.into()),
             fork_data: Some(grpc_sentry::Forks {
                 genesis: Some(config.genesis_hash.into()),
-                forks: self.forks.clone(),
+                forks: self
+                    .block_forks
+                    .iter()
+                    .chain(&self.time_forks)
+                    .copied()
+                    .collect(),
             }),
-            max_block: *height,
+            max_block: fork_head(&self.block_forks, &self.time_forks, height, timestamp),
         };
         self.set_status(status_data).await
     }
@@ -404,7 +429,7 @@ impl Node {
                 let mut buf = BytesMut::new();
                 PooledTransactions {
                     request_id,
-                    transactions,
+                    transactions: transactions.into_iter().map(From::from).collect(),
                 }
                 .encode(&mut buf);
                 buf.freeze()
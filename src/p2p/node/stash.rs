@@ -1,15 +1,22 @@
 use crate::{
     accessors::chain,
     kv::{tables, MdbxWithDirHandle},
-    models::{BlockBody, BlockHeader, BlockNumber, H256},
+    models::{BlockBody, BlockHeader, BlockNumber, Receipt, H256},
     p2p::types::{BlockId, GetBlockHeadersParams},
 };
+use fastrlp::Encodable;
 use mdbx::EnvironmentKind;
 use std::fmt::Debug;
 
+/// Most blocks receipts are served for in a single response.
+const MAX_RECEIPTS_SERVE: usize = 1024;
+/// Receipts stop being added to a response once it grows past this size.
+const SOFT_RESPONSE_LIMIT: usize = 2 * 1024 * 1024;
+
 pub trait Stash: Send + Sync + Debug {
     fn get_headers(&self, _: GetBlockHeadersParams) -> anyhow::Result<Vec<BlockHeader>>;
     fn get_bodies(&self, _: Vec<H256>) -> anyhow::Result<Vec<BlockBody>>;
+    fn get_receipts(&self, _: Vec<H256>) -> anyhow::Result<Vec<Vec<Receipt>>>;
 }
 
 impl Stash for () {
@@ -19,6 +26,9 @@ impl Stash for () {
     fn get_bodies(&self, _: Vec<H256>) -> anyhow::Result<Vec<BlockBody>> {
         Ok(vec![])
     }
+    fn get_receipts(&self, _: Vec<H256>) -> anyhow::Result<Vec<Vec<Receipt>>> {
+        Ok(vec![])
+    }
 }
 
 impl<E> Stash for MdbxWithDirHandle<E>
@@ -90,4 +100,36 @@ where
             })
             .collect::<Vec<_>>())
     }
+
+    fn get_receipts(&self, hashes: Vec<H256>) -> anyhow::Result<Vec<Vec<Receipt>>> {
+        let txn = self.begin()?;
+
+        // Receipts are only available when persisted by the execution stage.
+        // Peers match responses by position, so stop at the first missing block.
+        let mut receipts = Vec::new();
+        let mut response_size = 0;
+        for hash in hashes.into_iter().take(MAX_RECEIPTS_SERVE) {
+            if response_size >= SOFT_RESPONSE_LIMIT {
+                break;
+            }
+
+            let number = match txn.get(tables::HeaderNumber, hash)? {
+                Some(number) => number,
+                None => break,
+            };
+            // Receipts are stored for canonical blocks only.
+            if chain::canonical_hash::read(&txn, number)? != Some(hash) {
+                break;
+            }
+            let block_receipts = match txn.get(tables::Receipt, number)? {
+                Some(block_receipts) => block_receipts,
+                None => break,
+            };
+
+            response_size += block_receipts.iter().map(Encodable::length).sum::<usize>();
+            receipts.push(block_receipts);
+        }
+
+        Ok(receipts)
+    }
 }
@@ -1,5 +1,5 @@
 use crate::{
-    models::{BlockBody, MessageWithSignature, H256},
+    models::{BlockBody, MessageWithSignature, NetworkTransaction, Receipt, H256},
     p2p::types::*,
     sentry::devp2p::PeerId,
 };
@@ -108,7 +108,7 @@ pub struct GetPooledTransactions {
 #[derive(Debug, Clone, PartialEq, Eq, RlpEncodable, RlpDecodable)]
 pub struct PooledTransactions {
     pub request_id: u64,
-    pub transactions: Vec<MessageWithSignature>,
+    pub transactions: Vec<NetworkTransaction>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, RlpEncodable, RlpDecodable)]
@@ -117,6 +117,18 @@ pub struct BlockBodies {
     pub bodies: Vec<BlockBody>,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, RlpEncodable, RlpDecodable)]
+pub struct GetReceipts {
+    pub request_id: u64,
+    pub hashes: Vec<H256>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, RlpEncodable, RlpDecodable)]
+pub struct Receipts {
+    pub request_id: u64,
+    pub receipts: Vec<Vec<Receipt>>,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Message {
     NewBlockHashes(NewBlockHashes),
@@ -129,6 +141,8 @@ pub enum Message {
     Transactions(Transactions),
     GetPooledTransactions(GetPooledTransactions),
     PooledTransactions(PooledTransactions),
+    GetReceipts(GetReceipts),
+    Receipts(Receipts),
 }
 
 impl Message {
@@ -145,6 +159,8 @@ impl Message {
             Self::Transactions(_) => MessageId::Transactions,
             Self::GetPooledTransactions(_) => MessageId::GetPooledTransactions,
             Self::PooledTransactions(_) => MessageId::PooledTransactions,
+            Self::GetReceipts(_) => MessageId::GetReceipts,
+            Self::Receipts(_) => MessageId::Receipts,
         }
     }
 }
@@ -204,14 +220,14 @@ impl InboundMessage {
                 Message::GetBlockBodies(Decodable::decode(msg_data_slice)?)
             }
             MessageId::GetNodeData => todo!(),
-            MessageId::GetReceipts => todo!(),
+            MessageId::GetReceipts => Message::GetReceipts(Decodable::decode(msg_data_slice)?),
             MessageId::GetPooledTransactions => {
                 Message::GetPooledTransactions(Decodable::decode(msg_data_slice)?)
             }
             MessageId::BlockHeaders => Message::BlockHeaders(Decodable::decode(msg_data_slice)?),
             MessageId::BlockBodies => Message::BlockBodies(Decodable::decode(msg_data_slice)?),
             MessageId::NodeData => todo!(),
-            MessageId::Receipts => todo!(),
+            MessageId::Receipts => Message::Receipts(Decodable::decode(msg_data_slice)?),
             MessageId::PooledTransactions => {
                 Message::PooledTransactions(Decodable::decode(msg_data_slice)?)
             }
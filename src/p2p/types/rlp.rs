@@ -36,6 +36,8 @@ impl Encodable for Message {
             Message::Transactions(ref value) => value.encode(out),
             Message::GetPooledTransactions(ref value) => value.encode(out),
             Message::PooledTransactions(ref value) => value.encode(out),
+            Message::GetReceipts(ref value) => value.encode(out),
+            Message::Receipts(ref value) => value.encode(out),
         }
     }
 }
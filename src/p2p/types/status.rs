@@ -5,14 +5,16 @@ pub struct Status {
     pub height: BlockNumber,
     pub hash: H256,
     pub total_difficulty: H256,
+    pub timestamp: u64,
 }
 
 impl Status {
-    pub fn new(height: BlockNumber, hash: H256, td: U256) -> Self {
+    pub fn new(height: BlockNumber, hash: H256, td: U256, timestamp: u64) -> Self {
         Self {
             height,
             hash,
             total_difficulty: H256::from(td.to_be_bytes()),
+            timestamp,
         }
     }
 }
@@ -27,6 +29,7 @@ impl<'a> From<&'a ChainConfig> for Status {
             height,
             hash,
             total_difficulty,
+            timestamp: config.chain_spec.genesis.timestamp,
         }
     }
 }
@@ -37,5 +40,6 @@ impl PartialEq for Status {
         self.height == other.height
             && self.hash == other.hash
             && self.total_difficulty == other.total_difficulty
+            && self.timestamp == other.timestamp
     }
 }
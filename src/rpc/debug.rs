@@ -0,0 +1,683 @@
+use super::helpers;
+use crate::{
+    accessors::chain,
+    consensus::engine_factory,
+    execution::{
+        analysis_cache::AnalysisCache,
+        processor::{execute_pre_block_system_calls, execute_transaction},
+        tracer::{
+            CallFrame, CallFrameTracer, NoopTracer, PrestateAccount, PrestateDiff, PrestateTracer,
+            StructLogResult, StructLogger, StructLoggerConfig, Tracer,
+        },
+    },
+    kv::{mdbx::*, tables, MdbxWithDirHandle},
+    models::*,
+    Buffer, IntraBlockState,
+};
+use anyhow::format_err;
+use async_trait::async_trait;
+use bytes::Bytes;
+use ethereum_jsonrpc::types;
+use jsonrpsee::{core::RpcResult, proc_macros::rpc};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, sync::Arc};
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TracerConfig {
+    #[serde(default)]
+    pub only_top_call: bool,
+    /// Makes `prestateTracer` return accounts before and after the transaction, where they differ.
+    #[serde(default)]
+    pub diff_mode: bool,
+}
+
+/// Options of `debug_trace*` methods, as accepted by Geth.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceOptions {
+    #[serde(default)]
+    pub disable_stack: bool,
+    #[serde(default)]
+    pub disable_memory: bool,
+    #[serde(default)]
+    pub disable_storage: bool,
+    /// Name of a built-in tracer, struct logger is used if not set.
+    pub tracer: Option<String>,
+    pub tracer_config: Option<TracerConfig>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(untagged)]
+pub enum GethTrace {
+    StructLogs(StructLogResult),
+    Call(CallFrame),
+    Prestate(BTreeMap<Address, PrestateAccount>),
+    PrestateDiff(PrestateDiff),
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockTrace {
+    pub tx_hash: H256,
+    pub result: GethTrace,
+}
+
+#[rpc(server, namespace = "debug")]
+pub trait DebugApi {
+    #[method(name = "traceTransaction")]
+    async fn trace_transaction(
+        &self,
+        hash: H256,
+        options: Option<TraceOptions>,
+    ) -> RpcResult<GethTrace>;
+    #[method(name = "traceCall")]
+    async fn trace_call(
+        &self,
+        call: types::MessageCall,
+        block_id: Option<types::BlockId>,
+        options: Option<TraceOptions>,
+    ) -> RpcResult<GethTrace>;
+    #[method(name = "traceBlockByNumber")]
+    async fn trace_block_by_number(
+        &self,
+        block_number: types::BlockNumber,
+        options: Option<TraceOptions>,
+    ) -> RpcResult<Vec<BlockTrace>>;
+    #[method(name = "traceBlockByHash")]
+    async fn trace_block_by_hash(
+        &self,
+        block_hash: H256,
+        options: Option<TraceOptions>,
+    ) -> RpcResult<Vec<BlockTrace>>;
+}
+
+#[derive(Clone, Copy, Debug)]
+enum TracerKind {
+    StructLogs(StructLoggerConfig),
+    Call { only_top_call: bool },
+    Prestate { diff_mode: bool },
+}
+
+impl TryFrom<Option<TraceOptions>> for TracerKind {
+    type Error = anyhow::Error;
+
+    fn try_from(options: Option<TraceOptions>) -> Result<Self, Self::Error> {
+        let options = options.unwrap_or_default();
+        let config = options.tracer_config.unwrap_or_default();
+        Ok(match options.tracer.as_deref() {
+            None => Self::StructLogs(StructLoggerConfig {
+                disable_stack: options.disable_stack,
+                disable_memory: options.disable_memory,
+                disable_storage: options.disable_storage,
+            }),
+            Some("callTracer") => Self::Call {
+                only_top_call: config.only_top_call,
+            },
+            Some("prestateTracer") => Self::Prestate {
+                diff_mode: config.diff_mode,
+            },
+            Some(other) => return Err(format_err!("unsupported tracer: {other}")),
+        })
+    }
+}
+
+/// Executes transactions of a block one after another on top of the same state.
+struct Replayer<'db, 'tx, K, E>
+where
+    'db: 'tx,
+    K: TransactionKind,
+    E: EnvironmentKind,
+{
+    buffer: Buffer<'db, 'tx, K, E>,
+    block_spec: BlockExecutionSpec,
+    header: BlockHeader,
+    beneficiary: Address,
+    analysis_cache: AnalysisCache,
+    cumulative_gas_used: u64,
+}
+
+impl<'db, 'tx, K, E> Replayer<'db, 'tx, K, E>
+where
+    'db: 'tx,
+    K: TransactionKind,
+    E: EnvironmentKind,
+{
+    fn new(
+        txn: &'tx MdbxTransaction<'db, K, E>,
+        header: BlockHeader,
+        historical_block: Option<BlockNumber>,
+    ) -> anyhow::Result<Self> {
+        let chain_spec = txn
+            .get(tables::Config, ())?
+            .ok_or_else(|| format_err!("chain spec not found"))?;
+
+        let mut this = Self {
+            buffer: Buffer::new(txn, historical_block),
+            block_spec: chain_spec.collect_block_spec(header.number, header.timestamp),
+            beneficiary: engine_factory(None, chain_spec)?.get_beneficiary(&header),
+            header,
+            analysis_cache: AnalysisCache::global(),
+            cumulative_gas_used: 0,
+        };
+
+        {
+            let mut state = IntraBlockState::new(&mut this.buffer);
+            execute_pre_block_system_calls(
+                &mut state,
+                &this.block_spec,
+                &this.header,
+                &mut this.analysis_cache,
+            )?;
+            state.write_to_state_same_block()?;
+        }
+
+        Ok(this)
+    }
+
+    /// Returns output and gas used by the transaction. State changes are discarded unless `commit` is set.
+    fn execute(
+        &mut self,
+        sender: Address,
+        message: &Message,
+        tracer: &mut dyn Tracer,
+        commit: bool,
+    ) -> anyhow::Result<(Bytes, u64)> {
+        let cumulative_gas_used = self.cumulative_gas_used;
+
+        let mut state = IntraBlockState::new(&mut self.buffer);
+        let (output, _) = execute_transaction(
+            &mut state,
+            &self.block_spec,
+            &self.header,
+            tracer,
+            &mut self.analysis_cache,
+            &mut self.cumulative_gas_used,
+            message,
+            sender,
+            self.beneficiary,
+        )?;
+        let gas_used = self.cumulative_gas_used - cumulative_gas_used;
+
+        if commit {
+            state.write_to_state_same_block()?;
+        } else {
+            self.cumulative_gas_used = cumulative_gas_used;
+        }
+
+        Ok((output, gas_used))
+    }
+
+    fn trace(
+        &mut self,
+        sender: Address,
+        message: &Message,
+        kind: TracerKind,
+    ) -> anyhow::Result<GethTrace> {
+        Ok(match kind {
+            TracerKind::StructLogs(config) => {
+                let mut tracer = StructLogger::new(config);
+                let (_, gas_used) = self.execute(sender, message, &mut tracer, true)?;
+                GethTrace::StructLogs(tracer.into_result(gas_used))
+            }
+            TracerKind::Call { only_top_call } => {
+                let mut tracer = CallFrameTracer::new(only_top_call);
+                let (_, gas_used) = self.execute(sender, message, &mut tracer, true)?;
+                let mut frame = tracer
+                    .into_frame()
+                    .ok_or_else(|| format_err!("no call frame captured"))?;
+                // Top level frame accounts for intrinsic gas and refunds, as in receipt.
+                frame.gas = message.gas_limit().into();
+                frame.gas_used = gas_used.into();
+                GethTrace::Call(frame)
+            }
+            TracerKind::Prestate { diff_mode } => {
+                let mut tracer = PrestateTracer::default();
+                tracer.touch(sender);
+                tracer.touch(self.beneficiary);
+                if let TransactionAction::Call(to) = message.action() {
+                    tracer.touch(to);
+                }
+                self.execute(sender, message, &mut tracer, false)?;
+                let prestate = tracer.read_prestate(&self.buffer)?;
+                self.execute(sender, message, &mut NoopTracer, true)?;
+                if diff_mode {
+                    GethTrace::PrestateDiff(tracer.read_diff(prestate, &self.buffer)?)
+                } else {
+                    GethTrace::Prestate(prestate)
+                }
+            }
+        })
+    }
+}
+
+struct BlockTransactions {
+    header: BlockHeader,
+    transactions: Vec<MessageWithSignature>,
+    senders: Vec<Address>,
+}
+
+fn read_block<K: TransactionKind, E: EnvironmentKind>(
+    txn: &MdbxTransaction<'_, K, E>,
+    block_id: types::BlockId,
+) -> anyhow::Result<BlockTransactions> {
+    let (block_number, block_hash) = helpers::resolve_block_id(txn, block_id)?
+        .ok_or_else(|| format_err!("block {block_id:?} not found"))?;
+    let header = chain::header::read(txn, block_hash, block_number)?
+        .ok_or_else(|| format_err!("header not found for block #{block_number}/{block_hash}"))?;
+    let transactions = chain::block_body::read_without_senders(txn, block_hash, block_number)?
+        .ok_or_else(|| format_err!("body not found for block #{block_number}/{block_hash}"))?
+        .transactions;
+    let senders = chain::tx_sender::read(txn, block_hash, block_number)?;
+    if senders.len() != transactions.len() {
+        return Err(format_err!(
+            "senders count mismatch for block #{block_number}/{block_hash}: {} vs {}",
+            senders.len(),
+            transactions.len()
+        ));
+    }
+
+    Ok(BlockTransactions {
+        header,
+        transactions,
+        senders,
+    })
+}
+
+/// Parent of the block, on top of whose state the block is replayed.
+fn parent_block(header: &BlockHeader) -> anyhow::Result<BlockNumber> {
+    header
+        .number
+        .0
+        .checked_sub(1)
+        .map(BlockNumber)
+        .ok_or_else(|| format_err!("cannot replay genesis block"))
+}
+
+fn trace_block<K: TransactionKind, E: EnvironmentKind>(
+    txn: &MdbxTransaction<'_, K, E>,
+    block_id: types::BlockId,
+    kind: TracerKind,
+) -> anyhow::Result<Vec<BlockTrace>> {
+    let BlockTransactions {
+        header,
+        transactions,
+        senders,
+    } = read_block(txn, block_id)?;
+    let historical_block = parent_block(&header)?;
+
+    let mut replayer = Replayer::new(txn, header, Some(historical_block))?;
+    transactions
+        .into_iter()
+        .zip(senders)
+        .map(|(transaction, sender)| {
+            Ok(BlockTrace {
+                tx_hash: transaction.hash(),
+                result: replayer.trace(sender, &transaction.message, kind)?,
+            })
+        })
+        .collect()
+}
+
+pub struct DebugApiServerImpl<SE>
+where
+    SE: EnvironmentKind,
+{
+    pub db: Arc<MdbxWithDirHandle<SE>>,
+    pub call_gas_limit: u64,
+}
+
+#[async_trait]
+impl<DB> DebugApiServer for DebugApiServerImpl<DB>
+where
+    DB: EnvironmentKind,
+{
+    async fn trace_transaction(
+        &self,
+        hash: H256,
+        options: Option<TraceOptions>,
+    ) -> RpcResult<GethTrace> {
+        let db = self.db.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let kind = TracerKind::try_from(options)?;
+
+            let txn = db.begin()?;
+            let block_number = chain::tl::read(&txn, hash)?
+                .ok_or_else(|| format_err!("transaction {hash} not found"))?;
+            let BlockTransactions {
+                header,
+                transactions,
+                senders,
+            } = read_block(
+                &txn,
+                types::BlockId::Number(types::BlockNumber::Number(block_number.0.into())),
+            )?;
+            let index = transactions
+                .iter()
+                .position(|transaction| transaction.hash() == hash)
+                .ok_or_else(|| {
+                    format_err!(
+                        "transaction {hash} is not found in block #{block_number} - tx lookup index invalid?"
+                    )
+                })?;
+            let historical_block = parent_block(&header)?;
+
+            let mut replayer = Replayer::new(&txn, header, Some(historical_block))?;
+            for (transaction, &sender) in transactions.iter().zip(&senders).take(index) {
+                replayer.execute(sender, &transaction.message, &mut NoopTracer, true)?;
+            }
+
+            Ok(replayer.trace(senders[index], &transactions[index].message, kind)?)
+        })
+        .await
+        .unwrap_or_else(helpers::joinerror_to_result)
+    }
+
+    async fn trace_call(
+        &self,
+        call: types::MessageCall,
+        block_id: Option<types::BlockId>,
+        options: Option<TraceOptions>,
+    ) -> RpcResult<GethTrace> {
+        let db = self.db.clone();
+        let call_gas_limit = self.call_gas_limit;
+
+        tokio::task::spawn_blocking(move || {
+            let kind = TracerKind::try_from(options)?;
+
+            let txn = db.begin()?;
+
+            let block_id = block_id.unwrap_or(types::BlockId::Number(types::BlockNumber::Latest));
+            let (block_number, block_hash) = helpers::resolve_block_id(&txn, block_id)?
+                .ok_or_else(|| format_err!("failed to resolve block {block_id:?}"))?;
+            let historical_block = match block_id {
+                types::BlockId::Number(types::BlockNumber::Latest)
+                | types::BlockId::Number(types::BlockNumber::Pending) => None,
+                _ => Some(block_number),
+            };
+
+            let chain_id = txn
+                .get(tables::Config, ())?
+                .ok_or_else(|| format_err!("chain spec not found"))?
+                .params
+                .chain_id;
+
+            let header = chain::header::read(&txn, block_hash, block_number)?
+                .ok_or_else(|| format_err!("header not found"))?;
+
+            let (sender, message) = helpers::convert_message_call(
+                &Buffer::new(&txn, historical_block),
+                chain_id,
+                call,
+                &header,
+                U256::ZERO,
+                Some(call_gas_limit),
+            )?;
+
+            Ok(Replayer::new(&txn, header, historical_block)?.trace(sender, &message, kind)?)
+        })
+        .await
+        .unwrap_or_else(helpers::joinerror_to_result)
+    }
+
+    async fn trace_block_by_number(
+        &self,
+        block_number: types::BlockNumber,
+        options: Option<TraceOptions>,
+    ) -> RpcResult<Vec<BlockTrace>> {
+        let db = self.db.clone();
+
+        tokio::task::spawn_blocking(move || {
+            Ok(trace_block(
+                &db.begin()?,
+                types::BlockId::Number(block_number),
+                TracerKind::try_from(options)?,
+            )?)
+        })
+        .await
+        .unwrap_or_else(helpers::joinerror_to_result)
+    }
+
+    async fn trace_block_by_hash(
+        &self,
+        block_hash: H256,
+        options: Option<TraceOptions>,
+    ) -> RpcResult<Vec<BlockTrace>> {
+        let db = self.db.clone();
+
+        tokio::task::spawn_blocking(move || {
+            Ok(trace_block(
+                &db.begin()?,
+                types::BlockId::Hash(block_hash),
+                TracerKind::try_from(options)?,
+            )?)
+        })
+        .await
+        .unwrap_or_else(helpers::joinerror_to_result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpc::test_chain::*;
+    use hex_literal::hex;
+    use serde_json::{json, Value};
+
+    const CALLER: Address = H160(hex!("000000000000000000000000000000000000000a"));
+    const REVERTER: Address = H160(hex!("000000000000000000000000000000000000000b"));
+
+    /// Block with a transfer to the sender of the traced transaction, which calls `CALLER`.
+    ///
+    /// `CALLER` stores 0x2a into slot 1, which holds 1 before, and then calls `REVERTER`, which reverts.
+    fn traced_transaction() -> (TestChain, MessageWithSignature) {
+        let (key, other_key) = (secret_key(1), secret_key(2));
+        let chain = TestChain::new([
+            (address_of(&key), ETHER.as_u256()),
+            (address_of(&other_key), ETHER.as_u256()),
+        ]);
+        chain.set_account(
+            CALLER,
+            Account::default(),
+            Bytes::from_static(&[
+                0x60, 0x2a, 0x60, 0x01, 0x55, // SSTORE(1, 0x2a)
+                0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x0b, 0x5a,
+                0xf1, // CALL(GAS, REVERTER, 0, 0, 0, 0, 0)
+                0x50, 0x00, // POP STOP
+            ]),
+            &[(U256::ONE, U256::ONE)],
+        );
+        chain.set_account(
+            REVERTER,
+            Account::default(),
+            // REVERT(0, 0)
+            Bytes::from_static(&[0x60, 0x00, 0x60, 0x00, 0xfd]),
+            &[],
+        );
+
+        let message = |nonce, to, value| Message::EIP1559 {
+            chain_id: ChainId(1),
+            nonce,
+            max_priority_fee_per_gas: U256::ONE,
+            max_fee_per_gas: (2 * BASE_FEE_PER_GAS).as_u256(),
+            gas_limit: 100_000,
+            action: TransactionAction::Call(to),
+            value,
+            input: Bytes::new(),
+            access_list: vec![],
+        };
+        let transfer = sign(message(0, address_of(&key), U256::ONE), &other_key);
+        let transaction = sign(message(0, CALLER, U256::ZERO), &key);
+        chain.push_block(vec![transfer, transaction.clone()]);
+
+        (chain, transaction)
+    }
+
+    async fn trace_transaction(chain: &TestChain, hash: H256, options: Value) -> Value {
+        let api = DebugApiServerImpl {
+            db: chain.db.clone(),
+            call_gas_limit: 100_000_000,
+        };
+
+        json!(api
+            .trace_transaction(hash, Some(serde_json::from_value(options).unwrap()))
+            .await
+            .unwrap())
+    }
+
+    #[tokio::test]
+    async fn struct_logs() {
+        let (chain, transaction) = traced_transaction();
+        let trace = trace_transaction(&chain, transaction.hash(), json!({})).await;
+
+        assert_eq!(trace["failed"], json!(false));
+        assert_eq!(trace["returnValue"], json!(""));
+
+        let logs = trace["structLogs"].as_array().unwrap();
+        let steps = logs
+            .iter()
+            .map(|log| {
+                (
+                    log["pc"].as_u64().unwrap(),
+                    log["op"].as_str().unwrap(),
+                    log["depth"].as_u64().unwrap(),
+                )
+            })
+            .collect::<Vec<_>>();
+        let mut expected = vec![(0, "PUSH1", 1), (2, "PUSH1", 1), (4, "SSTORE", 1)];
+        expected.extend((5..=15).step_by(2).map(|pc| (pc, "PUSH1", 1)));
+        expected.extend([
+            (17, "GAS", 1),
+            (18, "CALL", 1),
+            (0, "PUSH1", 2),
+            (2, "PUSH1", 2),
+            (4, "REVERT", 2),
+            (19, "POP", 1),
+            (20, "STOP", 1),
+        ]);
+        assert_eq!(steps, expected);
+
+        // Gas left after intrinsic gas, decreasing by cost of every step.
+        assert_eq!(logs[0]["gas"], json!(100_000 - 21_000));
+        assert_eq!(logs[0]["gasCost"], json!(3));
+        assert_eq!(logs[1]["gas"], json!(100_000 - 21_000 - 3));
+
+        assert_eq!(logs[0]["stack"], json!([]));
+        assert_eq!(logs[2]["stack"], json!(["0x2a", "0x1"]));
+        assert_eq!(
+            logs[2]["storage"],
+            json!({ format!("{:064x}", 1): format!("{:064x}", 0x2a) })
+        );
+        assert_eq!(logs[10]["stack"].as_array().unwrap().len(), 7);
+
+        let trace = trace_transaction(
+            &chain,
+            transaction.hash(),
+            json!({ "disableStack": true, "disableStorage": true }),
+        )
+        .await;
+        assert!(trace["structLogs"][2].get("stack").is_none());
+        assert!(trace["structLogs"][2].get("storage").is_none());
+    }
+
+    #[tokio::test]
+    async fn call_frames() {
+        let (chain, transaction) = traced_transaction();
+        let sender = transaction.recover_sender().unwrap();
+        let trace = trace_transaction(
+            &chain,
+            transaction.hash(),
+            json!({ "tracer": "callTracer" }),
+        )
+        .await;
+
+        assert_eq!(trace["type"], json!("CALL"));
+        assert_eq!(trace["from"], json!(sender));
+        assert_eq!(trace["to"], json!(CALLER));
+        assert_eq!(trace["gas"], json!(U64::from(100_000_u64)));
+        assert!(trace.get("error").is_none());
+
+        let calls = trace["calls"].as_array().unwrap();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0]["type"], json!("CALL"));
+        assert_eq!(calls[0]["from"], json!(CALLER));
+        assert_eq!(calls[0]["to"], json!(REVERTER));
+        assert_eq!(calls[0]["value"], json!("0x0"));
+        assert_eq!(calls[0]["error"], json!("execution reverted"));
+
+        let trace = trace_transaction(
+            &chain,
+            transaction.hash(),
+            json!({ "tracer": "callTracer", "tracerConfig": { "onlyTopCall": true } }),
+        )
+        .await;
+        assert!(trace.get("calls").is_none());
+    }
+
+    #[tokio::test]
+    async fn prestate() {
+        let (chain, transaction) = traced_transaction();
+        let sender = transaction.recover_sender().unwrap();
+        let slot = |n: u64| format!("{:?}", H256::from_low_u64_be(n));
+
+        let trace = trace_transaction(
+            &chain,
+            transaction.hash(),
+            json!({ "tracer": "prestateTracer" }),
+        )
+        .await;
+        // Sender got 1 wei from the transaction before.
+        assert_eq!(
+            trace[format!("{sender:?}")],
+            json!({ "balance": format!("0x{:x}", ETHER.as_u256() + 1) })
+        );
+        assert_eq!(trace[format!("{CALLER:?}")]["balance"], json!("0x0"));
+        assert_eq!(
+            trace[format!("{CALLER:?}")]["storage"],
+            json!({ slot(1): slot(1) })
+        );
+        assert_eq!(
+            trace[format!("{REVERTER:?}")]["code"],
+            json!("0x60006000fd")
+        );
+        // Coinbase got the tip of the transaction before.
+        assert_eq!(
+            trace[format!("{COINBASE:?}")],
+            json!({ "balance": format!("0x{:x}", 21_000) })
+        );
+
+        let trace = trace_transaction(
+            &chain,
+            transaction.hash(),
+            json!({ "tracer": "prestateTracer", "tracerConfig": { "diffMode": true } }),
+        )
+        .await;
+        let (pre, post) = (&trace["pre"], &trace["post"]);
+
+        // Untouched by the transaction.
+        assert!(pre.get(format!("{REVERTER:?}")).is_none());
+        assert!(post.get(format!("{REVERTER:?}")).is_none());
+
+        assert_eq!(
+            pre[format!("{CALLER:?}")]["storage"],
+            json!({ slot(1): slot(1) })
+        );
+        assert_eq!(
+            post[format!("{CALLER:?}")],
+            json!({ "storage": { slot(1): slot(0x2a) } })
+        );
+
+        assert!(pre[format!("{sender:?}")].get("nonce").is_none());
+        assert_eq!(post[format!("{sender:?}")]["nonce"], json!(1));
+        assert_ne!(
+            post[format!("{sender:?}")]["balance"],
+            pre[format!("{sender:?}")]["balance"]
+        );
+
+        assert_ne!(
+            post[format!("{COINBASE:?}")]["balance"],
+            pre[format!("{COINBASE:?}")]["balance"]
+        );
+    }
+}
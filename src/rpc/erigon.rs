@@ -46,6 +46,7 @@ where
                     mix_hash,
                     nonce,
                     base_fee_per_gas,
+                    ..
                 }) = crate::accessors::chain::header::read(&tx, hash, block_number)?
                 {
                     return Ok(Some(types::Header {
@@ -1,30 +1,1433 @@
 use super::helpers;
 use crate::{
     accessors::{chain, state},
-    consensus::engine_factory,
+    consensus::{calc_base_fee_per_gas, engine_factory},
+    crypto::keccak256,
     execution::{
-        analysis_cache::AnalysisCache, evmglue, processor::ExecutionProcessor, tracer::NoopTracer,
+        address::create_address,
+        analysis_cache::AnalysisCache,
+        evm::StatusCode,
+        evmglue::{self, CallResult},
+        processor::{execute_transaction, ExecutionProcessor},
+        tracer::{AccessListTracer, NoopTracer},
     },
+    h256_to_u256,
     kv::{mdbx::*, tables, MdbxWithDirHandle},
     models::*,
     stagedsync::stages::{self, FINISH},
-    Buffer, IntraBlockState,
+    trie,
+    txpool::TransactionPool,
+    Buffer, IntraBlockState, StateReader, StateWriter,
 };
 use anyhow::format_err;
 use async_trait::async_trait;
+use bytes::Bytes;
 use ethereum_jsonrpc::{
     types::{self, TransactionLog},
     EthApiServer, LogFilter, SyncStatus,
 };
-use jsonrpsee::core::RpcResult;
-use std::sync::Arc;
+use hashbrown::HashMap;
+use jsonrpsee::{
+    core::{server::rpc_module::Methods, RpcResult},
+    proc_macros::rpc,
+};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashSet,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+use tokio::sync::broadcast::{self, error::TryRecvError};
+
+/// Installed filters are dropped if not polled for this long.
+const FILTER_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// Default for the most blocks a single `eth_getLogs` request may scan.
+pub const DEFAULT_MAX_LOGS_BLOCK_RANGE: u64 = 10_000;
+
+/// Most blocks reported by a single `eth_feeHistory` request.
+const FEE_HISTORY_MAX_BLOCK_COUNT: u64 = 1024;
+
+/// Number of latest blocks sampled by the gas price oracle.
+const GAS_PRICE_ORACLE_BLOCKS: u64 = 20;
+/// Number of lowest tips sampled from each block.
+const GAS_PRICE_ORACLE_SAMPLES_PER_BLOCK: usize = 3;
+/// Percentile of sampled tips suggested by the oracle.
+const GAS_PRICE_ORACLE_PERCENTILE: usize = 60;
+/// Tips below this are not sampled.
+const GAS_PRICE_ORACLE_IGNORE_PRICE: U256 = U256::new(2);
+/// Suggested tip never exceeds 500 gwei.
+const GAS_PRICE_ORACLE_MAX_PRICE: U256 = U256::new(500_000_000_000);
+/// Tip suggested if there are no transactions to sample, 1 gwei.
+const GAS_PRICE_ORACLE_DEFAULT_PRICE: U256 = U256::new(1_000_000_000);
+
+/// Executions `eth_createAccessList` makes beyond one per distinct account accessed, before
+/// giving up on the access list settling.
+const CREATE_ACCESS_LIST_EXTRA_EXECUTIONS: usize = 3;
+
+/// How many blocks behind the latest one `eth_getProof` can revert the state to.
+const PROOF_HISTORY_WINDOW: u64 = 128;
+
+/// Block range of a log filter, with both bounds defaulting to the latest block.
+fn log_filter_range<K: TransactionKind, E: EnvironmentKind>(
+    txn: &MdbxTransaction<'_, K, E>,
+    filter: &LogFilter,
+) -> anyhow::Result<(BlockNumber, BlockNumber)> {
+    if let Some(block_hash) = filter.block_hash {
+        let block_number = chain::header_number::read(txn, block_hash)?
+            .ok_or_else(|| format_err!("block {block_hash} not found"))?;
+        if chain::canonical_hash::read(txn, block_number)? != Some(block_hash) {
+            return Err(format_err!("block {block_hash} is not canonical"));
+        }
+
+        return Ok((block_number, block_number));
+    }
+
+    Ok((
+        helpers::resolve_block_number(
+            txn,
+            filter.from_block.unwrap_or(types::BlockNumber::Latest),
+        )?,
+        helpers::resolve_block_number(txn, filter.to_block.unwrap_or(types::BlockNumber::Latest))?,
+    ))
+}
+
+enum FilterKind {
+    Logs(LogFilter),
+    Blocks,
+    PendingTransactions(broadcast::Receiver<H256>),
+}
+
+struct InstalledFilter {
+    kind: FilterKind,
+    /// Latest block already reported by `eth_getFilterChanges`.
+    last_block: BlockNumber,
+    last_poll: Instant,
+}
+
+enum FilterPoll {
+    Logs {
+        filter: LogFilter,
+        last_block: BlockNumber,
+    },
+    Blocks {
+        last_block: BlockNumber,
+    },
+    PendingTransactions(Vec<H256>),
+}
+
+/// Filters installed with `eth_newFilter`, `eth_newBlockFilter` and `eth_newPendingTransactionFilter`.
+///
+/// Progress of each filter is tracked against `Finish` stage progress, so that
+/// `eth_getFilterChanges` only returns what appeared since the previous poll.
+#[derive(Default)]
+pub struct FilterRegistry {
+    next_id: AtomicU64,
+    filters: Mutex<HashMap<u64, InstalledFilter>>,
+}
+
+impl FilterRegistry {
+    fn expire(filters: &mut HashMap<u64, InstalledFilter>) {
+        filters.retain(|_, filter| filter.last_poll.elapsed() < FILTER_TIMEOUT);
+    }
+
+    fn install(&self, kind: FilterKind, last_block: BlockNumber) -> U64 {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+
+        let mut filters = self.filters.lock();
+        Self::expire(&mut filters);
+        filters.insert(
+            id,
+            InstalledFilter {
+                kind,
+                last_block,
+                last_poll: Instant::now(),
+            },
+        );
+
+        id.into()
+    }
+
+    fn uninstall(&self, id: U64) -> bool {
+        self.filters.lock().remove(&id.as_u64()).is_some()
+    }
+
+    fn poll(&self, id: U64) -> anyhow::Result<FilterPoll> {
+        let mut filters = self.filters.lock();
+        Self::expire(&mut filters);
+
+        let filter = filters
+            .get_mut(&id.as_u64())
+            .ok_or_else(|| format_err!("filter not found"))?;
+        filter.last_poll = Instant::now();
+
+        Ok(match &mut filter.kind {
+            FilterKind::Logs(log_filter) => FilterPoll::Logs {
+                filter: log_filter.clone(),
+                last_block: filter.last_block,
+            },
+            FilterKind::Blocks => FilterPoll::Blocks {
+                last_block: filter.last_block,
+            },
+            FilterKind::PendingTransactions(receiver) => {
+                let mut hashes = vec![];
+                loop {
+                    match receiver.try_recv() {
+                        Ok(hash) => hashes.push(hash),
+                        Err(TryRecvError::Lagged(_)) => continue,
+                        Err(_) => break,
+                    }
+                }
+                FilterPoll::PendingTransactions(hashes)
+            }
+        })
+    }
+
+    fn advance(&self, id: U64, last_block: BlockNumber) {
+        if let Some(filter) = self.filters.lock().get_mut(&id.as_u64()) {
+            filter.last_block = last_block;
+        }
+    }
+}
+
+/// Account fields replaced for the duration of a call, as accepted by Geth's `eth_call`.
+///
+/// `state` replaces the whole storage of the account, `stateDiff` only the given slots.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct AccountOverride {
+    pub balance: Option<U256>,
+    pub nonce: Option<U64>,
+    pub code: Option<types::Bytes>,
+    pub state: Option<HashMap<H256, H256>>,
+    pub state_diff: Option<HashMap<H256, H256>>,
+}
+
+pub type StateOverride = HashMap<Address, AccountOverride>;
+
+/// Block context fields replaced for the duration of a call.
+///
+/// Fields not supported here are ignored, as by Geth.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockOverrides {
+    pub number: Option<U64>,
+    pub difficulty: Option<U256>,
+    #[serde(alias = "timestamp")]
+    pub time: Option<U64>,
+    pub gas_limit: Option<U64>,
+    #[serde(alias = "baseFeePerGas")]
+    pub base_fee: Option<U256>,
+    #[serde(alias = "feeRecipient")]
+    pub coinbase: Option<Address>,
+    #[serde(alias = "random")]
+    pub prev_randao: Option<H256>,
+    /// Not a header field, so it is not applied by [`BlockOverrides::apply`].
+    pub blob_base_fee: Option<U256>,
+}
+
+/// Writes overridden accounts, code and storage into the state the call is executed on.
+pub fn apply_state_override<S>(state: &mut S, state_override: StateOverride) -> anyhow::Result<()>
+where
+    S: StateReader + StateWriter,
+{
+    for (address, account_override) in state_override {
+        if account_override.state.is_some() && account_override.state_diff.is_some() {
+            return Err(format_err!(
+                "account {address} has both state and stateDiff overrides"
+            ));
+        }
+
+        let initial = state.read_account(address)?;
+        let mut account = initial.unwrap_or_default();
+        if let Some(balance) = account_override.balance {
+            account.balance = balance;
+        }
+        if let Some(nonce) = account_override.nonce {
+            account.nonce = nonce.as_u64();
+        }
+        if let Some(code) = account_override.code {
+            let code = Bytes::from(code);
+            account.code_hash = keccak256(&code);
+            state.update_code(account.code_hash, code)?;
+        }
+        state.update_account(address, initial, Some(account));
+
+        if let Some(storage) = account_override.state {
+            state.erase_storage(address)?;
+            for (location, value) in storage {
+                state.update_storage(
+                    address,
+                    h256_to_u256(location),
+                    U256::ZERO,
+                    h256_to_u256(value),
+                )?;
+            }
+        }
+
+        if let Some(storage_diff) = account_override.state_diff {
+            for (location, value) in storage_diff {
+                let location = h256_to_u256(location);
+                let initial = state.read_storage(address, location)?;
+                state.update_storage(address, location, initial, h256_to_u256(value))?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+impl BlockOverrides {
+    /// Applies overrides to the header of the block the call is executed in.
+    pub fn apply(self, header: &mut BlockHeader) {
+        if let Some(number) = self.number {
+            header.number = BlockNumber(number.as_u64());
+        }
+        if let Some(difficulty) = self.difficulty {
+            header.difficulty = difficulty;
+        }
+        if let Some(time) = self.time {
+            header.timestamp = time.as_u64();
+        }
+        if let Some(gas_limit) = self.gas_limit {
+            header.gas_limit = gas_limit.as_u64();
+        }
+        if let Some(base_fee) = self.base_fee {
+            header.base_fee_per_gas = Some(base_fee);
+        }
+        if let Some(coinbase) = self.coinbase {
+            header.beneficiary = coinbase;
+        }
+        // PREVRANDAO reads mix hash since the Merge.
+        if let Some(prev_randao) = self.prev_randao {
+            header.mix_hash = prev_randao;
+        }
+    }
+}
+
+/// Account state accessors taking a block tag, number, hash or
+/// [EIP-1898](https://eips.ethereum.org/EIPS/eip-1898) object.
+///
+/// Replaces the plain methods of [`EthApiServer`], see [`EthApiServerImpl::into_methods`].
+#[rpc(server, namespace = "eth")]
+pub trait EthStateApi {
+    #[method(name = "getBalance")]
+    async fn get_balance(&self, address: Address, block: helpers::BlockParam) -> RpcResult<U256>;
+    #[method(name = "getCode")]
+    async fn get_code(
+        &self,
+        address: Address,
+        block: helpers::BlockParam,
+    ) -> RpcResult<types::Bytes>;
+    #[method(name = "getStorageAt")]
+    async fn get_storage_at(
+        &self,
+        address: Address,
+        key: U256,
+        block: helpers::BlockParam,
+    ) -> RpcResult<U256>;
+    #[method(name = "getTransactionCount")]
+    async fn get_transaction_count(
+        &self,
+        address: Address,
+        block: helpers::BlockParam,
+    ) -> RpcResult<U64>;
+}
+
+/// `eth_call` and `eth_estimateGas` with state and block overrides, and `eth_createAccessList`.
+///
+/// Replaces the plain methods of [`EthApiServer`], see [`EthApiServerImpl::into_methods`].
+#[rpc(server, namespace = "eth")]
+pub trait EthCallApi {
+    #[method(name = "call")]
+    async fn call(
+        &self,
+        call_data: types::MessageCall,
+        block: Option<helpers::BlockParam>,
+        state_override: Option<StateOverride>,
+        block_overrides: Option<BlockOverrides>,
+    ) -> RpcResult<types::Bytes>;
+    #[method(name = "estimateGas")]
+    async fn estimate_gas(
+        &self,
+        call_data: types::MessageCall,
+        block: Option<helpers::BlockParam>,
+        state_override: Option<StateOverride>,
+        block_overrides: Option<BlockOverrides>,
+    ) -> RpcResult<U64>;
+    #[method(name = "createAccessList")]
+    async fn create_access_list(
+        &self,
+        call_data: types::MessageCall,
+        block: Option<helpers::BlockParam>,
+    ) -> RpcResult<AccessListWithGasUsed>;
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccessListWithGasUsed {
+    pub access_list: Vec<types::AccessListEntry>,
+    /// Set if the transaction carrying the access list fails.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub gas_used: U64,
+}
+
+/// Same message, with access list replaced. Legacy messages are turned into EIP-2930 ones.
+fn with_access_list(message: Message, chain_id: ChainId, access_list: AccessList) -> Message {
+    match message {
+        Message::Legacy {
+            nonce,
+            gas_price,
+            gas_limit,
+            action,
+            value,
+            input,
+            ..
+        } => Message::EIP2930 {
+            chain_id,
+            nonce,
+            gas_price,
+            gas_limit,
+            action,
+            value,
+            input,
+            access_list,
+        },
+        Message::EIP2930 {
+            chain_id,
+            nonce,
+            gas_price,
+            gas_limit,
+            action,
+            value,
+            input,
+            ..
+        } => Message::EIP2930 {
+            chain_id,
+            nonce,
+            gas_price,
+            gas_limit,
+            action,
+            value,
+            input,
+            access_list,
+        },
+        Message::EIP1559 {
+            chain_id,
+            nonce,
+            max_priority_fee_per_gas,
+            max_fee_per_gas,
+            gas_limit,
+            action,
+            value,
+            input,
+            ..
+        } => Message::EIP1559 {
+            chain_id,
+            nonce,
+            max_priority_fee_per_gas,
+            max_fee_per_gas,
+            gas_limit,
+            action,
+            value,
+            input,
+            access_list,
+        },
+        Message::EIP4844 {
+            chain_id,
+            nonce,
+            max_priority_fee_per_gas,
+            max_fee_per_gas,
+            gas_limit,
+            to,
+            value,
+            input,
+            max_fee_per_blob_gas,
+            blob_versioned_hashes,
+            ..
+        } => Message::EIP4844 {
+            chain_id,
+            nonce,
+            max_priority_fee_per_gas,
+            max_fee_per_gas,
+            gas_limit,
+            to,
+            value,
+            input,
+            access_list,
+            max_fee_per_blob_gas,
+            blob_versioned_hashes,
+        },
+    }
+}
+
+/// Builds an access list for a call by executing it until accessed accounts and slots settle,
+/// as each execution with a new access list may take a different path.
+///
+/// Gives up after one execution per distinct account accessed plus a few more, returning the
+/// last access list. Result includes gas used by the transaction carrying the access list and
+/// the reason it fails, if it does.
+fn create_access_list<K: TransactionKind, E: EnvironmentKind>(
+    txn: &MdbxTransaction<'_, K, E>,
+    call_data: types::MessageCall,
+    block: Option<helpers::BlockParam>,
+    default_gas_limit: Option<u64>,
+) -> anyhow::Result<AccessListWithGasUsed> {
+    let (block_number, block_hash) =
+        helpers::resolve_state_block(txn, block.unwrap_or(types::BlockNumber::Latest.into()))?;
+
+    let chain_spec =
+        chain::chain_config::read(txn)?.ok_or_else(|| format_err!("no chainspec found"))?;
+    let chain_id = chain_spec.params.chain_id;
+
+    let header = chain::header::read(txn, block_hash, block_number)?
+        .ok_or_else(|| format_err!("Header not found for #{block_number}/{block_hash}"))?;
+
+    let block_spec = chain_spec.collect_block_spec(block_number, header.timestamp);
+    let beneficiary = engine_factory(None, chain_spec)?.get_beneficiary(&header);
+
+    let (sender, message) = helpers::convert_message_call(
+        &Buffer::new(txn, Some(block_number)),
+        chain_id,
+        call_data,
+        &header,
+        U256::ZERO,
+        default_gas_limit,
+    )?;
+
+    // https://eips.ethereum.org/EIPS/eip-2930
+    // Sender and recipient are always warm.
+    let recipient = match message.action() {
+        TransactionAction::Call(to) => to,
+        TransactionAction::Create => create_address(sender, message.nonce()),
+    };
+    let excluded = [sender, recipient].into_iter().collect::<HashSet<_>>();
+
+    let mut analysis_cache = AnalysisCache::global();
+    let mut access_list = message.access_list().into_owned();
+    let mut addresses = access_list
+        .iter()
+        .map(|item| item.address)
+        .collect::<HashSet<_>>();
+    let mut executions = 0;
+    loop {
+        let message = with_access_list(message.clone(), chain_id, access_list.clone());
+
+        let mut buffer = Buffer::new(txn, Some(block_number));
+        let mut state = IntraBlockState::new(&mut buffer);
+
+        let mut tracer = AccessListTracer::new(&block_spec, excluded.clone(), &access_list);
+
+        let mut gas_used = 0;
+        let (_, receipt) = execute_transaction(
+            &mut state,
+            &block_spec,
+            &header,
+            &mut tracer,
+            &mut analysis_cache,
+            &mut gas_used,
+            &message,
+            sender,
+            beneficiary,
+        )?;
+        executions += 1;
+
+        let error = (!receipt.success).then(|| match tracer.status_code() {
+            Some(StatusCode::Revert) => "execution reverted".to_string(),
+            Some(status_code) => status_code.to_string(),
+            None => "execution failed".to_string(),
+        });
+        let accessed = tracer.into_access_list();
+        addresses.extend(accessed.iter().map(|item| item.address));
+
+        if accessed == access_list
+            || executions > addresses.len() + CREATE_ACCESS_LIST_EXTRA_EXECUTIONS
+        {
+            return Ok(AccessListWithGasUsed {
+                access_list: access_list
+                    .into_iter()
+                    .map(|item| types::AccessListEntry {
+                        address: item.address,
+                        storage_keys: item.slots,
+                    })
+                    .collect(),
+                error,
+                gas_used: U64::from(gas_used),
+            });
+        }
+        access_list = accessed;
+    }
+}
+
+/// Executes a call at the end of a block, with overrides applied on top of the block's state.
+///
+/// Returns the result along with the gas limit the call was executed with.
+fn execute_call<K: TransactionKind, E: EnvironmentKind>(
+    txn: &MdbxTransaction<'_, K, E>,
+    call_data: types::MessageCall,
+    block: Option<helpers::BlockParam>,
+    state_override: Option<StateOverride>,
+    block_overrides: Option<BlockOverrides>,
+    default_gas_limit: Option<u64>,
+) -> anyhow::Result<(CallResult, u64)> {
+    let (block_number, block_hash) =
+        helpers::resolve_state_block(txn, block.unwrap_or(types::BlockNumber::Latest.into()))?;
+
+    let chain_spec =
+        chain::chain_config::read(txn)?.ok_or_else(|| format_err!("no chainspec found"))?;
+
+    let mut header = chain::header::read(txn, block_hash, block_number)?
+        .ok_or_else(|| format_err!("Header not found for #{block_number}/{block_hash}"))?;
+
+    let mut buffer = Buffer::new(txn, Some(block_number));
+    if let Some(state_override) = state_override {
+        apply_state_override(&mut buffer, state_override)?;
+    }
+
+    let mut beneficiary = engine_factory(None, chain_spec.clone())?.get_beneficiary(&header);
+    let mut blob_base_fee = None;
+    if let Some(block_overrides) = block_overrides {
+        if let Some(coinbase) = block_overrides.coinbase {
+            beneficiary = coinbase;
+        }
+        blob_base_fee = block_overrides.blob_base_fee;
+        block_overrides.apply(&mut header);
+    }
+
+    let (sender, message) = helpers::convert_message_call(
+        &buffer,
+        chain_spec.params.chain_id,
+        call_data,
+        &header,
+        U256::ZERO,
+        default_gas_limit,
+    )?;
+    let gas_limit = message.gas_limit();
+
+    let mut state = IntraBlockState::new(&mut buffer);
+
+    let mut analysis_cache = AnalysisCache::global();
+    let block_spec = chain_spec.collect_block_spec(header.number, header.timestamp);
+
+    let mut tracer = NoopTracer;
+
+    let res = evmglue::execute_with_blob_base_fee(
+        &mut state,
+        &mut tracer,
+        &mut analysis_cache,
+        &header,
+        &block_spec,
+        &message,
+        sender,
+        beneficiary,
+        gas_limit,
+        blob_base_fee,
+    )?;
+
+    Ok((res, gas_limit))
+}
+
+/// Fee estimation methods of the `eth` namespace.
+#[rpc(server, namespace = "eth")]
+pub trait EthFeeApi {
+    #[method(name = "feeHistory")]
+    async fn fee_history(
+        &self,
+        block_count: U64,
+        newest_block: types::BlockNumber,
+        reward_percentiles: Option<Vec<f64>>,
+    ) -> RpcResult<FeeHistory>;
+    #[method(name = "gasPrice")]
+    async fn gas_price(&self) -> RpcResult<U256>;
+    #[method(name = "maxPriorityFeePerGas")]
+    async fn max_priority_fee_per_gas(&self) -> RpcResult<U256>;
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeeHistory {
+    pub oldest_block: U64,
+    /// Base fees of reported blocks, followed by the base fee of the block after the newest one.
+    pub base_fee_per_gas: Vec<U256>,
+    pub gas_used_ratio: Vec<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reward: Option<Vec<Vec<U256>>>,
+}
+
+/// Base fee of the block following `parent`, computed the same way as in block validation.
+fn next_base_fee_per_gas(chain_spec: &ChainSpec, parent: &BlockHeader) -> Option<U256> {
+    calc_base_fee_per_gas(
+        chain_spec.consensus.eip1559_block,
+        BlockNumber(parent.number.0 + 1),
+        parent,
+    )
+}
+
+fn read_canonical_header<K: TransactionKind, E: EnvironmentKind>(
+    txn: &MdbxTransaction<'_, K, E>,
+    block_number: BlockNumber,
+) -> anyhow::Result<(H256, BlockHeader)> {
+    let block_hash = chain::canonical_hash::read(txn, block_number)?
+        .ok_or_else(|| format_err!("no canonical header for block #{block_number}"))?;
+    let header = chain::header::read(txn, block_hash, block_number)?
+        .ok_or_else(|| format_err!("header not found for block #{block_number}/{block_hash}"))?;
+
+    Ok((block_hash, header))
+}
+
+/// Effective tips paid by transactions of a block, along with gas used by each.
+///
+/// Gas used is taken from stored receipts. Without them, gas limit is used as an approximation.
+fn block_tips<K: TransactionKind, E: EnvironmentKind>(
+    txn: &MdbxTransaction<'_, K, E>,
+    block_hash: H256,
+    header: &BlockHeader,
+) -> anyhow::Result<Vec<(U256, u64)>> {
+    let block_number = header.number;
+    let base_fee_per_gas = header.base_fee_per_gas.unwrap_or(U256::ZERO);
+
+    let block_body = chain::block_body::read_without_senders(txn, block_hash, block_number)?
+        .ok_or_else(|| format_err!("body not found for block #{block_number}/{block_hash}"))?;
+    let receipts = txn.get(tables::Receipt, block_number)?;
+
+    let mut tips = Vec::with_capacity(block_body.transactions.len());
+    let mut last_cumulative_gas_used = 0;
+    for (i, transaction) in block_body.transactions.iter().enumerate() {
+        let gas_used = if let Some(receipt) = receipts.as_ref().and_then(|receipts| receipts.get(i))
+        {
+            let gas_used = receipt.cumulative_gas_used - last_cumulative_gas_used;
+            last_cumulative_gas_used = receipt.cumulative_gas_used;
+            gas_used
+        } else {
+            transaction.gas_limit()
+        };
+
+        tips.push((
+            transaction
+                .priority_fee_per_gas(base_fee_per_gas)
+                .unwrap_or(U256::ZERO),
+            gas_used,
+        ));
+    }
+
+    Ok(tips)
+}
+
+/// Tips at given percentiles of gas used in a block, as in Geth's `eth_feeHistory`.
+fn block_rewards(mut tips: Vec<(U256, u64)>, percentiles: &[f64]) -> Vec<U256> {
+    if tips.is_empty() {
+        return vec![U256::ZERO; percentiles.len()];
+    }
+
+    tips.sort_unstable_by_key(|&(tip, _)| tip);
+    let total_gas_used = tips.iter().map(|&(_, gas_used)| gas_used).sum::<u64>();
+
+    let mut index = 0;
+    let mut cumulative_gas_used = tips[0].1;
+    percentiles
+        .iter()
+        .map(|percentile| {
+            let threshold = (total_gas_used as f64 * percentile / 100.0) as u64;
+            while cumulative_gas_used < threshold && index < tips.len() - 1 {
+                index += 1;
+                cumulative_gas_used += tips[index].1;
+            }
+            tips[index].0
+        })
+        .collect()
+}
+
+fn fee_history<K: TransactionKind, E: EnvironmentKind>(
+    txn: &MdbxTransaction<'_, K, E>,
+    block_count: u64,
+    newest_block: types::BlockNumber,
+    reward_percentiles: Option<Vec<f64>>,
+) -> anyhow::Result<FeeHistory> {
+    // Same as Geth, nothing is reported for no blocks.
+    if block_count == 0 {
+        return Ok(FeeHistory {
+            oldest_block: U64::zero(),
+            base_fee_per_gas: vec![],
+            gas_used_ratio: vec![],
+            reward: None,
+        });
+    }
+
+    if let Some(percentiles) = &reward_percentiles {
+        if percentiles
+            .iter()
+            .any(|percentile| !(0.0..=100.0).contains(percentile))
+            || percentiles.windows(2).any(|w| w[0] > w[1])
+        {
+            return Err(format_err!("invalid reward percentiles {percentiles:?}"));
+        }
+    }
+
+    let chain_spec =
+        chain::chain_config::read(txn)?.ok_or_else(|| format_err!("no chainspec found"))?;
+
+    let newest_block = helpers::resolve_block_number(txn, newest_block)?;
+    let block_count = block_count
+        .min(FEE_HISTORY_MAX_BLOCK_COUNT)
+        .min(newest_block.0 + 1);
+    let oldest_block = newest_block.0 + 1 - block_count;
+
+    let mut history = FeeHistory {
+        oldest_block: U64::from(oldest_block),
+        base_fee_per_gas: Vec::with_capacity(block_count as usize + 1),
+        gas_used_ratio: Vec::with_capacity(block_count as usize),
+        reward: reward_percentiles
+            .as_ref()
+            .map(|_| Vec::with_capacity(block_count as usize)),
+    };
+
+    let mut newest_header = None;
+    for block_number in oldest_block..=newest_block.0 {
+        let (block_hash, header) = read_canonical_header(txn, BlockNumber(block_number))?;
+
+        history
+            .base_fee_per_gas
+            .push(header.base_fee_per_gas.unwrap_or(U256::ZERO));
+        history.gas_used_ratio.push(if header.gas_limit == 0 {
+            0.0
+        } else {
+            header.gas_used as f64 / header.gas_limit as f64
+        });
+        if let (Some(percentiles), Some(reward)) = (&reward_percentiles, &mut history.reward) {
+            reward.push(block_rewards(
+                block_tips(txn, block_hash, &header)?,
+                percentiles,
+            ));
+        }
+
+        newest_header = Some(header);
+    }
+
+    if let Some(header) = newest_header {
+        history
+            .base_fee_per_gas
+            .push(next_base_fee_per_gas(&chain_spec, &header).unwrap_or(U256::ZERO));
+    }
+
+    Ok(history)
+}
+
+/// Suggests a tip out of the lowest ones paid in latest blocks, similar to Geth's oracle.
+///
+/// Returns the tip along with the base fee of the next block, if there is one.
+fn suggest_tip<K: TransactionKind, E: EnvironmentKind>(
+    txn: &MdbxTransaction<'_, K, E>,
+) -> anyhow::Result<(U256, Option<U256>)> {
+    let chain_spec =
+        chain::chain_config::read(txn)?.ok_or_else(|| format_err!("no chainspec found"))?;
+
+    let latest_block = helpers::resolve_block_number(txn, types::BlockNumber::Latest)?;
+
+    let mut samples = Vec::new();
+    let mut latest_header = None;
+    for block_number in
+        (latest_block.0.saturating_sub(GAS_PRICE_ORACLE_BLOCKS - 1)..=latest_block.0).rev()
+    {
+        let (block_hash, header) = read_canonical_header(txn, BlockNumber(block_number))?;
+        let base_fee_per_gas = header.base_fee_per_gas.unwrap_or(U256::ZERO);
+
+        let block_body = chain::block_body::read_with_senders(txn, block_hash, header.number)?
+            .ok_or_else(|| format_err!("body not found for block #{block_number}/{block_hash}"))?;
+
+        let mut tips = block_body
+            .transactions
+            .iter()
+            // Block producers may include their own transactions for free.
+            .filter(|transaction| transaction.sender != header.beneficiary)
+            .filter_map(|transaction| transaction.message.priority_fee_per_gas(base_fee_per_gas))
+            .filter(|&tip| tip >= GAS_PRICE_ORACLE_IGNORE_PRICE)
+            .collect::<Vec<_>>();
+        tips.sort_unstable();
+        samples.extend(tips.into_iter().take(GAS_PRICE_ORACLE_SAMPLES_PER_BLOCK));
+
+        if latest_header.is_none() {
+            latest_header = Some(header);
+        }
+    }
+
+    let tip = if samples.is_empty() {
+        GAS_PRICE_ORACLE_DEFAULT_PRICE
+    } else {
+        samples.sort_unstable();
+        samples[(samples.len() - 1) * GAS_PRICE_ORACLE_PERCENTILE / 100]
+    };
+
+    Ok((
+        tip.min(GAS_PRICE_ORACLE_MAX_PRICE),
+        latest_header.and_then(|header| next_base_fee_per_gas(&chain_spec, &header)),
+    ))
+}
+
+/// Methods serving blocks and transactions in representations which cover blob transactions.
+#[rpc(server, namespace = "eth")]
+pub trait EthBlockApi {
+    #[method(name = "getBlockByHash")]
+    async fn get_block_by_hash(
+        &self,
+        hash: H256,
+        include_txs: bool,
+    ) -> RpcResult<Option<helpers::Block>>;
+    #[method(name = "getBlockByNumber")]
+    async fn get_block_by_number(
+        &self,
+        block_number: types::BlockNumber,
+        include_txs: bool,
+    ) -> RpcResult<Option<helpers::Block>>;
+    #[method(name = "getTransactionByHash")]
+    async fn get_transaction_by_hash(&self, hash: H256) -> RpcResult<Option<helpers::Tx>>;
+    #[method(name = "getTransactionByBlockHashAndIndex")]
+    async fn get_transaction_by_block_hash_and_index(
+        &self,
+        block_hash: H256,
+        index: U64,
+    ) -> RpcResult<Option<helpers::Tx>>;
+    #[method(name = "getTransactionByBlockNumberAndIndex")]
+    async fn get_transaction_by_block_number_and_index(
+        &self,
+        block_number: types::BlockNumber,
+        index: U64,
+    ) -> RpcResult<Option<helpers::Tx>>;
+}
+
+/// [EIP-1186](https://eips.ethereum.org/EIPS/eip-1186) account and storage proofs.
+#[rpc(server, namespace = "eth")]
+pub trait EthProofApi {
+    #[method(name = "getProof")]
+    async fn get_proof(
+        &self,
+        address: Address,
+        storage_keys: Vec<H256>,
+        block: helpers::BlockParam,
+    ) -> RpcResult<AccountProof>;
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageProof {
+    pub key: H256,
+    pub value: U256,
+    pub proof: Vec<types::Bytes>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountProof {
+    pub address: Address,
+    pub account_proof: Vec<types::Bytes>,
+    pub balance: U256,
+    pub code_hash: H256,
+    pub nonce: U64,
+    pub storage_hash: H256,
+    pub storage_proof: Vec<StorageProof>,
+}
+
+fn get_proof<E: EnvironmentKind>(
+    txn: &MdbxTransaction<'_, RO, E>,
+    address: Address,
+    storage_keys: Vec<H256>,
+    block: helpers::BlockParam,
+) -> anyhow::Result<AccountProof> {
+    let (block_number, block_hash) = helpers::resolve_state_block(txn, block)?;
+    let header = chain::header::read(txn, block_hash, block_number)?
+        .ok_or_else(|| format_err!("header not found for block #{block_number}/{block_hash}"))?;
+
+    let latest_block = helpers::resolve_block_number(txn, types::BlockNumber::Latest)?;
+    if latest_block.0 - block_number.0 > PROOF_HISTORY_WINDOW {
+        return Err(format_err!(
+            "proofs are only available for the latest {PROOF_HISTORY_WINDOW} blocks"
+        ));
+    }
+
+    // Trie is kept for the latest block only, older ones are reverted with change sets.
+    let overlay = if block_number < latest_block {
+        trie::historical_overlay(txn, block_number)?
+    } else {
+        Default::default()
+    };
+    let proof = trie::prove_with_overlay(txn, &overlay, address, &storage_keys)?;
+    if proof.state_root != header.state_root {
+        return Err(format_err!(
+            "state root mismatch for block #{block_number}/{block_hash}: expected {:?}, got {:?}",
+            header.state_root,
+            proof.state_root
+        ));
+    }
+
+    let account = state::account::read(txn, address, Some(block_number))?.unwrap_or_default();
+
+    let storage_proof = storage_keys
+        .into_iter()
+        .zip(proof.storage_proofs)
+        .map(|(key, proof)| {
+            Ok(StorageProof {
+                key,
+                value: state::storage::read(txn, address, h256_to_u256(key), Some(block_number))?,
+                proof: proof.into_iter().map(From::from).collect(),
+            })
+        })
+        .collect::<anyhow::Result<_>>()?;
+
+    Ok(AccountProof {
+        address,
+        account_proof: proof.account_proof.into_iter().map(From::from).collect(),
+        balance: account.balance,
+        code_hash: account.code_hash,
+        nonce: U64::from(account.nonce),
+        storage_hash: proof.storage_root,
+        storage_proof,
+    })
+}
+
+pub struct EthApiServerImpl<SE>
+where
+    SE: EnvironmentKind,
+{
+    pub db: Arc<MdbxWithDirHandle<SE>>,
+    pub call_gas_limit: u64,
+    /// Most blocks a single `eth_getLogs` request may scan.
+    pub max_logs_block_range: u64,
+    pub txpool: Option<Arc<TransactionPool<SE>>>,
+    pub filters: Arc<FilterRegistry>,
+}
+
+impl<SE> EthApiServerImpl<SE>
+where
+    SE: EnvironmentKind,
+{
+    /// Methods of the `eth` namespace, with `eth_call` and `eth_estimateGas` accepting overrides,
+    /// state accessors accepting EIP-1898 block parameters and blocks and transactions covering
+    /// blob transactions, along with methods not covered by [`EthApiServer`].
+    pub fn into_methods(self) -> Methods {
+        let call_api = self.clone();
+        let state_api = self.clone();
+        let fee_api = self.clone();
+        let proof_api = self.clone();
+        let block_api = self.clone();
+
+        let mut methods = Methods::from(EthApiServer::into_rpc(self));
+        for method in [
+            "eth_call",
+            "eth_estimateGas",
+            "eth_getBalance",
+            "eth_getCode",
+            "eth_getStorageAt",
+            "eth_getTransactionCount",
+            "eth_getBlockByHash",
+            "eth_getBlockByNumber",
+            "eth_getTransactionByHash",
+            "eth_getTransactionByBlockHashAndIndex",
+            "eth_getTransactionByBlockNumberAndIndex",
+        ] {
+            methods.remove_method(method);
+        }
+        methods
+            .merge(EthCallApiServer::into_rpc(call_api))
+            .expect("overridden methods are removed");
+        methods
+            .merge(EthStateApiServer::into_rpc(state_api))
+            .expect("overridden methods are removed");
+        methods
+            .merge(EthBlockApiServer::into_rpc(block_api))
+            .expect("overridden methods are removed");
+        methods.merge(EthFeeApiServer::into_rpc(fee_api)).unwrap();
+        methods
+            .merge(EthProofApiServer::into_rpc(proof_api))
+            .unwrap();
+
+        methods
+    }
+}
+
+impl<SE> Clone for EthApiServerImpl<SE>
+where
+    SE: EnvironmentKind,
+{
+    fn clone(&self) -> Self {
+        Self {
+            db: self.db.clone(),
+            call_gas_limit: self.call_gas_limit,
+            max_logs_block_range: self.max_logs_block_range,
+            txpool: self.txpool.clone(),
+            filters: self.filters.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl<DB> EthFeeApiServer for EthApiServerImpl<DB>
+where
+    DB: EnvironmentKind,
+{
+    async fn fee_history(
+        &self,
+        block_count: U64,
+        newest_block: types::BlockNumber,
+        reward_percentiles: Option<Vec<f64>>,
+    ) -> RpcResult<FeeHistory> {
+        let db = self.db.clone();
+
+        tokio::task::spawn_blocking(move || {
+            Ok(fee_history(
+                &db.begin()?,
+                block_count.as_u64(),
+                newest_block,
+                reward_percentiles,
+            )?)
+        })
+        .await
+        .unwrap_or_else(helpers::joinerror_to_result)
+    }
+
+    async fn gas_price(&self) -> RpcResult<U256> {
+        let db = self.db.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let (tip, base_fee_per_gas) = suggest_tip(&db.begin()?)?;
+
+            Ok(tip + base_fee_per_gas.unwrap_or(U256::ZERO))
+        })
+        .await
+        .unwrap_or_else(helpers::joinerror_to_result)
+    }
+
+    async fn max_priority_fee_per_gas(&self) -> RpcResult<U256> {
+        let db = self.db.clone();
+
+        tokio::task::spawn_blocking(move || Ok(suggest_tip(&db.begin()?)?.0))
+            .await
+            .unwrap_or_else(helpers::joinerror_to_result)
+    }
+}
+
+#[async_trait]
+impl<DB> EthStateApiServer for EthApiServerImpl<DB>
+where
+    DB: EnvironmentKind,
+{
+    async fn get_balance(&self, address: Address, block: helpers::BlockParam) -> RpcResult<U256> {
+        let db = self.db.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let txn = db.begin()?;
+            let (block_number, _) = helpers::resolve_state_block(&txn, block)?;
+
+            Ok(state::account::read(&txn, address, Some(block_number))?
+                .map(|acc| acc.balance)
+                .unwrap_or(U256::ZERO))
+        })
+        .await
+        .unwrap_or_else(helpers::joinerror_to_result)
+    }
+
+    async fn get_code(
+        &self,
+        address: Address,
+        block: helpers::BlockParam,
+    ) -> RpcResult<types::Bytes> {
+        let db = self.db.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let txn = db.begin()?;
+            let (block_number, _) = helpers::resolve_state_block(&txn, block)?;
+            Ok(
+                if let Some(account) = state::account::read(&txn, address, Some(block_number))? {
+                    txn.get(tables::Code, account.code_hash)?
+                        .ok_or_else(|| {
+                            format_err!("failed to find code for code hash {}", account.code_hash)
+                        })?
+                        .into()
+                } else {
+                    Default::default()
+                },
+            )
+        })
+        .await
+        .unwrap_or_else(helpers::joinerror_to_result)
+    }
+
+    async fn get_storage_at(
+        &self,
+        address: Address,
+        key: U256,
+        block: helpers::BlockParam,
+    ) -> RpcResult<U256> {
+        let db = self.db.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let txn = db.begin()?;
+            let (block_number, _) = helpers::resolve_state_block(&txn, block)?;
+
+            Ok(state::storage::read(
+                &txn,
+                address,
+                key,
+                Some(block_number),
+            )?)
+        })
+        .await
+        .unwrap_or_else(helpers::joinerror_to_result)
+    }
+
+    async fn get_transaction_count(
+        &self,
+        address: Address,
+        block: helpers::BlockParam,
+    ) -> RpcResult<U64> {
+        let db = self.db.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let txn = db.begin()?;
+            let (block_number, _) = helpers::resolve_state_block(&txn, block)?;
+
+            Ok(state::account::read(&txn, address, Some(block_number))?
+                .map(|account| account.nonce)
+                .unwrap_or(0)
+                .into())
+        })
+        .await
+        .unwrap_or_else(helpers::joinerror_to_result)
+    }
+}
+
+#[async_trait]
+impl<DB> EthCallApiServer for EthApiServerImpl<DB>
+where
+    DB: EnvironmentKind,
+{
+    async fn call(
+        &self,
+        call_data: types::MessageCall,
+        block: Option<helpers::BlockParam>,
+        state_override: Option<StateOverride>,
+        block_overrides: Option<BlockOverrides>,
+    ) -> RpcResult<types::Bytes> {
+        let db = self.db.clone();
+        let call_gas_limit = self.call_gas_limit;
 
-pub struct EthApiServerImpl<SE>
+        tokio::task::spawn_blocking(move || {
+            let txn = db.begin()?;
+
+            let (res, _) = execute_call(
+                &txn,
+                call_data,
+                block,
+                state_override,
+                block_overrides,
+                Some(call_gas_limit),
+            )?;
+
+            Ok(res.output_data.into())
+        })
+        .await
+        .unwrap_or_else(helpers::joinerror_to_result)
+    }
+
+    async fn estimate_gas(
+        &self,
+        call_data: types::MessageCall,
+        block: Option<helpers::BlockParam>,
+        state_override: Option<StateOverride>,
+        block_overrides: Option<BlockOverrides>,
+    ) -> RpcResult<U64> {
+        let db = self.db.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let txn = db.begin()?;
+
+            let (res, gas_limit) = execute_call(
+                &txn,
+                call_data,
+                block,
+                state_override,
+                block_overrides,
+                None,
+            )?;
+
+            Ok(U64::from(gas_limit as i64 - res.gas_left))
+        })
+        .await
+        .unwrap_or_else(helpers::joinerror_to_result)
+    }
+
+    async fn create_access_list(
+        &self,
+        call_data: types::MessageCall,
+        block: Option<helpers::BlockParam>,
+    ) -> RpcResult<AccessListWithGasUsed> {
+        let db = self.db.clone();
+        let call_gas_limit = self.call_gas_limit;
+
+        tokio::task::spawn_blocking(move || {
+            let txn = db.begin()?;
+
+            Ok(create_access_list(
+                &txn,
+                call_data,
+                block,
+                Some(call_gas_limit),
+            )?)
+        })
+        .await
+        .unwrap_or_else(helpers::joinerror_to_result)
+    }
+}
+
+#[async_trait]
+impl<DB> EthProofApiServer for EthApiServerImpl<DB>
 where
-    SE: EnvironmentKind,
+    DB: EnvironmentKind,
 {
-    pub db: Arc<MdbxWithDirHandle<SE>>,
-    pub call_gas_limit: u64,
+    async fn get_proof(
+        &self,
+        address: Address,
+        storage_keys: Vec<H256>,
+        block: helpers::BlockParam,
+    ) -> RpcResult<AccountProof> {
+        let db = self.db.clone();
+
+        tokio::task::spawn_blocking(move || {
+            Ok(get_proof(&db.begin()?, address, storage_keys, block)?)
+        })
+        .await
+        .unwrap_or_else(helpers::joinerror_to_result)
+    }
+}
+
+#[async_trait]
+impl<DB> EthBlockApiServer for EthApiServerImpl<DB>
+where
+    DB: EnvironmentKind,
+{
+    async fn get_block_by_hash(
+        &self,
+        hash: H256,
+        include_txs: bool,
+    ) -> RpcResult<Option<helpers::Block>> {
+        let db = self.db.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let txn = db.begin()?;
+
+            Ok(helpers::construct_block(&txn, hash, include_txs, None)?)
+        })
+        .await
+        .unwrap_or_else(helpers::joinerror_to_result)
+    }
+
+    async fn get_block_by_number(
+        &self,
+        block_number: types::BlockNumber,
+        include_txs: bool,
+    ) -> RpcResult<Option<helpers::Block>> {
+        let db = self.db.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let txn = db.begin()?;
+            Ok(helpers::construct_block(
+                &txn,
+                block_number,
+                include_txs,
+                None,
+            )?)
+        })
+        .await
+        .unwrap_or_else(helpers::joinerror_to_result)
+    }
+
+    async fn get_transaction_by_hash(&self, hash: H256) -> RpcResult<Option<helpers::Tx>> {
+        let db = self.db.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let txn = db.begin()?;
+            if let Some(block_number) = chain::tl::read(&txn, hash)? {
+                let block_hash = chain::canonical_hash::read(&txn, block_number)?
+                    .ok_or_else(|| format_err!("canonical hash for block #{block_number} not found"))?;
+                let (index, transaction) = chain::block_body::read_without_senders(
+                    &txn,
+                    block_hash,
+                    block_number,
+                )?.ok_or_else(|| format_err!("body not found for block #{block_number}/{block_hash}"))?
+                .transactions
+                .into_iter()
+                .enumerate()
+                .find(|(_, tx)| tx.hash() == hash)
+                .ok_or_else(|| {
+                    format_err!(
+                        "tx with hash {hash} is not found in block #{block_number}/{block_hash} - tx lookup index invalid?"
+                    )
+                })?;
+                let senders = chain::tx_sender::read(&txn, block_hash, block_number)?;
+                let sender = *senders
+                    .get(index)
+                    .ok_or_else(|| format_err!("senders to short: {index} vs len {}", senders.len()))?;
+                return Ok(Some(helpers::Tx::Transaction(Box::new(
+                    helpers::new_jsonrpc_tx(transaction, sender, Some(index as u64)),
+                ))));
+            }
+
+            Ok(None)
+        })
+        .await
+        .unwrap_or_else(helpers::joinerror_to_result)
+    }
+
+    async fn get_transaction_by_block_hash_and_index(
+        &self,
+        block_hash: H256,
+        index: U64,
+    ) -> RpcResult<Option<helpers::Tx>> {
+        let db = self.db.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let txn = db.begin()?;
+            Ok(
+                helpers::construct_block(&txn, block_hash, true, None)?.and_then(|mut block| {
+                    let index = index.as_usize();
+                    if index < block.transactions.len() {
+                        Some(block.transactions.remove(index))
+                    } else {
+                        None
+                    }
+                }),
+            )
+        })
+        .await
+        .unwrap_or_else(helpers::joinerror_to_result)
+    }
+
+    async fn get_transaction_by_block_number_and_index(
+        &self,
+        block_number: types::BlockNumber,
+        index: U64,
+    ) -> RpcResult<Option<helpers::Tx>> {
+        let db = self.db.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let txn = db.begin()?;
+            Ok(
+                helpers::construct_block(&txn, block_number, true, None)?.and_then(|mut block| {
+                    let index = index.as_usize();
+                    if index < block.transactions.len() {
+                        Some(block.transactions.remove(index))
+                    } else {
+                        None
+                    }
+                }),
+            )
+        })
+        .await
+        .unwrap_or_else(helpers::joinerror_to_result)
+    }
 }
 
 #[async_trait]
@@ -42,80 +1445,169 @@ where
         ))
     }
 
-    async fn get_logs(&self, filter: LogFilter) -> RpcResult<TransactionLog> {
-        let _ = filter;
-        Err(format_err!("not implemented").into())
+    async fn get_logs(&self, filter: LogFilter) -> RpcResult<Vec<TransactionLog>> {
+        let db = self.db.clone();
+        let max_logs_block_range = self.max_logs_block_range;
+
+        tokio::task::spawn_blocking(move || {
+            let txn = db.begin()?;
+
+            let (from_block, to_block) = log_filter_range(&txn, &filter)?;
+            if from_block > to_block {
+                return Err(format_err!(
+                    "from_block higher than to_block: {from_block} > {to_block}"
+                )
+                .into());
+            }
+            if to_block.0 - from_block.0 >= max_logs_block_range {
+                return Err(format_err!(
+                    "block range {from_block}..={to_block} exceeds the limit of {max_logs_block_range} blocks"
+                )
+                .into());
+            }
+
+            Ok(helpers::get_logs(
+                &txn,
+                from_block..=to_block,
+                &helpers::LogQuery::from(&filter),
+            )?)
+        })
+        .await
+        .unwrap_or_else(helpers::joinerror_to_result)
+    }
+
+    async fn new_filter(&self, filter: LogFilter) -> RpcResult<U64> {
+        let last_block =
+            helpers::resolve_block_number(&self.db.begin()?, types::BlockNumber::Latest)?;
+
+        Ok(self.filters.install(FilterKind::Logs(filter), last_block))
     }
 
-    async fn chain_id(&self) -> RpcResult<U64> {
-        Ok(chain::chain_config::read(&self.db.begin()?)?
-            .ok_or_else(|| format_err!("chain specification not found"))?
-            .params
-            .chain_id
-            .0
-            .into())
+    async fn new_block_filter(&self) -> RpcResult<U64> {
+        let last_block =
+            helpers::resolve_block_number(&self.db.begin()?, types::BlockNumber::Latest)?;
+
+        Ok(self.filters.install(FilterKind::Blocks, last_block))
     }
 
-    async fn call(
-        &self,
-        call_data: types::MessageCall,
-        block_number: types::BlockNumber,
-    ) -> RpcResult<types::Bytes> {
+    async fn new_pending_transaction_filter(&self) -> RpcResult<U64> {
+        let txpool = self
+            .txpool
+            .as_ref()
+            .ok_or_else(|| format_err!("transaction pool is not available"))?;
+
+        Ok(self.filters.install(
+            FilterKind::PendingTransactions(txpool.subscribe()),
+            BlockNumber(0),
+        ))
+    }
+
+    async fn uninstall_filter(&self, id: U64) -> RpcResult<bool> {
+        Ok(self.filters.uninstall(id))
+    }
+
+    async fn get_filter_changes(&self, id: U64) -> RpcResult<types::FilterChanges> {
         let db = self.db.clone();
-        let call_gas_limit = self.call_gas_limit;
+        let filters = self.filters.clone();
 
         tokio::task::spawn_blocking(move || {
+            let poll = filters.poll(id)?;
+
             let txn = db.begin()?;
+            let head = helpers::resolve_block_number(&txn, types::BlockNumber::Latest)?;
 
-            let (block_number, block_hash) = helpers::resolve_block_id(&txn, block_number)?
-                .ok_or_else(|| format_err!("failed to resolve block {block_number:?}"))?;
+            let changes = match poll {
+                FilterPoll::Logs { filter, last_block } => {
+                    let (from_block, to_block) = if filter.block_hash.is_some() {
+                        log_filter_range(&txn, &filter)?
+                    } else {
+                        let resolve = |block_number| match block_number {
+                            None
+                            | Some(types::BlockNumber::Latest)
+                            | Some(types::BlockNumber::Pending) => Ok(None),
+                            Some(block_number) => {
+                                helpers::resolve_block_number(&txn, block_number).map(Some)
+                            }
+                        };
+                        (
+                            resolve(filter.from_block)?.unwrap_or(BlockNumber(0)),
+                            resolve(filter.to_block)?.unwrap_or(head),
+                        )
+                    };
+                    let from_block = std::cmp::max(from_block, last_block + 1);
+                    let to_block = std::cmp::min(to_block, head);
+
+                    types::FilterChanges::Logs(if from_block <= to_block {
+                        helpers::get_logs(
+                            &txn,
+                            from_block..=to_block,
+                            &helpers::LogQuery::from(&filter),
+                        )?
+                    } else {
+                        vec![]
+                    })
+                }
+                FilterPoll::Blocks { last_block } => {
+                    let mut hashes = vec![];
+                    for block_number in last_block.0 + 1..=head.0 {
+                        hashes.push(chain::canonical_hash::read(&txn, block_number)?.ok_or_else(
+                            || format_err!("no canonical hash for block #{block_number}"),
+                        )?);
+                    }
+                    types::FilterChanges::Hashes(hashes)
+                }
+                FilterPoll::PendingTransactions(hashes) => types::FilterChanges::Hashes(hashes),
+            };
+
+            filters.advance(id, head);
+
+            Ok(changes)
+        })
+        .await
+        .unwrap_or_else(helpers::joinerror_to_result)
+    }
 
-            let chain_id = txn
-                .get(tables::Config, ())?
-                .ok_or_else(|| format_err!("chain spec not found"))?
-                .params
-                .chain_id;
+    async fn get_filter_logs(&self, id: U64) -> RpcResult<Vec<TransactionLog>> {
+        let db = self.db.clone();
+        let filters = self.filters.clone();
 
-            let header = chain::header::read(&txn, block_hash, block_number)?
-                .ok_or_else(|| format_err!("Header not found for #{block_number}/{block_hash}"))?;
+        tokio::task::spawn_blocking(move || {
+            let FilterPoll::Logs { filter, .. } = filters.poll(id)? else {
+                return Err(format_err!("filter {id} is not a log filter").into());
+            };
 
-            let mut buffer = Buffer::new(&txn, Some(block_number));
+            let txn = db.begin()?;
+            let (from_block, to_block) = log_filter_range(&txn, &filter)?;
 
-            let (sender, message) = helpers::convert_message_call(
-                &buffer,
-                chain_id,
-                call_data,
-                &header,
-                U256::ZERO,
-                Some(call_gas_limit),
-            )?;
+            Ok(if from_block <= to_block {
+                helpers::get_logs(
+                    &txn,
+                    from_block..=to_block,
+                    &helpers::LogQuery::from(&filter),
+                )?
+            } else {
+                vec![]
+            })
+        })
+        .await
+        .unwrap_or_else(helpers::joinerror_to_result)
+    }
 
-            let mut state = IntraBlockState::new(&mut buffer);
-
-            let mut analysis_cache = AnalysisCache::default();
-            let chain_spec = chain::chain_config::read(&txn)?
-                .ok_or_else(|| format_err!("no chainspec found"))?;
-            let block_spec = chain_spec.collect_block_spec(block_number);
-
-            let mut tracer = NoopTracer;
-
-            let beneficiary = engine_factory(None, chain_spec)?.get_beneficiary(&header);
-            Ok(evmglue::execute(
-                &mut state,
-                &mut tracer,
-                &mut analysis_cache,
-                &header,
-                &block_spec,
-                &message,
-                sender,
-                beneficiary,
-                message.gas_limit(),
-            )?
-            .output_data
+    async fn chain_id(&self) -> RpcResult<U64> {
+        Ok(chain::chain_config::read(&self.db.begin()?)?
+            .ok_or_else(|| format_err!("chain specification not found"))?
+            .params
+            .chain_id
+            .0
             .into())
-        })
-        .await
-        .unwrap_or_else(helpers::joinerror_to_result)
+    }
+
+    async fn call(
+        &self,
+        call_data: types::MessageCall,
+        block_number: types::BlockNumber,
+    ) -> RpcResult<types::Bytes> {
+        EthCallApiServer::call(self, call_data, Some(block_number.into()), None, None).await
     }
 
     async fn estimate_gas(
@@ -123,58 +1615,7 @@ where
         call_data: types::MessageCall,
         block_number: types::BlockNumber,
     ) -> RpcResult<U64> {
-        let db = self.db.clone();
-
-        tokio::task::spawn_blocking(move || {
-            let txn = db.begin()?;
-            let (block_number, hash) = helpers::resolve_block_id(&txn, block_number)?
-                .ok_or_else(|| format_err!("failed to resolve block {block_number:?}"))?;
-            let chain_id = txn
-                .get(tables::Config, ())?
-                .ok_or_else(|| format_err!("chain spec not found"))?
-                .params
-                .chain_id;
-            let header = chain::header::read(&txn, hash, block_number)?
-                .ok_or_else(|| format_err!("no header found for block #{block_number}/{hash}"))?;
-            let mut buffer = Buffer::new(&txn, Some(block_number));
-
-            let (sender, message) = helpers::convert_message_call(
-                &buffer,
-                chain_id,
-                call_data,
-                &header,
-                U256::ZERO,
-                None,
-            )?;
-
-            let mut state = IntraBlockState::new(&mut buffer);
-
-            let mut cache = AnalysisCache::default();
-            let chain_spec = chain::chain_config::read(&txn)?
-                .ok_or_else(|| format_err!("no chainspec found"))?;
-            let block_spec = chain_spec.collect_block_spec(block_number);
-            let mut tracer = NoopTracer;
-            let gas_limit = header.gas_limit;
-
-            let beneficiary = engine_factory(None, chain_spec)?.get_beneficiary(&header);
-            Ok(U64::from(
-                gas_limit as i64
-                    - evmglue::execute(
-                        &mut state,
-                        &mut tracer,
-                        &mut cache,
-                        &header,
-                        &block_spec,
-                        &message,
-                        sender,
-                        beneficiary,
-                        gas_limit,
-                    )?
-                    .gas_left,
-            ))
-        })
-        .await
-        .unwrap_or_else(helpers::joinerror_to_result)
+        EthCallApiServer::estimate_gas(self, call_data, Some(block_number.into()), None, None).await
     }
 
     async fn get_balance(
@@ -182,21 +1623,7 @@ where
         address: Address,
         block_number: types::BlockNumber,
     ) -> RpcResult<U256> {
-        let db = self.db.clone();
-
-        tokio::task::spawn_blocking(move || {
-            let txn = db.begin()?;
-
-            Ok(state::account::read(
-                &txn,
-                address,
-                Some(helpers::resolve_block_number(&txn, block_number)?),
-            )?
-            .map(|acc| acc.balance)
-            .unwrap_or(U256::ZERO))
-        })
-        .await
-        .unwrap_or_else(helpers::joinerror_to_result)
+        EthStateApiServer::get_balance(self, address, block_number.into()).await
     }
 
     async fn get_block_by_hash(
@@ -204,70 +1631,32 @@ where
         hash: H256,
         include_txs: bool,
     ) -> RpcResult<Option<types::Block>> {
-        let db = self.db.clone();
-
-        tokio::task::spawn_blocking(move || {
-            let txn = db.begin()?;
-
-            Ok(helpers::construct_block(&txn, hash, include_txs, None)?)
-        })
-        .await
-        .unwrap_or_else(helpers::joinerror_to_result)
+        Ok(
+            EthBlockApiServer::get_block_by_hash(self, hash, include_txs)
+                .await?
+                .map(types::Block::try_from)
+                .transpose()?,
+        )
     }
+
     async fn get_block_by_number(
         &self,
         block_number: types::BlockNumber,
         include_txs: bool,
     ) -> RpcResult<Option<types::Block>> {
-        let db = self.db.clone();
-
-        tokio::task::spawn_blocking(move || {
-            let txn = db.begin()?;
-            Ok(helpers::construct_block(
-                &txn,
-                block_number,
-                include_txs,
-                None,
-            )?)
-        })
-        .await
-        .unwrap_or_else(helpers::joinerror_to_result)
+        Ok(
+            EthBlockApiServer::get_block_by_number(self, block_number, include_txs)
+                .await?
+                .map(types::Block::try_from)
+                .transpose()?,
+        )
     }
-    async fn get_transaction_by_hash(&self, hash: H256) -> RpcResult<Option<types::Tx>> {
-        let db = self.db.clone();
-
-        tokio::task::spawn_blocking(move || {
-            let txn = db.begin()?;
-            if let Some(block_number) = chain::tl::read(&txn, hash)? {
-                let block_hash = chain::canonical_hash::read(&txn, block_number)?
-                    .ok_or_else(|| format_err!("canonical hash for block #{block_number} not found"))?;
-                let (index, transaction) = chain::block_body::read_without_senders(
-                    &txn,
-                    block_hash,
-                    block_number,
-                )?.ok_or_else(|| format_err!("body not found for block #{block_number}/{block_hash}"))?
-                .transactions
-                .into_iter()
-                .enumerate()
-                .find(|(_, tx)| tx.hash() == hash)
-                .ok_or_else(|| {
-                    format_err!(
-                        "tx with hash {hash} is not found in block #{block_number}/{block_hash} - tx lookup index invalid?"
-                    )
-                })?;
-                let senders = chain::tx_sender::read(&txn, block_hash, block_number)?;
-                let sender = *senders
-                    .get(index)
-                    .ok_or_else(|| format_err!("senders to short: {index} vs len {}", senders.len()))?;
-                return Ok(Some(types::Tx::Transaction(Box::new(
-                    helpers::new_jsonrpc_tx(transaction, sender, Some(index as u64)),
-                ))));
-            }
 
-            Ok(None)
-        })
-        .await
-        .unwrap_or_else(helpers::joinerror_to_result)
+    async fn get_transaction_by_hash(&self, hash: H256) -> RpcResult<Option<types::Tx>> {
+        Ok(EthBlockApiServer::get_transaction_by_hash(self, hash)
+            .await?
+            .map(types::Tx::try_from)
+            .transpose()?)
     }
 
     async fn get_block_transaction_count_by_hash(&self, hash: H256) -> RpcResult<U64> {
@@ -317,25 +1706,7 @@ where
         address: Address,
         block_number: types::BlockNumber,
     ) -> RpcResult<types::Bytes> {
-        let db = self.db.clone();
-
-        tokio::task::spawn_blocking(move || {
-            let txn = db.begin()?;
-            let block_number = helpers::resolve_block_number(&txn, block_number)?;
-            Ok(
-                if let Some(account) = state::account::read(&txn, address, Some(block_number))? {
-                    txn.get(tables::Code, account.code_hash)?
-                        .ok_or_else(|| {
-                            format_err!("failed to find code for code hash {}", account.code_hash)
-                        })?
-                        .into()
-                } else {
-                    Default::default()
-                },
-            )
-        })
-        .await
-        .unwrap_or_else(helpers::joinerror_to_result)
+        EthStateApiServer::get_code(self, address, block_number.into()).await
     }
 
     async fn get_storage_at(
@@ -344,19 +1715,7 @@ where
         key: U256,
         block_number: types::BlockNumber,
     ) -> RpcResult<U256> {
-        let db = self.db.clone();
-
-        tokio::task::spawn_blocking(move || {
-            let txn = db.begin()?;
-            Ok(state::storage::read(
-                &txn,
-                address,
-                key,
-                Some(helpers::resolve_block_number(&txn, block_number)?),
-            )?)
-        })
-        .await
-        .unwrap_or_else(helpers::joinerror_to_result)
+        EthStateApiServer::get_storage_at(self, address, key, block_number.into()).await
     }
 
     async fn get_transaction_by_block_hash_and_index(
@@ -364,23 +1723,12 @@ where
         block_hash: H256,
         index: U64,
     ) -> RpcResult<Option<types::Tx>> {
-        let db = self.db.clone();
-
-        tokio::task::spawn_blocking(move || {
-            let txn = db.begin()?;
-            Ok(
-                helpers::construct_block(&txn, block_hash, true, None)?.and_then(|mut block| {
-                    let index = index.as_usize();
-                    if index < block.transactions.len() {
-                        Some(block.transactions.remove(index))
-                    } else {
-                        None
-                    }
-                }),
-            )
-        })
-        .await
-        .unwrap_or_else(helpers::joinerror_to_result)
+        Ok(
+            EthBlockApiServer::get_transaction_by_block_hash_and_index(self, block_hash, index)
+                .await?
+                .map(types::Tx::try_from)
+                .transpose()?,
+        )
     }
 
     async fn get_transaction_by_block_number_and_index(
@@ -388,23 +1736,12 @@ where
         block_number: types::BlockNumber,
         index: U64,
     ) -> RpcResult<Option<types::Tx>> {
-        let db = self.db.clone();
-
-        tokio::task::spawn_blocking(move || {
-            let txn = db.begin()?;
-            Ok(
-                helpers::construct_block(&txn, block_number, true, None)?.and_then(|mut block| {
-                    let index = index.as_usize();
-                    if index < block.transactions.len() {
-                        Some(block.transactions.remove(index))
-                    } else {
-                        None
-                    }
-                }),
-            )
-        })
-        .await
-        .unwrap_or_else(helpers::joinerror_to_result)
+        Ok(
+            EthBlockApiServer::get_transaction_by_block_number_and_index(self, block_number, index)
+                .await?
+                .map(types::Tx::try_from)
+                .transpose()?,
+        )
     }
 
     async fn get_transaction_count(
@@ -412,21 +1749,7 @@ where
         address: Address,
         block_number: types::BlockNumber,
     ) -> RpcResult<U64> {
-        let db = self.db.clone();
-
-        tokio::task::spawn_blocking(move || {
-            let txn = db.begin()?;
-            Ok(state::account::read(
-                &txn,
-                address,
-                Some(helpers::resolve_block_number(&txn, block_number)?),
-            )?
-            .map(|account| account.nonce)
-            .unwrap_or(0)
-            .into())
-        })
-        .await
-        .unwrap_or_else(helpers::joinerror_to_result)
+        EthStateApiServer::get_transaction_count(self, address, block_number.into()).await
     }
 
     async fn get_transaction_receipt(
@@ -448,35 +1771,38 @@ where
                     .ok_or_else(|| {
                         format_err!("body not found for block #{block_number}/{block_hash}")
                     })?;
-                let chain_spec = chain::chain_config::read(&txn)?
-                    .ok_or_else(|| format_err!("chain specification not found"))?;
-
-                // Prepare the execution context.
-                let mut buffer = Buffer::new(&txn, Some(BlockNumber(block_number.0 - 1)));
-
-                let block_execution_spec = chain_spec.collect_block_spec(block_number);
-                let mut engine = engine_factory(None, chain_spec)?;
-                let mut analysis_cache = AnalysisCache::default();
-                let mut tracer = NoopTracer;
-
-                let mut processor = ExecutionProcessor::new(
-                    &mut buffer,
-                    &mut tracer,
-                    &mut analysis_cache,
-                    &mut *engine,
-                    &header,
-                    &block_body,
-                    &block_execution_spec,
-                );
+                let transaction_index = block_body
+                    .transactions
+                    .iter()
+                    .position(|tx| tx.hash() == hash)
+                    .ok_or_else(|| format_err!("transaction {hash} not found in block #{block_number}/{block_hash} despite lookup index"))?;
 
-                let transaction_index = chain::block_body::read_without_senders(&txn, block_hash, block_number)?.ok_or_else(|| format_err!("where's block body"))?.transactions
-                    .into_iter()
-                    .enumerate()
-                    .find(|(_, tx)| tx.hash() == hash)
-                    .ok_or_else(|| format_err!("transaction {hash} not found in block #{block_number}/{block_hash} despite lookup index"))?.0;
+                let receipts = if let Some(receipts) = txn.get(tables::Receipt, block_number)? {
+                    receipts
+                } else {
+                    let chain_spec = chain::chain_config::read(&txn)?
+                        .ok_or_else(|| format_err!("chain specification not found"))?;
+
+                    // Prepare the execution context.
+                    let mut buffer = Buffer::new(&txn, Some(BlockNumber(block_number.0 - 1)));
+
+                    let block_execution_spec = chain_spec.collect_block_spec(block_number, header.timestamp);
+                    let mut engine = engine_factory(None, chain_spec)?;
+                    let mut analysis_cache = AnalysisCache::global();
+                    let mut tracer = NoopTracer;
+
+                    let mut processor = ExecutionProcessor::new(
+                        &mut buffer,
+                        &mut tracer,
+                        &mut analysis_cache,
+                        &mut *engine,
+                        &header,
+                        &block_body,
+                        &block_execution_spec,
+                    );
 
-                let receipts =
-                    processor.execute_block_no_post_validation_while(|i, _| i <= transaction_index)?;
+                    processor.execute_block_no_post_validation_while(|i, _| i <= transaction_index)?
+                };
 
                 let transaction = &block_body.transactions[transaction_index];
                 let receipt = receipts.get(transaction_index).unwrap();
@@ -488,12 +1814,16 @@ where
                             .map(|receipt| receipt.cumulative_gas_used)
                             .unwrap_or(0),
                 );
+                let first_log_index = receipts[..transaction_index]
+                    .iter()
+                    .map(|receipt| receipt.logs.len())
+                    .sum::<usize>();
                 let logs = receipt
                     .logs
                     .iter()
                     .enumerate()
                     .map(|(i, log)| types::TransactionLog {
-                        log_index: Some(U64::from(i)),
+                        log_index: Some(U64::from(first_log_index + i)),
                         transaction_index: Some(U64::from(transaction_index)),
                         transaction_hash: Some(transaction.hash()),
                         block_hash: Some(block_hash),
@@ -546,12 +1876,11 @@ where
 
         tokio::task::spawn_blocking(move || {
             let txn = db.begin()?;
-            Ok(helpers::construct_block(
-                &txn,
-                block_hash,
-                false,
-                Some(index),
-            )?)
+            Ok(
+                helpers::construct_block(&txn, block_hash, false, Some(index))?
+                    .map(types::Block::try_from)
+                    .transpose()?,
+            )
         })
         .await
         .unwrap_or_else(helpers::joinerror_to_result)
@@ -566,12 +1895,11 @@ where
 
         tokio::task::spawn_blocking(move || {
             let txn = db.begin()?;
-            Ok(helpers::construct_block(
-                &txn,
-                block_number,
-                false,
-                Some(index),
-            )?)
+            Ok(
+                helpers::construct_block(&txn, block_number, false, Some(index))?
+                    .map(types::Block::try_from)
+                    .transpose()?,
+            )
         })
         .await
         .unwrap_or_else(helpers::joinerror_to_result)
@@ -614,6 +1942,23 @@ where
         .unwrap_or_else(helpers::joinerror_to_result)
     }
 
+    async fn send_raw_transaction(&self, tx: types::Bytes) -> RpcResult<H256> {
+        let txpool = self
+            .txpool
+            .clone()
+            .ok_or_else(|| format_err!("transaction pool is not available"))?;
+        let transaction = MessageWithSignature::decode_envelope(&tx)
+            .map_err(|e| format_err!("failed to decode transaction: {e}"))?;
+
+        tokio::task::spawn_blocking(move || {
+            Ok(txpool
+                .add_transaction(transaction)
+                .map_err(|e| format_err!("{e}"))?)
+        })
+        .await
+        .unwrap_or_else(helpers::joinerror_to_result)
+    }
+
     async fn syncing(&self) -> RpcResult<SyncStatus> {
         let db = self.db.clone();
 
@@ -639,3 +1984,621 @@ where
         .unwrap_or_else(helpers::joinerror_to_result)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{rpc::test_chain::*, txpool::PoolConfig, InMemoryState};
+    use hex_literal::hex;
+    use serde_json::{json, Value};
+
+    fn slot(n: u64) -> U256 {
+        U256::from(n)
+    }
+
+    fn state_with_accounts(addresses: &[Address]) -> InMemoryState {
+        let mut state = InMemoryState::new();
+        for &address in addresses {
+            state.update_account(
+                address,
+                None,
+                Some(Account {
+                    nonce: 1,
+                    balance: U256::ONE,
+                    ..Default::default()
+                }),
+            );
+            for n in 1..=2 {
+                state
+                    .update_storage(address, slot(n), U256::ZERO, slot(n))
+                    .unwrap();
+            }
+        }
+        state
+    }
+
+    #[test]
+    fn state_override() {
+        let a = Address::from_low_u64_be(0xa);
+        let b = Address::from_low_u64_be(0xb);
+        let c = Address::from_low_u64_be(0xc);
+        let mut state = state_with_accounts(&[a, b]);
+
+        let state_override: StateOverride = serde_json::from_value(json!({
+            format!("{a:?}"): {
+                "balance": "0x10",
+                "code": "0x6000",
+                "state": { format!("{:?}", H256::from_low_u64_be(1)): format!("{:?}", H256::from_low_u64_be(5)) },
+            },
+            format!("{b:?}"): {
+                "nonce": "0x7",
+                "stateDiff": { format!("{:?}", H256::from_low_u64_be(2)): format!("{:?}", H256::from_low_u64_be(7)) },
+            },
+            format!("{c:?}"): {
+                "balance": "0x1",
+            },
+        }))
+        .unwrap();
+        apply_state_override(&mut state, state_override).unwrap();
+
+        // `state` replaces the whole storage.
+        let account = state.read_account(a).unwrap().unwrap();
+        assert_eq!(account.balance, U256::from(0x10_u64));
+        assert_eq!(account.nonce, 1);
+        assert_eq!(account.code_hash, keccak256([0x60, 0x00]));
+        assert_eq!(
+            state.read_code(account.code_hash).unwrap(),
+            Bytes::from_static(&[0x60, 0x00])
+        );
+        assert_eq!(state.read_storage(a, slot(1)).unwrap(), slot(5));
+        assert_eq!(state.read_storage(a, slot(2)).unwrap(), U256::ZERO);
+
+        // `stateDiff` only replaces given slots.
+        let account = state.read_account(b).unwrap().unwrap();
+        assert_eq!(account.balance, U256::ONE);
+        assert_eq!(account.nonce, 7);
+        assert_eq!(account.code_hash, EMPTY_HASH);
+        assert_eq!(state.read_storage(b, slot(1)).unwrap(), slot(1));
+        assert_eq!(state.read_storage(b, slot(2)).unwrap(), slot(7));
+
+        // Missing accounts are created.
+        let account = state.read_account(c).unwrap().unwrap();
+        assert_eq!(account.balance, U256::ONE);
+        assert_eq!(account.nonce, 0);
+        assert_eq!(account.code_hash, EMPTY_HASH);
+    }
+
+    #[test]
+    fn state_override_with_state_and_state_diff() {
+        let a = Address::from_low_u64_be(0xa);
+        let mut state = state_with_accounts(&[a]);
+
+        let state_override: StateOverride = serde_json::from_value(json!({
+            format!("{a:?}"): {
+                "state": {},
+                "stateDiff": {},
+            },
+        }))
+        .unwrap();
+        assert!(apply_state_override(&mut state, state_override).is_err());
+    }
+
+    #[test]
+    fn block_rewards_at_percentiles() {
+        let percentiles = [0.0, 30.0, 31.0, 90.0, 91.0, 100.0];
+
+        assert_eq!(
+            block_rewards(vec![], &percentiles),
+            vec![U256::ZERO; percentiles.len()]
+        );
+
+        // Tips are weighed by gas used, sorted by tip: 1 for 30%, 2 for 60% and 3 for 10% of gas.
+        let tips = vec![
+            (U256::from(3_u64), 10),
+            (U256::from(1_u64), 30),
+            (U256::from(2_u64), 60),
+        ];
+        assert_eq!(
+            block_rewards(tips, &percentiles),
+            [1_u64, 1, 2, 2, 3, 3].map(U256::from).to_vec()
+        );
+    }
+
+    #[test]
+    fn block_overrides() {
+        let mut header = BlockHeader {
+            number: BlockNumber(1),
+            timestamp: 1,
+            base_fee_per_gas: Some(U256::ONE),
+            ..Default::default()
+        };
+
+        BlockOverrides::default().apply(&mut header);
+        assert_eq!(header.number, BlockNumber(1));
+
+        let coinbase = Address::from_low_u64_be(0xc);
+        let prev_randao = H256::repeat_byte(0xab);
+        let overrides: BlockOverrides = serde_json::from_value(json!({
+            "number": "0x2",
+            "timestamp": "0x3",
+            "baseFeePerGas": "0x4",
+            "feeRecipient": format!("{coinbase:?}"),
+            "gasLimit": "0x5",
+            "difficulty": "0x6",
+            "random": format!("{prev_randao:?}"),
+            "blobBaseFee": "0x7",
+            // Unsupported fields are ignored.
+            "withdrawals": [],
+        }))
+        .unwrap();
+        assert_eq!(overrides.blob_base_fee, Some(U256::from(7_u64)));
+        overrides.apply(&mut header);
+        assert_eq!(header.number, BlockNumber(2));
+        assert_eq!(header.timestamp, 3);
+        assert_eq!(header.base_fee_per_gas, Some(U256::from(4_u64)));
+        assert_eq!(header.beneficiary, coinbase);
+        assert_eq!(header.gas_limit, 5);
+        assert_eq!(header.difficulty, U256::from(6_u64));
+        assert_eq!(header.mix_hash, prev_randao);
+
+        let overrides: BlockOverrides = serde_json::from_value(json!({
+            "prevRandao": format!("{:?}", H256::repeat_byte(0xcd)),
+        }))
+        .unwrap();
+        overrides.apply(&mut header);
+        assert_eq!(header.mix_hash, H256::repeat_byte(0xcd));
+    }
+
+    const SECP256K1N: [u8; 32] =
+        hex!("fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141");
+
+    fn eth_api(chain: &TestChain) -> EthApiServerImpl<WriteMap> {
+        EthApiServerImpl {
+            db: chain.db.clone(),
+            call_gas_limit: 100_000_000,
+            max_logs_block_range: DEFAULT_MAX_LOGS_BLOCK_RANGE,
+            txpool: Some(Arc::new(
+                TransactionPool::new(
+                    chain.db.clone(),
+                    chain.chain_spec.clone(),
+                    PoolConfig::default(),
+                )
+                .unwrap(),
+            )),
+            filters: Default::default(),
+        }
+    }
+
+    fn transfer(nonce: u64, value: U256) -> Message {
+        Message::EIP1559 {
+            chain_id: ChainId(1),
+            nonce,
+            max_priority_fee_per_gas: U256::ONE,
+            max_fee_per_gas: (2 * BASE_FEE_PER_GAS).as_u256(),
+            gas_limit: 21_000,
+            action: TransactionAction::Call(Address::from_low_u64_be(0xa)),
+            value,
+            input: Bytes::new(),
+            access_list: vec![],
+        }
+    }
+
+    async fn send(
+        api: &EthApiServerImpl<WriteMap>,
+        transaction: &MessageWithSignature,
+    ) -> RpcResult<H256> {
+        EthApiServer::send_raw_transaction(api, transaction.encode_envelope().into()).await
+    }
+
+    async fn call(
+        api: &EthApiServerImpl<WriteMap>,
+        to: Address,
+        data: &[u8],
+        state_override: Value,
+        block_overrides: Value,
+    ) -> Value {
+        json!(EthCallApiServer::call(
+            api,
+            types::MessageCall::Legacy {
+                from: None,
+                to: Some(to),
+                gas: None,
+                gas_price: None,
+                value: None,
+                data: Some(Bytes::copy_from_slice(data).into()),
+            },
+            None,
+            serde_json::from_value(state_override).unwrap(),
+            serde_json::from_value(block_overrides).unwrap(),
+        )
+        .await
+        .unwrap())
+    }
+
+    #[tokio::test]
+    async fn call_with_overrides() {
+        let storage = Address::from_low_u64_be(0x5707);
+        let block_info = Address::from_low_u64_be(0xb10c);
+        let chain = TestChain::new([]);
+        // Returns storage slot given by the first word of calldata.
+        // 0      PUSH1  => 00
+        // 2      CALLDATALOAD
+        // 3      SLOAD
+        // 4      PUSH1  => 00
+        // 6      MSTORE
+        // 7      PUSH1  => 20
+        // 9      PUSH1  => 00
+        // 11     RETURN
+        chain.set_account(
+            storage,
+            Account::default(),
+            Bytes::from_static(&hex!("6000355460005260206000f3")),
+            &[(slot(1), slot(10)), (slot(2), slot(20))],
+        );
+        // Returns gas limit and difficulty of the block.
+        // 0      GASLIMIT
+        // 1      PUSH1  => 00
+        // 3      MSTORE
+        // 4      DIFFICULTY
+        // 5      PUSH1  => 20
+        // 7      MSTORE
+        // 8      PUSH1  => 40
+        // 10     PUSH1  => 00
+        // 12     RETURN
+        chain.set_account(
+            block_info,
+            Account::default(),
+            Bytes::from_static(&hex!("456000524460205260406000f3")),
+            &[],
+        );
+        let api = eth_api(&chain);
+        let word = |n: u64| H256::from_low_u64_be(n);
+
+        for (n, expected) in [(1, 10), (2, 20)] {
+            assert_eq!(
+                call(&api, storage, word(n).as_bytes(), Value::Null, Value::Null).await,
+                json!(word(expected))
+            );
+        }
+
+        // `state` replaces the whole storage, erased slots must not be read from the database.
+        let state_override = json!({
+            format!("{storage:?}"): {
+                "state": { format!("{:?}", word(3)): format!("{:?}", word(30)) },
+            },
+        });
+        for (n, expected) in [(1, 0), (2, 0), (3, 30)] {
+            assert_eq!(
+                call(
+                    &api,
+                    storage,
+                    word(n).as_bytes(),
+                    state_override.clone(),
+                    Value::Null
+                )
+                .await,
+                json!(word(expected))
+            );
+        }
+
+        // `stateDiff` replaces given slots only.
+        let state_override = json!({
+            format!("{storage:?}"): {
+                "stateDiff": { format!("{:?}", word(2)): format!("{:?}", word(22)) },
+            },
+        });
+        for (n, expected) in [(1, 10), (2, 22), (3, 0)] {
+            assert_eq!(
+                call(
+                    &api,
+                    storage,
+                    word(n).as_bytes(),
+                    state_override.clone(),
+                    Value::Null
+                )
+                .await,
+                json!(word(expected))
+            );
+        }
+
+        assert_eq!(
+            call(
+                &api,
+                block_info,
+                &[],
+                Value::Null,
+                json!({ "gasLimit": "0x1000000", "difficulty": "0x6" }),
+            )
+            .await,
+            json!(format!("0x{:064x}{:064x}", 0x1000000, 6))
+        );
+    }
+
+    #[tokio::test]
+    async fn send_raw_transaction_rejects_invalid() {
+        let key = secret_key(1);
+        let sender = address_of(&key);
+        let chain = TestChain::new([]);
+        chain.set_account(
+            sender,
+            Account {
+                nonce: 1,
+                balance: ETHER.as_u256(),
+                ..Default::default()
+            },
+            Bytes::new(),
+            &[],
+        );
+        let api = eth_api(&chain);
+        let error = |res: RpcResult<H256>| res.unwrap_err().to_string();
+
+        assert!(error(
+            EthApiServer::send_raw_transaction(&api, Bytes::from_static(&[0x02, 0xc0]).into())
+                .await
+        )
+        .contains("failed to decode transaction"));
+
+        // Same signature with `s` from the upper half of the curve order, forbidden since Homestead.
+        let mut transaction = sign(transfer(1, U256::ONE), &key);
+        let s = U256::from_be_bytes(SECP256K1N) - U256::from_be_bytes(transaction.s().0);
+        transaction.signature = MessageSignature::new(
+            !transaction.signature.odd_y_parity(),
+            transaction.r(),
+            H256(s.to_be_bytes()),
+        )
+        .unwrap();
+        assert!(error(send(&api, &transaction).await).contains("invalid sender"));
+
+        assert!(error(send(&api, &sign(transfer(0, U256::ONE), &key)).await)
+            .contains("nonce too low: expected 1, got 0"));
+        assert!(
+            error(send(&api, &sign(transfer(1, ETHER.as_u256()), &key)).await)
+                .contains("insufficient funds")
+        );
+
+        assert!(api.txpool.as_ref().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn send_raw_transaction_adds_to_pool() {
+        let key = secret_key(1);
+        let chain = TestChain::new([(address_of(&key), ETHER.as_u256())]);
+        let api = eth_api(&chain);
+
+        let transaction = sign(transfer(0, U256::ONE), &key);
+        assert_eq!(send(&api, &transaction).await.unwrap(), transaction.hash());
+        assert_eq!(api.txpool.as_ref().unwrap().len(), 1);
+
+        // Future nonces are queued too.
+        let queued = sign(transfer(2, U256::ONE), &key);
+        assert_eq!(send(&api, &queued).await.unwrap(), queued.hash());
+        assert_eq!(api.txpool.as_ref().unwrap().len(), 2);
+
+        assert!(send(&api, &transaction)
+            .await
+            .unwrap_err()
+            .to_string()
+            .contains("already known"));
+    }
+
+    fn hashes(changes: types::FilterChanges) -> Vec<H256> {
+        match changes {
+            types::FilterChanges::Hashes(hashes) => hashes,
+            _ => panic!("expected hashes"),
+        }
+    }
+
+    fn log_transactions(changes: types::FilterChanges) -> Vec<Value> {
+        match changes {
+            types::FilterChanges::Logs(logs) => logs
+                .iter()
+                .map(|log| json!(log)["transactionHash"].clone())
+                .collect(),
+            _ => panic!("expected logs"),
+        }
+    }
+
+    #[tokio::test]
+    async fn filter_changes_since_last_poll() {
+        let keys = [secret_key(1), secret_key(2)];
+        let chain = TestChain::new(keys.iter().map(|key| (address_of(key), ETHER.as_u256())));
+        let emitter = Address::from_low_u64_be(0xe);
+        // PUSH1 0x20 PUSH1 0x00 LOG0 STOP
+        chain.set_account(
+            emitter,
+            Account::default(),
+            Bytes::from_static(&[0x60, 0x20, 0x60, 0x00, 0xa0, 0x00]),
+            &[],
+        );
+        let api = eth_api(&chain);
+
+        let log_filter = api
+            .new_filter(serde_json::from_value(json!({})).unwrap())
+            .await
+            .unwrap();
+        let block_filter = api.new_block_filter().await.unwrap();
+        let pending_filter = api.new_pending_transaction_filter().await.unwrap();
+        assert_ne!(log_filter, block_filter);
+        assert_ne!(block_filter, pending_filter);
+
+        let changes = |id| api.get_filter_changes(id);
+        assert!(log_transactions(changes(log_filter).await.unwrap()).is_empty());
+        assert!(hashes(changes(block_filter).await.unwrap()).is_empty());
+        assert!(hashes(changes(pending_filter).await.unwrap()).is_empty());
+
+        // Blocks are not executed, so each one is sent from an account untouched before.
+        for key in &keys {
+            let mut message = transfer(0, U256::ZERO);
+            if let Message::EIP1559 {
+                action, gas_limit, ..
+            } = &mut message
+            {
+                *action = TransactionAction::Call(emitter);
+                *gas_limit = 100_000;
+            }
+            let transaction = sign(message, key);
+
+            send(&api, &transaction).await.unwrap();
+            assert_eq!(
+                hashes(changes(pending_filter).await.unwrap()),
+                vec![transaction.hash()]
+            );
+
+            let header = chain.push_block(vec![transaction.clone()]);
+            assert_eq!(
+                log_transactions(changes(log_filter).await.unwrap()),
+                vec![json!(transaction.hash())]
+            );
+            assert_eq!(
+                hashes(changes(block_filter).await.unwrap()),
+                vec![header.hash()]
+            );
+
+            // Second poll only returns what appeared since the first one.
+            assert!(log_transactions(changes(log_filter).await.unwrap()).is_empty());
+            assert!(hashes(changes(block_filter).await.unwrap()).is_empty());
+            assert!(hashes(changes(pending_filter).await.unwrap()).is_empty());
+        }
+    }
+
+    #[tokio::test]
+    async fn filters_uninstalled_or_expired() {
+        let chain = TestChain::new([]);
+        let api = eth_api(&chain);
+        let error = |res: RpcResult<types::FilterChanges>| res.unwrap_err().to_string();
+
+        assert!(
+            error(api.get_filter_changes(U64::from(100_u64)).await).contains("filter not found")
+        );
+        assert!(!api.uninstall_filter(U64::from(100_u64)).await.unwrap());
+
+        let uninstalled = api.new_block_filter().await.unwrap();
+        assert!(api.uninstall_filter(uninstalled).await.unwrap());
+        assert!(!api.uninstall_filter(uninstalled).await.unwrap());
+        assert!(error(api.get_filter_changes(uninstalled).await).contains("filter not found"));
+
+        let expired = api.new_block_filter().await.unwrap();
+        let polled = api.new_block_filter().await.unwrap();
+        api.filters
+            .filters
+            .lock()
+            .get_mut(&expired.as_u64())
+            .unwrap()
+            .last_poll = Instant::now().checked_sub(FILTER_TIMEOUT).unwrap();
+
+        api.get_filter_changes(polled).await.unwrap();
+        assert!(error(api.get_filter_changes(expired).await).contains("filter not found"));
+        assert!(!api.uninstall_filter(expired).await.unwrap());
+    }
+
+    fn field<T: serde::de::DeserializeOwned>(value: &Value, name: &str) -> T {
+        serde_json::from_value(value[name].clone()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn blob_transaction_round_trip() {
+        let key = secret_key(1);
+        let transaction = sign(
+            Message::EIP4844 {
+                chain_id: ChainId(1),
+                nonce: 7,
+                max_priority_fee_per_gas: 2_u64.as_u256(),
+                max_fee_per_gas: 3_u64.as_u256(),
+                gas_limit: 21_000,
+                to: Address::from_low_u64_be(0xa),
+                value: 5_u64.as_u256(),
+                input: Bytes::from_static(&[1, 2, 3]),
+                access_list: vec![AccessListItem {
+                    address: Address::from_low_u64_be(0xb),
+                    slots: vec![H256::from_low_u64_be(1)],
+                }],
+                max_fee_per_blob_gas: 4_u64.as_u256(),
+                blob_versioned_hashes: vec![H256::repeat_byte(1), H256::repeat_byte(2)],
+            },
+            &key,
+        );
+        // Blob transactions can't be executed before Cancun, which the test chain doesn't activate.
+        let chain = TestChain::new([]);
+        let header = chain.push_unexecuted_block(vec![transaction.clone()]);
+        let api = eth_api(&chain);
+
+        let tx = json!(
+            EthBlockApiServer::get_transaction_by_hash(&api, transaction.hash())
+                .await
+                .unwrap()
+                .unwrap()
+        );
+        assert_eq!(
+            json!(EthBlockApiServer::get_transaction_by_block_hash_and_index(
+                &api,
+                header.hash(),
+                U64::zero()
+            )
+            .await
+            .unwrap()
+            .unwrap()),
+            tx
+        );
+        let block = json!(EthBlockApiServer::get_block_by_number(
+            &api,
+            types::BlockNumber::Number(1.into()),
+            true
+        )
+        .await
+        .unwrap()
+        .unwrap());
+        assert_eq!(block["hash"], json!(header.hash()));
+        assert_eq!(block["transactions"], json!([tx]));
+        let block = json!(
+            EthBlockApiServer::get_block_by_hash(&api, header.hash(), false)
+                .await
+                .unwrap()
+                .unwrap()
+        );
+        assert_eq!(block["transactions"], json!([transaction.hash()]));
+
+        assert_eq!(tx["type"], json!("0x3"));
+        assert_eq!(tx["hash"], json!(transaction.hash()));
+        assert_eq!(tx["from"], json!(address_of(&key)));
+        assert_eq!(tx["transactionIndex"], json!("0x0"));
+
+        // Transaction rebuilt from its JSON representation is the one that was signed.
+        let rebuilt = MessageWithSignature {
+            message: Message::EIP4844 {
+                chain_id: ChainId(field::<U64>(&tx, "chainId").as_u64()),
+                nonce: field::<U64>(&tx, "nonce").as_u64(),
+                max_priority_fee_per_gas: field(&tx, "maxPriorityFeePerGas"),
+                max_fee_per_gas: field(&tx, "maxFeePerGas"),
+                gas_limit: field::<U64>(&tx, "gas").as_u64(),
+                to: field(&tx, "to"),
+                value: field(&tx, "value"),
+                input: field::<types::Bytes>(&tx, "input").into(),
+                access_list: field::<Vec<types::AccessListEntry>>(&tx, "accessList")
+                    .into_iter()
+                    .map(From::from)
+                    .collect(),
+                max_fee_per_blob_gas: field(&tx, "maxFeePerBlobGas"),
+                blob_versioned_hashes: field(&tx, "blobVersionedHashes"),
+            },
+            signature: MessageSignature::new(
+                field::<U64>(&tx, "yParity") == U64::one(),
+                field(&tx, "r"),
+                field(&tx, "s"),
+            )
+            .unwrap(),
+        };
+        assert_eq!(tx["v"], tx["yParity"]);
+        assert_eq!(rebuilt, transaction);
+        assert_eq!(rebuilt.recover_sender().unwrap(), address_of(&key));
+
+        // Methods which can't represent blob transactions refuse them instead of serving them
+        // as another type.
+        assert!(
+            EthApiServer::get_transaction_by_hash(&api, transaction.hash())
+                .await
+                .unwrap_err()
+                .to_string()
+                .contains("cannot be represented")
+        );
+    }
+}
@@ -1,12 +1,17 @@
+pub mod debug;
 pub mod erigon;
 pub mod eth;
 pub mod net;
 pub mod otterscan;
+pub mod pubsub;
+#[cfg(test)]
+mod test_chain;
 pub mod trace;
 pub mod web3;
 pub mod helpers {
     use crate::{
         accessors::chain,
+        bitmapdb,
         consensus::{engine_factory, DuoError},
         execution::{
             analysis_cache::AnalysisCache, processor::ExecutionProcessor, tracer::NoopTracer,
@@ -16,10 +21,14 @@ pub mod helpers {
         stagedsync::stages,
         Buffer, StateReader,
     };
-    use anyhow::format_err;
-    use ethereum_jsonrpc::types;
+    use anyhow::{bail, format_err};
+    use croaring::Treemap;
+    use ethereum_jsonrpc::{types, LogFilter};
     use ethereum_types::U64;
+    use itertools::Either;
     use jsonrpsee::core::Error as RpcError;
+    use serde::{ser::Error as _, Deserialize, Serialize, Serializer};
+    use std::ops::RangeInclusive;
     use tokio::task::JoinError;
 
     impl From<DuoError> for RpcError {
@@ -49,92 +58,232 @@ pub mod helpers {
         Err(RpcError::Custom(format!("{e}")))
     }
 
+    /// Transaction as served over JSON-RPC.
+    ///
+    /// JSON-RPC types have no blob fields, so blob transactions get their own representation
+    /// rather than being served as another type, which would give them a wrong hash and signing payload.
+    #[derive(Clone, Debug, Serialize)]
+    #[serde(untagged)]
+    pub enum Transaction {
+        Regular(types::Transaction),
+        Blob(BlobTransaction),
+    }
+
+    #[derive(Clone, Debug, Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct BlobTransaction {
+        #[serde(rename = "type")]
+        pub transaction_type: U64,
+        pub chain_id: U64,
+        pub nonce: U64,
+        pub to: Address,
+        pub gas: U64,
+        pub max_priority_fee_per_gas: U256,
+        pub max_fee_per_gas: U256,
+        pub max_fee_per_blob_gas: U256,
+        pub value: U256,
+        pub input: types::Bytes,
+        pub access_list: Vec<types::AccessListEntry>,
+        pub blob_versioned_hashes: Vec<H256>,
+        pub v: U64,
+        pub y_parity: U64,
+        pub r: H256,
+        pub s: H256,
+        pub from: Address,
+        pub hash: H256,
+        pub transaction_index: Option<U64>,
+    }
+
+    impl TryFrom<Transaction> for types::Transaction {
+        type Error = anyhow::Error;
+
+        fn try_from(tx: Transaction) -> anyhow::Result<Self> {
+            match tx {
+                Transaction::Regular(tx) => Ok(tx),
+                Transaction::Blob(tx) => bail!(
+                    "blob transaction {} cannot be represented by this method",
+                    tx.hash
+                ),
+            }
+        }
+    }
+
+    #[derive(Clone, Debug, Serialize)]
+    #[serde(untagged)]
+    pub enum Tx {
+        Hash(H256),
+        Transaction(Box<Transaction>),
+    }
+
+    impl TryFrom<Tx> for types::Tx {
+        type Error = anyhow::Error;
+
+        fn try_from(tx: Tx) -> anyhow::Result<Self> {
+            Ok(match tx {
+                Tx::Hash(hash) => types::Tx::Hash(hash),
+                Tx::Transaction(tx) => types::Tx::Transaction(Box::new((*tx).try_into()?)),
+            })
+        }
+    }
+
+    /// Block as served over JSON-RPC, with transactions which may include blob transactions.
+    #[derive(Clone, Debug)]
+    pub struct Block {
+        /// Block fields, with empty transactions.
+        pub inner: types::Block,
+        pub transactions: Vec<Tx>,
+    }
+
+    impl Serialize for Block {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let mut block = serde_json::to_value(&self.inner).map_err(S::Error::custom)?;
+            block["transactions"] =
+                serde_json::to_value(&self.transactions).map_err(S::Error::custom)?;
+            block.serialize(serializer)
+        }
+    }
+
+    impl TryFrom<Block> for types::Block {
+        type Error = anyhow::Error;
+
+        fn try_from(
+            Block {
+                inner,
+                transactions,
+            }: Block,
+        ) -> anyhow::Result<Self> {
+            Ok(types::Block {
+                transactions: transactions
+                    .into_iter()
+                    .map(TryFrom::try_from)
+                    .collect::<anyhow::Result<_>>()?,
+                ..inner
+            })
+        }
+    }
+
     pub fn new_jsonrpc_tx(
         tx: MessageWithSignature,
         sender: Address,
         transaction_index: Option<u64>,
-    ) -> types::Transaction {
+    ) -> Transaction {
         let hash = tx.hash();
-        types::Transaction {
-            v: tx.v().into(),
-            r: tx.r(),
-            s: tx.s(),
-            message: match tx.message {
-                Message::Legacy {
-                    chain_id,
-                    nonce,
-                    gas_price,
-                    gas_limit,
-                    action,
-                    value,
-                    input,
-                } => types::TransactionMessage::Legacy {
-                    chain_id: chain_id.map(|v| v.0.into()),
-                    nonce: nonce.into(),
-                    to: action.into_address(),
-                    gas: gas_limit.into(),
-                    gas_price,
-                    value,
-                    input: input.into(),
-                },
-                Message::EIP2930 {
-                    chain_id,
-                    nonce,
-                    gas_price,
-                    gas_limit,
-                    action,
-                    value,
-                    input,
-                    access_list,
-                } => types::TransactionMessage::EIP2930 {
-                    chain_id: chain_id.0.into(),
-                    nonce: nonce.into(),
-                    to: action.into_address(),
-                    gas: gas_limit.into(),
-                    gas_price,
-                    value,
-                    input: input.into(),
-                    access_list: access_list
-                        .into_iter()
-                        .map(|item| types::AccessListEntry {
-                            address: item.address,
-                            storage_keys: item.slots,
-                        })
-                        .collect(),
-                },
-                Message::EIP1559 {
-                    chain_id,
-                    nonce,
-                    max_priority_fee_per_gas,
-                    max_fee_per_gas,
-                    gas_limit,
-                    action,
-                    value,
-                    input,
-                    access_list,
-                } => types::TransactionMessage::EIP1559 {
+        let (v, odd_y_parity, r, s) = (tx.v(), tx.signature.odd_y_parity(), tx.r(), tx.s());
+        let access_list = |access_list: Vec<AccessListItem>| {
+            access_list
+                .into_iter()
+                .map(|item| types::AccessListEntry {
+                    address: item.address,
+                    storage_keys: item.slots,
+                })
+                .collect()
+        };
+        let message = match tx.message {
+            Message::Legacy {
+                chain_id,
+                nonce,
+                gas_price,
+                gas_limit,
+                action,
+                value,
+                input,
+            } => types::TransactionMessage::Legacy {
+                chain_id: chain_id.map(|v| v.0.into()),
+                nonce: nonce.into(),
+                to: action.into_address(),
+                gas: gas_limit.into(),
+                gas_price,
+                value,
+                input: input.into(),
+            },
+            Message::EIP2930 {
+                chain_id,
+                nonce,
+                gas_price,
+                gas_limit,
+                action,
+                value,
+                input,
+                access_list: list,
+            } => types::TransactionMessage::EIP2930 {
+                chain_id: chain_id.0.into(),
+                nonce: nonce.into(),
+                to: action.into_address(),
+                gas: gas_limit.into(),
+                gas_price,
+                value,
+                input: input.into(),
+                access_list: access_list(list),
+            },
+            Message::EIP1559 {
+                chain_id,
+                nonce,
+                max_priority_fee_per_gas,
+                max_fee_per_gas,
+                gas_limit,
+                action,
+                value,
+                input,
+                access_list: list,
+            } => types::TransactionMessage::EIP1559 {
+                chain_id: chain_id.0.into(),
+                nonce: nonce.into(),
+                to: action.into_address(),
+                gas: gas_limit.into(),
+                max_priority_fee_per_gas,
+                max_fee_per_gas,
+                value,
+                input: input.into(),
+                access_list: access_list(list),
+            },
+            Message::EIP4844 {
+                chain_id,
+                nonce,
+                max_priority_fee_per_gas,
+                max_fee_per_gas,
+                gas_limit,
+                to,
+                value,
+                input,
+                access_list: list,
+                max_fee_per_blob_gas,
+                blob_versioned_hashes,
+            } => {
+                // Typed transactions carry bare y parity as v.
+                let y_parity = U64::from(odd_y_parity as u64);
+                return Transaction::Blob(BlobTransaction {
+                    transaction_type: U64::from(3),
                     chain_id: chain_id.0.into(),
                     nonce: nonce.into(),
-                    to: action.into_address(),
+                    to,
                     gas: gas_limit.into(),
                     max_priority_fee_per_gas,
                     max_fee_per_gas,
+                    max_fee_per_blob_gas,
                     value,
                     input: input.into(),
-                    access_list: access_list
-                        .into_iter()
-                        .map(|item| types::AccessListEntry {
-                            address: item.address,
-                            storage_keys: item.slots,
-                        })
-                        .collect(),
-                },
-            },
+                    access_list: access_list(list),
+                    blob_versioned_hashes,
+                    v: y_parity,
+                    y_parity,
+                    r,
+                    s,
+                    from: sender,
+                    hash,
+                    transaction_index: transaction_index.map(From::from),
+                });
+            }
+        };
 
+        Transaction::Regular(types::Transaction {
+            v: v.into(),
+            r,
+            s,
+            message,
             from: sender,
             hash,
             transaction_index: transaction_index.map(From::from),
-        }
+        })
     }
 
     pub fn resolve_block_number<K: TransactionKind, E: EnvironmentKind>(
@@ -169,12 +318,98 @@ pub mod helpers {
         }
     }
 
+    /// Block to read state at: a tag, number or hash, as well as an
+    /// [EIP-1898](https://eips.ethereum.org/EIPS/eip-1898) object.
+    #[derive(Clone, Copy, Debug, Deserialize)]
+    #[serde(untagged)]
+    pub enum BlockParam {
+        Id(types::BlockId),
+        #[serde(rename_all = "camelCase")]
+        Number {
+            block_number: types::BlockNumber,
+        },
+        #[serde(rename_all = "camelCase")]
+        Hash {
+            block_hash: H256,
+            /// Ignored, canonical block is required either way since only canonical state is kept.
+            #[serde(default)]
+            require_canonical: bool,
+        },
+    }
+
+    impl From<types::BlockNumber> for BlockParam {
+        fn from(block_number: types::BlockNumber) -> Self {
+            Self::Id(types::BlockId::Number(block_number))
+        }
+    }
+
+    impl From<types::BlockId> for BlockParam {
+        fn from(block_id: types::BlockId) -> Self {
+            Self::Id(block_id)
+        }
+    }
+
+    /// Resolves the block to read state at, making sure its state can be read.
+    ///
+    /// Only state of canonical blocks is kept, from the latest synced block back to
+    /// the one history is pruned to. Hence blocks given by hash must be canonical even if
+    /// `requireCanonical` is false, and `earliest` stands for the oldest block with state.
+    pub fn resolve_state_block<K: TransactionKind, E: EnvironmentKind>(
+        txn: &MdbxTransaction<'_, K, E>,
+        block: impl Into<BlockParam>,
+    ) -> anyhow::Result<(BlockNumber, H256)> {
+        let (block_number, block_hash) = match block.into() {
+            BlockParam::Id(types::BlockId::Number(block_number))
+            | BlockParam::Number { block_number } => {
+                let block_number = match block_number {
+                    // Nothing is pruned until pruning progress is saved.
+                    types::BlockNumber::Earliest => txn
+                        .get(tables::PruneProgress, stages::FINISH)?
+                        .unwrap_or(BlockNumber(0)),
+                    block_number => resolve_block_number(txn, block_number)?,
+                };
+                let block_hash = chain::canonical_hash::read(txn, block_number)?
+                    .ok_or_else(|| format_err!("block #{block_number} not found"))?;
+
+                (block_number, block_hash)
+            }
+            BlockParam::Id(types::BlockId::Hash(block_hash))
+            | BlockParam::Hash { block_hash, .. } => {
+                let block_number = chain::header_number::read(txn, block_hash)?
+                    .ok_or_else(|| format_err!("block {block_hash} not found"))?;
+                // State of non-canonical blocks is not available whether it is required or not.
+                if chain::canonical_hash::read(txn, block_number)? != Some(block_hash) {
+                    return Err(format_err!("block {block_hash} is not canonical"));
+                }
+
+                (block_number, block_hash)
+            }
+        };
+
+        let latest_block = resolve_block_number(txn, types::BlockNumber::Latest)?;
+        if block_number > latest_block {
+            return Err(format_err!(
+                "block #{block_number} is not synced yet, latest block is #{latest_block}"
+            ));
+        }
+
+        if let Some(pruned_to) = txn.get(tables::PruneProgress, stages::FINISH)? {
+            if block_number < pruned_to {
+                return Err(format_err!(
+                    "state at block #{block_number} is pruned, earliest available block is #{pruned_to}"
+                ));
+            }
+        }
+
+        Ok((block_number, block_hash))
+    }
+
     pub fn construct_block<K: TransactionKind, E: EnvironmentKind>(
         txn: &MdbxTransaction<'_, K, E>,
         block_id: impl Into<types::BlockId>,
         include_txs: bool,
         uncle_index: Option<U64>,
-    ) -> anyhow::Result<Option<types::Block>> {
+    ) -> anyhow::Result<Option<Block>> {
         if let Some((block_number, block_hash)) = resolve_block_id(txn, block_id)? {
             if let Some((block_number, block_hash, header)) = {
                 if let Some(n) = uncle_index {
@@ -193,14 +428,14 @@ pub mod helpers {
                 if let Some(body) =
                     chain::block_body::read_without_senders(txn, block_hash, block_number)?
                 {
-                    let transactions: Vec<types::Tx> = if include_txs {
+                    let transactions = if include_txs {
                         let senders = chain::tx_sender::read(txn, block_hash, block_number)?;
                         body.transactions
                             .into_iter()
                             .zip(senders)
                             .enumerate()
                             .map(|(index, (tx, sender))| {
-                                types::Tx::Transaction(Box::new(new_jsonrpc_tx(
+                                Tx::Transaction(Box::new(new_jsonrpc_tx(
                                     tx,
                                     sender,
                                     Some(index as u64),
@@ -210,34 +445,37 @@ pub mod helpers {
                     } else {
                         body.transactions
                             .into_iter()
-                            .map(|tx| types::Tx::Hash(tx.hash()))
+                            .map(|tx| Tx::Hash(tx.hash()))
                             .collect()
                     };
 
                     let td = chain::td::read(txn, block_hash, block_number)?;
 
-                    return Ok(Some(types::Block {
-                        number: Some(U64::from(block_number.0)),
-                        hash: Some(block_hash),
-                        parent_hash: header.parent_hash,
-                        sha3_uncles: header.ommers_hash,
-                        logs_bloom: Some(header.logs_bloom),
-                        transactions_root: header.transactions_root,
-                        state_root: header.state_root,
-                        receipts_root: header.receipts_root,
-                        miner: header.beneficiary,
-                        difficulty: header.difficulty,
-                        total_difficulty: td,
-                        seal_fields: None,
-                        nonce: Some(header.nonce),
-                        mix_hash: Some(header.mix_hash),
-                        extra_data: header.extra_data.into(),
-                        size: U64::zero(),
-                        gas_limit: U64::from(header.gas_limit),
-                        gas_used: U64::from(header.gas_used),
-                        timestamp: U64::from(header.timestamp),
+                    return Ok(Some(Block {
+                        inner: types::Block {
+                            number: Some(U64::from(block_number.0)),
+                            hash: Some(block_hash),
+                            parent_hash: header.parent_hash,
+                            sha3_uncles: header.ommers_hash,
+                            logs_bloom: Some(header.logs_bloom),
+                            transactions_root: header.transactions_root,
+                            state_root: header.state_root,
+                            receipts_root: header.receipts_root,
+                            miner: header.beneficiary,
+                            difficulty: header.difficulty,
+                            total_difficulty: td,
+                            seal_fields: None,
+                            nonce: Some(header.nonce),
+                            mix_hash: Some(header.mix_hash),
+                            extra_data: header.extra_data.into(),
+                            size: U64::zero(),
+                            gas_limit: U64::from(header.gas_limit),
+                            gas_used: U64::from(header.gas_used),
+                            timestamp: U64::from(header.timestamp),
+                            transactions: vec![],
+                            uncles: body.ommers.into_iter().map(|uncle| uncle.hash()).collect(),
+                        },
                         transactions,
-                        uncles: body.ommers.into_iter().map(|uncle| uncle.hash()).collect(),
                     }));
                 }
             }
@@ -246,117 +484,279 @@ pub mod helpers {
         Ok(None)
     }
 
-    pub fn get_receipts<K: TransactionKind, E: EnvironmentKind>(
+    /// Returns receipts persisted by the execution stage, re-executing the block when they were not stored.
+    pub fn read_receipts<K: TransactionKind, E: EnvironmentKind>(
         txn: &MdbxTransaction<'_, K, E>,
-        block_number: BlockNumber,
-    ) -> Result<Vec<types::TransactionReceipt>, DuoError> {
-        let block_hash = chain::canonical_hash::read(txn, block_number)?
-            .ok_or_else(|| format_err!("no canonical header for block #{block_number:?}"))?;
-        let header = chain::header::read(txn, block_hash, block_number)?.ok_or_else(|| {
-            format_err!("header not found for block #{block_number}/{block_hash}")
-        })?;
-        let block_body = chain::block_body::read_with_senders(txn, block_hash, block_number)?
-            .ok_or_else(|| format_err!("body not found for block #{block_number}/{block_hash}"))?;
+        header: &BlockHeader,
+        block_body: &BlockBodyWithSenders,
+    ) -> Result<Vec<Receipt>, DuoError> {
+        if let Some(receipts) = txn.get(tables::Receipt, header.number)? {
+            return Ok(receipts);
+        }
+
         let chain_spec = chain::chain_config::read(txn)?
             .ok_or_else(|| format_err!("chain specification not found"))?;
 
         // Prepare the execution context.
-        let mut buffer = Buffer::new(txn, Some(BlockNumber(block_number.0 - 1)));
+        let mut buffer = Buffer::new(txn, Some(BlockNumber(header.number.0 - 1)));
 
-        let block_execution_spec = chain_spec.collect_block_spec(block_number);
+        let block_execution_spec = chain_spec.collect_block_spec(header.number, header.timestamp);
         let mut engine = engine_factory(None, chain_spec)?;
-        let mut analysis_cache = AnalysisCache::default();
+        let mut analysis_cache = AnalysisCache::global();
         let mut tracer = NoopTracer;
 
-        let mut processor = ExecutionProcessor::new(
+        ExecutionProcessor::new(
             &mut buffer,
             &mut tracer,
             &mut analysis_cache,
             &mut *engine,
-            &header,
-            &block_body,
+            header,
+            block_body,
             &block_execution_spec,
-        );
+        )
+        .execute_block_no_post_validation()
+    }
 
-        processor
-            .execute_block_no_post_validation()
-            .map(|receipts| {
-                let mut last_cumul_gas_used = 0;
-                receipts
-                    .into_iter()
-                    .enumerate()
-                    .map(
-                        |(
-                            transaction_index,
-                            Receipt {
-                                success,
-                                cumulative_gas_used,
-                                bloom,
-                                logs,
-                                ..
-                            },
-                        )| {
-                            let transaction = &block_body.transactions[transaction_index];
-                            let transaction_hash = transaction.hash();
-                            let gas_used = (cumulative_gas_used - last_cumul_gas_used).into();
-                            last_cumul_gas_used = cumulative_gas_used;
-                            types::TransactionReceipt {
-                                transaction_hash,
-                                transaction_index: U64::from(transaction_index),
-                                block_hash,
-                                block_number: U64::from(block_number.0),
-                                from: transaction.sender,
-                                to: transaction.message.action().into_address(),
-                                cumulative_gas_used: cumulative_gas_used.into(),
-                                gas_used,
-                                contract_address: if let TransactionAction::Create =
-                                    transaction.message.action()
-                                {
-                                    Some(crate::execution::address::create_address(
-                                        transaction.sender,
-                                        transaction.message.nonce(),
-                                    ))
-                                } else {
-                                    None
-                                },
-                                logs: logs
-                                    .into_iter()
-                                    .enumerate()
-                                    .map(
-                                        |(
-                                            log_index,
-                                            Log {
-                                                address,
-                                                data,
-                                                topics,
-                                            },
-                                        )| {
-                                            types::TransactionLog {
-                                                log_index: Some(U64::from(log_index)),
-                                                transaction_index: Some(U64::from(
-                                                    transaction_index,
-                                                )),
-                                                transaction_hash: Some(transaction_hash),
-                                                block_hash: Some(block_hash),
-                                                block_number: Some(U64::from(block_number.0)),
-                                                address,
-                                                data: data.into(),
-                                                topics,
-                                            }
-                                        },
-                                    )
-                                    .collect::<Vec<_>>(),
-                                logs_bloom: bloom,
-                                status: if success {
-                                    U64::from(1_u16)
-                                } else {
-                                    U64::zero()
-                                },
-                            }
+    pub fn get_receipts<K: TransactionKind, E: EnvironmentKind>(
+        txn: &MdbxTransaction<'_, K, E>,
+        block_number: BlockNumber,
+    ) -> Result<Vec<types::TransactionReceipt>, DuoError> {
+        let block_hash = chain::canonical_hash::read(txn, block_number)?
+            .ok_or_else(|| format_err!("no canonical header for block #{block_number:?}"))?;
+        let header = chain::header::read(txn, block_hash, block_number)?.ok_or_else(|| {
+            format_err!("header not found for block #{block_number}/{block_hash}")
+        })?;
+        let block_body = chain::block_body::read_with_senders(txn, block_hash, block_number)?
+            .ok_or_else(|| format_err!("body not found for block #{block_number}/{block_hash}"))?;
+
+        read_receipts(txn, &header, &block_body).map(|receipts| {
+            let mut last_cumul_gas_used = 0;
+            let mut log_index = 0;
+            receipts
+                .into_iter()
+                .enumerate()
+                .map(
+                    |(
+                        transaction_index,
+                        Receipt {
+                            success,
+                            cumulative_gas_used,
+                            bloom,
+                            logs,
+                            ..
                         },
-                    )
-                    .collect()
+                    )| {
+                        let transaction = &block_body.transactions[transaction_index];
+                        let transaction_hash = transaction.hash();
+                        let gas_used = (cumulative_gas_used - last_cumul_gas_used).into();
+                        last_cumul_gas_used = cumulative_gas_used;
+                        types::TransactionReceipt {
+                            transaction_hash,
+                            transaction_index: U64::from(transaction_index),
+                            block_hash,
+                            block_number: U64::from(block_number.0),
+                            from: transaction.sender,
+                            to: transaction.message.action().into_address(),
+                            cumulative_gas_used: cumulative_gas_used.into(),
+                            gas_used,
+                            contract_address: if let TransactionAction::Create =
+                                transaction.message.action()
+                            {
+                                Some(crate::execution::address::create_address(
+                                    transaction.sender,
+                                    transaction.message.nonce(),
+                                ))
+                            } else {
+                                None
+                            },
+                            logs: logs
+                                .into_iter()
+                                .map(
+                                    |Log {
+                                         address,
+                                         data,
+                                         topics,
+                                     }| {
+                                        log_index += 1;
+                                        types::TransactionLog {
+                                            log_index: Some(U64::from(log_index - 1)),
+                                            transaction_index: Some(U64::from(transaction_index)),
+                                            transaction_hash: Some(transaction_hash),
+                                            block_hash: Some(block_hash),
+                                            block_number: Some(U64::from(block_number.0)),
+                                            address,
+                                            data: data.into(),
+                                            topics,
+                                        }
+                                    },
+                                )
+                                .collect::<Vec<_>>(),
+                            logs_bloom: bloom,
+                            status: if success {
+                                U64::from(1_u16)
+                            } else {
+                                U64::zero()
+                            },
+                        }
+                    },
+                )
+                .collect()
+        })
+    }
+
+    /// Address and topic criteria of a log filter.
+    ///
+    /// Every topic position holds a list of alternatives, an empty list matches any topic.
+    #[derive(Clone, Debug, Default, PartialEq, Eq)]
+    pub struct LogQuery {
+        pub addresses: Vec<Address>,
+        pub topics: Vec<Vec<H256>>,
+    }
+
+    impl From<&LogFilter> for LogQuery {
+        fn from(filter: &LogFilter) -> Self {
+            Self {
+                addresses: filter.address.clone().unwrap_or_default(),
+                topics: filter
+                    .topics
+                    .clone()
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(Option::unwrap_or_default)
+                    .collect(),
+            }
+        }
+    }
+
+    impl LogQuery {
+        pub fn matches(&self, address: Address, topics: &[H256]) -> bool {
+            if !self.addresses.is_empty() && !self.addresses.contains(&address) {
+                return false;
+            }
+
+            for (position, alternatives) in self.topics.iter().enumerate() {
+                if alternatives.is_empty() {
+                    continue;
+                }
+
+                match topics.get(position) {
+                    Some(topic) if alternatives.contains(topic) => {}
+                    _ => return false,
+                }
+            }
+
+            true
+        }
+
+        pub fn matches_bloom(&self, bloom: &Bloom) -> bool {
+            if !self.addresses.is_empty()
+                && !self
+                    .addresses
+                    .iter()
+                    .any(|address| bloom_contains(bloom, address.as_bytes()))
+            {
+                return false;
+            }
+
+            self.topics.iter().all(|alternatives| {
+                alternatives.is_empty()
+                    || alternatives
+                        .iter()
+                        .any(|topic| bloom_contains(bloom, topic.as_bytes()))
             })
+        }
+
+        /// Blocks that may contain matching logs according to log indexes.
+        ///
+        /// Returns `None` if the query has no criteria and every block has to be scanned.
+        /// Resulting bitmap may contain blocks outside of the requested range.
+        pub fn candidate_blocks<K: TransactionKind, E: EnvironmentKind>(
+            &self,
+            txn: &MdbxTransaction<'_, K, E>,
+            range: RangeInclusive<BlockNumber>,
+        ) -> anyhow::Result<Option<Treemap>> {
+            let mut out: Option<Treemap> = None;
+
+            if !self.addresses.is_empty() {
+                let mut bitmap = Treemap::create();
+                for address in &self.addresses {
+                    bitmap.or_inplace(&bitmapdb::get(
+                        txn,
+                        tables::LogAddressIndex,
+                        *address,
+                        range.clone(),
+                    )?);
+                }
+                out = Some(bitmap);
+            }
+
+            for alternatives in &self.topics {
+                if alternatives.is_empty() {
+                    continue;
+                }
+
+                let mut bitmap = Treemap::create();
+                for topic in alternatives {
+                    bitmap.or_inplace(&bitmapdb::get(
+                        txn,
+                        tables::LogTopicIndex,
+                        *topic,
+                        range.clone(),
+                    )?);
+                }
+
+                if let Some(out) = &mut out {
+                    out.and_inplace(&bitmap);
+                } else {
+                    out = Some(bitmap);
+                }
+            }
+
+            Ok(out)
+        }
+    }
+
+    pub fn get_logs<K: TransactionKind, E: EnvironmentKind>(
+        txn: &MdbxTransaction<'_, K, E>,
+        range: RangeInclusive<BlockNumber>,
+        query: &LogQuery,
+    ) -> anyhow::Result<Vec<types::TransactionLog>> {
+        let mut out = vec![];
+
+        let candidates = query.candidate_blocks(txn, range.clone())?;
+        let blocks = if let Some(candidates) = &candidates {
+            Either::Left(
+                candidates
+                    .iter()
+                    .map(BlockNumber)
+                    .filter(|block_number| range.contains(block_number)),
+            )
+        } else {
+            Either::Right(range.clone())
+        };
+
+        for block_number in blocks {
+            let block_hash = chain::canonical_hash::read(txn, block_number)?
+                .ok_or_else(|| format_err!("no canonical header for block #{block_number:?}"))?;
+            let header = chain::header::read(txn, block_hash, block_number)?.ok_or_else(|| {
+                format_err!("header not found for block #{block_number}/{block_hash}")
+            })?;
+
+            if header.logs_bloom == Bloom::zero() || !query.matches_bloom(&header.logs_bloom) {
+                continue;
+            }
+
+            for receipt in get_receipts(txn, block_number)? {
+                out.extend(
+                    receipt
+                        .logs
+                        .into_iter()
+                        .filter(|log| query.matches(log.address, &log.topics)),
+                );
+            }
+        }
+
+        Ok(out)
     }
 
     pub fn convert_message_call<S: StateReader>(
@@ -497,4 +897,117 @@ pub mod helpers {
             }
         })
     }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::kv::new_mem_chaindata;
+        use serde_json::json;
+
+        #[test]
+        fn decode_block_param() {
+            let decode = |value| serde_json::from_value::<BlockParam>(value).unwrap();
+            let hash = H256::repeat_byte(0xab);
+
+            assert!(matches!(
+                decode(json!("latest")),
+                BlockParam::Id(types::BlockId::Number(types::BlockNumber::Latest))
+            ));
+            assert!(matches!(
+                decode(json!("0x10")),
+                BlockParam::Id(types::BlockId::Number(types::BlockNumber::Number(number)))
+                    if number == U64::from(0x10_u64)
+            ));
+            assert!(matches!(
+                decode(json!(format!("{hash:?}"))),
+                BlockParam::Id(types::BlockId::Hash(block_hash)) if block_hash == hash
+            ));
+            assert!(matches!(
+                decode(json!({ "blockNumber": "0x10" })),
+                BlockParam::Number {
+                    block_number: types::BlockNumber::Number(number)
+                } if number == U64::from(0x10_u64)
+            ));
+            assert!(matches!(
+                decode(json!({ "blockHash": format!("{hash:?}") })),
+                BlockParam::Hash {
+                    block_hash,
+                    require_canonical: false
+                } if block_hash == hash
+            ));
+            assert!(matches!(
+                decode(json!({ "blockHash": format!("{hash:?}"), "requireCanonical": true })),
+                BlockParam::Hash {
+                    block_hash,
+                    require_canonical: true
+                } if block_hash == hash
+            ));
+        }
+
+        #[test]
+        fn resolve_state_block_within_history() {
+            let hash = |number: u64| H256::from_low_u64_be(number + 1);
+            let non_canonical = H256::repeat_byte(0xff);
+
+            let db = new_mem_chaindata().unwrap();
+            let txn = db.begin_mutable().unwrap();
+            for number in 0..=5 {
+                txn.set(tables::CanonicalHeader, BlockNumber(number), hash(number))
+                    .unwrap();
+                txn.set(tables::HeaderNumber, hash(number), BlockNumber(number))
+                    .unwrap();
+            }
+            txn.set(tables::HeaderNumber, non_canonical, BlockNumber(3))
+                .unwrap();
+            // Block #5 is known, but not executed yet.
+            stages::FINISH.save_progress(&txn, BlockNumber(4)).unwrap();
+
+            let resolve = |block: BlockParam| resolve_state_block(&txn, block);
+            let error = |block: BlockParam| resolve(block).unwrap_err().to_string();
+
+            assert_eq!(
+                resolve(types::BlockNumber::Latest.into()).unwrap(),
+                (BlockNumber(4), hash(4))
+            );
+            assert_eq!(
+                resolve(types::BlockNumber::Earliest.into()).unwrap(),
+                (BlockNumber(0), hash(0))
+            );
+            assert!(
+                error(types::BlockNumber::Number(U64::from(5_u64)).into()).contains("not synced")
+            );
+
+            // Canonical block is required even if not asked for.
+            assert_eq!(
+                resolve(BlockParam::Hash {
+                    block_hash: hash(3),
+                    require_canonical: false,
+                })
+                .unwrap(),
+                (BlockNumber(3), hash(3))
+            );
+            assert!(error(BlockParam::Hash {
+                block_hash: non_canonical,
+                require_canonical: false,
+            })
+            .contains("not canonical"));
+
+            stages::FINISH
+                .save_prune_progress(&txn, BlockNumber(2))
+                .unwrap();
+
+            assert_eq!(
+                resolve(types::BlockNumber::Earliest.into()).unwrap(),
+                (BlockNumber(2), hash(2))
+            );
+            assert_eq!(
+                resolve(types::BlockId::Hash(hash(2)).into()).unwrap(),
+                (BlockNumber(2), hash(2))
+            );
+            assert!(error(BlockParam::Number {
+                block_number: types::BlockNumber::Number(U64::from(1_u64)),
+            })
+            .contains("pruned"));
+        }
+    }
 }
@@ -4,7 +4,7 @@ use crate::{
     consensus::{engine_factory, FinalizationChange},
     execution::{
         analysis_cache::AnalysisCache,
-        processor::{execute_transaction, ExecutionProcessor},
+        processor::{execute_pre_block_system_calls, execute_transaction, ExecutionProcessor},
         tracer::{CallKind, MessageKind, NoopTracer, Tracer},
     },
     kv::{
@@ -21,11 +21,14 @@ use async_trait::async_trait;
 use bytes::Bytes;
 use croaring::Treemap;
 use ethereum_jsonrpc::{
-    types, BlockData, BlockDetails, BlockTransactions, ContractCreatorData, InternalOperation,
-    Issuance, OperationType, OtterscanApiServer, ReceiptWithTimestamp, TraceEntry,
-    TransactionsWithReceipts,
+    types, BlockData, BlockDetails, ContractCreatorData, InternalOperation, Issuance,
+    OperationType, OtterscanApiServer, ReceiptWithTimestamp, TraceEntry,
 };
-use jsonrpsee::core::RpcResult;
+use jsonrpsee::{
+    core::{server::rpc_module::Methods, RpcResult},
+    proc_macros::rpc,
+};
+use serde::Serialize;
 use std::{cmp::Ordering, sync::Arc};
 use tokio::pin;
 
@@ -36,18 +39,123 @@ where
     pub db: Arc<MdbxWithDirHandle<SE>>,
 }
 
+/// Otterscan methods serving transactions in representations which cover blob transactions.
+#[rpc(server, namespace = "ots")]
+pub trait OtterscanTransactionApi {
+    #[method(name = "searchTransactionsBefore")]
+    async fn search_transactions_before(
+        &self,
+        addr: Address,
+        block_num: u64,
+        page_size: usize,
+    ) -> RpcResult<TransactionsWithReceipts>;
+    #[method(name = "searchTransactionsAfter")]
+    async fn search_transactions_after(
+        &self,
+        addr: Address,
+        block_num: u64,
+        page_size: usize,
+    ) -> RpcResult<TransactionsWithReceipts>;
+    #[method(name = "getBlockTransactions")]
+    async fn get_block_transactions(
+        &self,
+        number: u64,
+        page_number: usize,
+        page_size: usize,
+    ) -> RpcResult<Option<BlockTransactions>>;
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionsWithReceipts {
+    pub txs: Vec<helpers::Transaction>,
+    pub receipts: Vec<ReceiptWithTimestamp>,
+    pub first_page: bool,
+    pub last_page: bool,
+}
+
+impl TryFrom<TransactionsWithReceipts> for ethereum_jsonrpc::TransactionsWithReceipts {
+    type Error = anyhow::Error;
+
+    fn try_from(
+        TransactionsWithReceipts {
+            txs,
+            receipts,
+            first_page,
+            last_page,
+        }: TransactionsWithReceipts,
+    ) -> anyhow::Result<Self> {
+        Ok(Self {
+            txs: txs
+                .into_iter()
+                .map(TryFrom::try_from)
+                .collect::<anyhow::Result<_>>()?,
+            receipts,
+            first_page,
+            last_page,
+        })
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct BlockTransactions {
+    pub fullblock: helpers::Block,
+    pub receipts: Vec<types::TransactionReceipt>,
+}
+
+impl TryFrom<BlockTransactions> for ethereum_jsonrpc::BlockTransactions {
+    type Error = anyhow::Error;
+
+    fn try_from(
+        BlockTransactions {
+            fullblock,
+            receipts,
+        }: BlockTransactions,
+    ) -> anyhow::Result<Self> {
+        Ok(Self {
+            fullblock: fullblock.try_into()?,
+            receipts,
+        })
+    }
+}
+
+impl<SE> OtterscanApiServerImpl<SE>
+where
+    SE: EnvironmentKind,
+{
+    /// Methods of the `ots` namespace, with transactions covering blob transactions.
+    pub fn into_methods(self) -> Methods {
+        let transaction_api = Self {
+            db: self.db.clone(),
+        };
+
+        let mut methods = Methods::from(OtterscanApiServer::into_rpc(self));
+        for method in [
+            "ots_searchTransactionsBefore",
+            "ots_searchTransactionsAfter",
+            "ots_getBlockTransactions",
+        ] {
+            methods.remove_method(method);
+        }
+        methods
+            .merge(OtterscanTransactionApiServer::into_rpc(transaction_api))
+            .expect("overridden methods are removed");
+
+        methods
+    }
+}
+
 fn get_block_details_inner<K, E>(
     tx: &MdbxTransaction<'_, K, E>,
     block_id: impl Into<types::BlockId>,
-    include_txs: bool,
 ) -> RpcResult<Option<BlockDetails>>
 where
     K: TransactionKind,
     E: EnvironmentKind,
 {
-    if let Some(block) = helpers::construct_block(tx, block_id, include_txs, None)? {
-        let block_number = block.number.unwrap().as_u64().into();
-        let block_hash = block.hash.unwrap();
+    if let Some(block) = helpers::construct_block(tx, block_id, false, None)? {
+        let block_number = block.inner.number.unwrap().as_u64().into();
+        let block_hash = block.inner.hash.unwrap();
 
         let header = tx.get(tables::Header, (block_number, block_hash))?.unwrap();
         let ommers = tx
@@ -76,10 +184,10 @@ where
         }
         let issuance = block_reward + uncle_reward;
 
-        let mut details = BlockDetails {
+        let details = BlockDetails {
             block: BlockData {
                 transaction_count: block.transactions.len() as u64,
-                inner: block,
+                inner: block.inner,
             },
             issuance: Issuance {
                 block_reward,
@@ -89,9 +197,6 @@ where
             total_fees: U256::ZERO,
         };
 
-        if !include_txs {
-            details.block.inner.transactions.clear();
-        }
         return Ok(Some(details));
     }
 
@@ -146,8 +251,7 @@ where
     let mut buffer = Buffer::new(txn, Some(BlockNumber(block_number.0 - 1)));
     let mut state = IntraBlockState::new(&mut buffer);
 
-    let block_spec = chain_spec.collect_block_spec(block_number);
-    let mut analysis_cache = AnalysisCache::default();
+    let mut analysis_cache = AnalysisCache::global();
 
     let mut prev_cumulative_gas_used = 0;
     let mut cumulative_gas_used = 0;
@@ -156,6 +260,7 @@ where
         .ok_or_else(|| format_err!("no canonical hash for block #{block_number}"))?;
     let header: BlockHeader = chain::header::read(txn, block_hash, block_number)?
         .ok_or_else(|| format_err!("no header for block #{block_number}"))?;
+    let block_spec = chain_spec.collect_block_spec(block_number, header.timestamp);
     let senders = chain::tx_sender::read(txn, block_hash, block_number)?;
     let messages = chain::block_body::read_without_senders(txn, block_hash, block_number)?
         .ok_or_else(|| format_err!("where's block body"))?
@@ -165,6 +270,8 @@ where
         state.set_balance(address, balance)?;
     }
 
+    execute_pre_block_system_calls(&mut state, &block_spec, &header, &mut analysis_cache)?;
+
     let mut results = TransactionsWithReceipts {
         txs: Vec::new(),
         receipts: Vec::new(),
@@ -346,129 +453,10 @@ where
 }
 
 #[async_trait]
-impl<DB> OtterscanApiServer for OtterscanApiServerImpl<DB>
+impl<DB> OtterscanTransactionApiServer for OtterscanApiServerImpl<DB>
 where
     DB: EnvironmentKind,
 {
-    async fn get_api_level(&self) -> RpcResult<u8> {
-        Ok(8)
-    }
-    async fn get_internal_operations(&self, hash: H256) -> RpcResult<Vec<InternalOperation>> {
-        #[derive(Debug, Default)]
-        struct OperationsTracer {
-            results: Vec<InternalOperation>,
-        }
-
-        impl Tracer for OperationsTracer {
-            fn capture_start(
-                &mut self,
-                depth: u16,
-                from: Address,
-                to: Address,
-                call_type: MessageKind,
-                _: Bytes,
-                _: u64,
-                value: U256,
-            ) {
-                if depth > 0 {
-                    match call_type {
-                        MessageKind::Create { salt } => {
-                            self.results.push(InternalOperation {
-                                op_type: if salt.is_some() {
-                                    OperationType::Create2
-                                } else {
-                                    OperationType::Create
-                                },
-                                from,
-                                to,
-                                value,
-                            });
-                        }
-                        MessageKind::Call { call_kind, .. } => {
-                            if matches!(call_kind, CallKind::Call) && value > 0 {
-                                self.results.push(InternalOperation {
-                                    op_type: OperationType::Transfer,
-                                    from,
-                                    to,
-                                    value,
-                                });
-                            }
-                        }
-                    }
-                }
-            }
-            fn capture_self_destruct(
-                &mut self,
-                caller: Address,
-                beneficiary: Address,
-                balance: U256,
-            ) {
-                self.results.push(InternalOperation {
-                    op_type: OperationType::SelfDestruct,
-                    from: caller,
-                    to: beneficiary,
-                    value: balance,
-                })
-            }
-        }
-
-        let db = self.db.clone();
-
-        tokio::task::spawn_blocking(move || {
-            let txn = db.begin()?;
-
-            if let Some(block_number) = chain::tl::read(&txn, hash)? {
-                let block_hash = chain::canonical_hash::read(&txn, block_number)?
-                    .ok_or_else(|| format_err!("no canonical header for block #{block_number:?}"))?;
-                let header = chain::header::read(&txn, block_hash, block_number)?.ok_or_else(|| {
-                    format_err!("header not found for block #{block_number}/{block_hash}")
-                })?;
-                let block_body = chain::block_body::read_with_senders(&txn, block_hash, block_number)?
-                    .ok_or_else(|| {
-                        format_err!("body not found for block #{block_number}/{block_hash}")
-                    })?;
-                let chain_spec = chain::chain_config::read(&txn)?
-                    .ok_or_else(|| format_err!("chain specification not found"))?;
-
-                // Prepare the execution context.
-                let mut buffer = Buffer::new(&txn, Some(BlockNumber(block_number.0 - 1)));
-
-                let block_execution_spec = chain_spec.collect_block_spec(block_number);
-                let mut engine = engine_factory(None, chain_spec)?;
-                let mut analysis_cache = AnalysisCache::default();
-                let mut tracer = NoopTracer;
-
-                let mut processor = ExecutionProcessor::new(
-                    &mut buffer,
-                    &mut tracer,
-                    &mut analysis_cache,
-                    &mut *engine,
-                    &header,
-                    &block_body,
-                    &block_execution_spec,
-                );
-
-                let transaction_index = chain::block_body::read_without_senders(&txn, block_hash, block_number)?.ok_or_else(|| format_err!("where's block body"))?.transactions
-                    .into_iter()
-                    .enumerate()
-                    .find(|(_, tx)| tx.hash() == hash)
-                    .ok_or_else(|| format_err!("transaction {hash} not found in block #{block_number}/{block_hash} despite lookup index"))?.0;
-
-                processor.execute_block_no_post_validation_while(|i, _| i < transaction_index)?;
-
-                let tx = block_body.transactions.get(transaction_index).ok_or_else(|| format_err!("block #{block_number}/{block_hash} too short: tx #{transaction_index} not in body"))?;
-                let mut operations_tracer = OperationsTracer::default();
-                processor.set_tracer(&mut operations_tracer);
-                processor.execute_transaction(&tx.message, tx.sender)?;
-
-                return Ok(operations_tracer.results);
-            }
-
-            Ok(vec![])
-        })
-        .await
-        .unwrap_or_else(helpers::joinerror_to_result)
-    }
     async fn search_transactions_before(
         &self,
         addr: Address,
@@ -622,26 +610,6 @@ where
         .await
         .unwrap_or_else(helpers::joinerror_to_result)
     }
-    async fn get_block_details(&self, number: u64) -> RpcResult<Option<BlockDetails>> {
-        let db = self.db.clone();
-
-        tokio::task::spawn_blocking(move || {
-            let txn = db.begin()?;
-            get_block_details_inner(&txn, types::BlockNumber::Number(number.into()), false)
-        })
-        .await
-        .unwrap_or_else(helpers::joinerror_to_result)
-    }
-    async fn get_block_details_by_hash(&self, hash: H256) -> RpcResult<Option<BlockDetails>> {
-        let db = self.db.clone();
-
-        tokio::task::spawn_blocking(move || {
-            let txn = db.begin()?;
-            get_block_details_inner(&txn, hash, false)
-        })
-        .await
-        .unwrap_or_else(helpers::joinerror_to_result)
-    }
     async fn get_block_transactions(
         &self,
         number: u64,
@@ -653,20 +621,19 @@ where
         tokio::task::spawn_blocking(move || {
             let txn = db.begin()?;
 
-            if let Some(mut block_details) =
-                get_block_details_inner(&txn, types::BlockNumber::Number(number.into()), true)?
-            {
-                let page_end = block_details
-                    .block
-                    .inner
+            if let Some(mut block) = helpers::construct_block(
+                &txn,
+                types::BlockNumber::Number(number.into()),
+                true,
+                None,
+            )? {
+                let page_end = block
                     .transactions
                     .len()
                     .saturating_sub(page_number * page_size);
                 let page_start = page_end.saturating_sub(page_size);
 
-                block_details.block.inner.transactions = block_details
-                    .block
-                    .inner
+                block.transactions = block
                     .transactions
                     .get(page_start..page_end)
                     .map(|v| v.to_vec())
@@ -674,7 +641,7 @@ where
 
                 return Ok(Some(BlockTransactions {
                     receipts: helpers::get_receipts(&txn, number.into())?,
-                    fullblock: block_details.block.inner,
+                    fullblock: block,
                 }));
             }
 
@@ -683,6 +650,192 @@ where
         .await
         .unwrap_or_else(helpers::joinerror_to_result)
     }
+}
+
+#[async_trait]
+impl<DB> OtterscanApiServer for OtterscanApiServerImpl<DB>
+where
+    DB: EnvironmentKind,
+{
+    async fn get_api_level(&self) -> RpcResult<u8> {
+        Ok(8)
+    }
+    async fn get_internal_operations(&self, hash: H256) -> RpcResult<Vec<InternalOperation>> {
+        #[derive(Debug, Default)]
+        struct OperationsTracer {
+            results: Vec<InternalOperation>,
+        }
+
+        impl Tracer for OperationsTracer {
+            fn capture_start(
+                &mut self,
+                depth: u16,
+                from: Address,
+                to: Address,
+                call_type: MessageKind,
+                _: Bytes,
+                _: u64,
+                value: U256,
+            ) {
+                if depth > 0 {
+                    match call_type {
+                        MessageKind::Create { salt } => {
+                            self.results.push(InternalOperation {
+                                op_type: if salt.is_some() {
+                                    OperationType::Create2
+                                } else {
+                                    OperationType::Create
+                                },
+                                from,
+                                to,
+                                value,
+                            });
+                        }
+                        MessageKind::Call { call_kind, .. } => {
+                            if matches!(call_kind, CallKind::Call) && value > 0 {
+                                self.results.push(InternalOperation {
+                                    op_type: OperationType::Transfer,
+                                    from,
+                                    to,
+                                    value,
+                                });
+                            }
+                        }
+                    }
+                }
+            }
+            fn capture_self_destruct(
+                &mut self,
+                caller: Address,
+                beneficiary: Address,
+                balance: U256,
+            ) {
+                self.results.push(InternalOperation {
+                    op_type: OperationType::SelfDestruct,
+                    from: caller,
+                    to: beneficiary,
+                    value: balance,
+                })
+            }
+        }
+
+        let db = self.db.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let txn = db.begin()?;
+
+            if let Some(block_number) = chain::tl::read(&txn, hash)? {
+                let block_hash = chain::canonical_hash::read(&txn, block_number)?
+                    .ok_or_else(|| format_err!("no canonical header for block #{block_number:?}"))?;
+                let header = chain::header::read(&txn, block_hash, block_number)?.ok_or_else(|| {
+                    format_err!("header not found for block #{block_number}/{block_hash}")
+                })?;
+                let block_body = chain::block_body::read_with_senders(&txn, block_hash, block_number)?
+                    .ok_or_else(|| {
+                        format_err!("body not found for block #{block_number}/{block_hash}")
+                    })?;
+                let chain_spec = chain::chain_config::read(&txn)?
+                    .ok_or_else(|| format_err!("chain specification not found"))?;
+
+                // Prepare the execution context.
+                let mut buffer = Buffer::new(&txn, Some(BlockNumber(block_number.0 - 1)));
+
+                let block_execution_spec = chain_spec.collect_block_spec(block_number, header.timestamp);
+                let mut engine = engine_factory(None, chain_spec)?;
+                let mut analysis_cache = AnalysisCache::global();
+                let mut tracer = NoopTracer;
+
+                let mut processor = ExecutionProcessor::new(
+                    &mut buffer,
+                    &mut tracer,
+                    &mut analysis_cache,
+                    &mut *engine,
+                    &header,
+                    &block_body,
+                    &block_execution_spec,
+                );
+
+                let transaction_index = chain::block_body::read_without_senders(&txn, block_hash, block_number)?.ok_or_else(|| format_err!("where's block body"))?.transactions
+                    .into_iter()
+                    .enumerate()
+                    .find(|(_, tx)| tx.hash() == hash)
+                    .ok_or_else(|| format_err!("transaction {hash} not found in block #{block_number}/{block_hash} despite lookup index"))?.0;
+
+                processor.execute_block_no_post_validation_while(|i, _| i < transaction_index)?;
+
+                let tx = block_body.transactions.get(transaction_index).ok_or_else(|| format_err!("block #{block_number}/{block_hash} too short: tx #{transaction_index} not in body"))?;
+                let mut operations_tracer = OperationsTracer::default();
+                processor.set_tracer(&mut operations_tracer);
+                processor.execute_transaction(&tx.message, tx.sender)?;
+
+                return Ok(operations_tracer.results);
+            }
+
+            Ok(vec![])
+        })
+        .await
+        .unwrap_or_else(helpers::joinerror_to_result)
+    }
+    async fn search_transactions_before(
+        &self,
+        addr: Address,
+        block_num: u64,
+        page_size: usize,
+    ) -> RpcResult<ethereum_jsonrpc::TransactionsWithReceipts> {
+        Ok(OtterscanTransactionApiServer::search_transactions_before(
+            self, addr, block_num, page_size,
+        )
+        .await?
+        .try_into()?)
+    }
+    async fn search_transactions_after(
+        &self,
+        addr: Address,
+        block_num: u64,
+        page_size: usize,
+    ) -> RpcResult<ethereum_jsonrpc::TransactionsWithReceipts> {
+        Ok(OtterscanTransactionApiServer::search_transactions_after(
+            self, addr, block_num, page_size,
+        )
+        .await?
+        .try_into()?)
+    }
+    async fn get_block_details(&self, number: u64) -> RpcResult<Option<BlockDetails>> {
+        let db = self.db.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let txn = db.begin()?;
+            get_block_details_inner(&txn, types::BlockNumber::Number(number.into()))
+        })
+        .await
+        .unwrap_or_else(helpers::joinerror_to_result)
+    }
+    async fn get_block_details_by_hash(&self, hash: H256) -> RpcResult<Option<BlockDetails>> {
+        let db = self.db.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let txn = db.begin()?;
+            get_block_details_inner(&txn, hash)
+        })
+        .await
+        .unwrap_or_else(helpers::joinerror_to_result)
+    }
+    async fn get_block_transactions(
+        &self,
+        number: u64,
+        page_number: usize,
+        page_size: usize,
+    ) -> RpcResult<Option<ethereum_jsonrpc::BlockTransactions>> {
+        Ok(OtterscanTransactionApiServer::get_block_transactions(
+            self,
+            number,
+            page_number,
+            page_size,
+        )
+        .await?
+        .map(TryFrom::try_from)
+        .transpose()?)
+    }
     async fn has_code(&self, address: Address, block_id: types::BlockId) -> RpcResult<bool> {
         let db = self.db.clone();
 
@@ -0,0 +1,433 @@
+use super::helpers;
+use crate::{
+    accessors::chain,
+    kv::{mdbx::*, MdbxWithDirHandle},
+    models::*,
+    stagedsync::stages::FINISH,
+    txpool::TransactionPool,
+};
+use anyhow::format_err;
+use ethereum_jsonrpc::{types, LogFilter};
+use jsonrpsee::{types::error::CallError, RpcModule};
+use serde::Serialize;
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tokio::{
+    sync::broadcast::{self, error::RecvError},
+    time::sleep,
+};
+use tracing::*;
+
+/// Maximum number of blocks notified about at once, e. g. when staged sync commits a batch.
+const MAX_NOTIFIED_BLOCKS: u64 = 128;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Debug)]
+enum ChainEvent {
+    /// Block became canonical.
+    New {
+        header: types::Block,
+        logs: Arc<Vec<types::TransactionLog>>,
+    },
+    /// Previously notified block was replaced by a reorg.
+    Removed {
+        logs: Arc<Vec<types::TransactionLog>>,
+    },
+}
+
+/// Log as sent to `logs` subscribers, which also learn about logs of blocks reverted by a reorg.
+#[derive(Serialize)]
+struct SubscriptionLog<'a> {
+    #[serde(flatten)]
+    log: &'a types::TransactionLog,
+    removed: bool,
+}
+
+#[derive(Debug)]
+struct NotifiedBlock {
+    number: BlockNumber,
+    hash: H256,
+    logs: Arc<Vec<types::TransactionLog>>,
+}
+
+/// Reads blocks that became canonical since the last call, as seen by progress of `Finish` stage.
+///
+/// `recent` holds the latest notified blocks, so that blocks replaced by a reorg are notified again
+/// and their logs are notified as removed. Receipts are only re-read when `with_logs` is set.
+fn read_new_blocks<K: TransactionKind, E: EnvironmentKind>(
+    txn: &MdbxTransaction<'_, K, E>,
+    recent: &mut VecDeque<NotifiedBlock>,
+    with_logs: bool,
+) -> anyhow::Result<Vec<ChainEvent>> {
+    let head = FINISH.get_progress(txn)?.unwrap_or_default();
+
+    let mut out = vec![];
+    while let Some(block) = recent.back() {
+        if block.number <= head
+            && chain::canonical_hash::read(txn, block.number)? == Some(block.hash)
+        {
+            break;
+        }
+        let block = recent.pop_back().unwrap();
+        if !block.logs.is_empty() {
+            out.push(ChainEvent::Removed { logs: block.logs });
+        }
+    }
+
+    let from = recent
+        .back()
+        .map(|block| block.number + 1)
+        .unwrap_or(head)
+        .max(BlockNumber(
+            (head.0 + 1).saturating_sub(MAX_NOTIFIED_BLOCKS),
+        ));
+
+    for number in from.0..=head.0 {
+        let number = BlockNumber(number);
+        let hash = chain::canonical_hash::read(txn, number)?
+            .ok_or_else(|| format_err!("no canonical hash for block #{number}"))?;
+        let header = helpers::construct_block(txn, types::BlockId::Hash(hash), false, None)?
+            .ok_or_else(|| format_err!("block #{number}/{hash} not found"))?
+            .inner;
+        let logs_bloom = chain::header::read(txn, hash, number)?
+            .ok_or_else(|| format_err!("header not found for block #{number}/{hash}"))?
+            .logs_bloom;
+        let logs = Arc::new(if !with_logs || logs_bloom == Bloom::zero() {
+            vec![]
+        } else {
+            helpers::get_receipts(txn, number)?
+                .into_iter()
+                .flat_map(|receipt| receipt.logs)
+                .collect()
+        });
+
+        recent.push_back(NotifiedBlock {
+            number,
+            hash,
+            logs: logs.clone(),
+        });
+        if recent.len() > MAX_NOTIFIED_BLOCKS as usize {
+            recent.pop_front();
+        }
+
+        out.push(ChainEvent::New { header, logs });
+    }
+
+    Ok(out)
+}
+
+async fn recv<T: Clone>(receiver: &mut broadcast::Receiver<T>) -> Option<T> {
+    loop {
+        match receiver.recv().await {
+            Ok(v) => return Some(v),
+            Err(RecvError::Lagged(_)) => continue,
+            Err(RecvError::Closed) => return None,
+        }
+    }
+}
+
+/// Keeps `logs` subscription counted for as long as it is alive.
+struct SubscriberGuard(Arc<AtomicUsize>);
+
+impl SubscriberGuard {
+    fn new(counter: Arc<AtomicUsize>) -> Self {
+        counter.fetch_add(1, Ordering::Relaxed);
+        Self(counter)
+    }
+}
+
+impl Drop for SubscriberGuard {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// `eth_subscribe` and `eth_unsubscribe`, available over WebSocket only.
+pub struct PubSubApiServerImpl<SE>
+where
+    SE: EnvironmentKind,
+{
+    pub db: Arc<MdbxWithDirHandle<SE>>,
+    pub txpool: Option<Arc<TransactionPool<SE>>>,
+}
+
+impl<SE> PubSubApiServerImpl<SE>
+where
+    SE: EnvironmentKind,
+{
+    /// Starts watching the database for new canonical blocks and returns subscription methods.
+    pub fn into_rpc(self) -> RpcModule<()> {
+        let (blocks, _) = broadcast::channel::<Arc<ChainEvent>>(MAX_NOTIFIED_BLOCKS as usize);
+        let log_subscribers = Arc::new(AtomicUsize::new(0));
+
+        tokio::spawn({
+            let db = self.db;
+            let blocks = blocks.clone();
+            let log_subscribers = log_subscribers.clone();
+            async move {
+                let mut recent = VecDeque::new();
+                loop {
+                    let (r, res) = tokio::task::spawn_blocking({
+                        let db = db.clone();
+                        let with_logs = log_subscribers.load(Ordering::Relaxed) > 0;
+                        move || {
+                            let res = db
+                                .begin()
+                                .and_then(|txn| read_new_blocks(&txn, &mut recent, with_logs));
+                            (recent, res)
+                        }
+                    })
+                    .await
+                    .unwrap();
+                    recent = r;
+
+                    match res {
+                        Ok(events) => {
+                            for event in events {
+                                let _ = blocks.send(Arc::new(event));
+                            }
+                        }
+                        Err(e) => warn!("Failed to read new blocks for subscriptions: {e}"),
+                    }
+
+                    sleep(POLL_INTERVAL).await;
+                }
+            }
+        });
+
+        let txpool = self.txpool;
+        let mut module = RpcModule::new(());
+        module
+            .register_subscription(
+                "eth_subscribe",
+                "eth_subscription",
+                "eth_unsubscribe",
+                move |params, mut sink, _| {
+                    let mut params = params.sequence();
+                    let kind = match params.next::<String>() {
+                        Ok(kind) => kind,
+                        Err(e) => {
+                            let _ = sink.reject(e);
+                            return Ok(());
+                        }
+                    };
+
+                    match kind.as_str() {
+                        "newHeads" => {
+                            let mut blocks = blocks.subscribe();
+                            tokio::spawn(async move {
+                                while let Some(event) = recv(&mut blocks).await {
+                                    if let ChainEvent::New { header, .. } = &*event {
+                                        if !matches!(sink.send(header), Ok(true)) {
+                                            break;
+                                        }
+                                    }
+                                }
+                            });
+                        }
+                        "logs" => {
+                            let query = match params.optional_next::<LogFilter>() {
+                                Ok(filter) => filter
+                                    .as_ref()
+                                    .map(helpers::LogQuery::from)
+                                    .unwrap_or_default(),
+                                Err(e) => {
+                                    let _ = sink.reject(e);
+                                    return Ok(());
+                                }
+                            };
+
+                            let mut blocks = blocks.subscribe();
+                            let guard = SubscriberGuard::new(log_subscribers.clone());
+                            tokio::spawn(async move {
+                                let _guard = guard;
+                                while let Some(event) = recv(&mut blocks).await {
+                                    let (logs, removed) = match &*event {
+                                        ChainEvent::New { logs, .. } => (logs, false),
+                                        ChainEvent::Removed { logs } => (logs, true),
+                                    };
+                                    for log in logs
+                                        .iter()
+                                        .filter(|log| query.matches(log.address, &log.topics))
+                                    {
+                                        if !matches!(
+                                            sink.send(&SubscriptionLog { log, removed }),
+                                            Ok(true)
+                                        ) {
+                                            return;
+                                        }
+                                    }
+                                }
+                            });
+                        }
+                        "newPendingTransactions" => {
+                            let Some(txpool) = &txpool else {
+                                let _ = sink.reject(CallError::Failed(format_err!(
+                                    "transaction pool is not available"
+                                )));
+                                return Ok(());
+                            };
+
+                            let mut hashes = txpool.subscribe();
+                            tokio::spawn(async move {
+                                while let Some(hash) = recv(&mut hashes).await {
+                                    if !matches!(sink.send(&hash), Ok(true)) {
+                                        break;
+                                    }
+                                }
+                            });
+                        }
+                        other => {
+                            let _ = sink.reject(CallError::Failed(format_err!(
+                                "unsupported subscription: {other}"
+                            )));
+                        }
+                    }
+
+                    Ok(())
+                },
+            )
+            .unwrap();
+
+        module
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{rpc::test_chain::*, txpool::PoolConfig};
+    use bytes::Bytes;
+    use jsonrpsee::core::server::rpc_module::Subscription;
+    use serde::de::DeserializeOwned;
+    use serde_json::{json, Value};
+
+    async fn next<T: DeserializeOwned>(subscription: &mut Subscription) -> T {
+        tokio::time::timeout(Duration::from_secs(10), subscription.next())
+            .await
+            .expect("no notification received")
+            .unwrap()
+            .unwrap()
+            .0
+    }
+
+    /// Contract emitting a log with given topic.
+    fn emitter(topic: u8) -> Bytes {
+        // PUSH1 topic PUSH1 0x20 PUSH1 0x00 LOG1 STOP
+        Bytes::from(vec![0x60, topic, 0x60, 0x20, 0x60, 0x00, 0xa1, 0x00])
+    }
+
+    #[tokio::test]
+    async fn notifications_match_subscriptions() {
+        let key = secret_key(1);
+        let chain = TestChain::new([(address_of(&key), ETHER.as_u256())]);
+        let a = Address::from_low_u64_be(0xa);
+        let b = Address::from_low_u64_be(0xb);
+        chain.set_account(a, Account::default(), emitter(1), &[]);
+        chain.set_account(b, Account::default(), emitter(2), &[]);
+
+        let txpool = Arc::new(
+            TransactionPool::new(
+                chain.db.clone(),
+                chain.chain_spec.clone(),
+                PoolConfig::default(),
+            )
+            .unwrap(),
+        );
+        let module = PubSubApiServerImpl {
+            db: chain.db.clone(),
+            txpool: Some(txpool.clone()),
+        }
+        .into_rpc();
+
+        let mut heads = module
+            .subscribe("eth_subscribe", ["newHeads"])
+            .await
+            .unwrap();
+        let mut logs = module.subscribe("eth_subscribe", ["logs"]).await.unwrap();
+        let mut logs_of_a = module
+            .subscribe("eth_subscribe", [json!("logs"), json!({ "address": a })])
+            .await
+            .unwrap();
+        let mut pending = module
+            .subscribe("eth_subscribe", ["newPendingTransactions"])
+            .await
+            .unwrap();
+
+        let call = |nonce, to| {
+            sign(
+                Message::EIP1559 {
+                    chain_id: ChainId(1),
+                    nonce,
+                    max_priority_fee_per_gas: U256::ONE,
+                    max_fee_per_gas: (2 * BASE_FEE_PER_GAS).as_u256(),
+                    gas_limit: 100_000,
+                    action: TransactionAction::Call(to),
+                    value: U256::ZERO,
+                    input: Bytes::new(),
+                    access_list: vec![],
+                },
+                &key,
+            )
+        };
+        let transactions = vec![call(0, b), call(1, a)];
+
+        for transaction in &transactions {
+            txpool.add_transaction(transaction.clone()).unwrap();
+        }
+        for transaction in &transactions {
+            assert_eq!(next::<H256>(&mut pending).await, transaction.hash());
+        }
+
+        let header = chain.push_block(transactions.clone());
+
+        // Genesis is notified as well if it was read before the block was added.
+        let head = loop {
+            let head = next::<Value>(&mut heads).await;
+            if head["number"] != json!("0x0") {
+                break head;
+            }
+        };
+        assert_eq!(head["number"], json!("0x1"));
+        assert_eq!(head["hash"], json!(header.hash()));
+
+        for (transaction, address, topic) in [(&transactions[0], b, 2), (&transactions[1], a, 1)] {
+            let log = next::<Value>(&mut logs).await;
+            assert_eq!(log["address"], json!(address));
+            assert_eq!(log["topics"], json!([H256::from_low_u64_be(topic)]));
+            assert_eq!(log["transactionHash"], json!(transaction.hash()));
+            assert_eq!(log["removed"], json!(false));
+        }
+
+        // Log of `b` comes first, but is filtered out.
+        let log = next::<Value>(&mut logs_of_a).await;
+        assert_eq!(log["address"], json!(a));
+        assert_eq!(log["transactionHash"], json!(transactions[1].hash()));
+    }
+
+    #[tokio::test]
+    async fn unsupported_subscriptions() {
+        let chain = TestChain::new([]);
+        let module = PubSubApiServerImpl {
+            db: chain.db.clone(),
+            txpool: None,
+        }
+        .into_rpc();
+
+        assert!(module
+            .subscribe("eth_subscribe", ["newPendingTransactions"])
+            .await
+            .is_err());
+        assert!(module
+            .subscribe("eth_subscribe", ["syncing"])
+            .await
+            .is_err());
+    }
+}
@@ -0,0 +1,236 @@
+//! Small chain in a temporary database, to test RPC methods against.
+
+use crate::{
+    accessors::chain,
+    consensus::engine_factory,
+    crypto::keccak256,
+    execution::{analysis_cache::AnalysisCache, processor::ExecutionProcessor, tracer::NoopTracer},
+    kv::{mdbx::*, new_mem_chaindata, tables, MdbxWithDirHandle},
+    models::*,
+    res::chainspec::MAINNET,
+    stagedsync::stages,
+    state::genesis::initialize_genesis,
+    trie::root_hash,
+    Buffer, StateWriter,
+};
+use bytes::Bytes;
+use secp256k1::{Message as SecpMessage, PublicKey, SecretKey, SECP256K1};
+use sha3::{Digest, Keccak256};
+use std::sync::Arc;
+use tempfile::TempDir;
+
+pub const BASE_FEE_PER_GAS: u64 = 1_000_000_000;
+pub const COINBASE: Address = H160([0xcb; 20]);
+
+pub fn secret_key(seed: u8) -> SecretKey {
+    SecretKey::from_slice(&[seed; 32]).unwrap()
+}
+
+pub fn address_of(secret_key: &SecretKey) -> Address {
+    let public = PublicKey::from_secret_key(SECP256K1, secret_key);
+    Address::from_slice(&Keccak256::digest(&public.serialize_uncompressed()[1..])[12..])
+}
+
+pub fn sign(message: Message, secret_key: &SecretKey) -> MessageWithSignature {
+    let (rec, sig) = SECP256K1
+        .sign_ecdsa_recoverable(
+            &SecpMessage::from_slice(message.hash().as_bytes()).unwrap(),
+            secret_key,
+        )
+        .serialize_compact();
+
+    MessageWithSignature {
+        message,
+        signature: MessageSignature::new(
+            rec.to_i32() != 0,
+            H256::from_slice(&sig[..32]),
+            H256::from_slice(&sig[32..]),
+        )
+        .unwrap(),
+    }
+}
+
+pub struct TestChain {
+    pub db: Arc<MdbxWithDirHandle<WriteMap>>,
+    pub chain_spec: ChainSpec,
+}
+
+impl TestChain {
+    /// Chain with all forks up to London active from genesis, which funds given accounts.
+    pub fn new(balances: impl IntoIterator<Item = (Address, U256)>) -> Self {
+        let mut chain_spec = MAINNET.clone();
+        chain_spec.name = "Test".to_string();
+        chain_spec.upgrades = Upgrades {
+            homestead: Some(0.into()),
+            tangerine: Some(0.into()),
+            spurious: Some(0.into()),
+            byzantium: Some(0.into()),
+            constantinople: Some(0.into()),
+            petersburg: Some(0.into()),
+            istanbul: Some(0.into()),
+            berlin: Some(0.into()),
+            london: Some(0.into()),
+            ..Default::default()
+        };
+        chain_spec.consensus.eip1559_block = Some(0.into());
+        chain_spec.genesis.gas_limit = 30_000_000;
+        chain_spec.genesis.base_fee_per_gas = Some(BASE_FEE_PER_GAS.as_u256());
+        chain_spec.balances = [(BlockNumber(0), balances.into_iter().collect())].into();
+
+        let db = Arc::new(new_mem_chaindata().unwrap());
+        let txn = db.begin_mutable().unwrap();
+        initialize_genesis(
+            &txn,
+            &TempDir::new().unwrap(),
+            false,
+            Some(chain_spec.clone()),
+        )
+        .unwrap();
+        stages::FINISH.save_progress(&txn, BlockNumber(0)).unwrap();
+        txn.commit().unwrap();
+
+        Self { db, chain_spec }
+    }
+
+    /// Puts account with given code and storage into the current state.
+    pub fn set_account(
+        &self,
+        address: Address,
+        account: Account,
+        code: Bytes,
+        storage: &[(U256, U256)],
+    ) {
+        let txn = self.db.begin_mutable().unwrap();
+        let mut buffer = Buffer::new(&txn, None);
+        buffer.begin_block(self.head(&txn).number);
+
+        let code_hash = keccak256(&code);
+        buffer.update_code(code_hash, code).unwrap();
+        buffer.update_account(
+            address,
+            None,
+            Some(Account {
+                code_hash,
+                ..account
+            }),
+        );
+        for &(location, value) in storage {
+            buffer
+                .update_storage(address, location, U256::ZERO, value)
+                .unwrap();
+        }
+
+        buffer.write_to_db().unwrap();
+        txn.commit().unwrap();
+    }
+
+    /// Appends a block with given transactions.
+    ///
+    /// Transactions are executed to fill gas used, logs bloom and roots of the header,
+    /// but state is left as of the parent block, which is what replaying the block needs.
+    pub fn push_block(&self, transactions: Vec<MessageWithSignature>) -> BlockHeader {
+        self.append_block(transactions, true)
+    }
+
+    /// Appends a block with given transactions without executing them, for transactions
+    /// which forks active on this chain can't execute. Gas used, logs bloom and receipts root
+    /// of the header are left empty.
+    pub fn push_unexecuted_block(&self, transactions: Vec<MessageWithSignature>) -> BlockHeader {
+        self.append_block(transactions, false)
+    }
+
+    fn append_block(&self, transactions: Vec<MessageWithSignature>, execute: bool) -> BlockHeader {
+        let txn = self.db.begin_mutable().unwrap();
+        let parent = self.head(&txn);
+        let parent_hash = parent.hash();
+        let parent_body = chain::storage_body::read(&txn, parent_hash, parent.number)
+            .unwrap()
+            .unwrap();
+
+        let senders = transactions
+            .iter()
+            .map(|transaction| transaction.recover_sender().unwrap())
+            .collect::<Vec<_>>();
+        let mut header = BlockHeader {
+            parent_hash,
+            beneficiary: COINBASE,
+            state_root: parent.state_root,
+            difficulty: parent.difficulty,
+            number: parent.number + 1,
+            gas_limit: parent.gas_limit,
+            timestamp: parent.timestamp + 12,
+            base_fee_per_gas: parent.base_fee_per_gas,
+            ommers_hash: EMPTY_LIST_HASH,
+            transactions_root: root_hash(&transactions),
+            ..Default::default()
+        };
+
+        if execute {
+            let block = BlockBodyWithSenders {
+                transactions: transactions
+                    .iter()
+                    .zip(&senders)
+                    .map(|(transaction, &sender)| MessageWithSender {
+                        message: transaction.message.clone(),
+                        sender,
+                    })
+                    .collect(),
+                ommers: vec![],
+                withdrawals: None,
+            };
+            let block_spec = self
+                .chain_spec
+                .collect_block_spec(header.number, header.timestamp);
+            let mut engine = engine_factory(None, self.chain_spec.clone()).unwrap();
+
+            let receipts = ExecutionProcessor::new(
+                &mut Buffer::new(&txn, Some(parent.number)),
+                &mut NoopTracer,
+                &mut AnalysisCache::default(),
+                &mut *engine,
+                &header,
+                &block,
+                &block_spec,
+            )
+            .execute_block_no_post_validation()
+            .unwrap();
+
+            header.gas_used = receipts
+                .last()
+                .map(|receipt| receipt.cumulative_gas_used)
+                .unwrap_or_default();
+            header.logs_bloom = logs_bloom(receipts.iter().flat_map(|receipt| &receipt.logs));
+            header.receipts_root = root_hash(&receipts);
+        }
+
+        let number = header.number;
+        let hash = header.hash();
+        let body = BodyForStorage {
+            base_tx_id: parent_body.base_tx_id + parent_body.tx_amount,
+            tx_amount: transactions.len() as u64,
+            uncles: vec![],
+            withdrawals: None,
+        };
+
+        txn.set(tables::Header, (number, hash), header.clone())
+            .unwrap();
+        txn.set(tables::CanonicalHeader, number, hash).unwrap();
+        txn.set(tables::HeaderNumber, hash, number).unwrap();
+        chain::storage_body::write(&txn, hash, number, &body).unwrap();
+        chain::tx::write(&txn, body.base_tx_id, &transactions).unwrap();
+        chain::tx_sender::write(&txn, hash, number, senders).unwrap();
+        for transaction in &transactions {
+            chain::tl::write(&txn, transaction.hash(), number).unwrap();
+        }
+        stages::FINISH.save_progress(&txn, number).unwrap();
+        txn.commit().unwrap();
+
+        header
+    }
+
+    fn head<K: TransactionKind>(&self, txn: &MdbxTransaction<'_, K, WriteMap>) -> BlockHeader {
+        let number = stages::FINISH.get_progress(txn).unwrap().unwrap();
+        let hash = chain::canonical_hash::read(txn, number).unwrap().unwrap();
+        chain::header::read(txn, hash, number).unwrap().unwrap()
+    }
+}
@@ -3,7 +3,9 @@ use crate::{
     bitmapdb,
     consensus::engine_factory,
     execution::{
-        analysis_cache::AnalysisCache, processor::execute_transaction, tracer::adhoc::AdhocTracer,
+        analysis_cache::AnalysisCache,
+        processor::{execute_pre_block_system_calls, execute_transaction},
+        tracer::adhoc::AdhocTracer,
     },
     kv::{mdbx::*, tables, MdbxWithDirHandle},
     models::*,
@@ -213,6 +215,7 @@ where
         .get(tables::Config, ())?
         .ok_or_else(|| format_err!("chain spec not found"))?;
 
+    let replay = matches!(kind, CallManyMode::Replay(_));
     let (historical_block, block_number, header) = match kind {
         CallManyMode::Replay(b) => {
             let (block_number, block_hash) =
@@ -256,12 +259,18 @@ where
         },
     };
 
-    let block_spec = chain_spec.collect_block_spec(block_number);
+    let block_spec = chain_spec.collect_block_spec(block_number, header.timestamp);
     let mut buffer = Buffer::new(txn, historical_block);
 
     let engine = engine_factory(None, chain_spec.clone())?;
 
-    let mut analysis_cache = AnalysisCache::default();
+    let mut analysis_cache = AnalysisCache::global();
+    if replay {
+        let mut state = IntraBlockState::new(&mut buffer);
+        execute_pre_block_system_calls(&mut state, &block_spec, &header, &mut analysis_cache)?;
+        state.write_to_state_same_block()?;
+    }
+
     for (sender, message, trace_types) in calls {
         let (output, updates, trace) = {
             let mut buffer = LoggingBuffer::new(&mut buffer);
@@ -427,6 +436,7 @@ where
             let BlockBody {
                 transactions,
                 ommers,
+                ..
             } = crate::accessors::chain::block_body::read_without_senders(
                 txn,
                 block_hash,
@@ -3,9 +3,10 @@ use crate::models::*;
 use anyhow::anyhow;
 use arrayvec::ArrayString;
 use enum_primitive_derive::*;
-use ethereum_forkid::{ForkFilter, ForkId};
+use ethereum_forkid::{ForkHash, ForkId};
 use fastrlp::*;
-use std::{collections::BTreeSet, convert::TryFrom};
+use std::convert::TryFrom;
+use thiserror::Error;
 
 pub fn capability_name() -> CapabilityName {
     CapabilityName(ArrayString::from("eth").unwrap())
@@ -24,7 +25,104 @@ pub struct StatusMessage {
 #[derive(Clone, Debug)]
 pub struct Forks {
     pub genesis: H256,
-    pub forks: BTreeSet<u64>,
+    /// Fork block numbers followed by fork timestamps, in activation order.
+    pub forks: Vec<u64>,
+}
+
+/// Value passed to sentry along with the fork list, as `max_block`.
+///
+/// Block forks are passed by height, and only once all of them are, time forks are checked
+/// against head timestamp, see [EIP-6122](https://eips.ethereum.org/EIPS/eip-6122).
+/// Timestamps grow by at least one every block, so the timestamp never falls below height
+/// and block forks stay passed after the switch.
+pub fn fork_head(
+    block_forks: &[u64],
+    time_forks: &[u64],
+    height: BlockNumber,
+    timestamp: u64,
+) -> u64 {
+    if !time_forks.is_empty() && block_forks.iter().all(|&fork| fork <= *height) {
+        timestamp
+    } else {
+        *height
+    }
+}
+
+#[derive(Clone, Copy, Debug, Error, PartialEq, Eq)]
+pub enum ForkIdError {
+    #[error("remote is stale, its next fork {remote_next} has already passed")]
+    RemoteStale { remote_next: u64 },
+    #[error("local chain is incompatible or stale")]
+    LocalIncompatibleOrStale,
+}
+
+/// Fork id of the local chain and validation of remote ones,
+/// see [EIP-2124](https://eips.ethereum.org/EIPS/eip-2124).
+///
+/// Forks are passed in list order up to the first one above head, see [`fork_head`].
+#[derive(Clone, Debug)]
+pub struct ForkFilter {
+    head: u64,
+    /// Hashes after each fork in the list, starting with genesis.
+    hashes: Vec<ForkHash>,
+    forks: Vec<u64>,
+    /// Number of passed forks.
+    passed: usize,
+}
+
+impl ForkFilter {
+    pub fn new(head: u64, genesis: H256, forks: impl IntoIterator<Item = u64>) -> Self {
+        let mut forks = forks.into_iter().collect::<Vec<_>>();
+        // Forks activating at the same point are hashed in once.
+        forks.dedup();
+
+        let mut hash = ForkHash::from(genesis);
+        let mut hashes = vec![hash];
+        for &fork in &forks {
+            hash += fork;
+            hashes.push(hash);
+        }
+        let passed = forks.iter().take_while(|&&fork| fork <= head).count();
+
+        Self {
+            head,
+            hashes,
+            forks,
+            passed,
+        }
+    }
+
+    pub fn current(&self) -> ForkId {
+        ForkId {
+            hash: self.hashes[self.passed],
+            next: self.forks.get(self.passed).copied().unwrap_or(0),
+        }
+    }
+
+    pub fn validate(&self, remote: ForkId) -> Result<(), ForkIdError> {
+        let Some(remote_passed) = self.hashes.iter().position(|&hash| hash == remote.hash) else {
+            return Err(ForkIdError::LocalIncompatibleOrStale);
+        };
+
+        if remote_passed == self.passed {
+            // Same forks passed, remote must not expect a fork we are already past.
+            if remote.next > 0 && remote.next <= self.head {
+                return Err(ForkIdError::LocalIncompatibleOrStale);
+            }
+            Ok(())
+        } else if remote_passed < self.passed {
+            // Remote is behind, it must be aware of the fork that follows.
+            if remote.next != self.forks[remote_passed] {
+                return Err(ForkIdError::RemoteStale {
+                    remote_next: remote.next,
+                });
+            }
+            Ok(())
+        } else {
+            // Remote is ahead of us, but on our chain.
+            Ok(())
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -59,7 +157,8 @@ impl TryFrom<ethereum_interfaces::sentry::StatusData> for FullStatusData {
             .ok_or_else(|| anyhow!("no genesis"))?
             .into();
 
-        let fork_filter = ForkFilter::new(max_block, genesis, fork_data.forks.clone());
+        // `max_block` is the fork head, which may be a timestamp, see `fork_head`.
+        let fork_filter = ForkFilter::new(max_block, genesis, fork_data.forks.iter().copied());
         let status = StatusData {
             network_id,
             total_difficulty: total_difficulty
@@ -68,7 +167,7 @@ impl TryFrom<ethereum_interfaces::sentry::StatusData> for FullStatusData {
             best_hash: best_hash.ok_or_else(|| anyhow!("no best hash"))?.into(),
             fork_data: Forks {
                 genesis,
-                forks: fork_data.forks.into_iter().collect(),
+                forks: fork_data.forks,
             },
         };
 
@@ -103,3 +202,101 @@ pub enum EthProtocolVersion {
     Eth65 = 65,
     Eth66 = 66,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::res::chainspec::MAINNET;
+    use ethereum_forkid::ForkHash;
+    use hex_literal::hex;
+
+    fn fork_id(
+        genesis: H256,
+        config: &ChainConfig,
+        height: u64,
+        timestamp: u64,
+    ) -> (ForkFilter, ForkId) {
+        let block_forks = config.block_forks();
+        let time_forks = config.time_forks();
+        let head = fork_head(&block_forks, &time_forks, BlockNumber(height), timestamp);
+        let filter = ForkFilter::new(head, genesis, block_forks.into_iter().chain(time_forks));
+        let id = filter.current();
+        (filter, id)
+    }
+
+    #[test]
+    fn mainnet_fork_id() {
+        let genesis = H256(hex!(
+            "d4e56740f876aef8c010b86a40d5f56745a118d0906a34e69aec8c0db1cb8fa3"
+        ));
+        let config = ChainConfig {
+            chain_spec: MAINNET.clone(),
+            genesis_hash: genesis,
+        };
+
+        for (height, timestamp, hash, next) in [
+            (0, 1_438_269_973, hex!("fc64ec04"), 1_150_000),
+            (15_049_999, 1_656_586_443, hex!("20c327fc"), 15_050_000),
+            (15_050_000, 1_656_586_444, hex!("f0afd0e3"), 1_681_338_455),
+            (17_034_869, 1_681_338_443, hex!("f0afd0e3"), 1_681_338_455),
+            (17_034_870, 1_681_338_455, hex!("dce96c2d"), 1_710_338_135),
+            (19_426_586, 1_710_338_123, hex!("dce96c2d"), 1_710_338_135),
+            (19_426_587, 1_710_338_135, hex!("9f3d2254"), 0),
+            (20_000_000, 2_000_000_000, hex!("9f3d2254"), 0),
+        ] {
+            assert_eq!(
+                fork_id(genesis, &config, height, timestamp).1,
+                ForkId {
+                    hash: ForkHash(hash),
+                    next
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn time_forks_below_block_forks() {
+        let genesis = H256::repeat_byte(1);
+        let mut chain_spec = MAINNET.clone();
+        chain_spec.genesis.timestamp = 0;
+        chain_spec.upgrades.shanghai_time = Some(10);
+        chain_spec.upgrades.cancun_time = None;
+        let config = ChainConfig {
+            chain_spec,
+            genesis_hash: genesis,
+        };
+
+        // Shanghai time has come, but it is not active until all block forks are passed.
+        let (_, before) = fork_id(genesis, &config, 15_049_999, 15_050_000);
+        assert_eq!(before.next, 15_050_000);
+
+        let (filter, after) = fork_id(genesis, &config, 15_050_000, 15_050_001);
+        assert_eq!(after.next, 0);
+        assert_ne!(after.hash, before.hash);
+
+        assert_eq!(filter.validate(after), Ok(()));
+        // Remote that is behind is fine as long as it knows about the fork it is missing.
+        assert_eq!(filter.validate(before), Ok(()));
+        assert_eq!(
+            filter.validate(ForkId {
+                hash: before.hash,
+                next: 0
+            }),
+            Err(ForkIdError::RemoteStale { remote_next: 0 })
+        );
+        assert_eq!(
+            filter.validate(ForkId {
+                hash: after.hash,
+                next: 20
+            }),
+            Err(ForkIdError::LocalIncompatibleOrStale)
+        );
+        assert_eq!(
+            filter.validate(ForkId {
+                hash: ForkHash(hex!("deadbeef")),
+                next: 0
+            }),
+            Err(ForkIdError::LocalIncompatibleOrStale)
+        );
+    }
+}
@@ -179,11 +179,23 @@ impl BodyDownload {
         target: BlockNumber,
         will_reach_tip: bool,
     ) -> Result<(), StageError> {
-        let requests = Arc::new(RwLock::new(Self::prepare_requests(
-            txn,
-            starting_block,
-            target,
-        )?));
+        let mut requests = Self::prepare_requests(txn, starting_block, target)?;
+
+        // Take bodies of blocks supplied by the consensus engine, if any.
+        let mut prefilled = HashMap::new();
+        requests.retain(|_, &mut (number, hash)| {
+            if let Some(block) = self.consensus.buffered_block(hash) {
+                prefilled.insert(number, (hash, BlockBody::from(block)));
+                false
+            } else {
+                true
+            }
+        });
+        if !prefilled.is_empty() {
+            info!("Using {} buffered block bodies", prefilled.len());
+        }
+
+        let requests = Arc::new(RwLock::new(requests));
         let (pending_responses, mut pending_responses_watch) = PendingResponses::new();
         let pending_responses = Arc::new(Mutex::new(pending_responses));
         let handler = self.node.clone();
@@ -307,7 +319,8 @@ impl BodyDownload {
                 }
             }));
 
-            let mut bodies = HashMap::with_capacity(requests.read().len());
+            let mut bodies = prefilled;
+            bodies.reserve(requests.read().len());
             let mut stats = VecDeque::new();
             let mut total_received = 0;
             let started_at = Instant::now();
@@ -360,12 +373,14 @@ impl BodyDownload {
                                     Block {
                                         transactions,
                                         ommers,
+                                        withdrawals,
                                         ..
                                     },
                                 ),
                             )| BlockBody {
                                 transactions,
                                 ommers,
+                                withdrawals,
                             }).collect();
 
                             if !cached_blocks.is_empty() {
@@ -385,7 +400,16 @@ impl BodyDownload {
                     let tmp = pending_bodies
                         .par_drain(..)
                         .flatten()
-                        .map(|body| ((body.ommers_hash(), body.transactions_root()), body))
+                        .map(|body| {
+                            (
+                                (
+                                    body.ommers_hash(),
+                                    body.transactions_root(),
+                                    body.withdrawals_root(),
+                                ),
+                                body,
+                            )
+                        })
                         .collect::<Vec<_>>();
 
                     let mut requests = requests.write();
@@ -445,14 +469,20 @@ impl BodyDownload {
                 (hash, BlockBody::default())
             });
 
+            let header = header_cur
+                .seek_exact((block_number, hash))
+                .unwrap()
+                .unwrap()
+                .1;
+            // Bodies with nothing in them are not requested.
+            let withdrawals = body
+                .withdrawals
+                .or_else(|| header.withdrawals_root.map(|_| vec![]));
             let block = Block {
-                header: header_cur
-                    .seek_exact((block_number, hash))
-                    .unwrap()
-                    .unwrap()
-                    .1,
+                header,
                 transactions: body.transactions,
                 ommers: body.ommers,
+                withdrawals,
             };
 
             self.consensus
@@ -471,6 +501,7 @@ impl BodyDownload {
                     base_tx_id: TxIndex(base_tx_id),
                     tx_amount: block.transactions.len() as u64,
                     uncles: block.ommers,
+                    withdrawals: block.withdrawals,
                 },
             )?;
 
@@ -487,7 +518,7 @@ impl BodyDownload {
         txn: &mut MdbxTransaction<'_, RW, E>,
         starting_block: BlockNumber,
         target: BlockNumber,
-    ) -> anyhow::Result<HashMap<(H256, H256), (BlockNumber, H256)>> {
+    ) -> anyhow::Result<HashMap<(H256, H256, Option<H256>), (BlockNumber, H256)>> {
         let cap = match target.0.saturating_sub(starting_block.0) + 1 {
             0 => return Ok(HashMap::new()),
             cap => cap as usize,
@@ -502,12 +533,22 @@ impl BodyDownload {
 
         while let Some(Ok((block_number, hash))) = canonical_cursor.next() {
             let (_, header) = header_cursor.seek_exact((block_number, hash))?.unwrap();
-            if header.ommers_hash == EMPTY_LIST_HASH && header.transactions_root == EMPTY_ROOT {
+            if header.ommers_hash == EMPTY_LIST_HASH
+                && header.transactions_root == EMPTY_ROOT
+                && header
+                    .withdrawals_root
+                    .map(|root| root == EMPTY_ROOT)
+                    .unwrap_or(true)
+            {
                 continue;
             }
 
             map.insert(
-                (header.ommers_hash, header.transactions_root),
+                (
+                    header.ommers_hash,
+                    header.transactions_root,
+                    header.withdrawals_root,
+                ),
                 (block_number, hash),
             );
         }
@@ -28,6 +28,9 @@ pub struct Execution {
     pub exit_after_batch: bool,
     pub batch_until: Option<BlockNumber>,
     pub commit_every: Option<Duration>,
+    pub write_receipts: bool,
+    /// Speculatively execute transactions of each block in parallel.
+    pub parallel: bool,
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -39,6 +42,8 @@ fn execute_batch_of_blocks<E: EnvironmentKind>(
     history_batch_size: u64,
     batch_until: Option<BlockNumber>,
     commit_every: Option<Duration>,
+    write_receipts: bool,
+    parallel: bool,
     starting_block: BlockNumber,
     first_started_at: (Instant, Option<BlockNumber>),
 ) -> Result<BlockNumber, StageError> {
@@ -46,7 +51,7 @@ fn execute_batch_of_blocks<E: EnvironmentKind>(
     consensus_engine.set_state(ConsensusState::recover(tx, &chain_config, starting_block)?);
 
     let mut buffer = Buffer::new(tx, None);
-    let mut analysis_cache = AnalysisCache::default();
+    let mut analysis_cache = AnalysisCache::global();
 
     let mut block_number = starting_block;
     let mut gas_since_start = 0;
@@ -73,14 +78,14 @@ fn execute_batch_of_blocks<E: EnvironmentKind>(
                 format_err!("Block body not found: {}/{:?}", block_number, block_hash)
             })?;
 
-        let block_spec = chain_config.collect_block_spec(block_number);
+        let block_spec = chain_config.collect_block_spec(block_number, header.timestamp);
 
         if !consensus_engine.is_state_valid(&header) {
             consensus_engine.set_state(ConsensusState::recover(tx, &chain_config, block_number)?);
         }
 
         let mut call_tracer = CallTracer::default();
-        let receipts = ExecutionProcessor::new(
+        let mut processor = ExecutionProcessor::new(
             &mut buffer,
             &mut call_tracer,
             &mut analysis_cache,
@@ -88,9 +93,9 @@ fn execute_batch_of_blocks<E: EnvironmentKind>(
             &header,
             &block,
             &block_spec,
-        )
-        .execute_and_write_block()
-        .map_err(|e| match e {
+        );
+        processor.set_parallel_execution(parallel);
+        let receipts = processor.execute_and_write_block().map_err(|e| match e {
             DuoError::Validation(error) => StageError::Validation {
                 block: block_number,
                 error,
@@ -101,6 +106,10 @@ fn execute_batch_of_blocks<E: EnvironmentKind>(
             ))),
         })?;
 
+        if write_receipts {
+            tx.set(tables::Receipt, block_number, receipts.clone())?;
+        }
+
         buffer.insert_receipts(block_number, receipts);
 
         {
@@ -220,6 +229,8 @@ where
                 self.history_batch_size,
                 self.batch_until,
                 self.commit_every,
+                self.write_receipts,
+                self.parallel,
                 starting_block,
                 input.first_started_at,
             );
@@ -333,6 +344,9 @@ where
         info!("Unwinding call trace sets");
         unwind_by_block_key_duplicates(tx, tables::CallTraceSet, input, std::convert::identity)?;
 
+        info!("Unwinding receipts");
+        unwind_by_block_key(tx, tables::Receipt, input, std::convert::identity)?;
+
         Ok(UnwindOutput {
             stage_progress: input.unwind_to,
         })
@@ -361,6 +375,7 @@ where
         )?;
         prune_by_block_key_duplicates(tx, tables::LogTopicsByBlock, input, std::convert::identity)?;
         prune_by_block_key_duplicates(tx, tables::CallTraceSet, input, std::convert::identity)?;
+        prune_by_block_key(tx, tables::Receipt, input, std::convert::identity)?;
 
         Ok(())
     }
@@ -329,6 +329,7 @@ mod tests {
                 deployment_code.into_iter().chain(contract_code).collect(),
             )],
             ommers: vec![],
+            withdrawals: None,
         };
 
         let mut buffer = Buffer::new(&tx, None);
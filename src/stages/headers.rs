@@ -102,39 +102,63 @@ where
                     };
                     let _ = chain_finalized_hash;
 
-                    info!("Received chain tip hash: {chain_tip_hash}, starting_download");
+                    if let Some((fork_point, buffered_headers)) =
+                        self.buffered_chain(txn, chain_tip_hash)?
+                    {
+                        if fork_point < prev_progress {
+                            info!("Chain tip {chain_tip_hash} forks off at block {fork_point}, unwinding");
+                            return Ok(ExecOutput::Unwind {
+                                unwind_to: fork_point,
+                            });
+                        }
 
-                    let mut stream = self.node.stream_headers().await;
+                        info!(
+                            "Received chain tip hash: {chain_tip_hash}, using {} buffered headers",
+                            buffered_headers.len()
+                        );
 
-                    match self
-                        .reverse_download_linear(
-                            &mut stream,
-                            prev_progress_hash,
-                            &prev_progress_block,
-                            chain_tip_hash,
-                            chain_finalized_hash,
-                        )
-                        .await
-                    {
-                        LinearDownloadResult::Done(buffered_headers) => (
-                            Box::new(buffered_headers.into_values())
+                        (
+                            Box::new(buffered_headers.into_iter())
                                 as Box<dyn Iterator<Item = (H256, BlockHeader)> + Send>,
                             true,
-                        ),
-                        LinearDownloadResult::DoesNotAttach => {
-                            return Ok(ExecOutput::Unwind {
-                                unwind_to: prev_progress
-                                    .checked_sub(1)
-                                    .ok_or_else(|| format_err!("Attempting to reorg past genesis"))?
-                                    .into(),
-                            })
-                        }
-                        LinearDownloadResult::NoResponse => {
-                            return Ok(ExecOutput::Progress {
-                                stage_progress: prev_progress,
-                                done: false,
-                                reached_tip: false,
-                            });
+                        )
+                    } else {
+                        info!("Received chain tip hash: {chain_tip_hash}, starting_download");
+
+                        let mut stream = self.node.stream_headers().await;
+
+                        match self
+                            .reverse_download_linear(
+                                &mut stream,
+                                prev_progress_hash,
+                                &prev_progress_block,
+                                chain_tip_hash,
+                                chain_finalized_hash,
+                            )
+                            .await
+                        {
+                            LinearDownloadResult::Done(buffered_headers) => (
+                                Box::new(buffered_headers.into_values())
+                                    as Box<dyn Iterator<Item = (H256, BlockHeader)> + Send>,
+                                true,
+                            ),
+                            LinearDownloadResult::DoesNotAttach => {
+                                return Ok(ExecOutput::Unwind {
+                                    unwind_to: prev_progress
+                                        .checked_sub(1)
+                                        .ok_or_else(|| {
+                                            format_err!("Attempting to reorg past genesis")
+                                        })?
+                                        .into(),
+                                })
+                            }
+                            LinearDownloadResult::NoResponse => {
+                                return Ok(ExecOutput::Progress {
+                                    stage_progress: prev_progress,
+                                    done: false,
+                                    reached_tip: false,
+                                });
+                            }
                         }
                     }
                 }
@@ -298,6 +322,35 @@ enum LinearDownloadResult {
 impl HeaderDownload {
     const BACK_OFF: Duration = Duration::from_secs(5);
 
+    /// Assembles the chain leading to `tip` from blocks buffered by the consensus engine.
+    ///
+    /// Returns the canonical block it forks off at along with the headers on top of it,
+    /// or `None` if the chain does not attach to the canonical chain.
+    fn buffered_chain<K: TransactionKind, E: EnvironmentKind>(
+        &self,
+        txn: &MdbxTransaction<'_, K, E>,
+        tip: H256,
+    ) -> anyhow::Result<Option<(BlockNumber, Vec<(H256, BlockHeader)>)>> {
+        let mut headers = vec![];
+        let mut hash = tip;
+        loop {
+            if let Some(number) = txn.get(tables::HeaderNumber, hash)? {
+                if txn.get(tables::CanonicalHeader, number)? == Some(hash) {
+                    headers.reverse();
+                    return Ok(Some((number, headers)));
+                }
+            }
+
+            if let Some(block) = self.consensus.buffered_block(hash) {
+                let parent_hash = block.header.parent_hash;
+                headers.push((hash, block.header));
+                hash = parent_hash;
+            } else {
+                return Ok(None);
+            }
+        }
+    }
+
     async fn reverse_download_linear(
         &self,
         stream: &mut NodeStream,
@@ -578,7 +631,8 @@ impl HeaderDownload {
         let td = txn
             .get(tables::HeadersTotalDifficulty, (height, hash))?
             .unwrap();
-        let status = Status::new(height, hash, td);
+        let timestamp = txn.get(tables::Header, (height, hash))?.unwrap().timestamp;
+        let status = Status::new(height, hash, td, timestamp);
         self.node.update_chain_head(Some(status)).await;
         Ok(())
     }
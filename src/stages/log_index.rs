@@ -0,0 +1,393 @@
+use crate::{
+    etl::collector::*,
+    kv::{mdbx::*, tables},
+    models::*,
+    stagedsync::{stage::*, stages::*},
+    stages::stage_util::*,
+    StageId,
+};
+use anyhow::format_err;
+use async_trait::async_trait;
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tempfile::TempDir;
+use tokio::pin;
+use tracing::info;
+
+/// Generate log address and topic indexes
+#[derive(Debug)]
+pub struct LogIndex {
+    pub temp_dir: Arc<TempDir>,
+    pub flush_interval: u64,
+}
+
+#[async_trait]
+impl<'db, E> Stage<'db, E> for LogIndex
+where
+    E: EnvironmentKind,
+{
+    fn id(&self) -> StageId {
+        LOG_INDEX
+    }
+
+    async fn execute<'tx>(
+        &mut self,
+        tx: &'tx mut MdbxTransaction<'db, RW, E>,
+        input: StageInput,
+    ) -> Result<ExecOutput, StageError>
+    where
+        'db: 'tx,
+    {
+        let starting_block = input.stage_progress.unwrap_or(BlockNumber(0));
+        let max_block = input
+            .previous_stage
+            .ok_or_else(|| format_err!("Log index generation cannot be the first stage"))?
+            .1;
+
+        let mut addresses_collector =
+            Collector::<Address, croaring::Treemap>::new(&*self.temp_dir, OPTIMAL_BUFFER_CAPACITY);
+        let mut topics_collector =
+            Collector::<H256, croaring::Treemap>::new(&*self.temp_dir, OPTIMAL_BUFFER_CAPACITY);
+
+        let mut printed = false;
+
+        {
+            let mut addresses = HashMap::<Address, croaring::Treemap>::new();
+
+            let cursor = tx.cursor(tables::LogAddressesByBlock)?;
+            let walker = cursor.walk(Some(starting_block + 1));
+            pin!(walker);
+
+            let mut highest_block = starting_block;
+            let mut last_flush = starting_block;
+            let mut last_log = Instant::now();
+            while let Some((block_number, address)) = walker.next().transpose()? {
+                if block_number > max_block {
+                    break;
+                }
+
+                addresses.entry(address).or_default().add(block_number.0);
+
+                if highest_block != block_number {
+                    highest_block = block_number;
+
+                    if highest_block.0 - last_flush.0 >= self.flush_interval {
+                        flush_bitmap(&mut addresses_collector, &mut addresses);
+
+                        last_flush = highest_block;
+                    }
+                }
+
+                let now = Instant::now();
+                if now - last_log > Duration::from_secs(30) {
+                    info!("Current block (addresses): {}", block_number);
+                    printed = true;
+                    last_log = now;
+                }
+            }
+
+            flush_bitmap(&mut addresses_collector, &mut addresses);
+        }
+
+        {
+            let mut topics = HashMap::<H256, croaring::Treemap>::new();
+
+            let cursor = tx.cursor(tables::LogTopicsByBlock)?;
+            let walker = cursor.walk(Some(starting_block + 1));
+            pin!(walker);
+
+            let mut highest_block = starting_block;
+            let mut last_flush = starting_block;
+            let mut last_log = Instant::now();
+            while let Some((block_number, topic)) = walker.next().transpose()? {
+                if block_number > max_block {
+                    break;
+                }
+
+                topics.entry(topic).or_default().add(block_number.0);
+
+                if highest_block != block_number {
+                    highest_block = block_number;
+
+                    if highest_block.0 - last_flush.0 >= self.flush_interval {
+                        flush_bitmap(&mut topics_collector, &mut topics);
+
+                        last_flush = highest_block;
+                    }
+                }
+
+                let now = Instant::now();
+                if now - last_log > Duration::from_secs(30) {
+                    info!("Current block (topics): {}", block_number);
+                    printed = true;
+                    last_log = now;
+                }
+            }
+
+            flush_bitmap(&mut topics_collector, &mut topics);
+        }
+
+        if printed {
+            info!("Flushing address index");
+        }
+        load_bitmap(
+            &mut tx.cursor(tables::LogAddressIndex)?,
+            addresses_collector,
+        )?;
+
+        if printed {
+            info!("Flushing topic index");
+        }
+        load_bitmap(&mut tx.cursor(tables::LogTopicIndex)?, topics_collector)?;
+
+        Ok(ExecOutput::Progress {
+            stage_progress: max_block,
+            done: true,
+            reached_tip: true,
+        })
+    }
+
+    async fn unwind<'tx>(
+        &mut self,
+        tx: &'tx mut MdbxTransaction<'db, RW, E>,
+        input: UnwindInput,
+    ) -> anyhow::Result<UnwindOutput>
+    where
+        'db: 'tx,
+    {
+        let mut addresses = BTreeSet::<Address>::new();
+        {
+            let cursor = tx.cursor(tables::LogAddressesByBlock)?;
+            let walker = cursor.walk(Some(input.unwind_to + 1));
+            pin!(walker);
+            while let Some((_, address)) = walker.next().transpose()? {
+                addresses.insert(address);
+            }
+        }
+
+        let mut topics = BTreeSet::<H256>::new();
+        {
+            let cursor = tx.cursor(tables::LogTopicsByBlock)?;
+            let walker = cursor.walk(Some(input.unwind_to + 1));
+            pin!(walker);
+            while let Some((_, topic)) = walker.next().transpose()? {
+                topics.insert(topic);
+            }
+        }
+
+        unwind_bitmap(
+            &mut tx.cursor(tables::LogAddressIndex)?,
+            addresses,
+            input.unwind_to,
+        )?;
+        unwind_bitmap(
+            &mut tx.cursor(tables::LogTopicIndex)?,
+            topics,
+            input.unwind_to,
+        )?;
+
+        Ok(UnwindOutput {
+            stage_progress: input.unwind_to,
+        })
+    }
+
+    async fn prune<'tx>(
+        &mut self,
+        tx: &'tx mut MdbxTransaction<'db, RW, E>,
+        input: PruningInput,
+    ) -> anyhow::Result<()>
+    where
+        'db: 'tx,
+    {
+        let mut addresses = BTreeSet::<Address>::new();
+        {
+            let cursor = tx.cursor(tables::LogAddressesByBlock)?;
+            let walker = cursor.walk(None);
+            pin!(walker);
+            while let Some((b, address)) = walker.next().transpose()? {
+                if b >= input.prune_to {
+                    break;
+                }
+
+                addresses.insert(address);
+            }
+        }
+
+        let mut topics = BTreeSet::<H256>::new();
+        {
+            let cursor = tx.cursor(tables::LogTopicsByBlock)?;
+            let walker = cursor.walk(None);
+            pin!(walker);
+            while let Some((b, topic)) = walker.next().transpose()? {
+                if b >= input.prune_to {
+                    break;
+                }
+
+                topics.insert(topic);
+            }
+        }
+
+        prune_bitmap(
+            &mut tx.cursor(tables::LogAddressIndex)?,
+            addresses,
+            input.prune_to,
+        )?;
+        prune_bitmap(
+            &mut tx.cursor(tables::LogTopicIndex)?,
+            topics,
+            input.prune_to,
+        )?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitmapdb;
+    use std::time::Instant;
+
+    #[tokio::test]
+    async fn log_index() {
+        let db = crate::kv::new_mem_chaindata().unwrap();
+
+        let mut tx = db.begin_mutable().unwrap();
+
+        for i in 0..30 {
+            let mut address = [0; 20];
+            address[19] = u8::try_from(i % 5).unwrap();
+
+            let mut topic = [0; 32];
+            topic[31] = u8::try_from(i % 3).unwrap();
+
+            tx.set(
+                tables::LogAddressesByBlock,
+                BlockNumber(i),
+                Address::from(address),
+            )
+            .unwrap();
+            tx.set(tables::LogTopicsByBlock, BlockNumber(i), H256::from(topic))
+                .unwrap();
+        }
+
+        let mut address = Address::zero();
+        address.0[19] = 1;
+
+        let mut topic = H256::zero();
+        topic.0[31] = 2;
+
+        fn addresses<K: TransactionKind, E: EnvironmentKind>(
+            tx: &MdbxTransaction<'_, K, E>,
+            address: Address,
+        ) -> croaring::Treemap {
+            bitmapdb::get(
+                tx,
+                tables::LogAddressIndex,
+                address,
+                BlockNumber(0)..=BlockNumber(30),
+            )
+            .unwrap()
+        }
+
+        fn topics<K: TransactionKind, E: EnvironmentKind>(
+            tx: &MdbxTransaction<'_, K, E>,
+            topic: H256,
+        ) -> croaring::Treemap {
+            bitmapdb::get(
+                tx,
+                tables::LogTopicIndex,
+                topic,
+                BlockNumber(0)..=BlockNumber(30),
+            )
+            .unwrap()
+        }
+
+        let stage = || LogIndex {
+            temp_dir: Arc::new(TempDir::new().unwrap()),
+            flush_interval: 0,
+        };
+
+        assert_eq!(
+            (stage)()
+                .execute(
+                    &mut tx,
+                    StageInput {
+                        restarted: false,
+                        first_started_at: (Instant::now(), Some(BlockNumber(0))),
+                        previous_stage: Some((EXECUTION, BlockNumber(20))),
+                        stage_progress: None,
+                    },
+                )
+                .await
+                .unwrap(),
+            ExecOutput::Progress {
+                stage_progress: BlockNumber(20),
+                done: true,
+                reached_tip: true,
+            }
+        );
+
+        assert_eq!(
+            vec![1, 6, 11, 16],
+            (addresses)(&tx, address).iter().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![2, 5, 8, 11, 14, 17, 20],
+            (topics)(&tx, topic).iter().collect::<Vec<_>>()
+        );
+
+        (stage)()
+            .unwind(
+                &mut tx,
+                UnwindInput {
+                    stage_progress: BlockNumber(20),
+                    unwind_to: BlockNumber(10),
+                    bad_block: None,
+                },
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            vec![1, 6],
+            (addresses)(&tx, address).iter().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![2, 5, 8],
+            (topics)(&tx, topic).iter().collect::<Vec<_>>()
+        );
+
+        assert_eq!(
+            (stage)()
+                .execute(
+                    &mut tx,
+                    StageInput {
+                        restarted: false,
+                        first_started_at: (Instant::now(), Some(BlockNumber(10))),
+                        previous_stage: Some((EXECUTION, BlockNumber(30))),
+                        stage_progress: Some(BlockNumber(10)),
+                    },
+                )
+                .await
+                .unwrap(),
+            ExecOutput::Progress {
+                stage_progress: BlockNumber(30),
+                done: true,
+                reached_tip: true,
+            }
+        );
+
+        assert_eq!(
+            vec![1, 6, 11, 16, 21, 26],
+            (addresses)(&tx, address).iter().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![2, 5, 8, 11, 14, 17, 20, 23, 26, 29],
+            (topics)(&tx, topic).iter().collect::<Vec<_>>()
+        );
+    }
+}
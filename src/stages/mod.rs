@@ -7,11 +7,13 @@ mod hashstate;
 mod headers;
 mod history_index;
 mod interhashes;
+mod log_index;
 mod sender_recovery;
 mod stage_util;
 mod total_gas_index;
 mod total_tx_index;
 mod tx_lookup;
+mod tx_pool;
 
 pub use block_hashes::BlockHashes;
 pub use bodies::BodyDownload;
@@ -22,7 +24,9 @@ pub use hashstate::{promote_clean_accounts, promote_clean_storage, HashState};
 pub use headers::HeaderDownload;
 pub use history_index::{AccountHistoryIndex, StorageHistoryIndex};
 pub use interhashes::Interhashes;
+pub use log_index::LogIndex;
 pub use sender_recovery::SenderRecovery;
 pub use total_gas_index::TotalGasIndex;
 pub use total_tx_index::TotalTxIndex;
 pub use tx_lookup::TxLookup;
+pub use tx_pool::TxPool;
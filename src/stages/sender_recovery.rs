@@ -214,6 +214,7 @@ mod tests {
             base_tx_id: 1.into(),
             tx_amount: 2,
             uncles: vec![],
+            withdrawals: None,
         };
 
         let tx1_1 = MessageWithSignature {
@@ -264,6 +265,7 @@ mod tests {
             base_tx_id: 3.into(),
             tx_amount: 3,
             uncles: vec![],
+            withdrawals: None,
         };
 
         let tx2_1 = MessageWithSignature {
@@ -336,6 +338,7 @@ mod tests {
             base_tx_id: 6.into(),
             tx_amount: 0,
             uncles: vec![],
+            withdrawals: None,
         };
 
         let hash1 = H256::random();
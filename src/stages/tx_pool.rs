@@ -0,0 +1,96 @@
+use crate::{
+    accessors::chain,
+    kv::mdbx::*,
+    models::*,
+    stagedsync::{stage::*, stages::*},
+    txpool::TransactionPool,
+    StageId,
+};
+use anyhow::format_err;
+use async_trait::async_trait;
+use std::sync::Arc;
+
+/// Maximum number of reverted blocks whose transactions are returned to the pool.
+const MAX_REINJECTED_BLOCKS: u64 = 128;
+
+/// Keep transaction pool in sync with canonical chain
+#[derive(Debug)]
+pub struct TxPool<E>
+where
+    E: EnvironmentKind,
+{
+    pool: Arc<TransactionPool<E>>,
+    reverted: Vec<MessageWithSignature>,
+}
+
+impl<E> TxPool<E>
+where
+    E: EnvironmentKind,
+{
+    pub fn new(pool: Arc<TransactionPool<E>>) -> Self {
+        Self {
+            pool,
+            reverted: vec![],
+        }
+    }
+}
+
+#[async_trait]
+impl<'db, E> Stage<'db, E> for TxPool<E>
+where
+    E: EnvironmentKind,
+{
+    fn id(&self) -> StageId {
+        TX_POOL
+    }
+
+    async fn execute<'tx>(
+        &mut self,
+        tx: &'tx mut MdbxTransaction<'db, RW, E>,
+        input: StageInput,
+    ) -> Result<ExecOutput, StageError>
+    where
+        'db: 'tx,
+    {
+        let max_block = input
+            .previous_stage
+            .ok_or_else(|| format_err!("Transaction pool cannot be the first stage"))?
+            .1;
+
+        self.pool
+            .update_head(tx, max_block, std::mem::take(&mut self.reverted))?;
+
+        Ok(ExecOutput::Progress {
+            stage_progress: max_block,
+            done: true,
+            reached_tip: true,
+        })
+    }
+
+    async fn unwind<'tx>(
+        &mut self,
+        tx: &'tx mut MdbxTransaction<'db, RW, E>,
+        input: UnwindInput,
+    ) -> anyhow::Result<UnwindOutput>
+    where
+        'db: 'tx,
+    {
+        // State is not unwound yet, so reverted transactions are only validated
+        // once the pool moves onto the new head.
+        let from = std::cmp::max(
+            input.unwind_to + 1,
+            BlockNumber(input.stage_progress.0.saturating_sub(MAX_REINJECTED_BLOCKS)),
+        );
+        for number in from.0..=input.stage_progress.0 {
+            if let Some(hash) = chain::canonical_hash::read(tx, number)? {
+                if let Some(body) = chain::block_body::read_without_senders(tx, hash, number)? {
+                    self.reverted.extend(body.transactions);
+                }
+            }
+        }
+
+        Ok(UnwindOutput {
+            stage_progress: input.unwind_to,
+        })
+    }
+}
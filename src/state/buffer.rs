@@ -1,12 +1,16 @@
 use crate::{
-    accessors, h256_to_u256,
+    accessors,
+    crypto::keccak256,
+    h256_to_u256,
     kv::{
         mdbx::*,
         tables::{self, AccountChange, StorageChange, StorageChangeKey},
     },
     models::*,
+    stagedsync::stages::EXECUTION,
     state::database::*,
-    u256_to_h256, BlockReader, HeaderReader, StateReader, StateWriter,
+    trie::{HashedStateOverlay, HashedStorageOverlay},
+    u256_to_h256, BlockReader, ConcurrentReader, HeaderReader, StateReader, StateWriter,
 };
 use bytes::Bytes;
 use std::collections::{BTreeMap, BTreeSet, HashMap};
@@ -92,6 +96,36 @@ where
             ),
         );
     }
+
+    /// Buffered state changes in hashed form, for computing state root without writing them out.
+    pub fn hashed_state_overlay(&self) -> HashedStateOverlay {
+        HashedStateOverlay {
+            accounts: self
+                .accounts
+                .iter()
+                .map(|(&address, &account)| (keccak256(address), account))
+                .collect(),
+            storage: self
+                .storage
+                .iter()
+                .map(|(&address, storage)| {
+                    (
+                        keccak256(address),
+                        HashedStorageOverlay {
+                            erased: storage.erased,
+                            slots: storage
+                                .slots
+                                .iter()
+                                .map(|(&location, &value)| {
+                                    (keccak256(u256_to_h256(location)), value)
+                                })
+                                .collect(),
+                        },
+                    )
+                })
+                .collect(),
+        }
+    }
 }
 
 impl<'db, 'tx, K, E> HeaderReader for MdbxTransaction<'db, K, E>
@@ -124,6 +158,57 @@ where
     }
 }
 
+impl<'db, 'tx, K, E> Buffer<'db, 'tx, K, E>
+where
+    'db: 'tx,
+    K: TransactionKind,
+    E: EnvironmentKind,
+{
+    fn read_account_from<TK: TransactionKind>(
+        &self,
+        txn: &MdbxTransaction<'_, TK, E>,
+        address: Address,
+    ) -> anyhow::Result<Option<Account>> {
+        if let Some(account) = self.accounts.get(&address) {
+            return Ok(*account);
+        }
+
+        accessors::state::account::read(txn, address, self.historical_block)
+    }
+
+    fn read_code_from<TK: TransactionKind>(
+        &self,
+        txn: &MdbxTransaction<'_, TK, E>,
+        code_hash: H256,
+    ) -> anyhow::Result<Bytes> {
+        if let Some(code) = self.hash_to_code.get(&code_hash).cloned() {
+            Ok(code)
+        } else {
+            Ok(txn
+                .get(tables::Code, code_hash)?
+                .map(From::from)
+                .unwrap_or_default())
+        }
+    }
+
+    fn read_storage_from<TK: TransactionKind>(
+        &self,
+        txn: &MdbxTransaction<'_, TK, E>,
+        address: Address,
+        location: U256,
+    ) -> anyhow::Result<U256> {
+        if let Some(account_storage) = self.storage.get(&address) {
+            if let Some(value) = account_storage.slots.get(&location) {
+                return Ok(*value);
+            } else if account_storage.erased {
+                return Ok(U256::ZERO);
+            }
+        }
+
+        accessors::state::storage::read(txn, address, location, self.historical_block)
+    }
+}
+
 impl<'db, 'tx, K, E> HeaderReader for Buffer<'db, 'tx, K, E>
 where
     'db: 'tx,
@@ -146,35 +231,76 @@ where
     E: EnvironmentKind,
 {
     fn read_account(&self, address: Address) -> anyhow::Result<Option<Account>> {
-        if let Some(account) = self.accounts.get(&address) {
-            return Ok(*account);
-        }
-
-        accessors::state::account::read(self.txn, address, self.historical_block)
+        self.read_account_from(self.txn, address)
     }
 
     fn read_code(&self, code_hash: H256) -> anyhow::Result<Bytes> {
-        if let Some(code) = self.hash_to_code.get(&code_hash).cloned() {
-            Ok(code)
-        } else {
-            Ok(self
-                .txn
-                .get(tables::Code, code_hash)?
-                .map(From::from)
-                .unwrap_or_default())
-        }
+        self.read_code_from(self.txn, code_hash)
     }
 
     fn read_storage(&self, address: Address, location: U256) -> anyhow::Result<U256> {
-        if let Some(account_storage) = self.storage.get(&address) {
-            if let Some(value) = account_storage.slots.get(&location) {
-                return Ok(*value);
-            } else if account_storage.erased {
-                return Ok(U256::ZERO);
-            }
+        self.read_storage_from(self.txn, address, location)
+    }
+
+    /// Buffered changes along with a read-only transaction of its own, since transaction of the
+    /// buffer may not be used from other threads.
+    ///
+    /// Read-only transaction only sees committed data, so `None` if the buffer's transaction holds
+    /// execution progress which is not committed yet: state written by previous batches would be
+    /// read as stale and every speculatively executed transaction re-executed.
+    fn concurrent_reader(&self) -> anyhow::Result<Option<Box<dyn ConcurrentReader + '_>>> {
+        let txn = self.txn.begin_ro()?;
+        if EXECUTION.get_progress(&txn)? != EXECUTION.get_progress(self.txn)? {
+            return Ok(None);
         }
 
-        accessors::state::storage::read(self.txn, address, location, self.historical_block)
+        Ok(Some(Box::new(BufferSnapshot { buffer: self, txn })))
+    }
+}
+
+/// [`Buffer`] read through a separate read-only transaction.
+#[derive(Debug)]
+struct BufferSnapshot<'b, 'db, 'tx, K, E>
+where
+    'db: 'tx,
+    K: TransactionKind,
+    E: EnvironmentKind,
+{
+    buffer: &'b Buffer<'db, 'tx, K, E>,
+    txn: MdbxTransaction<'b, RO, E>,
+}
+
+impl<'b, 'db, 'tx, K, E> HeaderReader for BufferSnapshot<'b, 'db, 'tx, K, E>
+where
+    'db: 'tx,
+    K: TransactionKind,
+    E: EnvironmentKind,
+{
+    fn read_header(
+        &self,
+        block_number: BlockNumber,
+        block_hash: H256,
+    ) -> anyhow::Result<Option<BlockHeader>> {
+        self.txn.read_header(block_number, block_hash)
+    }
+}
+
+impl<'b, 'db, 'tx, K, E> StateReader for BufferSnapshot<'b, 'db, 'tx, K, E>
+where
+    'db: 'tx,
+    K: TransactionKind,
+    E: EnvironmentKind,
+{
+    fn read_account(&self, address: Address) -> anyhow::Result<Option<Account>> {
+        self.buffer.read_account_from(&self.txn, address)
+    }
+
+    fn read_code(&self, code_hash: H256) -> anyhow::Result<Bytes> {
+        self.buffer.read_code_from(&self.txn, code_hash)
+    }
+
+    fn read_storage(&self, address: Address, location: U256) -> anyhow::Result<U256> {
+        self.buffer.read_storage_from(&self.txn, address, location)
     }
 }
 
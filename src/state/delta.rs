@@ -44,6 +44,14 @@ pub enum Delta {
     AccountAccess {
         address: Address,
     },
+    TransientStorageChange {
+        address: Address,
+        key: U256,
+        previous: U256,
+    },
+    ContractCreate {
+        address: Address,
+    },
 }
 
 impl Delta {
@@ -110,6 +118,20 @@ impl Delta {
             Delta::AccountAccess { address } => {
                 state.accessed_addresses.remove(&address);
             }
+            Delta::TransientStorageChange {
+                address,
+                key,
+                previous,
+            } => {
+                state
+                    .transient_storage
+                    .entry(address)
+                    .or_default()
+                    .insert(key, previous);
+            }
+            Delta::ContractCreate { address } => {
+                state.created_contracts.remove(&address);
+            }
         }
     }
 }
@@ -38,6 +38,10 @@ impl GenesisState {
         let genesis = &self.chain_spec.genesis;
         let seal = &genesis.seal;
         let state_root = initial_state.state_root_hash();
+        let revision = self
+            .chain_spec
+            .collect_block_spec(genesis.number, genesis.timestamp)
+            .revision;
 
         BlockHeader {
             parent_hash: H256::zero(),
@@ -53,6 +57,10 @@ impl GenesisState {
             mix_hash: seal.mix_hash(),
             nonce: seal.nonce(),
             base_fee_per_gas: genesis.base_fee_per_gas,
+            withdrawals_root: (revision >= Revision::Shanghai).then_some(EMPTY_ROOT),
+            blob_gas_used: (revision >= Revision::Cancun).then_some(0),
+            excess_blob_gas: (revision >= Revision::Cancun).then_some(0),
+            parent_beacon_block_root: (revision >= Revision::Cancun).then_some(H256::zero()),
 
             receipts_root: EMPTY_ROOT,
             ommers_hash: EMPTY_LIST_HASH,
@@ -111,6 +119,9 @@ where
     crate::stages::promote_clean_storage(txn, etl_temp_dir)?;
     let state_root = crate::trie::regenerate_intermediate_hashes(txn, etl_temp_dir, None)?;
 
+    let revision = chainspec
+        .collect_block_spec(genesis, chainspec.genesis.timestamp)
+        .revision;
     let header = BlockHeader {
         parent_hash: H256::zero(),
         beneficiary: chainspec.genesis.author,
@@ -125,6 +136,10 @@ where
         mix_hash: chainspec.genesis.seal.mix_hash(),
         nonce: chainspec.genesis.seal.nonce(),
         base_fee_per_gas: chainspec.genesis.base_fee_per_gas,
+        withdrawals_root: (revision >= Revision::Shanghai).then_some(EMPTY_ROOT),
+        blob_gas_used: (revision >= Revision::Cancun).then_some(0),
+        excess_blob_gas: (revision >= Revision::Cancun).then_some(0),
+        parent_beacon_block_root: (revision >= Revision::Cancun).then_some(H256::zero()),
 
         receipts_root: EMPTY_ROOT,
         ommers_hash: EMPTY_LIST_HASH,
@@ -148,6 +163,7 @@ where
             base_tx_id: 0.into(),
             tx_amount: 0,
             uncles: vec![],
+            withdrawals: (revision >= Revision::Shanghai).then(Vec::new),
         },
     )?;
 
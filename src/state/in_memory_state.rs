@@ -3,7 +3,7 @@ use crate::{
     models::*,
     trie::{unpack_nibbles, HashBuilder},
     util::*,
-    BlockReader, HeaderReader, StateReader, StateWriter,
+    BlockReader, ConcurrentReader, HeaderReader, StateReader, StateWriter,
 };
 use bytes::{Bytes, BytesMut};
 use std::{collections::*, convert::TryInto};
@@ -136,6 +136,7 @@ impl InMemoryState {
             header,
             transactions,
             ommers,
+            withdrawals,
         } = block;
 
         let block_number = header.number.0 as usize;
@@ -155,6 +156,7 @@ impl InMemoryState {
             BlockBody {
                 transactions,
                 ommers,
+                withdrawals,
             },
         );
 
@@ -197,6 +199,7 @@ impl InMemoryState {
                             })
                             .collect::<anyhow::Result<_>>()?,
                         ommers: body.ommers.clone(),
+                        withdrawals: body.withdrawals.clone(),
                     })
                 })
                 .transpose();
@@ -304,6 +307,10 @@ impl StateReader for InMemoryState {
 
         Ok(U256::ZERO)
     }
+
+    fn concurrent_reader(&self) -> anyhow::Result<Option<Box<dyn ConcurrentReader + '_>>> {
+        Ok(Some(Box::new(self)))
+    }
 }
 
 impl StateWriter for InMemoryState {
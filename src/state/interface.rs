@@ -29,15 +29,28 @@ pub trait BlockReader: HeaderReader {
     ) -> anyhow::Result<Option<BlockBody>>;
 }
 
-#[auto_impl(&mut, Box)]
+#[auto_impl(&mut, &, Box)]
 pub trait StateReader: Debug + Send + Sync {
     fn read_account(&self, address: Address) -> anyhow::Result<Option<Account>>;
 
     fn read_code(&self, code_hash: H256) -> anyhow::Result<Bytes>;
 
     fn read_storage(&self, address: Address, location: U256) -> anyhow::Result<U256>;
+
+    /// Reader of this state that can be used from other threads, e.g. for speculative execution.
+    ///
+    /// It may lag behind this state, but never returns values this state never had.
+    /// `None` if this state cannot be read concurrently.
+    fn concurrent_reader(&self) -> anyhow::Result<Option<Box<dyn ConcurrentReader + '_>>> {
+        Ok(None)
+    }
 }
 
+/// Header and state reader returned by [`StateReader::concurrent_reader`].
+pub trait ConcurrentReader: HeaderReader + StateReader {}
+
+impl<T> ConcurrentReader for T where T: HeaderReader + StateReader {}
+
 #[auto_impl(&mut, Box)]
 pub trait StateWriter: Debug + Send + Sync {
     fn erase_storage(&mut self, address: Address) -> anyhow::Result<()>;
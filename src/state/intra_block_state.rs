@@ -11,12 +11,22 @@ pub struct Snapshot {
     refund: u64,
 }
 
+/// Net changes made by a single transaction, see [`IntraBlockState::into_write_set`].
+#[derive(Debug, Default)]
+pub struct WriteSet {
+    pub accounts: Vec<(Address, Option<Account>)>,
+    /// Number of times storage of an account was wiped.
+    pub incarnations: Vec<(Address, u64)>,
+    pub storage: Vec<(Address, U256, U256)>,
+    pub code: Vec<(H256, Bytes)>,
+}
+
 #[derive(Debug)]
 pub struct IntraBlockState<'db, S>
 where
     S: StateReader,
 {
-    state: &'db mut S,
+    pub(crate) state: &'db mut S,
 
     pub(crate) objects: HashMap<Address, Object>,
     pub(crate) storage: HashMap<Address, Storage>,
@@ -36,6 +46,10 @@ where
     // EIP-2929 substate
     pub(crate) accessed_addresses: HashSet<Address>,
     pub(crate) accessed_storage_keys: HashMap<Address, HashSet<U256>>,
+    // EIP-1153 substate
+    pub(crate) transient_storage: HashMap<Address, HashMap<U256, U256>>,
+    // EIP-6780 substate
+    pub(crate) created_contracts: HashSet<Address>,
 }
 
 fn get_object<'m, S: StateReader>(
@@ -111,6 +125,8 @@ impl<'r, S: StateReader> IntraBlockState<'r, S> {
             refund: Default::default(),
             accessed_addresses: Default::default(),
             accessed_storage_keys: Default::default(),
+            transient_storage: Default::default(),
+            created_contracts: Default::default(),
         }
     }
 
@@ -184,6 +200,10 @@ impl<'r, S: StateReader> IntraBlockState<'r, S> {
             self.journal.push(Delta::StorageCreate { address });
         }
 
+        if self.created_contracts.insert(address) {
+            self.journal.push(Delta::ContractCreate { address });
+        }
+
         Ok(())
     }
 
@@ -222,10 +242,19 @@ impl<'r, S: StateReader> IntraBlockState<'r, S> {
         Ok(())
     }
 
+    // https://eips.ethereum.org/EIPS/eip-6780
+    pub fn created_in_transaction(&self, address: Address) -> bool {
+        self.created_contracts.contains(&address)
+    }
+
     pub fn number_of_self_destructs(&self) -> usize {
         self.self_destructs.len()
     }
 
+    pub fn get_account(&mut self, address: Address) -> anyhow::Result<Option<Account>> {
+        Ok(get_object(self.state, &mut self.objects, address)?.and_then(|object| object.current))
+    }
+
     pub fn get_balance(&mut self, address: Address) -> anyhow::Result<U256> {
         Ok(get_object(self.state, &mut self.objects, address)?
             .and_then(|object| object.current.as_ref().map(|current| current.balance))
@@ -449,6 +478,32 @@ impl<'r, S: StateReader> IntraBlockState<'r, S> {
         Ok(())
     }
 
+    // https://eips.ethereum.org/EIPS/eip-1153
+    pub fn get_transient_storage(&self, address: Address, key: U256) -> U256 {
+        self.transient_storage
+            .get(&address)
+            .and_then(|storage| storage.get(&key))
+            .copied()
+            .unwrap_or(U256::ZERO)
+    }
+
+    pub fn set_transient_storage(&mut self, address: Address, key: U256, value: U256) {
+        let previous = self.get_transient_storage(address, key);
+        if previous == value {
+            return;
+        }
+        self.transient_storage
+            .entry(address)
+            .or_default()
+            .insert(key, value);
+
+        self.journal.push(Delta::TransientStorageChange {
+            address,
+            key,
+            previous,
+        });
+    }
+
     pub fn take_snapshot(&self) -> Snapshot {
         Snapshot {
             journal_size: self.journal.len(),
@@ -485,6 +540,83 @@ impl<'r, S: StateReader> IntraBlockState<'r, S> {
         // EIP-2929
         self.accessed_addresses.clear();
         self.accessed_storage_keys.clear();
+        // EIP-1153
+        self.transient_storage.clear();
+        // EIP-6780
+        self.created_contracts.clear();
+    }
+
+    /// Changes made on top of the underlying state.
+    ///
+    /// Only meaningful for a state that has executed a single finalized transaction.
+    pub fn into_write_set(self) -> WriteSet {
+        let mut write_set = WriteSet::default();
+
+        for (&address, object) in &self.objects {
+            if object.current != object.initial {
+                write_set.accounts.push((address, object.current));
+            }
+        }
+
+        write_set.incarnations = self.incarnations.into_iter().collect();
+
+        for (address, storage) in self.storage {
+            if let Some(Object {
+                current: Some(_), ..
+            }) = self.objects.get(&address)
+            {
+                for (key, value) in storage.committed {
+                    if value.original != value.initial {
+                        write_set.storage.push((address, key, value.original));
+                    }
+                }
+            }
+        }
+
+        write_set.code = self.new_code.into_iter().collect();
+
+        write_set
+    }
+
+    /// Applies changes of a transaction that was executed on top of this state elsewhere,
+    /// as if it was executed here.
+    pub fn apply_write_set(&mut self, write_set: WriteSet) -> anyhow::Result<()> {
+        for (address, incarnations) in write_set.incarnations {
+            *self.incarnations.entry(address).or_default() += incarnations;
+            self.storage.remove(&address);
+        }
+
+        for (address, current) in write_set.accounts {
+            if let Some(object) = get_object(self.state, &mut self.objects, address)? {
+                object.current = current;
+            } else {
+                self.objects.insert(
+                    address,
+                    Object {
+                        initial: None,
+                        current,
+                    },
+                );
+            }
+        }
+
+        for (address, key, value) in write_set.storage {
+            // Load the value at the beginning of the block first, as set_storage would.
+            self.get_current_storage(address, key)?;
+            self.storage
+                .entry(address)
+                .or_default()
+                .committed
+                .entry(key)
+                .or_default()
+                .original = value;
+        }
+
+        for (code_hash, code) in write_set.code {
+            self.new_code.entry(code_hash).or_insert(code);
+        }
+
+        Ok(())
     }
 
     pub fn add_log(&mut self, log: Log) {
@@ -3,13 +3,13 @@ use crate::{
     models::*,
     trie::{
         node::Node,
-        util::{assert_subset, prefix_length},
+        util::{assert_subset, has_prefix, prefix_length},
     },
 };
 use bytes::{BufMut, Bytes, BytesMut};
 use ethereum_types::H256;
 use fastrlp::{Encodable, RlpEncodable, EMPTY_STRING_CODE};
-use std::{boxed::Box, cmp};
+use std::{boxed::Box, cmp, collections::BTreeMap};
 
 const RLP_EMPTY_STRING_CODE: u8 = 0x80;
 
@@ -50,7 +50,42 @@ fn node_ref(rlp: &[u8]) -> Vec<u8> {
     }
 }
 
-type NodeCollector<'nc> = Box<dyn FnMut(&[u8], &Node) + Send + Sync + 'nc>;
+pub(crate) type NodeCollector<'nc> = Box<dyn FnMut(&[u8], &Node) + Send + Sync + 'nc>;
+
+/// Retains nodes on the paths to target keys, making up their Merkle proofs.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ProofRetainer {
+    targets: Vec<Vec<u8>>,
+    nodes: BTreeMap<Vec<u8>, Vec<u8>>,
+}
+
+impl ProofRetainer {
+    /// Creates a retainer for unpacked `targets`.
+    pub(crate) fn new(targets: Vec<Vec<u8>>) -> Self {
+        Self {
+            targets,
+            nodes: BTreeMap::new(),
+        }
+    }
+
+    fn retain(&mut self, path: &[u8], rlp: &[u8]) {
+        // Nodes shorter than a hash are embedded into their parents, root is always included.
+        if (path.is_empty() || rlp.len() >= KECCAK_LENGTH)
+            && self.targets.iter().any(|target| has_prefix(target, path))
+        {
+            self.nodes.insert(path.to_vec(), rlp.to_vec());
+        }
+    }
+
+    /// RLP of nodes on the path to unpacked `target`, starting with the root.
+    pub(crate) fn proof(&self, target: &[u8]) -> Vec<Bytes> {
+        self.nodes
+            .iter()
+            .filter(|(path, _)| has_prefix(target, path))
+            .map(|(_, rlp)| Bytes::copy_from_slice(rlp))
+            .collect()
+    }
+}
 
 #[derive(Clone)]
 enum HashBuilderValue {
@@ -92,6 +127,7 @@ fn extension_node_rlp(path: &[u8], child_ref: &[u8]) -> BytesMut {
 
 pub struct HashBuilder<'nc> {
     pub(crate) node_collector: Option<NodeCollector<'nc>>,
+    pub(crate) proof_retainer: Option<ProofRetainer>,
     key: Vec<u8>,
     value: HashBuilderValue,
     is_in_db_trie: bool,
@@ -105,6 +141,7 @@ impl<'nc> HashBuilder<'nc> {
     pub fn new(node_collector: Option<NodeCollector<'nc>>) -> Self {
         Self {
             node_collector,
+            proof_retainer: None,
             key: vec![],
             value: HashBuilderValue::Bytes(vec![]),
             is_in_db_trie: false,
@@ -199,9 +236,9 @@ impl<'nc> HashBuilder<'nc> {
                 let value = self.value.clone();
                 match &value {
                     HashBuilderValue::Bytes(leaf_value) => {
-                        let node_ref =
-                            node_ref(leaf_node_rlp(short_node_key.as_slice(), leaf_value).as_ref());
-                        self.stack.push(node_ref);
+                        let rlp = leaf_node_rlp(short_node_key.as_slice(), leaf_value);
+                        self.retain_proof_node(&current[..len_from], &rlp);
+                        self.stack.push(node_ref(&rlp));
                     }
                     HashBuilderValue::Hash(hash) => {
                         self.stack.push(wrap_hash(hash));
@@ -229,10 +266,9 @@ impl<'nc> HashBuilder<'nc> {
                 }
 
                 let stack_last = self.stack.pop().unwrap();
-                let new_stack_last = node_ref(
-                    extension_node_rlp(short_node_key.as_slice(), stack_last.as_slice()).as_ref(),
-                );
-                self.stack.push(new_stack_last);
+                let rlp = extension_node_rlp(short_node_key.as_slice(), stack_last.as_slice());
+                self.retain_proof_node(&current[..len_from], &rlp);
+                self.stack.push(node_ref(&rlp));
 
                 self.hash_masks.resize(len_from, 0u16);
                 self.tree_masks.resize(len_from, 0u16);
@@ -243,7 +279,8 @@ impl<'nc> HashBuilder<'nc> {
             }
 
             if !succeeding.is_empty() || preceding_exists {
-                let child_hashes = self.branch_ref(self.groups[len], self.hash_masks[len]);
+                let child_hashes =
+                    self.branch_ref(&current[..len], self.groups[len], self.hash_masks[len]);
 
                 if self.collects_nodes() {
                     if len > 0 {
@@ -295,7 +332,13 @@ impl<'nc> HashBuilder<'nc> {
         }
     }
 
-    fn branch_ref(&mut self, state_mask: u16, hash_mask: u16) -> Vec<Vec<u8>> {
+    fn retain_proof_node(&mut self, path: &[u8], rlp: &[u8]) {
+        if let Some(proof_retainer) = &mut self.proof_retainer {
+            proof_retainer.retain(path, rlp);
+        }
+    }
+
+    fn branch_ref(&mut self, path: &[u8], state_mask: u16, hash_mask: u16) -> Vec<Vec<u8>> {
         assert_subset(hash_mask, state_mask);
         let mut child_hashes = Vec::<Vec<u8>>::with_capacity(hash_mask.count_ones() as usize);
         let first_child_idx = self.stack.len() - state_mask.count_ones() as usize;
@@ -336,6 +379,8 @@ impl<'nc> HashBuilder<'nc> {
         // branch nodes with values are not supported
         rlp_buffer.put_u8(EMPTY_STRING_CODE);
 
+        self.retain_proof_node(path, &rlp_buffer);
+
         self.stack.resize(first_child_idx, vec![]);
         self.stack.push(node_ref(&rlp_buffer));
 
@@ -473,6 +518,37 @@ mod tests {
         assert_eq!(hb.compute_root_hash(), root_hash);
     }
 
+    #[test]
+    fn test_hash_builder_proof() {
+        let keys = [
+            H256::from_low_u64_be(1),
+            H256::from_low_u64_be(2),
+            H256::repeat_byte(0xff),
+        ];
+        // Long enough for leaves not to be embedded into the branch.
+        let value = vec![0xab; 40];
+        let target = unpack_nibbles(keys[0].as_bytes());
+
+        let mut hb = HashBuilder::new(None);
+        hb.proof_retainer = Some(ProofRetainer::new(vec![target.clone()]));
+        for key in &keys {
+            hb.add_leaf(unpack_nibbles(key.as_bytes()), &value);
+        }
+        let root_hash = hb.compute_root_hash();
+        let proof = hb.proof_retainer.take().unwrap().proof(&target);
+
+        // Root branch, extension over common zeros, branch of the first two keys, leaf.
+        assert_eq!(proof.len(), 4);
+        assert_eq!(keccak256(&proof[0]), root_hash);
+        for nodes in proof.windows(2) {
+            let hash = keccak256(&nodes[1]);
+            assert!(nodes[0]
+                .windows(KECCAK_LENGTH)
+                .any(|window| window == hash.as_bytes()));
+        }
+        assert!(proof[3].ends_with(&value));
+    }
+
     #[test]
     fn test_hash_builder_pack_nibbles() {
         assert_eq!(pack_nibbles(&[]), Vec::<u8>::new());
@@ -6,16 +6,19 @@ use crate::{
     models::*,
     stagedsync::format_duration,
     trie::{
-        hash_builder::{pack_nibbles, unpack_nibbles, HashBuilder},
+        hash_builder::{pack_nibbles, unpack_nibbles, HashBuilder, NodeCollector, ProofRetainer},
         node::{marshal_node, unmarshal_node, Node},
         prefix_set::PrefixSet,
         util::has_prefix,
     },
 };
 use anyhow::Result;
+use bytes::Bytes;
 use parking_lot::Mutex;
 use std::{
+    collections::BTreeMap,
     marker::PhantomData,
+    ops::Bound,
     time::{Duration, Instant},
 };
 use tempfile::TempDir;
@@ -94,12 +97,67 @@ fn increment_key(unpacked: &[u8]) -> Option<Vec<u8>> {
     None
 }
 
-struct Cursor<'cu, 'tx, 'ps, T>
+/// Hashed state changes on top of the state in the database.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct HashedStateOverlay {
+    /// Hashed address -> account, `None` for deleted accounts.
+    pub accounts: BTreeMap<H256, Option<Account>>,
+    pub storage: BTreeMap<H256, HashedStorageOverlay>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct HashedStorageOverlay {
+    /// Storage in the database is discarded.
+    pub erased: bool,
+    /// Hashed location -> value, zero for deleted slots.
+    pub slots: BTreeMap<H256, U256>,
+}
+
+impl HashedStateOverlay {
+    /// Applies `changes` made on top of this overlay.
+    pub fn apply(&mut self, changes: HashedStateOverlay) {
+        self.accounts.extend(changes.accounts);
+        for (hashed_address, storage) in changes.storage {
+            if storage.erased {
+                self.storage.insert(hashed_address, storage);
+            } else {
+                self.storage
+                    .entry(hashed_address)
+                    .or_default()
+                    .slots
+                    .extend(storage.slots);
+            }
+        }
+    }
+}
+
+/// Transaction kinds the trie can be walked in.
+///
+/// Read-write walks drop consumed nodes so that updated ones can be loaded in their place,
+/// read-only walks leave the database intact.
+trait TrieTransactionKind: TransactionKind + Sized {
+    fn drop_consumed_node<T: Table>(cursor: &mut MdbxCursor<'_, Self, T>) -> Result<()>;
+}
+
+impl TrieTransactionKind for RW {
+    fn drop_consumed_node<T: Table>(cursor: &mut MdbxCursor<'_, Self, T>) -> Result<()> {
+        cursor.delete_current()
+    }
+}
+
+impl TrieTransactionKind for RO {
+    fn drop_consumed_node<T: Table>(_: &mut MdbxCursor<'_, Self, T>) -> Result<()> {
+        Ok(())
+    }
+}
+
+struct Cursor<'cu, 'tx, 'ps, K, T>
 where
+    K: TrieTransactionKind,
     T: Table<Key = Vec<u8>, SeekKey = Vec<u8>, Value = Vec<u8>>,
     'tx: 'cu,
 {
-    cursor: Mutex<&'cu mut MdbxCursor<'tx, RW, T>>,
+    cursor: Mutex<&'cu mut MdbxCursor<'tx, K, T>>,
     changed: &'ps mut PrefixSet,
     prefix: Vec<u8>,
     stack: Vec<CursorSubNode>,
@@ -107,16 +165,17 @@ where
     _marker: PhantomData<&'tx T>,
 }
 
-impl<'cu, 'tx, 'ps, T> Cursor<'cu, 'tx, 'ps, T>
+impl<'cu, 'tx, 'ps, K, T> Cursor<'cu, 'tx, 'ps, K, T>
 where
+    K: TrieTransactionKind,
     T: Table<Key = Vec<u8>, SeekKey = Vec<u8>, Value = Vec<u8>>,
     'tx: 'cu,
 {
     fn new(
-        cursor: &'cu mut MdbxCursor<'tx, RW, T>,
+        cursor: &'cu mut MdbxCursor<'tx, K, T>,
         changed: &'ps mut PrefixSet,
         prefix: &[u8],
-    ) -> Result<Cursor<'cu, 'tx, 'ps, T>> {
+    ) -> Result<Cursor<'cu, 'tx, 'ps, K, T>> {
         let mut new_cursor = Self {
             cursor: Mutex::new(cursor),
             changed,
@@ -214,7 +273,7 @@ where
         self.update_skip_state();
 
         if entry.is_some() && (!self.can_skip_state || nibble != -1) {
-            self.cursor.lock().delete_current()?;
+            K::drop_consumed_node(&mut **self.cursor.lock())?;
         }
 
         Ok(())
@@ -266,49 +325,258 @@ where
     }
 }
 
-struct DbTrieLoader<'db, 'tx, 'tmp, 'co, 'nc, E>
+/// Hashed account cursor with overlay changes merged in.
+struct HashedAccountCursor<'tx, 'ov, K>
+where
+    K: TransactionKind,
+{
+    db: MdbxCursor<'tx, K, tables::HashedAccount>,
+    overlay: &'ov BTreeMap<H256, Option<Account>>,
+    db_entry: Option<(H256, Account)>,
+    current: Option<H256>,
+}
+
+impl<'tx, 'ov, K> HashedAccountCursor<'tx, 'ov, K>
+where
+    K: TransactionKind,
+{
+    fn new(
+        db: MdbxCursor<'tx, K, tables::HashedAccount>,
+        overlay: &'ov BTreeMap<H256, Option<Account>>,
+    ) -> Self {
+        Self {
+            db,
+            overlay,
+            db_entry: None,
+            current: None,
+        }
+    }
+
+    fn skip_overlaid(&mut self) -> Result<()> {
+        while let Some((key, _)) = self.db_entry {
+            if !self.overlay.contains_key(&key) {
+                break;
+            }
+            self.db_entry = self.db.next()?;
+        }
+
+        Ok(())
+    }
+
+    fn merge(&mut self, from: Bound<H256>) -> Option<(H256, Account)> {
+        let overlay_entry = self
+            .overlay
+            .range((from, Bound::Unbounded))
+            .find_map(|(&key, account)| account.map(|account| (key, account)));
+
+        let entry = match (self.db_entry, overlay_entry) {
+            (Some(db_entry), Some(overlay_entry)) => Some(if overlay_entry.0 < db_entry.0 {
+                overlay_entry
+            } else {
+                db_entry
+            }),
+            (db_entry, overlay_entry) => db_entry.or(overlay_entry),
+        };
+        self.current = entry.map(|(key, _)| key);
+
+        entry
+    }
+
+    fn seek(&mut self, key: H256) -> Result<Option<(H256, Account)>> {
+        self.db_entry = self.db.seek(key)?;
+        self.skip_overlaid()?;
+
+        Ok(self.merge(Bound::Included(key)))
+    }
+
+    fn next(&mut self) -> Result<Option<(H256, Account)>> {
+        let Some(current) = self.current else {
+            return Ok(None);
+        };
+
+        if matches!(self.db_entry, Some((key, _)) if key == current) {
+            self.db_entry = self.db.next()?;
+            self.skip_overlaid()?;
+        }
+
+        Ok(self.merge(Bound::Excluded(current)))
+    }
+}
+
+/// Hashed storage cursor with overlay changes merged in.
+struct HashedStorageCursor<'tx, 'ov, K>
 where
+    K: TransactionKind,
+{
+    db: MdbxCursor<'tx, K, tables::HashedStorage>,
+    overlay: &'ov BTreeMap<H256, HashedStorageOverlay>,
+    account: H256,
+    db_entry: Option<(H256, U256)>,
+    current: Option<H256>,
+}
+
+impl<'tx, 'ov, K> HashedStorageCursor<'tx, 'ov, K>
+where
+    K: TransactionKind,
+{
+    fn new(
+        db: MdbxCursor<'tx, K, tables::HashedStorage>,
+        overlay: &'ov BTreeMap<H256, HashedStorageOverlay>,
+    ) -> Self {
+        Self {
+            db,
+            overlay,
+            account: H256::zero(),
+            db_entry: None,
+            current: None,
+        }
+    }
+
+    fn skip_overlaid(&mut self) -> Result<()> {
+        if let Some(overlay) = self.overlay.get(&self.account) {
+            while let Some((location, _)) = self.db_entry {
+                if !overlay.slots.contains_key(&location) {
+                    break;
+                }
+                self.db_entry = self.db.next_dup()?.map(|(_, v)| v);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn merge(&mut self, from: Bound<H256>) -> Option<(H256, U256)> {
+        let overlay_entry = self.overlay.get(&self.account).and_then(|overlay| {
+            overlay
+                .slots
+                .range((from, Bound::Unbounded))
+                .find(|(_, &value)| value != U256::ZERO)
+                .map(|(&location, &value)| (location, value))
+        });
+
+        let entry = match (self.db_entry, overlay_entry) {
+            (Some(db_entry), Some(overlay_entry)) => Some(if overlay_entry.0 < db_entry.0 {
+                overlay_entry
+            } else {
+                db_entry
+            }),
+            (db_entry, overlay_entry) => db_entry.or(overlay_entry),
+        };
+        self.current = entry.map(|(location, _)| location);
+
+        entry
+    }
+
+    fn has_storage(&mut self, account: H256) -> Result<bool> {
+        Ok(self.seek_both_range(account, H256::zero())?.is_some())
+    }
+
+    fn seek_both_range(&mut self, account: H256, location: H256) -> Result<Option<(H256, U256)>> {
+        self.account = account;
+        self.db_entry = if self
+            .overlay
+            .get(&account)
+            .map(|overlay| overlay.erased)
+            .unwrap_or(false)
+        {
+            None
+        } else {
+            self.db.seek_both_range(account, location)?
+        };
+        self.skip_overlaid()?;
+
+        Ok(self.merge(Bound::Included(location)))
+    }
+
+    fn next_dup(&mut self) -> Result<Option<(H256, U256)>> {
+        let Some(current) = self.current else {
+            return Ok(None);
+        };
+
+        if matches!(self.db_entry, Some((location, _)) if location == current) {
+            self.db_entry = self.db.next_dup()?.map(|(_, v)| v);
+            self.skip_overlaid()?;
+        }
+
+        Ok(self.merge(Bound::Excluded(current)))
+    }
+}
+
+fn account_node_collector<'tmp: 'nc, 'nc>(
+    collector: &'nc mut TableCollector<'tmp, tables::TrieAccount>,
+) -> NodeCollector<'nc> {
+    Box::new(move |unpacked_key: &[u8], node: &Node| {
+        if !unpacked_key.is_empty() {
+            collector.push(unpacked_key.to_vec(), marshal_node(node));
+        }
+    })
+}
+
+fn storage_node_collector<'tmp: 'nc, 'nc>(
+    account_key: &'nc [u8],
+    collector: &'nc mut TableCollector<'tmp, tables::TrieStorage>,
+) -> NodeCollector<'nc> {
+    Box::new(move |unpacked_storage_key: &[u8], node: &Node| {
+        let key = [account_key, unpacked_storage_key].concat();
+        collector.push(key, marshal_node(node));
+    })
+}
+
+/// Storage slots to prove while calculating the root, all keys are hashed.
+struct StorageProofTargets {
+    account: H256,
+    locations: Vec<H256>,
+    storage_root: H256,
+    proof_retainer: Option<ProofRetainer>,
+}
+
+struct DbTrieLoader<'db, 'tx, 'tmp, 'co, 'nc, 'ov, K, E>
+where
+    K: TrieTransactionKind,
     E: EnvironmentKind,
     'db: 'tx,
     'tmp: 'co,
     'co: 'nc,
 {
-    txn: &'tx MdbxTransaction<'db, RW, E>,
+    txn: &'tx MdbxTransaction<'db, K, E>,
+    overlay: &'ov HashedStateOverlay,
     hb: HashBuilder<'nc>,
-    storage_collector: &'co mut TableCollector<'tmp, tables::TrieStorage>,
+    storage_collector: Option<&'co mut TableCollector<'tmp, tables::TrieStorage>>,
+    storage_proof_targets: Option<StorageProofTargets>,
     rlp: Vec<u8>,
     _marker: PhantomData<&'db ()>,
 }
 
-impl<'db, 'tx, 'tmp, 'co, 'nc, E> DbTrieLoader<'db, 'tx, 'tmp, 'co, 'nc, E>
+impl<'db, 'tx, 'tmp, 'co, 'nc, 'ov, K, E> DbTrieLoader<'db, 'tx, 'tmp, 'co, 'nc, 'ov, K, E>
 where
+    K: TrieTransactionKind,
     E: EnvironmentKind,
     'db: 'tx,
     'tmp: 'co,
     'co: 'nc,
 {
     fn new(
-        txn: &'tx MdbxTransaction<'db, RW, E>,
-        account_collector: &'co mut TableCollector<'tmp, tables::TrieAccount>,
-        storage_collector: &'co mut TableCollector<'tmp, tables::TrieStorage>,
+        txn: &'tx MdbxTransaction<'db, K, E>,
+        overlay: &'ov HashedStateOverlay,
+        account_collector: Option<&'co mut TableCollector<'tmp, tables::TrieAccount>>,
+        storage_collector: Option<&'co mut TableCollector<'tmp, tables::TrieStorage>>,
     ) -> Self {
-        let node_collector = |unpacked_key: &[u8], node: &Node| {
-            if !unpacked_key.is_empty() {
-                account_collector.push(unpacked_key.to_vec(), marshal_node(node));
-            }
-        };
-
         Self {
             txn,
-            hb: HashBuilder::new(Some(Box::new(node_collector))),
+            overlay,
+            hb: HashBuilder::new(account_collector.map(account_node_collector)),
             storage_collector,
+            storage_proof_targets: None,
             rlp: vec![],
             _marker: PhantomData,
         }
     }
 
     fn calculate_root(&mut self, changed: &mut PrefixSet) -> Result<H256> {
-        let mut state = self.txn.cursor(tables::HashedAccount)?;
+        let mut state = HashedAccountCursor::new(
+            self.txn.cursor(tables::HashedAccount)?,
+            &self.overlay.accounts,
+        );
         let mut trie_db_cursor = self.txn.cursor(tables::TrieAccount)?;
 
         let mut trie = Cursor::new(&mut trie_db_cursor, changed, &[])?;
@@ -389,21 +657,36 @@ where
         account_key: &[u8],
         changed: &mut PrefixSet,
     ) -> Result<H256> {
-        let mut state = self.txn.cursor(tables::HashedStorage)?;
+        let mut state = HashedStorageCursor::new(
+            self.txn.cursor(tables::HashedStorage)?,
+            &self.overlay.storage,
+        );
 
         let mut trie_db_cursor = self.txn.cursor(tables::TrieStorage)?;
 
-        let mut hb = HashBuilder::new(Some(Box::new(
-            |unpacked_storage_key: &[u8], node: &Node| {
-                let key = [account_key, unpacked_storage_key].concat();
-                self.storage_collector.push(key, marshal_node(node));
-            },
-        )));
+        let mut hb = HashBuilder::new(
+            self.storage_collector
+                .as_deref_mut()
+                .map(|storage_collector| storage_node_collector(account_key, storage_collector)),
+        );
+        let proof_targets = self
+            .storage_proof_targets
+            .as_mut()
+            .filter(|targets| targets.account.as_bytes() == account_key);
+        if let Some(targets) = &proof_targets {
+            hb.proof_retainer = Some(ProofRetainer::new(
+                targets
+                    .locations
+                    .iter()
+                    .map(|location| unpack_nibbles(location.as_bytes()))
+                    .collect(),
+            ));
+        }
 
         let mut trie = Cursor::new(&mut trie_db_cursor, changed, account_key)?;
         while let Some(key) = trie.key() {
             if trie.can_skip_state {
-                if state.seek_exact(H256::from_slice(account_key))?.is_none() {
+                if !state.has_storage(H256::from_slice(account_key))? {
                     return Ok(EMPTY_ROOT);
                 }
                 hb.add_branch_node(
@@ -437,11 +720,17 @@ where
                     }
                 }
                 hb.add_leaf(unpacked_loc, fastrlp::encode_fixed_size(&value).as_ref());
-                storage = state.next_dup()?.map(|(_, v)| v);
+                storage = state.next_dup()?;
             }
         }
 
-        Ok(hb.compute_root_hash())
+        let root = hb.compute_root_hash();
+        if let Some(targets) = proof_targets {
+            targets.storage_root = root;
+            targets.proof_retainer = hb.proof_retainer.take();
+        }
+
+        Ok(root)
     }
 }
 
@@ -458,8 +747,14 @@ where
     let mut account_collector = TableCollector::new(etl_dir, OPTIMAL_BUFFER_CAPACITY);
     let mut storage_collector = TableCollector::new(etl_dir, OPTIMAL_BUFFER_CAPACITY);
 
+    let overlay = HashedStateOverlay::default();
     let root = {
-        let mut loader = DbTrieLoader::new(txn, &mut account_collector, &mut storage_collector);
+        let mut loader = DbTrieLoader::new(
+            txn,
+            &overlay,
+            Some(&mut account_collector),
+            Some(&mut storage_collector),
+        );
         loader.calculate_root(changed)?
     };
 
@@ -573,6 +868,192 @@ where
     do_increment_intermediate_hashes(txn, etl_dir, expected_root, &mut empty)
 }
 
+fn overlay_changes<'db, 'tx, K, E>(
+    txn: &'tx MdbxTransaction<'db, K, E>,
+    overlay: &HashedStateOverlay,
+) -> Result<PrefixSet>
+where
+    'db: 'tx,
+    K: TransactionKind,
+    E: EnvironmentKind,
+{
+    let mut changed = PrefixSet::new();
+
+    for hashed_address in overlay.accounts.keys() {
+        changed.insert(unpack_nibbles(hashed_address.as_bytes()).as_slice());
+    }
+
+    let mut hashed_storage = txn.cursor(tables::HashedStorage)?;
+    for (hashed_address, storage) in &overlay.storage {
+        changed.insert(unpack_nibbles(hashed_address.as_bytes()).as_slice());
+
+        let mut insert_location = |hashed_location: H256| {
+            changed.insert(
+                [
+                    hashed_address.as_bytes(),
+                    unpack_nibbles(hashed_location.as_bytes()).as_slice(),
+                ]
+                .concat()
+                .as_slice(),
+            )
+        };
+
+        for &hashed_location in storage.slots.keys() {
+            insert_location(hashed_location);
+        }
+
+        if storage.erased {
+            // Every slot in the database is gone.
+            let mut entry = hashed_storage.seek_both_range(*hashed_address, H256::zero())?;
+            while let Some((hashed_location, _)) = entry {
+                insert_location(hashed_location);
+                entry = hashed_storage.next_dup()?.map(|(_, v)| v);
+            }
+        }
+    }
+
+    Ok(changed)
+}
+
+/// Computes state root of the database state with `overlay` applied on top, leaving the database intact.
+///
+/// Hashed state and intermediate hashes in the database must be up to date.
+pub fn state_root_with_overlay<'db, 'tx, E>(
+    txn: &'tx MdbxTransaction<'db, RO, E>,
+    overlay: &HashedStateOverlay,
+) -> Result<H256>
+where
+    'db: 'tx,
+    E: EnvironmentKind,
+{
+    let mut changed = overlay_changes(txn, overlay)?;
+
+    DbTrieLoader::new(txn, overlay, None, None).calculate_root(&mut changed)
+}
+
+/// Merkle proofs of an account and its storage slots, as returned by
+/// [EIP-1186](https://eips.ethereum.org/EIPS/eip-1186) `eth_getProof`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StateProof {
+    pub state_root: H256,
+    /// Nodes on the path to the account in the state trie, starting with the root.
+    pub account_proof: Vec<Bytes>,
+    pub storage_root: H256,
+    /// Nodes on the path to each slot in the storage trie, in order of requested locations.
+    pub storage_proofs: Vec<Vec<Bytes>>,
+}
+
+/// Builds proofs of an account and its storage slots in the database state with `overlay` applied on top.
+///
+/// Proofs of absent accounts and slots end with the node where their path diverges.
+/// Hashed state and intermediate hashes in the database must be up to date.
+pub fn prove_with_overlay<'db, 'tx, E>(
+    txn: &'tx MdbxTransaction<'db, RO, E>,
+    overlay: &HashedStateOverlay,
+    address: Address,
+    locations: &[H256],
+) -> Result<StateProof>
+where
+    'db: 'tx,
+    E: EnvironmentKind,
+{
+    let hashed_address = keccak256(address);
+    let unpacked_address = unpack_nibbles(hashed_address.as_bytes());
+    let hashed_locations = locations.iter().map(keccak256).collect::<Vec<_>>();
+
+    // Walk down to the proven keys instead of taking hashes of their subtries.
+    let mut changed = overlay_changes(txn, overlay)?;
+    changed.insert(&unpacked_address);
+    for hashed_location in &hashed_locations {
+        changed.insert(
+            [
+                hashed_address.as_bytes(),
+                unpack_nibbles(hashed_location.as_bytes()).as_slice(),
+            ]
+            .concat()
+            .as_slice(),
+        );
+    }
+
+    let mut loader = DbTrieLoader::new(txn, overlay, None, None);
+    loader.hb.proof_retainer = Some(ProofRetainer::new(vec![unpacked_address.clone()]));
+    loader.storage_proof_targets = Some(StorageProofTargets {
+        account: hashed_address,
+        locations: hashed_locations.clone(),
+        storage_root: EMPTY_ROOT,
+        proof_retainer: None,
+    });
+
+    let state_root = loader.calculate_root(&mut changed)?;
+
+    let account_proof = loader
+        .hb
+        .proof_retainer
+        .take()
+        .unwrap()
+        .proof(&unpacked_address);
+    let storage_targets = loader.storage_proof_targets.take().unwrap();
+    let storage_proofs = hashed_locations
+        .iter()
+        .map(|hashed_location| {
+            storage_targets
+                .proof_retainer
+                .as_ref()
+                .map(|proof_retainer| {
+                    proof_retainer.proof(&unpack_nibbles(hashed_location.as_bytes()))
+                })
+                .unwrap_or_default()
+        })
+        .collect();
+
+    Ok(StateProof {
+        state_root,
+        account_proof,
+        storage_root: storage_targets.storage_root,
+        storage_proofs,
+    })
+}
+
+/// Overlay reverting the hashed state in the database to the one after `block_number`,
+/// gathered from change sets of subsequent blocks.
+pub fn historical_overlay<'db, 'tx, K, E>(
+    txn: &'tx MdbxTransaction<'db, K, E>,
+    block_number: BlockNumber,
+) -> Result<HashedStateOverlay>
+where
+    'db: 'tx,
+    K: TransactionKind,
+    E: EnvironmentKind,
+{
+    let mut overlay = HashedStateOverlay::default();
+
+    // Change sets hold values prior to the change, so the earliest one is kept.
+    let mut account_changes = txn.cursor(tables::AccountChangeSet)?;
+    let mut data = account_changes.seek(block_number + 1)?;
+    while let Some((_, account_change)) = data {
+        overlay
+            .accounts
+            .entry(keccak256(account_change.address))
+            .or_insert(account_change.account);
+        data = account_changes.next()?;
+    }
+
+    let mut storage_changes = txn.cursor(tables::StorageChangeSet)?;
+    let mut data = storage_changes.seek(block_number + 1)?;
+    while let Some((key, storage_change)) = data {
+        overlay
+            .storage
+            .entry(keccak256(key.address))
+            .or_default()
+            .slots
+            .entry(keccak256(storage_change.location))
+            .or_insert(storage_change.value);
+        data = storage_changes.next()?;
+    }
+
+    Ok(overlay)
+}
+
 #[cfg(test)]
 mod tests {
     use std::collections::HashMap;
@@ -1734,6 +2215,262 @@ mod tests {
         }
     }
 
+    #[test]
+    fn state_root_with_overlay_matches_regeneration() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = new_mem_chaindata().unwrap();
+
+        const N: u128 = 1_000;
+
+        let account = |i: u128| Account {
+            nonce: i as u64,
+            balance: (i + 1).as_u256() * ETHER,
+            ..Default::default()
+        };
+        let location = |i: u128| keccak256(u256_to_h256(i.as_u256()));
+
+        let mut overlay = HashedStateOverlay::default();
+
+        let txn = db.begin_mutable().unwrap();
+        {
+            let mut hashed_accounts = txn.cursor(tables::HashedAccount).unwrap();
+            let mut hashed_storage = txn.cursor(tables::HashedStorage).unwrap();
+            for i in 0..N {
+                hashed_accounts
+                    .upsert(keccak256(int_to_address(i)), account(i))
+                    .unwrap();
+            }
+            for i in 0..N {
+                for address in [int_to_address(0), int_to_address(1), int_to_address(2)] {
+                    upsert_hashed_storage_value(
+                        &mut hashed_storage,
+                        keccak256(address),
+                        location(i),
+                        (i + 1).as_u256(),
+                    )
+                    .unwrap();
+                }
+            }
+        }
+        let root = regenerate_intermediate_hashes(&txn, &temp_dir, None).unwrap();
+        txn.commit().unwrap();
+
+        // Empty overlay yields the database state root
+        assert_eq!(
+            state_root_with_overlay(&db.begin().unwrap(), &overlay).unwrap(),
+            root
+        );
+
+        // Update, delete and create accounts
+        for i in (0..N).step_by(7) {
+            overlay
+                .accounts
+                .insert(keccak256(int_to_address(i)), Some(account(i + 1)));
+        }
+        for i in (3..N).step_by(11) {
+            overlay.accounts.insert(keccak256(int_to_address(i)), None);
+        }
+        for i in N..N + 50 {
+            overlay
+                .accounts
+                .insert(keccak256(int_to_address(i)), Some(account(i)));
+        }
+
+        // Update and delete slots of the first account, erase storage of the second one
+        let storage = overlay
+            .storage
+            .entry(keccak256(int_to_address(0)))
+            .or_default();
+        for i in (0..N).step_by(3) {
+            storage.slots.insert(location(i), U256::ZERO);
+        }
+        for i in (1..N + 20).step_by(5) {
+            storage.slots.insert(location(i), 0x42.as_u256());
+        }
+        let storage = overlay
+            .storage
+            .entry(keccak256(int_to_address(1)))
+            .or_default();
+        storage.erased = true;
+        storage.slots.insert(location(N + 1), 0x43.as_u256());
+
+        let overlay_root = state_root_with_overlay(&db.begin().unwrap(), &overlay).unwrap();
+
+        // Database is left intact
+        assert_eq!(
+            state_root_with_overlay(&db.begin().unwrap(), &HashedStateOverlay::default()).unwrap(),
+            root
+        );
+
+        // Apply the same changes to the database and regenerate
+        let txn = db.begin_mutable().unwrap();
+        {
+            let mut hashed_accounts = txn.cursor(tables::HashedAccount).unwrap();
+            let mut hashed_storage = txn.cursor(tables::HashedStorage).unwrap();
+            for (&hashed_address, &account) in &overlay.accounts {
+                if let Some(account) = account {
+                    hashed_accounts.upsert(hashed_address, account).unwrap();
+                } else if hashed_accounts
+                    .seek_exact(hashed_address)
+                    .unwrap()
+                    .is_some()
+                {
+                    hashed_accounts.delete_current().unwrap();
+                }
+            }
+            for (&hashed_address, storage) in &overlay.storage {
+                if storage.erased && hashed_storage.seek_exact(hashed_address).unwrap().is_some() {
+                    hashed_storage.delete_current_duplicates().unwrap();
+                }
+                for (&hashed_location, &value) in &storage.slots {
+                    upsert_hashed_storage_value(
+                        &mut hashed_storage,
+                        hashed_address,
+                        hashed_location,
+                        value,
+                    )
+                    .unwrap();
+                }
+            }
+        }
+
+        assert_eq!(
+            regenerate_intermediate_hashes(&txn, &temp_dir, None).unwrap(),
+            overlay_root
+        );
+    }
+
+    #[test]
+    fn apply_overlay() {
+        let account = |nonce| Account {
+            nonce,
+            ..Default::default()
+        };
+        let slot = H256::repeat_byte;
+
+        let mut overlay = HashedStateOverlay::default();
+        overlay.accounts.insert(slot(1), Some(account(1)));
+        overlay.accounts.insert(slot(2), Some(account(2)));
+        for i in [1, 2] {
+            overlay
+                .storage
+                .entry(slot(i))
+                .or_default()
+                .slots
+                .insert(slot(10), i.as_u256());
+        }
+
+        let mut changes = HashedStateOverlay::default();
+        changes.accounts.insert(slot(2), None);
+        changes
+            .storage
+            .entry(slot(1))
+            .or_default()
+            .slots
+            .insert(slot(11), 0x42.as_u256());
+        let storage = changes.storage.entry(slot(2)).or_default();
+        storage.erased = true;
+        storage.slots.insert(slot(12), 0x43.as_u256());
+
+        overlay.apply(changes);
+
+        assert_eq!(overlay.accounts[&slot(1)], Some(account(1)));
+        assert_eq!(overlay.accounts[&slot(2)], None);
+        assert_eq!(
+            overlay.storage[&slot(1)],
+            HashedStorageOverlay {
+                erased: false,
+                slots: [(slot(10), 1.as_u256()), (slot(11), 0x42.as_u256())]
+                    .into_iter()
+                    .collect(),
+            }
+        );
+        assert_eq!(
+            overlay.storage[&slot(2)],
+            HashedStorageOverlay {
+                erased: true,
+                slots: [(slot(12), 0x43.as_u256())].into_iter().collect(),
+            }
+        );
+    }
+
+    #[test]
+    fn prove_with_overlay_matches_state_root() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = new_mem_chaindata().unwrap();
+
+        const N: u128 = 300;
+
+        let account = |i: u128| Account {
+            nonce: i as u64,
+            balance: (i + 1).as_u256() * ETHER,
+            ..Default::default()
+        };
+        let location = |i: u128| u256_to_h256(i.as_u256());
+
+        let txn = db.begin_mutable().unwrap();
+        {
+            let mut hashed_accounts = txn.cursor(tables::HashedAccount).unwrap();
+            let mut hashed_storage = txn.cursor(tables::HashedStorage).unwrap();
+            for i in 0..N {
+                hashed_accounts
+                    .upsert(keccak256(int_to_address(i)), account(i))
+                    .unwrap();
+            }
+            for i in 0..N {
+                upsert_hashed_storage_value(
+                    &mut hashed_storage,
+                    keccak256(int_to_address(0)),
+                    keccak256(location(i)),
+                    (i + 1).as_u256(),
+                )
+                .unwrap();
+            }
+        }
+        regenerate_intermediate_hashes(&txn, &temp_dir, None).unwrap();
+        txn.commit().unwrap();
+
+        let mut overlay = HashedStateOverlay::default();
+        overlay
+            .accounts
+            .insert(keccak256(int_to_address(1)), Some(account(N)));
+        overlay
+            .storage
+            .entry(keccak256(int_to_address(0)))
+            .or_default()
+            .slots
+            .insert(keccak256(location(5)), 0x42.as_u256());
+
+        for overlay in [HashedStateOverlay::default(), overlay] {
+            let txn = db.begin().unwrap();
+            let proof = prove_with_overlay(
+                &txn,
+                &overlay,
+                int_to_address(0),
+                &[location(5), location(N + 1)],
+            )
+            .unwrap();
+
+            assert_eq!(
+                proof.state_root,
+                state_root_with_overlay(&txn, &overlay).unwrap()
+            );
+            assert_eq!(keccak256(&proof.account_proof[0]), proof.state_root);
+
+            let account_rlp = fastrlp::encode_fixed_size(&account(0).to_rlp(proof.storage_root));
+            assert!(proof
+                .account_proof
+                .last()
+                .unwrap()
+                .ends_with(account_rlp.as_ref()));
+
+            assert_eq!(proof.storage_proofs.len(), 2);
+            for storage_proof in &proof.storage_proofs {
+                assert_eq!(keccak256(&storage_proof[0]), proof.storage_root);
+            }
+        }
+    }
+
     #[test]
     fn test_intermediate_hashes_increment_key() {
         assert_eq!(increment_key(&[]), None);
@@ -7,6 +7,8 @@ mod vector_root;
 
 pub use hash_builder::{unpack_nibbles, HashBuilder};
 pub use intermediate_hashes::{
-    increment_intermediate_hashes, regenerate_intermediate_hashes, unwind_intermediate_hashes,
+    historical_overlay, increment_intermediate_hashes, prove_with_overlay,
+    regenerate_intermediate_hashes, state_root_with_overlay, unwind_intermediate_hashes,
+    HashedStateOverlay, HashedStorageOverlay, StateProof,
 };
 pub use vector_root::{root_hash, TrieEncode};
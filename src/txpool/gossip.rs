@@ -0,0 +1,163 @@
+use super::{PoolError, TransactionPool};
+use crate::{
+    kv::mdbx::*,
+    p2p::{node::Node, types::*},
+};
+use std::sync::Arc;
+use task_group::TaskGroup;
+use tokio::sync::broadcast::error::RecvError;
+use tokio_stream::StreamExt;
+use tracing::*;
+
+/// Maximum number of hashes sent in, or requested after, a single announcement.
+const MAX_ANNOUNCED_HASHES: usize = 256;
+
+impl<E> TransactionPool<E>
+where
+    E: EnvironmentKind,
+{
+    /// Exchanges transactions with peers: imports broadcasted and announced transactions,
+    /// answers `GetPooledTransactions` and propagates transactions added to the pool,
+    /// sending them in full to a square root of peers and announcing their hashes to the rest.
+    pub async fn run_gossip(self: Arc<Self>, node: Arc<Node>) -> anyhow::Result<()> {
+        let tasks = TaskGroup::new();
+
+        tasks.spawn({
+            let mut new_transactions = self.subscribe();
+            let pool = self.clone();
+            let node = node.clone();
+
+            async move {
+                loop {
+                    let mut hashes = match new_transactions.recv().await {
+                        Ok(hash) => vec![hash],
+                        Err(RecvError::Lagged(_)) => continue,
+                        Err(RecvError::Closed) => break,
+                    };
+                    while hashes.len() < MAX_ANNOUNCED_HASHES {
+                        match new_transactions.try_recv() {
+                            Ok(hash) => hashes.push(hash),
+                            Err(_) => break,
+                        }
+                    }
+
+                    let transactions = {
+                        let pool = pool.pool.lock();
+                        hashes
+                            .iter()
+                            .filter_map(|&hash| pool.get(hash))
+                            .map(|tx| tx.transaction.clone())
+                            .collect::<Vec<_>>()
+                    };
+                    if !transactions.is_empty() {
+                        let max_peers = std::cmp::max(node.sqrt_peers().await, 1) as u64;
+                        node.send_message(
+                            Message::Transactions(Transactions(transactions)),
+                            PeerFilter::Random(max_peers),
+                        )
+                        .await;
+                    }
+
+                    node.send_message(
+                        Message::NewPooledTransactionHashes(NewPooledTransactionHashes(hashes)),
+                        PeerFilter::All,
+                    )
+                    .await;
+                }
+
+                Ok::<_, anyhow::Error>(())
+            }
+        });
+
+        let mut stream = node.stream_transactions().await;
+        while let Some(msg) = stream.next().await {
+            let peer_id = msg.peer_id;
+            let peer = PeerFilter::Peer(peer_id, msg.sentry_id);
+
+            match msg.msg {
+                Message::Transactions(Transactions(transactions)) => {
+                    let pool = self.clone();
+                    let results = match tokio::task::spawn_blocking(move || {
+                        pool.add_transactions(transactions)
+                    })
+                    .await
+                    .map_err(anyhow::Error::from)
+                    .and_then(|res| res)
+                    {
+                        Ok(results) => results,
+                        Err(e) => {
+                            warn!("Failed to add transactions from peer {peer_id}: {e}");
+                            continue;
+                        }
+                    };
+
+                    if results
+                        .iter()
+                        .any(|res| matches!(res, Err(PoolError::InvalidSignature)))
+                    {
+                        node.penalize_peer(peer_id).await;
+                    }
+                }
+                Message::PooledTransactions(PooledTransactions { transactions, .. }) => {
+                    // Sidecars are dropped, blob transactions are not accepted by the pool anyway.
+                    let transactions = transactions
+                        .into_iter()
+                        .map(|tx| tx.transaction)
+                        .collect::<Vec<_>>();
+                    let pool = self.clone();
+                    let results = match tokio::task::spawn_blocking(move || {
+                        pool.add_transactions(transactions)
+                    })
+                    .await
+                    .map_err(anyhow::Error::from)
+                    .and_then(|res| res)
+                    {
+                        Ok(results) => results,
+                        Err(e) => {
+                            warn!("Failed to add transactions from peer {peer_id}: {e}");
+                            continue;
+                        }
+                    };
+
+                    if results
+                        .iter()
+                        .any(|res| matches!(res, Err(PoolError::InvalidSignature)))
+                    {
+                        node.penalize_peer(peer_id).await;
+                    }
+                }
+                Message::NewPooledTransactionHashes(NewPooledTransactionHashes(hashes)) => {
+                    let unknown = {
+                        let pool = self.pool.lock();
+                        hashes
+                            .into_iter()
+                            .filter(|&hash| !pool.contains(hash))
+                            .take(MAX_ANNOUNCED_HASHES)
+                            .collect::<Vec<_>>()
+                    };
+
+                    if !unknown.is_empty() {
+                        node.get_pooled_transactions(rand::random(), &unknown, peer)
+                            .await;
+                    }
+                }
+                Message::GetPooledTransactions(GetPooledTransactions { request_id, hashes }) => {
+                    let transactions = {
+                        let pool = self.pool.lock();
+                        hashes
+                            .into_iter()
+                            .filter_map(|hash| pool.get(hash))
+                            .map(|tx| tx.transaction.clone())
+                            .collect()
+                    };
+
+                    node.send_pooled_transactions(request_id, transactions, peer)
+                        .await;
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,281 @@
+mod gossip;
+mod pool;
+
+pub use self::pool::*;
+use crate::{
+    accessors::{chain, state},
+    consensus::{
+        pre_validate_transaction, BadTransactionError, TransactionSource, ValidationError,
+    },
+    execution::processor::{gas_after_intrinsic, validate_sender_account},
+    kv::{mdbx::*, MdbxWithDirHandle},
+    models::*,
+    stagedsync::stages::EXECUTION,
+};
+use anyhow::format_err;
+use parking_lot::{Mutex, RwLock};
+use std::{
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tokio::sync::broadcast;
+use tracing::*;
+
+fn read_head<K: TransactionKind, E: EnvironmentKind>(
+    txn: &MdbxTransaction<'_, K, E>,
+    number: BlockNumber,
+) -> anyhow::Result<BlockHeader> {
+    let hash = chain::canonical_hash::read(txn, number)?
+        .ok_or_else(|| format_err!("no canonical hash for block #{number}"))?;
+    chain::header::read(txn, hash, number)?
+        .ok_or_else(|| format_err!("header not found for block #{number}/{hash}"))
+}
+
+/// Transaction pool validating transactions against the state at the head of executed chain.
+#[derive(Debug)]
+pub struct TransactionPool<E>
+where
+    E: EnvironmentKind,
+{
+    db: Arc<MdbxWithDirHandle<E>>,
+    chain_spec: ChainSpec,
+    head: RwLock<BlockHeader>,
+    pool: Mutex<Pool>,
+    new_transactions: broadcast::Sender<H256>,
+}
+
+impl<E> TransactionPool<E>
+where
+    E: EnvironmentKind,
+{
+    pub fn new(
+        db: Arc<MdbxWithDirHandle<E>>,
+        chain_spec: ChainSpec,
+        config: PoolConfig,
+    ) -> anyhow::Result<Self> {
+        let head = {
+            let txn = db.begin()?;
+            let executed_to = EXECUTION.get_progress(&txn)?.unwrap_or_default();
+            read_head(&txn, executed_to)?
+        };
+
+        let mut pool = Pool::new(config);
+        pool.set_base_fee_per_gas(head.base_fee_per_gas);
+
+        Ok(Self {
+            db,
+            chain_spec,
+            head: RwLock::new(head),
+            pool: Mutex::new(pool),
+            new_transactions: broadcast::channel(1024).0,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.pool.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pool.lock().is_empty()
+    }
+
+    pub fn get(&self, hash: H256) -> Option<Arc<PooledTransaction>> {
+        self.pool.lock().get(hash)
+    }
+
+    pub fn pending(&self) -> Vec<Arc<PooledTransaction>> {
+        self.pool.lock().pending()
+    }
+
+    pub fn queued(&self) -> Vec<Arc<PooledTransaction>> {
+        self.pool.lock().queued()
+    }
+
+    /// Hashes of transactions as they are added to the pool.
+    pub fn subscribe(&self) -> broadcast::Receiver<H256> {
+        self.new_transactions.subscribe()
+    }
+
+    pub fn add_transaction(&self, transaction: MessageWithSignature) -> Result<H256, PoolError> {
+        self.add_transactions([transaction])?.pop().unwrap()
+    }
+
+    /// Validates transactions against the latest state and adds them to the pool.
+    pub fn add_transactions(
+        &self,
+        transactions: impl IntoIterator<Item = MessageWithSignature>,
+    ) -> anyhow::Result<Vec<Result<H256, PoolError>>> {
+        let txn = self.db.begin()?;
+        let head = self.head.read().clone();
+
+        Ok(transactions
+            .into_iter()
+            .map(|transaction| self.add_transaction_with(&txn, &head, transaction))
+            .collect())
+    }
+
+    fn add_transaction_with<K: TransactionKind>(
+        &self,
+        txn: &MdbxTransaction<'_, K, E>,
+        head: &BlockHeader,
+        transaction: MessageWithSignature,
+    ) -> Result<H256, PoolError> {
+        let hash = transaction.hash();
+        if self.pool.lock().contains(hash) {
+            return Err(PoolError::AlreadyKnown);
+        }
+
+        let (tx, info) = self.validate(txn, head, transaction)?;
+        self.pool.lock().insert(tx, info)?;
+        let _ = self.new_transactions.send(hash);
+
+        Ok(hash)
+    }
+
+    fn sender_info<K: TransactionKind>(
+        txn: &MdbxTransaction<'_, K, E>,
+        address: Address,
+    ) -> anyhow::Result<(SenderInfo, H256)> {
+        let account = state::account::read(txn, address, None)?.unwrap_or_default();
+
+        Ok((
+            SenderInfo {
+                nonce: account.nonce,
+                balance: account.balance,
+            },
+            account.code_hash,
+        ))
+    }
+
+    fn validate<K: TransactionKind>(
+        &self,
+        txn: &MdbxTransaction<'_, K, E>,
+        head: &BlockHeader,
+        transaction: MessageWithSignature,
+    ) -> Result<(PooledTransaction, SenderInfo), PoolError> {
+        // Timestamp of the next block is not known yet, assume it's built right now.
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        let block_spec = self
+            .chain_spec
+            .collect_block_spec(head.number + 1, std::cmp::max(now, head.timestamp + 1));
+        let revision = block_spec.revision;
+
+        match transaction.tx_type() {
+            TxType::Legacy => {}
+            TxType::EIP2930 if revision >= Revision::Berlin => {}
+            TxType::EIP1559 if revision >= Revision::London => {}
+            // Pool does not keep blob sidecars, so it cannot serve blob transactions to peers.
+            _ => return Err(PoolError::TxTypeNotSupported),
+        }
+
+        // https://eips.ethereum.org/EIPS/eip-2
+        if revision >= Revision::Homestead && transaction.signature.malleable() {
+            return Err(PoolError::InvalidSignature);
+        }
+        let sender = transaction
+            .recover_sender()
+            .map_err(|_| PoolError::InvalidSignature)?;
+
+        pre_validate_transaction(
+            &transaction.message,
+            block_spec.params.chain_id,
+            head.base_fee_per_gas,
+        )
+        .map_err(|e| match e {
+            ValidationError::WrongChainId => PoolError::WrongChainId,
+            ValidationError::MaxFeeLessThanBase => PoolError::FeeCapTooLow,
+            ValidationError::MaxPriorityFeeGreaterThanMax => PoolError::TipAboveFeeCap,
+            other => PoolError::Internal(format_err!("unexpected validation error: {other}")),
+        })?;
+
+        if gas_after_intrinsic(&transaction.message, revision).is_err() {
+            return Err(PoolError::IntrinsicGasTooLow);
+        }
+
+        if transaction.gas_limit() > head.gas_limit {
+            return Err(PoolError::GasLimitExceeded);
+        }
+
+        let (info, code_hash) = Self::sender_info(txn, sender)?;
+
+        // Unlike in a block, transactions with future nonces are kept queued.
+        if transaction.nonce() < info.nonce {
+            return Err(PoolError::NonceTooLow {
+                expected: info.nonce,
+                got: transaction.nonce(),
+            });
+        }
+
+        validate_sender_account(
+            &block_spec,
+            head,
+            &transaction.message,
+            sender,
+            code_hash,
+            info.balance,
+        )
+        .map_err(|e| match e {
+            BadTransactionError::SenderNoEOA { .. } => PoolError::SenderNoEOA,
+            BadTransactionError::InsufficientFunds { .. } => PoolError::InsufficientFunds,
+            BadTransactionError::InitCodeTooLarge { .. } => PoolError::MaxInitCodeSizeExceeded,
+            other => PoolError::Internal(format_err!("unexpected validation error: {other:?}")),
+        })?;
+
+        Ok((PooledTransaction::new(transaction, sender), info))
+    }
+
+    /// Moves the pool onto new canonical head.
+    ///
+    /// Refreshes state of every sender, which drops transactions included into canonical chain,
+    /// and then tries to add back `reinjected` transactions from blocks reverted by a reorg.
+    pub fn update_head<K: TransactionKind>(
+        &self,
+        txn: &MdbxTransaction<'_, K, E>,
+        number: BlockNumber,
+        reinjected: Vec<MessageWithSignature>,
+    ) -> anyhow::Result<()> {
+        let head = read_head(txn, number)?;
+
+        {
+            let mut pool = self.pool.lock();
+            pool.set_base_fee_per_gas(head.base_fee_per_gas);
+            for address in pool.senders() {
+                let (info, _) = Self::sender_info(txn, address)?;
+                pool.update_sender(address, info);
+            }
+        }
+
+        let mut added = 0;
+        for transaction in reinjected {
+            match self.add_transaction_with(txn, &head, transaction) {
+                Ok(_) => added += 1,
+                Err(PoolError::Internal(e)) => return Err(e),
+                Err(e) => trace!("Dropping reverted transaction: {e}"),
+            }
+        }
+        if added > 0 {
+            debug!("Re-added {added} transactions from reverted blocks");
+        }
+
+        *self.head.write() = head;
+
+        Ok(())
+    }
+}
+
+impl<E> TransactionSource for TransactionPool<E>
+where
+    E: EnvironmentKind,
+{
+    fn best_transactions(&self) -> Vec<(MessageWithSignature, Address)> {
+        self.pool
+            .lock()
+            .best_transactions()
+            .into_iter()
+            .map(|tx| (tx.transaction.clone(), tx.sender))
+            .collect()
+    }
+}
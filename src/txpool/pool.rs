@@ -0,0 +1,579 @@
+use crate::models::*;
+use hashbrown::HashMap;
+use std::{
+    collections::{BTreeMap, BinaryHeap, VecDeque},
+    sync::Arc,
+};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum PoolError {
+    #[error("already known")]
+    AlreadyKnown,
+    #[error("transaction type not supported")]
+    TxTypeNotSupported,
+    #[error("invalid sender")]
+    InvalidSignature,
+    #[error("invalid chain id")]
+    WrongChainId,
+    #[error("max fee per gas less than block base fee")]
+    FeeCapTooLow,
+    #[error("max priority fee per gas higher than max fee per gas")]
+    TipAboveFeeCap,
+    #[error("intrinsic gas too low")]
+    IntrinsicGasTooLow,
+    #[error("max initcode size exceeded")]
+    MaxInitCodeSizeExceeded,
+    #[error("exceeds block gas limit")]
+    GasLimitExceeded,
+    #[error("sender not an eoa")]
+    SenderNoEOA,
+    #[error("nonce too low: expected {expected}, got {got}")]
+    NonceTooLow { expected: u64, got: u64 },
+    #[error("insufficient funds for gas * price + value")]
+    InsufficientFunds,
+    #[error("replacement transaction underpriced")]
+    ReplacementUnderpriced,
+    #[error("too many transactions from sender")]
+    TooManyFromSender,
+    #[error("transaction pool is full")]
+    PoolFull,
+    #[error(transparent)]
+    Internal(#[from] anyhow::Error),
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct PoolConfig {
+    /// Maximum number of transactions kept in the pool.
+    pub capacity: usize,
+    /// Maximum number of transactions kept per sender.
+    pub max_per_sender: usize,
+    /// Minimum fee increase, in percent, for a transaction to replace another one with the same nonce.
+    pub price_bump: u64,
+}
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        Self {
+            capacity: 4096,
+            max_per_sender: 64,
+            price_bump: 10,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PooledTransaction {
+    pub hash: H256,
+    pub sender: Address,
+    pub transaction: MessageWithSignature,
+}
+
+impl PooledTransaction {
+    pub fn new(transaction: MessageWithSignature, sender: Address) -> Self {
+        Self {
+            hash: transaction.hash(),
+            sender,
+            transaction,
+        }
+    }
+
+    pub fn nonce(&self) -> u64 {
+        self.transaction.nonce()
+    }
+
+    /// Upper bound of what this transaction can take from sender's balance.
+    pub fn cost(&self) -> Option<U256> {
+        self.transaction
+            .max_fee_per_gas()
+            .checked_mul(self.transaction.gas_limit().into())?
+            .checked_add(self.transaction.value())
+    }
+
+    fn tip(&self, base_fee_per_gas: Option<U256>) -> U256 {
+        match base_fee_per_gas {
+            Some(base_fee_per_gas) => self
+                .transaction
+                .priority_fee_per_gas(base_fee_per_gas)
+                .unwrap_or(U256::ZERO),
+            None => self.transaction.max_priority_fee_per_gas(),
+        }
+    }
+}
+
+/// Sender's account state the pool checks transactions against.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SenderInfo {
+    pub nonce: u64,
+    pub balance: U256,
+}
+
+#[derive(Debug)]
+struct SenderTransactions {
+    info: SenderInfo,
+    transactions: BTreeMap<u64, Arc<PooledTransaction>>,
+}
+
+impl SenderTransactions {
+    /// Number of leading transactions that are executable on top of the current state:
+    /// they have no nonce gaps and the sender can afford all of them.
+    fn pending_count(&self) -> usize {
+        let mut nonce = self.info.nonce;
+        let mut balance = self.info.balance;
+        let mut count = 0;
+        for (&n, tx) in self.transactions.range(self.info.nonce..) {
+            if n != nonce {
+                break;
+            }
+
+            match tx.cost().and_then(|cost| balance.checked_sub(cost)) {
+                Some(rest) => balance = rest,
+                None => break,
+            }
+
+            nonce += 1;
+            count += 1;
+        }
+
+        count
+    }
+}
+
+/// In-memory transaction pool.
+///
+/// Transactions are kept per sender ordered by nonce. Pending transactions are those
+/// that can be executed right away in nonce order, the rest are queued until the gaps are filled
+/// or sender's balance allows them.
+#[derive(Debug)]
+pub struct Pool {
+    config: PoolConfig,
+    base_fee_per_gas: Option<U256>,
+    by_hash: HashMap<H256, Arc<PooledTransaction>>,
+    senders: HashMap<Address, SenderTransactions>,
+}
+
+impl Pool {
+    pub fn new(config: PoolConfig) -> Self {
+        Self {
+            config,
+            base_fee_per_gas: None,
+            by_hash: Default::default(),
+            senders: Default::default(),
+        }
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.by_hash.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.by_hash.is_empty()
+    }
+
+    #[inline]
+    pub fn contains(&self, hash: H256) -> bool {
+        self.by_hash.contains_key(&hash)
+    }
+
+    #[inline]
+    pub fn get(&self, hash: H256) -> Option<Arc<PooledTransaction>> {
+        self.by_hash.get(&hash).cloned()
+    }
+
+    #[inline]
+    pub fn base_fee_per_gas(&self) -> Option<U256> {
+        self.base_fee_per_gas
+    }
+
+    pub fn set_base_fee_per_gas(&mut self, base_fee_per_gas: Option<U256>) {
+        self.base_fee_per_gas = base_fee_per_gas;
+    }
+
+    pub fn senders(&self) -> Vec<Address> {
+        self.senders.keys().copied().collect()
+    }
+
+    /// Inserts transaction, given current state of its sender.
+    ///
+    /// Returns the transaction it replaced, if any.
+    pub fn insert(
+        &mut self,
+        tx: PooledTransaction,
+        info: SenderInfo,
+    ) -> Result<Option<Arc<PooledTransaction>>, PoolError> {
+        if self.contains(tx.hash) {
+            return Err(PoolError::AlreadyKnown);
+        }
+
+        self.update_sender(tx.sender, info);
+
+        let nonce = tx.nonce();
+        if nonce < info.nonce {
+            return Err(PoolError::NonceTooLow {
+                expected: info.nonce,
+                got: nonce,
+            });
+        }
+
+        let price_bump = self.config.price_bump;
+        let max_per_sender = self.config.max_per_sender;
+        let sender = self
+            .senders
+            .entry(tx.sender)
+            .or_insert_with(|| SenderTransactions {
+                info,
+                transactions: Default::default(),
+            });
+
+        if let Some(old) = sender.transactions.get(&nonce) {
+            let bumped = |old: U256| {
+                old.checked_mul(U256::from(100 + price_bump))
+                    .map(|v| v / 100)
+                    .unwrap_or(U256::MAX)
+            };
+            if tx.transaction.max_fee_per_gas() < bumped(old.transaction.max_fee_per_gas())
+                || tx.transaction.max_priority_fee_per_gas()
+                    < bumped(old.transaction.max_priority_fee_per_gas())
+            {
+                return Err(PoolError::ReplacementUnderpriced);
+            }
+        } else if sender.transactions.len() >= max_per_sender {
+            return Err(PoolError::TooManyFromSender);
+        }
+
+        let hash = tx.hash;
+        let tx = Arc::new(tx);
+        let replaced = sender.transactions.insert(nonce, tx.clone());
+        self.by_hash.insert(hash, tx);
+
+        if let Some(replaced) = &replaced {
+            // Replacement takes the slot of the old transaction, pool size stays the same
+            // and nothing has to be evicted.
+            self.by_hash.remove(&replaced.hash);
+        } else {
+            // Only the new transaction can be over capacity, so if it is the one evicted
+            // the pool is left exactly as it was.
+            self.evict();
+            if !self.contains(hash) {
+                return Err(PoolError::PoolFull);
+            }
+        }
+
+        Ok(replaced)
+    }
+
+    /// Updates known state of the sender, dropping transactions with nonces that are already used.
+    pub fn update_sender(&mut self, address: Address, info: SenderInfo) {
+        if let Some(sender) = self.senders.get_mut(&address) {
+            sender.info = info;
+
+            let stale = sender.transactions.range(..info.nonce).count();
+            for _ in 0..stale {
+                let (_, tx) = sender.transactions.pop_first().unwrap();
+                self.by_hash.remove(&tx.hash);
+            }
+
+            if sender.transactions.is_empty() {
+                self.senders.remove(&address);
+            }
+        }
+    }
+
+    pub fn remove(&mut self, hash: H256) -> Option<Arc<PooledTransaction>> {
+        let tx = self.by_hash.remove(&hash)?;
+        if let Some(sender) = self.senders.get_mut(&tx.sender) {
+            sender.transactions.remove(&tx.nonce());
+            if sender.transactions.is_empty() {
+                self.senders.remove(&tx.sender);
+            }
+        }
+
+        Some(tx)
+    }
+
+    /// Executable transactions, grouped by sender in nonce order.
+    pub fn pending(&self) -> Vec<Arc<PooledTransaction>> {
+        self.senders
+            .values()
+            .flat_map(|sender| {
+                sender
+                    .transactions
+                    .values()
+                    .take(sender.pending_count())
+                    .cloned()
+            })
+            .collect()
+    }
+
+    /// Transactions waiting for nonce gaps to be filled or for sender's balance to increase.
+    pub fn queued(&self) -> Vec<Arc<PooledTransaction>> {
+        self.senders
+            .values()
+            .flat_map(|sender| {
+                sender
+                    .transactions
+                    .values()
+                    .skip(sender.pending_count())
+                    .cloned()
+            })
+            .collect()
+    }
+
+    /// Pending transactions in the order they should be included into a block:
+    /// highest tip first, with each sender's transactions kept in nonce order.
+    pub fn best_transactions(&self) -> Vec<Arc<PooledTransaction>> {
+        let base_fee_per_gas = self.base_fee_per_gas;
+        let executable = |tx: &PooledTransaction| {
+            base_fee_per_gas
+                .map(|base_fee_per_gas| tx.transaction.max_fee_per_gas() >= base_fee_per_gas)
+                .unwrap_or(true)
+        };
+
+        let mut queues = HashMap::<Address, VecDeque<Arc<PooledTransaction>>>::new();
+        let mut heap = BinaryHeap::new();
+        for (&address, sender) in &self.senders {
+            let queue = sender
+                .transactions
+                .values()
+                .take(sender.pending_count())
+                .take_while(|tx| executable(tx))
+                .cloned()
+                .collect::<VecDeque<_>>();
+            if let Some(tx) = queue.front() {
+                heap.push((tx.tip(base_fee_per_gas), address));
+                queues.insert(address, queue);
+            }
+        }
+
+        let mut out = vec![];
+        while let Some((_, address)) = heap.pop() {
+            let queue = queues.get_mut(&address).unwrap();
+            out.extend(queue.pop_front());
+
+            if let Some(next) = queue.front() {
+                heap.push((next.tip(base_fee_per_gas), address));
+            }
+        }
+
+        out
+    }
+
+    /// Drops transactions over capacity, starting with the cheapest queued ones.
+    ///
+    /// Only the highest nonce of a sender is ever evicted, so that no gaps are introduced.
+    fn evict(&mut self) {
+        while self.len() > self.config.capacity {
+            let victim = self
+                .senders
+                .values()
+                .filter_map(|sender| {
+                    let (_, tx) = sender.transactions.last_key_value()?;
+                    let pending = sender.pending_count() == sender.transactions.len();
+                    Some(((pending, tx.tip(self.base_fee_per_gas)), tx.hash))
+                })
+                .min()
+                .map(|(_, hash)| hash)
+                .unwrap();
+
+            self.remove(victim);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex_literal::hex;
+
+    fn tx(nonce: u64, tip: u64, sender: Address) -> PooledTransaction {
+        PooledTransaction::new(
+            MessageWithSignature {
+                message: Message::EIP1559 {
+                    chain_id: ChainId(1),
+                    nonce,
+                    max_priority_fee_per_gas: tip.into(),
+                    max_fee_per_gas: (100 + tip).into(),
+                    gas_limit: 21_000,
+                    action: TransactionAction::Call(Address::zero()),
+                    value: U256::ZERO,
+                    input: Default::default(),
+                    access_list: vec![],
+                },
+                signature: MessageSignature::new(false, H256::repeat_byte(1), H256::repeat_byte(2))
+                    .unwrap(),
+            },
+            sender,
+        )
+    }
+
+    const ALICE: Address = H160(hex!("00000000000000000000000000000000000a11ce"));
+    const BOB: Address = H160(hex!("0000000000000000000000000000000000000b0b"));
+
+    fn rich(nonce: u64) -> SenderInfo {
+        SenderInfo {
+            nonce,
+            balance: U256::MAX,
+        }
+    }
+
+    #[test]
+    fn pending_and_queued() {
+        let mut pool = Pool::new(PoolConfig::default());
+
+        pool.insert(tx(0, 1, ALICE), rich(0)).unwrap();
+        pool.insert(tx(2, 1, ALICE), rich(0)).unwrap();
+        assert_eq!(pool.pending().len(), 1);
+        assert_eq!(pool.queued().len(), 1);
+
+        pool.insert(tx(1, 1, ALICE), rich(0)).unwrap();
+        assert_eq!(pool.pending().len(), 3);
+        assert!(pool.queued().is_empty());
+
+        // Can only afford two transactions.
+        let cost = tx(0, 1, BOB).cost().unwrap();
+        let poor = SenderInfo {
+            nonce: 0,
+            balance: cost * 2,
+        };
+        for nonce in 0..3 {
+            pool.insert(tx(nonce, 1, BOB), poor).unwrap();
+        }
+        assert_eq!(pool.pending().len(), 5);
+        assert_eq!(pool.queued().len(), 1);
+
+        assert!(matches!(
+            pool.insert(tx(0, 1, ALICE), rich(0)),
+            Err(PoolError::AlreadyKnown)
+        ));
+        assert!(matches!(
+            pool.insert(tx(0, 5, ALICE), rich(1)),
+            Err(PoolError::NonceTooLow {
+                expected: 1,
+                got: 0
+            })
+        ));
+        // First transaction was mined, as seen by the previous insertion.
+        assert_eq!(pool.len(), 5);
+
+        pool.update_sender(ALICE, rich(3));
+        pool.update_sender(BOB, rich(3));
+        assert!(pool.is_empty());
+    }
+
+    #[test]
+    fn replacement() {
+        let mut pool = Pool::new(PoolConfig::default());
+
+        let original = tx(0, 10, ALICE);
+        pool.insert(original.clone(), rich(0)).unwrap();
+
+        assert!(matches!(
+            pool.insert(tx(0, 10, ALICE), rich(0)),
+            Err(PoolError::AlreadyKnown)
+        ));
+        assert!(matches!(
+            pool.insert(tx(0, 10, ALICE).with_max_fee(109), rich(0)),
+            Err(PoolError::ReplacementUnderpriced)
+        ));
+
+        let replaced = pool.insert(tx(0, 21, ALICE), rich(0)).unwrap().unwrap();
+        assert_eq!(*replaced, original);
+        assert_eq!(pool.len(), 1);
+        assert!(!pool.contains(original.hash));
+    }
+
+    impl PooledTransaction {
+        fn with_max_fee(mut self, max_fee_per_gas: u64) -> Self {
+            if let Message::EIP1559 {
+                max_fee_per_gas: fee,
+                ..
+            } = &mut self.transaction.message
+            {
+                *fee = max_fee_per_gas.into();
+            }
+            self.hash = self.transaction.hash();
+            self
+        }
+    }
+
+    #[test]
+    fn ordering_and_eviction() {
+        let mut pool = Pool::new(PoolConfig {
+            capacity: 4,
+            ..Default::default()
+        });
+
+        pool.insert(tx(0, 1, ALICE), rich(0)).unwrap();
+        pool.insert(tx(1, 5, ALICE), rich(0)).unwrap();
+        pool.insert(tx(0, 3, BOB), rich(0)).unwrap();
+        pool.insert(tx(1, 2, BOB), rich(0)).unwrap();
+
+        assert_eq!(
+            pool.best_transactions()
+                .iter()
+                .map(|tx| (tx.sender, tx.nonce()))
+                .collect::<Vec<_>>(),
+            vec![(BOB, 0), (BOB, 1), (ALICE, 0), (ALICE, 1)]
+        );
+
+        pool.set_base_fee_per_gas(Some(102_u64.into()));
+        assert_eq!(
+            pool.best_transactions()
+                .iter()
+                .map(|tx| (tx.sender, tx.nonce()))
+                .collect::<Vec<_>>(),
+            vec![(BOB, 0), (BOB, 1)]
+        );
+        pool.set_base_fee_per_gas(None);
+
+        // Queued transaction is the first to go.
+        assert!(matches!(
+            pool.insert(tx(5, 100, ALICE), rich(0)),
+            Err(PoolError::PoolFull)
+        ));
+        assert_eq!(pool.len(), 4);
+        assert!(!pool.contains(tx(5, 100, ALICE).hash));
+
+        // Then the cheapest tail among pending ones.
+        pool.insert(tx(2, 100, ALICE), rich(0)).unwrap();
+        assert_eq!(pool.len(), 4);
+        assert!(!pool.contains(tx(1, 2, BOB).hash));
+
+        assert!(matches!(
+            pool.insert(tx(1, 0, BOB), rich(0)),
+            Err(PoolError::PoolFull)
+        ));
+        assert_eq!(pool.len(), 4);
+    }
+
+    #[test]
+    fn replacement_in_full_pool() {
+        let mut pool = Pool::new(PoolConfig {
+            capacity: 2,
+            ..Default::default()
+        });
+
+        let original = tx(0, 1, ALICE);
+        pool.insert(original.clone(), rich(0)).unwrap();
+        pool.insert(tx(0, 50, BOB), rich(0)).unwrap();
+
+        // Replacement is still the cheapest transaction in a full pool, yet it must not be
+        // evicted along with the transaction it replaced.
+        let replacement = tx(0, 11, ALICE);
+        let replaced = pool.insert(replacement.clone(), rich(0)).unwrap().unwrap();
+        assert_eq!(*replaced, original);
+        assert_eq!(pool.len(), 2);
+        assert!(pool.contains(replacement.hash));
+        assert!(!pool.contains(original.hash));
+
+        // New transaction that does not make it in leaves the pool untouched.
+        assert!(matches!(
+            pool.insert(tx(1, 0, ALICE), rich(0)),
+            Err(PoolError::PoolFull)
+        ));
+        assert_eq!(pool.len(), 2);
+        assert!(pool.contains(replacement.hash));
+    }
+}